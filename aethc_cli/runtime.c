@@ -1,7 +1,237 @@
 /* runtime.c – \u043c\u0438\u043d\u0438\u043c\u0430\u043b\u043d\u0438 I/O \u0437\u0430 Aether 0.1 */
 #include <stdio.h>
 #include <stdint.h>
+#include <stdlib.h>
+#include <string.h>
 
 /* \u0418\u0441\u043f\u0438\u0441 \u0441\u0430 \u043d\u043e\u0432\u0438\u043c \u0440\u0435\u0434\u043e\u043c \u0434\u0430 \u0431\u0438 \u043a\u043e\u0440\u0438\u0441\u043d\u0438\u043a \u043e\u0434\u043c\u0430\u0445 \u0432\u0438\u0434\u0435\u043e \u0440\u0435\u0437\u0443\u043b\u0442\u0430\u0442. */
 void aethc_print_int(int32_t v)  { printf("%d\n",  v); }
 void aethc_print_str(const char* s) { puts(s); }  /* puts \u0434\u043e\u0434\u0430\u0458\u0435 '\n' */
+void aethc_print_i64(int64_t v)  { printf("%lld\n", (long long)v); }
+void aethc_print_u64(uint64_t v) { printf("%llu\n", (unsigned long long)v); }
+
+/* Char је Unicode скалар; исписује се кодиран у UTF-8. */
+void aethc_print_char(int32_t c) {
+    char buf[4];
+    int n;
+    if (c < 0x80)         { buf[0] = (char)c; n = 1; }
+    else if (c < 0x800)   { buf[0] = (char)(0xC0 | c >> 6); n = 2; }
+    else if (c < 0x10000) { buf[0] = (char)(0xE0 | c >> 12); n = 3; }
+    else                  { buf[0] = (char)(0xF0 | c >> 18); n = 4; }
+    for (int i = 1; i < n; i++)
+        buf[i] = (char)(0x80 | ((c >> (6 * (n - 1 - i))) & 0x3F));
+    fwrite(buf, 1, (size_t)n, stdout);
+    putchar('\n');
+}
+
+/* Низ бајтова (`b"..."`) исписује се онакав какав је. */
+void aethc_print_bytes(const uint8_t* p, int64_t len) {
+    fwrite(p, 1, (size_t)len, stdout);
+    putchar('\n');
+}
+
+/* Поређење `Str` вредности по садржају: -1, 0 или 1. */
+int32_t aethc_str_cmp(const char* a, const char* b) {
+    int c = strcmp(a, b);
+    return (c > 0) - (c < 0);
+}
+
+/* ───────────── паника ─────────────
+ * Индекс ван граница низа: порука са функцијом и местом у изворном коду,
+ * затим излаз са кодом 101. */
+void aethc_panic_bounds(int64_t index, int64_t len, const char* fn, int32_t line, int32_t col) {
+    fflush(stdout);
+    fprintf(stderr, "panic in `%s` at %d:%d: index out of bounds: the len is %lld but the index is %lld\n",
+            fn, line, col, (long long)len, (long long)index);
+    exit(101);
+}
+
+/* Дељење нулом, или `MIN / -1` који не стаје у тип: исти излаз као изнад. */
+void aethc_panic_div(const char* fn, int64_t divisor, int32_t rem) {
+    fflush(stdout);
+    const char* op = rem ? "calculate the remainder" : "divide";
+    if (divisor == 0)
+        fprintf(stderr, "panic in `%s`: attempt to %s %s\n", fn, op, rem ? "with a divisor of zero" : "by zero");
+    else
+        fprintf(stderr, "panic in `%s`: attempt to %s with overflow\n", fn, op);
+    exit(101);
+}
+
+/* ───────────── нити и канали ─────────────
+ * Канал је неограничен ред 64-битних речи; `recv` чека док ред не добије
+ * вредност. Свака нит се памти у листи коју `aethc_join_all` празни на
+ * крају `main`-а. */
+
+#ifdef _WIN32
+#include <windows.h>
+typedef SRWLOCK aethc_mutex;
+typedef CONDITION_VARIABLE aethc_cond;
+typedef HANDLE aethc_thread;
+#define AETHC_MUTEX_INIT SRWLOCK_INIT
+static void mutex_init(aethc_mutex* m) { InitializeSRWLock(m); }
+static void mutex_lock(aethc_mutex* m) { AcquireSRWLockExclusive(m); }
+static void mutex_unlock(aethc_mutex* m) { ReleaseSRWLockExclusive(m); }
+static void cond_init(aethc_cond* c) { InitializeConditionVariable(c); }
+static void cond_wait(aethc_cond* c, aethc_mutex* m) { SleepConditionVariableSRW(c, m, INFINITE, 0); }
+static void cond_signal(aethc_cond* c) { WakeConditionVariable(c); }
+#else
+#include <pthread.h>
+typedef pthread_mutex_t aethc_mutex;
+typedef pthread_cond_t aethc_cond;
+typedef pthread_t aethc_thread;
+#define AETHC_MUTEX_INIT PTHREAD_MUTEX_INITIALIZER
+static void mutex_init(aethc_mutex* m) { pthread_mutex_init(m, NULL); }
+static void mutex_lock(aethc_mutex* m) { pthread_mutex_lock(m); }
+static void mutex_unlock(aethc_mutex* m) { pthread_mutex_unlock(m); }
+static void cond_init(aethc_cond* c) { pthread_cond_init(c, NULL); }
+static void cond_wait(aethc_cond* c, aethc_mutex* m) { pthread_cond_wait(c, m); }
+static void cond_signal(aethc_cond* c) { pthread_cond_signal(c); }
+#endif
+
+static void* xmalloc(size_t n) {
+    void* p = malloc(n ? n : 1);
+    if (!p) { fputs("aethc: out of memory\n", stderr); abort(); }
+    return p;
+}
+
+typedef struct node { int64_t v; struct node* next; } node;
+typedef struct { aethc_mutex lock; aethc_cond ready; node* head; node* tail; } aethc_chan;
+
+void* aethc_chan_new(void) {
+    aethc_chan* c = xmalloc(sizeof *c);
+    mutex_init(&c->lock);
+    cond_init(&c->ready);
+    c->head = c->tail = NULL;
+    return c;
+}
+
+void aethc_chan_send(void* chan, int64_t v) {
+    aethc_chan* c = chan;
+    node* n = xmalloc(sizeof *n);
+    n->v = v;
+    n->next = NULL;
+    mutex_lock(&c->lock);
+    if (c->tail) c->tail->next = n; else c->head = n;
+    c->tail = n;
+    cond_signal(&c->ready);
+    mutex_unlock(&c->lock);
+}
+
+int64_t aethc_chan_recv(void* chan) {
+    aethc_chan* c = chan;
+    mutex_lock(&c->lock);
+    while (!c->head) cond_wait(&c->ready, &c->lock);
+    node* n = c->head;
+    c->head = n->next;
+    if (!c->head) c->tail = NULL;
+    mutex_unlock(&c->lock);
+    int64_t v = n->v;
+    free(n);
+    return v;
+}
+
+/* трамполин који је генерисао компајлер + копија његових аргумената */
+typedef struct { void (*fn)(void*); void* env; } spawn_req;
+
+static aethc_mutex threads_lock = AETHC_MUTEX_INIT;
+static aethc_thread* threads;
+static size_t n_threads, cap_threads;
+
+#ifdef _WIN32
+static DWORD WINAPI thread_main(LPVOID p)
+#else
+static void* thread_main(void* p)
+#endif
+{
+    spawn_req* r = p;
+    r->fn(r->env);
+    free(r->env);
+    free(r);
+    return 0;
+}
+
+void aethc_spawn(void* fn, void* env, int64_t size) {
+    spawn_req* r = xmalloc(sizeof *r);
+    r->fn = (void (*)(void*))fn;
+    r->env = xmalloc((size_t)size);
+    memcpy(r->env, env, (size_t)size);
+
+    aethc_thread t;
+#ifdef _WIN32
+    t = CreateThread(NULL, 0, thread_main, r, 0, NULL);
+    int failed = t == NULL;
+#else
+    int failed = pthread_create(&t, NULL, thread_main, r) != 0;
+#endif
+    if (failed) { fputs("aethc: cannot spawn a thread\n", stderr); abort(); }
+
+    mutex_lock(&threads_lock);
+    if (n_threads == cap_threads) {
+        cap_threads = cap_threads ? cap_threads * 2 : 8;
+        threads = realloc(threads, cap_threads * sizeof *threads);
+        if (!threads) { fputs("aethc: out of memory\n", stderr); abort(); }
+    }
+    threads[n_threads++] = t;
+    mutex_unlock(&threads_lock);
+}
+
+/* чека и нити које су покренуле друге нити */
+void aethc_join_all(void) {
+    for (;;) {
+        mutex_lock(&threads_lock);
+        if (n_threads == 0) { mutex_unlock(&threads_lock); return; }
+        aethc_thread t = threads[--n_threads];
+        mutex_unlock(&threads_lock);
+#ifdef _WIN32
+        WaitForSingleObject(t, INFINITE);
+        CloseHandle(t);
+#else
+        pthread_join(t, NULL);
+#endif
+    }
+}
+
+/* ───────────── Vec ─────────────
+ * Низ 64-битних речи који расте удвостручавањем. Вредност `Vec`-а у
+ * програму је показивач на ово заглавље; премештање копира само показивач,
+ * а borrow checker брани употребу старог имена. Меморија се за сада не
+ * ослобађа. */
+typedef struct { int64_t* data; int64_t len; int64_t cap; } aethc_vec;
+
+void* aethc_vec_new(void) {
+    aethc_vec* v = xmalloc(sizeof *v);
+    v->data = NULL;
+    v->len = v->cap = 0;
+    return v;
+}
+
+void aethc_vec_push(void* vec, int64_t x) {
+    aethc_vec* v = vec;
+    if (v->len == v->cap) {
+        v->cap = v->cap ? v->cap * 2 : 4;
+        v->data = realloc(v->data, (size_t)v->cap * sizeof *v->data);
+        if (!v->data) { fputs("aethc: out of memory\n", stderr); abort(); }
+    }
+    v->data[v->len++] = x;
+}
+
+int64_t aethc_vec_pop(void* vec) {
+    aethc_vec* v = vec;
+    if (v->len == 0) {
+        fflush(stdout);
+        fputs("panic: pop from an empty Vec\n", stderr);
+        exit(101);
+    }
+    return v->data[--v->len];
+}
+
+int64_t aethc_vec_len(void* vec) { return ((aethc_vec*)vec)->len; }
+
+/* индекс је већ прошао проверу граница коју је генерисао компајлер */
+int64_t aethc_vec_get(void* vec, int64_t i) { return ((aethc_vec*)vec)->data[i]; }
+void aethc_vec_set(void* vec, int64_t i, int64_t x) { ((aethc_vec*)vec)->data[i] = x; }
+
+/* ───────────── Closures ─────────────
+ * Ћелије променљивих које затворења хватају по референци и окружења
+ * затворења. Живе колико и програм, као и `Vec`. */
+void* aethc_alloc(int64_t size) { return xmalloc((size_t)size); }
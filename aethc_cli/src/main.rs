@@ -1,4 +1,9 @@
-use aethc_core::{self, lexer::Span};
+use aethc_core::{
+    self, borrowck, cfg,
+    lexer::Span,
+    loader::{self, SourceFile},
+    resolver::{self, FileError},
+};
 use ariadne::{Label, Report, ReportKind, Source};
 use clap::{Parser, Subcommand};
 use std::{
@@ -40,7 +45,7 @@ fn main() -> std::process::ExitCode {
         Cmd::Parse { file, emit_hir } => {
             let src = fs::read_to_string(&file).expect("read");
             let (ast, lex_errs) = aethc_core::parse(&src);
-            report_errors(&lex_errs, &src);
+            report_errors(&lex_errs, &file, &src);
             println!("{:#?}", ast);
             if emit_hir {
                 let (hir, _res_errs) = aethc_core::lower_to_hir(&ast, &src);
@@ -53,7 +58,7 @@ fn main() -> std::process::ExitCode {
             exit_code(ok)
         }
         Cmd::Build { file, output, emit } => match run_full_frontend(&file, emit.as_deref()) {
-            Ok(mut llvm_module) => {
+            Ok(llvm_module) => {
                 let bitcode_path = output.with_extension("bc");
                 llvm_module.module.write_bitcode_to_path(&bitcode_path);
                 link_with_clang(&bitcode_path, &output);
@@ -65,35 +70,76 @@ fn main() -> std::process::ExitCode {
     }
 }
 
-fn report_errors<E>(errs: &[E], src: &str)
+fn report_errors<E>(errs: &[E], file: &Path, src: &str)
+where
+    E: SpannedError,
+{
+    report(ReportKind::Error, errs, file, src);
+}
+
+fn report<E>(kind: ReportKind, errs: &[E], file: &Path, src: &str)
 where
     E: SpannedError,
 {
+    let name = file.display().to_string();
     for e in errs {
         let span = e.span();
         let msg = e.msg();
-        Report::build(ReportKind::Error, (), span.start)
+        Report::build(kind, name.as_str(), span.start)
             .with_message(&msg)
-            .with_label(Label::new(span.start..span.end).with_message(msg))
+            .with_label(Label::new((name.as_str(), span.start..span.end)).with_message(msg))
             .finish()
-            .print(Source::from(src))
+            .print((name.as_str(), Source::from(src)))
             .unwrap();
     }
 }
 
+/// Report diagnostics that carry the index of their source file.
+fn report_files(kind: ReportKind, errs: &[FileError], files: &[SourceFile]) {
+    for e in errs {
+        let file = &files[e.file];
+        report(kind, std::slice::from_ref(&e.err), &file.path, &file.src);
+    }
+}
+
 type LlvmModule<'ctx> = aethc_core::codegen::LlvmCtx<'ctx>;
 
-fn run_full_frontend(path: &PathBuf, emit: Option<&str>) -> Result<LlvmModule<'static>, ()> {
-    let src = fs::read_to_string(path).expect("read");
-    let (ast, lex_errs) = aethc_core::parse(&src);
-    report_errors(&lex_errs, &src);
-    if !lex_errs.is_empty() {
+fn run_full_frontend(path: &Path, emit: Option<&str>) -> Result<LlvmModule<'static>, ()> {
+    let tree = match loader::load(path) {
+        Ok(tree) => tree,
+        Err((tree, errs)) => {
+            report_files(ReportKind::Error, &errs, &tree.files);
+            return Err(());
+        }
+    };
+
+    let (hir, diags) = resolver::resolve_modules(&tree.module_asts());
+    report_files(ReportKind::Warning, &diags.warnings, &tree.files);
+    report_files(ReportKind::Error, &diags.errors, &tree.files);
+    if !diags.errors.is_empty() {
+        return Err(());
+    }
+
+    let borrow_errs = borrowck::check_module(&hir);
+    report_files(ReportKind::Error, &borrow_errs, &tree.files);
+    if !borrow_errs.is_empty() {
         return Err(());
     }
 
-    let (hir, res_errs) = aethc_core::lower_to_hir(&ast, &src);
-    report_errors(&res_errs, &src);
-    if !res_errs.is_empty() {
+    let mut cfg_ok = true;
+    for it in &hir.items {
+        if let aethc_core::hir::Item::Fn(f) = it {
+            let mut diags = cfg::CfgDiagnostics::default();
+            for body in aethc_core::mir::lower_fn_and_closures(f) {
+                cfg::check_body(&body.name, &body.body, &mut diags);
+            }
+            let file = &tree.files[f.file];
+            report(ReportKind::Warning, &diags.warnings, &file.path, &file.src);
+            report_errors(&diags.errors, &file.path, &file.src);
+            cfg_ok &= diags.errors.is_empty();
+        }
+    }
+    if !cfg_ok {
         return Err(());
     }
 
@@ -106,10 +152,7 @@ fn run_full_frontend(path: &PathBuf, emit: Option<&str>) -> Result<LlvmModule<'s
     }
 
     // Lower to MIR
-    let mir = match &hir.items[0] {
-        aethc_core::hir::Item::Fn(f) => aethc_core::mir::lower_fn(f),
-        _ => return Err(()),
-    };
+    let mir = aethc_core::mir::lower_module(&hir);
 
     if let Some("mir") = emit {
         println!("{:#?}", mir);
@@ -117,7 +160,7 @@ fn run_full_frontend(path: &PathBuf, emit: Option<&str>) -> Result<LlvmModule<'s
 
     // Codegen
     let mut llcx = aethc_core::codegen::LlvmCtx::new("app");
-    aethc_core::codegen::codegen_fn(&mut llcx, "main", &mir);
+    aethc_core::codegen::codegen_module(&mut llcx, &mir);
 
     if let Some("llvm") = emit {
         let txt = llcx.module.print_to_string();
@@ -239,6 +282,6 @@ impl SpannedError for aethc_core::borrow::BorrowError {
         self.span
     }
     fn msg(&self) -> String {
-        format!("{:?}", self.kind)
+        aethc_core::borrow::BorrowError::msg(self)
     }
 }
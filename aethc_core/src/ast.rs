@@ -1,5 +1,8 @@
 // ast.rs – zajednički, netipizovan AST
 
+use crate::lexer::Span;
+use crate::type_::NumTy;
+
 #[derive(Debug, Clone)]
 pub struct Module {
     pub items: Vec<Item>,
@@ -9,28 +12,131 @@ pub struct Module {
 pub enum Item {
     Function(Function),
     Let(GlobalLet), //  globalni let
+    Const(Const),
+    /// `mod name;` – podmodul iz fajla `name.ae` ili `name/mod.ae`
+    Mod(String),
+    /// `use a::b::item;`
+    Use(Vec<String>),
+    Trait(Trait),
+    Impl(Impl),
 }
 
 #[derive(Debug, Clone)]
 pub struct Function {
     pub name: String,
+    pub span: Span, // ime funkcije
+    pub is_pub: bool,
+    pub lifetimes: Vec<String>,      // `fn first<'a>(..)`, bez apostrofa
+    pub type_params: Vec<TypeParam>, // `fn max<T: Ord>(..)`
     pub params: Vec<Param>,
-    pub return_ty: Option<String>,
-    pub body: Vec<Stmt>,
+    pub return_ty: Option<TypeExpr>,
+    pub self_mode: Option<SelfMode>,
+    pub body: Block,
+}
+
+/// `T` ili `T: Show + fmt::Debug` – ime i putanje osobina koje mora imati
+#[derive(Debug, Clone)]
+pub struct TypeParam {
+    pub name: String,
+    pub bounds: Vec<Vec<String>>,
+}
+
+/// `trait Shape { fn area(self) -> Float; }`
+#[derive(Debug, Clone)]
+pub struct Trait {
+    pub name: String,
+    pub is_pub: bool,
+    pub methods: Vec<FnDecl>,
+}
+
+/// Potpis metode u osobini, bez tela.
+#[derive(Debug, Clone)]
+pub struct FnDecl {
+    pub name: String,
+    pub span: Span,
+    pub lifetimes: Vec<String>,
+    pub type_params: Vec<TypeParam>,
+    pub params: Vec<Param>,
+    pub return_ty: Option<TypeExpr>,
+    /// kako metoda prima `self`, ako joj je on prvi parametar
+    pub self_mode: Option<SelfMode>,
+}
+
+/// `self`, `&self` ili `&mut self`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelfMode {
+    Value,
+    Ref,
+    RefMut,
+}
+
+impl SelfMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SelfMode::Value => "self",
+            SelfMode::Ref => "&self",
+            SelfMode::RefMut => "&mut self",
+        }
+    }
+}
+
+/// `impl<T: Show> Show for Vec<T> { fn show(self) { .. } }`, or without
+/// a trait `impl Int { fn double(&self) -> Int { .. } }`
+#[derive(Debug, Clone)]
+pub struct Impl {
+    pub lifetimes: Vec<String>,
+    pub type_params: Vec<TypeParam>,
+    pub trait_path: Option<Vec<String>>,
+    pub self_ty: TypeExpr,
+    pub methods: Vec<Function>,
 }
 
 #[derive(Debug, Clone)]
 pub struct Param {
     pub name: String,
-    pub ty: Option<String>, // ako postoji anotacija:  x: Int
+    pub ty: Option<TypeExpr>, // ako postoji anotacija:  x: Int
+}
+
+/// Anotacija tipa: `Int`, `Sender<Int>`, `[Int; 4]`, `(Int, Bool)`
+#[derive(Debug, Clone)]
+pub enum TypeExpr {
+    Named { name: String, args: Vec<TypeExpr> },
+    /// `[T; N]` – dužina je konstantni izraz
+    Array { elem: Box<TypeExpr>, len: Box<Expr> },
+    /// `(A, B)`; `()` je unit, `(A,)` torka sa jednim elementom
+    Tuple(Vec<TypeExpr>),
+    /// `fn(A, B) -> R` – funkcija ili zatvorenje kao vrednost
+    Fn { params: Vec<TypeExpr>, ret: Option<Box<TypeExpr>> },
+    /// `&T`, `&mut T` ili `&'a T` – životni vek se piše bez apostrofa
+    Ref { mutable: bool, lifetime: Option<String>, inner: Box<TypeExpr> },
+    /// `dyn Trait` – samo iza reference, `&dyn Trait`
+    Dyn(Vec<String>),
+}
+
+/// Šablon levo od `=` u `let (a, (b, _)) = expr;`
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    Ident { name: String, mutable: bool },
+    Tuple(Vec<Pattern>),
+    /// `_` – vrednost se ne vezuje
+    Wildcard,
 }
 
 #[derive(Debug, Clone)]
 pub struct GlobalLet {
     pub name: String,
+    pub is_pub: bool,
     pub expr: Expr,
     pub mutable: bool,
 }
+/// `const NAME: T = expr;` – vrednost se računa u vreme prevođenja
+#[derive(Debug, Clone)]
+pub struct Const {
+    pub name: String,
+    pub is_pub: bool,
+    pub ty: TypeExpr,
+    pub expr: Expr,
+}
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BinOp {
     Plus,
@@ -46,43 +152,141 @@ pub enum BinOp {
     Ge,
     AndAnd,
     OrOr,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
+}
+impl BinOp {
+    /// Left and right binding power, from `||` (weakest) to `*`, `/`, `%`.
+    pub fn binding_power(&self) -> (u8, u8) {
+        use BinOp::*;
+        match self {
+            OrOr => (1, 2),
+            AndAnd => (3, 4),
+            EqEq | NotEq | Lt | Le | Gt | Ge => (5, 6),
+            BitOr => (7, 8),
+            BitXor => (9, 10),
+            BitAnd => (11, 12),
+            Shl | Shr => (13, 14),
+            Plus | Minus => (15, 16),
+            Star | Slash | Percent => (17, 18),
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        use BinOp::*;
+        match self {
+            Plus => "+",
+            Minus => "-",
+            Star => "*",
+            Slash => "/",
+            Percent => "%",
+            EqEq => "==",
+            NotEq => "!=",
+            Lt => "<",
+            Le => "<=",
+            Gt => ">",
+            Ge => ">=",
+            AndAnd => "&&",
+            OrOr => "||",
+            BitAnd => "&",
+            BitOr => "|",
+            BitXor => "^",
+            Shl => "<<",
+            Shr => ">>",
+        }
+    }
+
+    /// Comparisons are non-associative: `a < b < c` is an error.
+    pub fn is_comparison(&self) -> bool {
+        matches!(self, BinOp::EqEq | BinOp::NotEq | BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge)
+    }
 }
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum UnOp {
     Negate,
     Not,
+    /// `~x` – bitwise complement of an Int
+    BitNot,
 }
 impl UnOp {
+    /// Prefix operators bind tighter than any binary one.
     pub fn binding_power(&self) -> u8 {
-        match self {
-            UnOp::Negate => 5, // Example: Higher than multiplication/division (3,4)
-            UnOp::Not => 5,    // Example: Higher than logical AND/OR (0,1)
-        }
+        19
     }
 }
+/// `{ stmts tail }` – uvodi novi leksički opseg
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub stmts: Vec<Stmt>,
+    /// završni izraz bez `;` – vrednost bloka
+    pub tail: Option<Box<Expr>>,
+    /// prvi token svake naredbe iz `stmts`, pa repa
+    pub spans: Vec<Span>,
+}
+
 #[derive(Debug, Clone)]
 pub enum Stmt {
     Let {
         name: String,
+        /// `let x: T = expr;` – zadati tip vrednosti
+        ty: Option<TypeExpr>,
         expr: Expr,
         mutable: bool,
     },
+    /// `let x: T;` – vrednost dobija kasnijom dodelom; `cfg` proverava da
+    /// je dodeljena pre svake upotrebe
+    Decl {
+        name: String,
+        ty: TypeExpr,
+        mutable: bool,
+    },
+    /// `let (q, r) = expr;` – destrukturiranje torke
+    LetTuple {
+        pats: Vec<Pattern>,
+        expr: Expr,
+    },
     Assign {
         name: String,
         expr: Expr,
     },
+    /// `a[i] = expr;`, `p.0 = expr;`, `*r = expr;` – `target` je izraz
+    /// mesta: promenljiva ili `*` reference, pa polja i elementi
+    AssignPlace {
+        target: Expr,
+        expr: Expr,
+    },
+    /// `target op= expr` – `target` je promenljiva ili izraz mesta
+    AssignOp {
+        target: Expr,
+        op: BinOp,
+        expr: Expr,
+    },
     Expr(Expr),
     Return(Option<Expr>),
+    Break,
+    Continue,
 }
 
 #[derive(Debug, Clone)]
 pub enum Expr {
     Ident(String),
+    /// kvalifikovana putanja `a::b::item` (bar dva segmenta)
+    Path(Vec<String>),
     Int(i64),
     Float(f64),
+    /// literal with a type suffix: `10u8`, `2.5f32`
+    TypedInt(i64, NumTy),
+    TypedFloat(f64, NumTy),
     Bool(bool),
     Unit,
     Str(String),
+    /// `'a'`
+    Char(char),
+    /// `b"..."`, niz tipa `[u8; N]`
+    ByteStr(Vec<u8>),
     Call {
         callee: Box<Expr>,
         args: Vec<Expr>,
@@ -96,4 +300,99 @@ pub enum Expr {
         op: UnOp,
         expr: Box<Expr>,
     },
+    Block(Block),
+    If {
+        cond: Box<Expr>,
+        then_branch: Block,
+        else_branch: Option<Box<Expr>>, // Block ili ugnježdeni If
+    },
+    While {
+        cond: Box<Expr>,
+        body: Block,
+    },
+    /// `for var in iter { body }`
+    For {
+        var: String,
+        iter: Box<Expr>,
+        body: Block,
+        /// ključna reč `for`; na nju ukazuje panika indeksa van granica
+        span: Span,
+    },
+    /// `start..end` ili `start..=end`
+    Range {
+        start: Box<Expr>,
+        end: Box<Expr>,
+        inclusive: bool,
+    },
+    /// `spawn f(args)` – poziv funkcije u novoj niti
+    Spawn {
+        callee: Box<Expr>,
+        args: Vec<Expr>,
+    },
+    /// `channel::<T>()` – par (Sender<T>, Receiver<T>)
+    Channel(Option<TypeExpr>),
+    /// `vec::<T>()` – prazan `Vec<T>`
+    VecNew(TypeExpr),
+    /// `(a, b)` ili `(a,)`
+    Tuple(Vec<Expr>),
+    /// `base.0` – element torke
+    Field {
+        base: Box<Expr>,
+        index: usize,
+    },
+    /// `&expr` ili `&mut expr`
+    Ref {
+        mutable: bool,
+        expr: Box<Expr>,
+    },
+    /// `*expr` – vrednost na koju referenca pokazuje
+    Deref(Box<Expr>),
+    /// `receiver.method(args)`
+    MethodCall {
+        receiver: Box<Expr>,
+        method: String,
+        args: Vec<Expr>,
+    },
+    /// `[a, b, c]`
+    Array(Vec<Expr>),
+    /// `[elem; len]` – `len` je konstantni izraz
+    Repeat {
+        elem: Box<Expr>,
+        len: Box<Expr>,
+    },
+    /// `base[index]`; `span` je `[` – mesto koje javlja panika granica
+    Index {
+        base: Box<Expr>,
+        index: Box<Expr>,
+        span: Span,
+    },
+    /// `|a: Int| body` ili `move |a: Int| -> T { .. }`
+    Closure {
+        params: Vec<Param>,
+        ret: Option<TypeExpr>,
+        body: Box<Expr>,
+        by_move: bool,
+    },
+    /// `match scrutinee { Some(x) => a, None => b }`
+    Match {
+        scrutinee: Box<Expr>,
+        arms: Vec<MatchArm>,
+    },
+    /// `expr?` – vraća `None` ili grešku iz funkcije
+    Try(Box<Expr>),
+}
+
+/// `pattern => body` u `match`-u
+#[derive(Debug, Clone)]
+pub struct MatchArm {
+    pub pattern: MatchPattern,
+    pub body: Expr,
+}
+
+/// Obrazac grane `match`-a: varijanta `Option`-a ili `Result`-a, sa
+/// obrascem za njen sadržaj (`Some(x)`, `Err(_)`, `Ok((a, b))`), ili `_`.
+#[derive(Debug, Clone)]
+pub enum MatchPattern {
+    Variant { name: String, inner: Option<Pattern> },
+    Wildcard,
 }
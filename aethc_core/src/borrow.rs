@@ -1,9 +1,38 @@
 // borrow.rs - block-local borrow checker
+//
+// Tracks moves of non-Copy values (`String`, `Vec`, arrays and tuples of
+// them) and the mutable borrow `push`/`pop` take of a `Vec` for the rest of
+// the statement. Moving a field out of a tuple moves only that field; the
+// tuple as a whole is unusable afterwards.
+//
+// A closure captures by reference unless it is `move`. A captured variable
+// cannot be moved for the rest of the function, since the closure may still
+// be called; a `move` closure moves its non-Copy captures in. Either way the
+// body may run many times, so it cannot move a captured variable out.
+// `Receiver`s are left to `borrowck::FnCheck`, which knows about threads.
+//
+// The receiver of a `&self` method is only read, that of a `&mut self`
+// method is mutably borrowed like the `Vec` of `push`. Inside such a
+// method `self` belongs to the caller and cannot be moved out.
+//
+// A reference passed to a function is reborrowed, not moved, so a `&mut`
+// stays usable after the call. Nothing that is not Copy can be moved out
+// from behind a reference: `*r` only reads it.
+//
+// `let r = &v` and `let r = &mut v` are loans of `v`. Moving, assigning or
+// mutably borrowing `v` ends every loan of it, and so does reading it for a
+// `&mut` loan; using `r` after its loan ended is an error. So a reference
+// that is never used again doesn't hold `v` back.
+//
+// A loop body is checked twice: the second pass starts from what the first
+// one moved, on the way to its end or to a `continue`, so a value moved in
+// one iteration can't be used in the next.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::hir::{self, Expr, Stmt};
 use crate::lexer::Span;
+use crate::type_::Type;
 
 pub type VarId = hir::NodeId;
 
@@ -18,11 +47,36 @@ pub type BorrowId = u32;
 
 pub type VarStates = HashMap<VarId, BorrowState>;
 
+/// Variables and tuple fields moved so far, see `BorrowCtx::moves`.
+pub type Moves = (HashSet<VarId>, HashSet<(VarId, usize)>);
+
 pub struct BorrowCtx<'hir> {
     pub states: VarStates,
     pub errors: Vec<BorrowError>,
     pub hir: &'hir hir::Block,
     pub next_borrow_id: BorrowId,
+    /// names of the variables seen so far, for error messages
+    pub names: HashMap<VarId, String>,
+    /// fields moved out of tuple variables, `(variable, field index)`
+    pub partial: HashSet<(VarId, usize)>,
+    /// variables a closure created so far captures by reference
+    pub closure_borrows: HashSet<VarId>,
+    /// inside a closure body: the variables it captures
+    pub captured: HashSet<VarId>,
+    /// `self` of a `&self` or `&mut self` method
+    pub borrowed: HashSet<VarId>,
+    /// the final expression of the body is the value the function returns
+    pub tail_moves: bool,
+    /// `let` bound references: the variable each one borrows and whether
+    /// the loan is `&mut`
+    pub loans: HashMap<VarId, (VarId, bool)>,
+    /// references whose loan ended, with the conflict to report on their
+    /// next use
+    pub ended: HashMap<VarId, (BorrowErrorKind, VarId)>,
+    /// moves on the paths reaching a `continue` or `break` of the innermost
+    /// loop
+    pub continued: Moves,
+    pub broken: Moves,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -31,13 +85,30 @@ pub enum BorrowErrorKind {
     AssignWhileBorrowed,
     SecondMutBorrow,
     DoubleMove,
+    MoveWhileCaptured,
+    MoveOutOfClosure,
+    MoveOutOfRef,
+    /// `v` was mutably borrowed while the loan of the reference used was live
+    MutBorrowWhileBorrowed,
+    MoveWhileBorrowed,
+    AssignWhileBorrowedRef,
+    UseWhileMutBorrowed,
 }
 
 impl BorrowErrorKind {
     pub fn code(&self) -> &'static str {
         match self {
-            BorrowErrorKind::AssignWhileBorrowed | BorrowErrorKind::SecondMutBorrow => "E010",
-            BorrowErrorKind::UseAfterMove | BorrowErrorKind::DoubleMove => "E011",
+            BorrowErrorKind::AssignWhileBorrowed
+            | BorrowErrorKind::SecondMutBorrow
+            | BorrowErrorKind::MoveWhileCaptured
+            | BorrowErrorKind::MutBorrowWhileBorrowed
+            | BorrowErrorKind::MoveWhileBorrowed
+            | BorrowErrorKind::AssignWhileBorrowedRef
+            | BorrowErrorKind::UseWhileMutBorrowed => "E010",
+            BorrowErrorKind::UseAfterMove
+            | BorrowErrorKind::DoubleMove
+            | BorrowErrorKind::MoveOutOfClosure
+            | BorrowErrorKind::MoveOutOfRef => "E011",
         }
     }
 }
@@ -46,10 +117,48 @@ impl BorrowErrorKind {
 pub struct BorrowError {
     pub code: &'static str,
     pub kind: BorrowErrorKind,
+    pub name: String,
     pub span: Span,
     pub prev_span: Span,
 }
 
+impl BorrowError {
+    pub fn msg(&self) -> String {
+        let name = &self.name;
+        match self.kind {
+            BorrowErrorKind::UseAfterMove => format!("use of moved value `{name}`"),
+            BorrowErrorKind::DoubleMove => format!("value `{name}` is moved twice"),
+            BorrowErrorKind::SecondMutBorrow => {
+                format!("cannot borrow `{name}` as mutable more than once in one statement")
+            }
+            BorrowErrorKind::AssignWhileBorrowed => {
+                format!("cannot move out of `{name}` while it is mutably borrowed")
+            }
+            BorrowErrorKind::MoveWhileCaptured => {
+                format!("cannot move out of `{name}` because a closure borrows it")
+            }
+            BorrowErrorKind::MoveOutOfClosure => {
+                format!("cannot move captured variable `{name}` out of a closure")
+            }
+            BorrowErrorKind::MoveOutOfRef => {
+                format!("cannot move out of `{name}`, which is behind a reference")
+            }
+            BorrowErrorKind::MutBorrowWhileBorrowed => {
+                format!("cannot borrow `{name}` as mutable while a reference to it is still used")
+            }
+            BorrowErrorKind::MoveWhileBorrowed => {
+                format!("cannot move out of `{name}` while a reference to it is still used")
+            }
+            BorrowErrorKind::AssignWhileBorrowedRef => {
+                format!("cannot assign to `{name}` while a reference to it is still used")
+            }
+            BorrowErrorKind::UseWhileMutBorrowed => {
+                format!("cannot use `{name}` while a mutable reference to it is still used")
+            }
+        }
+    }
+}
+
 impl<'hir> BorrowCtx<'hir> {
     pub fn new(hir: &'hir hir::Block) -> Self {
         Self {
@@ -57,12 +166,36 @@ impl<'hir> BorrowCtx<'hir> {
             errors: Vec::new(),
             hir,
             next_borrow_id: 0,
+            names: HashMap::new(),
+            partial: HashSet::new(),
+            closure_borrows: HashSet::new(),
+            captured: HashSet::new(),
+            borrowed: HashSet::new(),
+            tail_moves: false,
+            loans: HashMap::new(),
+            ended: HashMap::new(),
+            continued: Moves::default(),
+            broken: Moves::default(),
         }
     }
 
+    fn report(&mut self, kind: BorrowErrorKind, id: VarId) {
+        self.errors.push(BorrowError {
+            code: kind.code(),
+            name: self.names.get(&id).cloned().unwrap_or_default(),
+            kind,
+            span: Span::default(),
+            prev_span: Span::default(),
+        });
+    }
+
     pub fn check(mut self) -> Vec<BorrowError> {
-        for stmt in &self.hir.stmts {
-            self.check_stmt(stmt);
+        let last = self.hir.stmts.len().saturating_sub(1);
+        for (i, stmt) in self.hir.stmts.iter().enumerate() {
+            match stmt {
+                Stmt::Expr(e) if i == last && self.tail_moves => self.check_expr(e, true),
+                _ => self.check_stmt(stmt),
+            }
             self.cleanup();
         }
         self.errors
@@ -72,20 +205,30 @@ impl<'hir> BorrowCtx<'hir> {
         match stmt {
             Stmt::Let(l) => {
                 self.check_expr(&l.init, true);
+                self.names.insert(l.id, l.name.clone());
                 self.states.insert(l.id, BorrowState::Live);
+                self.lend(l.id, &l.init);
+            }
+            Stmt::Decl { id, name, .. } => {
+                self.names.insert(*id, name.clone());
+                self.states.insert(*id, BorrowState::Live);
             }
             Stmt::Assign { id, expr, .. } => {
                 if matches!(self.states.get(id), Some(BorrowState::MutBorrowed(_))) {
-                    let kind = BorrowErrorKind::AssignWhileBorrowed;
-                    self.errors.push(BorrowError {
-                        code: kind.code(),
-                        kind,
-                        span: Span::default(),
-                        prev_span: Span::default(),
-                    });
+                    self.report(BorrowErrorKind::AssignWhileBorrowed, *id);
                 }
                 self.check_expr(expr, true);
+                self.end_loans(*id, BorrowErrorKind::AssignWhileBorrowedRef);
                 self.states.insert(*id, BorrowState::Live);
+                self.partial.retain(|&(var, _)| var != *id);
+                self.lend(*id, expr);
+            }
+            Stmt::AssignPlace { target, expr } => {
+                self.check_expr(expr, true);
+                self.check_expr(target, false);
+                if let Some(root) = place_root(target) {
+                    self.end_loans(root, BorrowErrorKind::AssignWhileBorrowedRef);
+                }
             }
             Stmt::Expr(e) | Stmt::Semi(e) => {
                 self.check_expr(e, false);
@@ -95,14 +238,24 @@ impl<'hir> BorrowCtx<'hir> {
                     self.check_expr(e, true);
                 }
             }
+            Stmt::Break => {
+                let moves = self.moves();
+                extend(&mut self.broken, moves);
+            }
+            Stmt::Continue => {
+                let moves = self.moves();
+                extend(&mut self.continued, moves);
+            }
         }
     }
 
     fn check_expr(&mut self, expr: &Expr, move_ctx: bool) {
         use Expr::*;
         match expr {
-            Ident { id, ty, .. } => {
-                if move_ctx && !ty.is_copy() {
+            Ident { id, name, ty } => {
+                self.names.entry(*id).or_insert_with(|| name.clone());
+                // the `rx` of a channel is bound to the same object as `tx`
+                if move_ctx && !ty.is_copy() && !matches!(ty, Type::Receiver(_)) {
                     self.move_var(*id);
                 } else {
                     self.use_var(*id);
@@ -115,79 +268,274 @@ impl<'hir> BorrowCtx<'hir> {
             Unary { rhs, .. } => {
                 self.check_expr(rhs, false);
             }
-            Call { callee, args, .. } => {
+            // builtins only read their arguments, except the value that
+            // `send` and `push` take over; `push`/`pop` borrow the `Vec`
+            Call { callee, args, .. } if matches!(**callee, Builtin { .. }) => {
+                let Builtin { kind, .. } = **callee else { unreachable!() };
+                for (i, a) in args.iter().enumerate() {
+                    match (kind, i, a) {
+                        (hir::Builtin::Push | hir::Builtin::Pop, 0, Ident { id, name, .. }) => {
+                            self.names.entry(*id).or_insert_with(|| name.clone());
+                            self.borrow_var(*id);
+                        }
+                        (hir::Builtin::Send | hir::Builtin::Push, 1, _) => self.check_expr(a, true),
+                        _ => self.check_expr(a, false),
+                    }
+                }
+            }
+            Call { callee, args, .. } | Spawn { callee, args, .. } => {
                 self.check_expr(callee, false);
                 for a in args {
-                    self.check_expr(a, true);
+                    self.check_expr(a, !matches!(a.ty(), Type::Ref { .. }));
+                }
+            }
+            Block { block, .. } => self.check_block(block, move_ctx),
+            If {
+                cond,
+                then_blk,
+                else_branch,
+                ..
+            } => {
+                self.check_expr(cond, false);
+                // only one branch runs: each starts from the same state, and
+                // a value moved in either is moved afterwards
+                // a branch that leaves by `return`, `break` or `continue` moves
+                // nothing for the code after the `if`
+                let before = (self.states.clone(), self.partial.clone());
+                self.check_block(then_blk, move_ctx);
+                let then_diverges = then_blk.diverges();
+                match else_branch {
+                    Some(e) => {
+                        let after_then = std::mem::replace(&mut self.states, before.0);
+                        let partial_then = std::mem::replace(&mut self.partial, before.1);
+                        self.check_expr(e, move_ctx);
+                        if e.diverges() && !then_diverges {
+                            self.states = after_then;
+                            self.partial = partial_then;
+                        } else if !then_diverges {
+                            for (id, state) in after_then {
+                                if state == BorrowState::Moved {
+                                    self.states.insert(id, state);
+                                }
+                            }
+                            self.partial.extend(partial_then);
+                        }
+                    }
+                    None if then_diverges => (self.states, self.partial) = before,
+                    None => {}
+                }
+            }
+            While { cond, body, .. } => {
+                let outer = (std::mem::take(&mut self.continued), std::mem::take(&mut self.broken));
+                let before = (self.states.clone(), self.partial.clone());
+                self.check_expr(cond, false);
+                self.check_block(body, false);
+                // the next iteration starts from the end of the body, if it
+                // is reached, or a `continue`; errors the first pass found
+                // aren't repeated
+                if body.diverges() {
+                    (self.states, self.partial) = before;
+                }
+                let continued = std::mem::take(&mut self.continued);
+                self.apply(continued);
+                let seen = self.errors.len();
+                self.check_expr(cond, false);
+                self.check_block(body, false);
+                let again = self.errors.split_off(seen);
+                for e in again {
+                    if !self.errors.iter().any(|f| f.kind == e.kind && f.name == e.name) {
+                        self.errors.push(e);
+                    }
+                }
+                let broken = std::mem::replace(&mut self.broken, outer.1);
+                self.apply(broken);
+                self.continued = outer.0;
+            }
+            Array { elems, .. } | Tuple { elems, .. } => {
+                for e in elems {
+                    self.check_expr(e, true);
+                }
+            }
+            Field { base, ty, .. } => match field_root(expr) {
+                Some((id, name, index)) => {
+                    self.names.entry(id).or_insert_with(|| name.to_string());
+                    self.use_field(id, index, move_ctx && !ty.is_copy());
+                }
+                // a copied field doesn't move the value it is read from
+                None => self.check_expr(base, move_ctx && !ty.is_copy()),
+            },
+            Repeat { elem, .. } => self.check_expr(elem, true),
+            Ref { expr, mutable, .. } => match &**expr {
+                Ident { id, name, .. } => {
+                    self.names.entry(*id).or_insert_with(|| name.clone());
+                    if *mutable {
+                        self.borrow_var(*id);
+                    } else {
+                        self.use_var(*id);
+                    }
+                }
+                e => self.check_expr(e, false),
+            },
+            Deref { expr: inner, ty, .. } => {
+                if move_ctx && !ty.is_copy() {
+                    let name = match &**inner {
+                        Ident { name, .. } => format!("*{name}"),
+                        _ => "*_".to_string(),
+                    };
+                    let kind = BorrowErrorKind::MoveOutOfRef;
+                    let (span, prev_span) = (Span::default(), Span::default());
+                    self.errors.push(BorrowError { code: kind.code(), kind, name, span, prev_span });
                 }
+                self.check_expr(inner, false);
+            }
+            ToDyn { expr: inner, .. } => self.check_expr(inner, move_ctx),
+            Variant { value, .. } => value.iter().for_each(|v| self.check_expr(v, true)),
+            // `e?` takes over `e`, whichever way it goes
+            Try { expr: inner, .. } => self.check_expr(inner, true),
+            Index { base, index, .. } => {
+                self.check_expr(base, false);
+                self.check_expr(index, false);
+            }
+            Closure { body, captures, by_move, .. } => {
+                for c in captures {
+                    self.names.entry(c.id).or_insert_with(|| c.name.clone());
+                    if *by_move && !c.ty.is_copy() {
+                        self.move_var(c.id);
+                    } else {
+                        self.use_var(c.id);
+                    }
+                    if !by_move {
+                        self.closure_borrows.insert(c.id);
+                    }
+                }
+                let mut inner = BorrowCtx::new(body);
+                inner.names = self.names.clone();
+                inner.borrowed = self.borrowed.clone();
+                inner.captured = captures.iter().map(|c| c.id).collect();
+                self.errors.extend(inner.check());
             }
             _ => {}
         }
     }
 
-    pub fn use_var(&mut self, id: VarId) {
-        if let Some(state) = self.states.get(&id) {
-            if let BorrowState::Moved = state {
-                self.errors.push(BorrowError {
-                    code: BorrowErrorKind::UseAfterMove.code(),
-                    kind: BorrowErrorKind::UseAfterMove,
-                    span: Span::default(),
-                    prev_span: Span::default(),
-                });
+    /// The tail value goes wherever the block's value goes, so it is moved
+    /// when `move_ctx` is.
+    fn check_block(&mut self, block: &hir::Block, move_ctx: bool) {
+        let last = block.stmts.len().saturating_sub(1);
+        for (i, stmt) in block.stmts.iter().enumerate() {
+            match stmt {
+                Stmt::Expr(e) if i == last => self.check_expr(e, move_ctx),
+                _ => self.check_stmt(stmt),
+            }
+            self.cleanup();
+        }
+    }
+
+    fn partly_moved(&self, id: VarId) -> bool {
+        self.partial.iter().any(|&(var, _)| var == id)
+    }
+
+    /// `let r = &v` or `&mut v`, or `r = ...`: `r` holds a loan of `v` or
+    /// none. A reference copied from another holds the same loan.
+    fn lend(&mut self, id: VarId, init: &Expr) {
+        self.ended.remove(&id);
+        let loan = match init {
+            Expr::Ref { expr, mutable, .. } => match &**expr {
+                Expr::Ident { id: target, .. } => Some((*target, *mutable)),
+                _ => None,
+            },
+            Expr::Ident { id: src, .. } => self.loans.get(src).copied(),
+            _ => None,
+        };
+        match loan {
+            Some(loan) => self.loans.insert(id, loan),
+            None => self.loans.remove(&id),
+        };
+    }
+
+    /// `target` is moved, assigned or mutably borrowed: its loans end. A
+    /// read ends only the `&mut` ones.
+    fn end_loans(&mut self, target: VarId, kind: BorrowErrorKind) {
+        let read = kind == BorrowErrorKind::UseWhileMutBorrowed;
+        for (&r, &(t, mutable)) in &self.loans {
+            if t == target && (mutable || !read) {
+                self.ended.entry(r).or_insert((kind.clone(), target));
             }
         }
     }
 
+    /// A use of `id`; if it is a reference whose loan ended, the conflict
+    /// that ended it is an error.
+    fn use_ref(&mut self, id: VarId) {
+        if let Some((kind, target)) = self.ended.remove(&id) {
+            self.report(kind, target);
+        }
+    }
+
+    fn moves(&self) -> Moves {
+        let vars = self.states.iter().filter(|(_, s)| **s == BorrowState::Moved).map(|(id, _)| *id);
+        (vars.collect(), self.partial.clone())
+    }
+
+    fn apply(&mut self, (vars, fields): Moves) {
+        for id in vars {
+            self.states.insert(id, BorrowState::Moved);
+        }
+        self.partial.extend(fields);
+    }
+
+    pub fn use_var(&mut self, id: VarId) {
+        self.use_ref(id);
+        self.end_loans(id, BorrowErrorKind::UseWhileMutBorrowed);
+        if self.states.get(&id) == Some(&BorrowState::Moved) || self.partly_moved(id) {
+            self.report(BorrowErrorKind::UseAfterMove, id);
+        }
+    }
+
+    /// Reads or moves field `index` of tuple variable `id`.
+    pub fn use_field(&mut self, id: VarId, index: usize, moves: bool) {
+        self.use_ref(id);
+        let kind = if moves { BorrowErrorKind::MoveWhileBorrowed } else { BorrowErrorKind::UseWhileMutBorrowed };
+        self.end_loans(id, kind);
+        if let Some(BorrowState::Moved) = self.states.get(&id) {
+            self.report(BorrowErrorKind::UseAfterMove, id);
+        } else if self.partial.contains(&(id, index)) {
+            let kind = if moves { BorrowErrorKind::DoubleMove } else { BorrowErrorKind::UseAfterMove };
+            self.report(kind, id);
+        }
+        if moves && self.captured.contains(&id) {
+            self.report(BorrowErrorKind::MoveOutOfClosure, id);
+        }
+        if moves && self.borrowed.contains(&id) {
+            self.report(BorrowErrorKind::MoveOutOfRef, id);
+        }
+        if moves {
+            self.partial.insert((id, index));
+        }
+    }
+
     pub fn move_var(&mut self, id: VarId) {
-        if let Some(state) = self.states.get(&id) {
-            match state {
-                BorrowState::MutBorrowed(_) => {
-                    let kind = BorrowErrorKind::AssignWhileBorrowed;
-                    self.errors.push(BorrowError {
-                        code: kind.code(),
-                        kind,
-                        span: Span::default(),
-                        prev_span: Span::default(),
-                    })
-                }
-                BorrowState::Moved => {
-                    let kind = BorrowErrorKind::DoubleMove;
-                    self.errors.push(BorrowError {
-                        code: kind.code(),
-                        kind,
-                        span: Span::default(),
-                        prev_span: Span::default(),
-                    })
-                }
-                _ => {}
-            }
+        self.use_ref(id);
+        self.end_loans(id, BorrowErrorKind::MoveWhileBorrowed);
+        match self.states.get(&id) {
+            Some(BorrowState::MutBorrowed(_)) => self.report(BorrowErrorKind::AssignWhileBorrowed, id),
+            Some(BorrowState::Moved) => self.report(BorrowErrorKind::DoubleMove, id),
+            _ if self.partly_moved(id) => self.report(BorrowErrorKind::UseAfterMove, id),
+            _ if self.borrowed.contains(&id) => self.report(BorrowErrorKind::MoveOutOfRef, id),
+            _ if self.captured.contains(&id) => self.report(BorrowErrorKind::MoveOutOfClosure, id),
+            _ if self.closure_borrows.contains(&id) => self.report(BorrowErrorKind::MoveWhileCaptured, id),
+            _ => {}
         }
         self.states.insert(id, BorrowState::Moved);
     }
 
     pub fn borrow_var(&mut self, id: VarId) {
-        if let Some(state) = self.states.get(&id) {
-            match state {
-                BorrowState::MutBorrowed(_) => {
-                    let kind = BorrowErrorKind::SecondMutBorrow;
-                    self.errors.push(BorrowError {
-                        code: kind.code(),
-                        kind,
-                        span: Span::default(),
-                        prev_span: Span::default(),
-                    });
-                }
-                BorrowState::Moved => {
-                    let kind = BorrowErrorKind::UseAfterMove;
-                    self.errors.push(BorrowError {
-                        code: kind.code(),
-                        kind,
-                        span: Span::default(),
-                        prev_span: Span::default(),
-                    });
-                }
-                _ => {}
-            }
+        self.use_ref(id);
+        self.end_loans(id, BorrowErrorKind::MutBorrowWhileBorrowed);
+        match self.states.get(&id) {
+            Some(BorrowState::MutBorrowed(_)) => self.report(BorrowErrorKind::SecondMutBorrow, id),
+            Some(BorrowState::Moved) => self.report(BorrowErrorKind::UseAfterMove, id),
+            _ => {}
         }
         self.states.insert(id, BorrowState::MutBorrowed(self.next_borrow_id));
         self.next_borrow_id += 1;
@@ -202,10 +550,45 @@ impl<'hir> BorrowCtx<'hir> {
     }
 }
 
+/// The variable a chain of field accesses starts from, with the index of the
+/// outermost field: `t.1.0` gives `(t, 1)`.
+fn field_root(e: &Expr) -> Option<(VarId, &str, usize)> {
+    let Expr::Field { base, index, .. } = e else { return None };
+    match &**base {
+        Expr::Ident { id, name, .. } => Some((*id, name, *index)),
+        base => field_root(base),
+    }
+}
+
+/// The variable a place `a[i].0` or `*r` is rooted at.
+fn place_root(e: &Expr) -> Option<VarId> {
+    match e {
+        Expr::Ident { id, .. } => Some(*id),
+        Expr::Field { base, .. } | Expr::Index { base, .. } => place_root(base),
+        _ => None,
+    }
+}
+
+fn extend(into: &mut Moves, (vars, fields): Moves) {
+    into.0.extend(vars);
+    into.1.extend(fields);
+}
+
 pub fn check_fn_body(body: &hir::Block) -> Vec<BorrowError> {
     BorrowCtx::new(body).check()
 }
 
+/// `check_fn_body`, knowing that `self` of a `&self` method is borrowed.
+pub fn check_fn(f: &hir::HirFn) -> Vec<BorrowError> {
+    let mut cx = BorrowCtx::new(&f.body);
+    if matches!(f.self_mode, Some(hir::SelfMode::Ref | hir::SelfMode::RefMut)) {
+        cx.borrowed.insert(f.params[0].id);
+        cx.names.insert(f.params[0].id, "self".to_string());
+    }
+    cx.tail_moves = f.return_ty != Type::Unit;
+    cx.check()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,55 +1,335 @@
 //! borrowck.rs – минимални borrow-checker α
-//! Правило: име се сме поново дефинисати у истом блоку
-//!          само ако је претходна дефиниција имала `mutable: true`.
+//! Правило: свако `let` уводи ново везивање (shadowing), а додела је
+//!          дозвољена само везивању декларисаном са `mut`. Везивања се
+//!          прате по NodeId-у, па сенчење никад не мења претходно везивање.
+//! Премештања и позајмице `Vec`-а и осталих не-Copy вредности проверава
+//!          `borrow::BorrowCtx` за свако тело функције.
+//! Животне векове референци проверава `region::check_fn`.
+//! `let x: T;` без `mut` сме да се додели, а да је то тачно једном и пре
+//!          сваке употребе проверава `cfg::check_body`.
+//! `spawn`: у нову нит не сме да пређе позајмљена вредност ни функција
+//!          која (и посредно) дира променљиву глобалну; `Receiver` се
+//!          премешта у нит и после тога се не сме користити.
 
-use crate::{hir, resolver::ResolveError};
 use crate::lexer::Span;
-use std::collections::HashMap;
+use crate::resolver::{FileError, ResolveError};
+use crate::type_::Type;
+use crate::{borrow, hir, region};
+use std::collections::{HashMap, HashSet};
 
 /*────────── јавни улаз ──────────*/
 pub fn borrow_check(m: &hir::HirModule) -> Vec<ResolveError> {
+    check_module(m).into_iter().map(|e| e.err).collect()
+}
+
+/// Као `borrow_check`, али свака грешка носи фајл функције у којој је.
+pub fn check_module(m: &hir::HirModule) -> Vec<FileError> {
     let mut errs = Vec::new();
+
+    // глобална везивања су видљива у свим функцијама
+    let mut globals = HashMap::new();
+    for it in &m.items {
+        if let hir::Item::Let(g) = it {
+            globals.insert(g.id, g.mutable);
+        }
+    }
+    let racy = racy_fns(m);
+
     for it in &m.items {
         if let hir::Item::Fn(f) = it {
-            check_block(&f.body, &mut errs, &mut HashMap::new());
+            let mut cx = FnCheck {
+                errs: Vec::new(),
+                defined: globals.clone(),
+                racy: &racy,
+                receivers: HashMap::new(),
+                sent: HashSet::new(),
+                deferred: HashSet::new(),
+                loop_depth: 0,
+            };
+            for p in &f.params {
+                if matches!(p.ty, Type::Receiver(_)) {
+                    cx.receivers.insert(p.id, 0);
+                }
+            }
+            // `&mut self` сме да мења вредност позиваоца
+            if f.self_mode == Some(hir::SelfMode::RefMut) {
+                cx.defined.insert(f.params[0].id, true);
+            }
+            cx.check_block(&f.body);
+            errs.extend(cx.errs.into_iter().map(|err| FileError { file: f.file, err }));
+            errs.extend(borrow::check_fn(f).into_iter().map(|e| FileError {
+                file: f.file,
+                err: ResolveError { span: e.span, msg: e.msg() },
+            }));
+            errs.extend(region::check_fn(f).into_iter().map(|err| FileError { file: f.file, err }));
         }
     }
     errs
 }
 
-/*────────── рекурзивна провера блока ──────────*/
-fn check_block(
-    blk: &hir::Block,
-    errs: &mut Vec<ResolveError>,
-    defined: &mut HashMap<String, bool>, // name → mutable?
-) {
-    for st in &blk.stmts {
-        match st {
-            hir::Stmt::Let(l) => {
-                match defined.get(&l.name) {
-                    Some(prev_mut) if !prev_mut => {
-                        // већ постоји immutable – грешка
-                        errs.push(ResolveError {
-                            span: Span { start: 0, end: 0, line: 0, column: 0 },
-                            msg:  format!("cannot reassign immutable binding `{}`", l.name),
-                        });
+/*────────── провера једне функције ──────────*/
+struct FnCheck<'a> {
+    errs: Vec<ResolveError>,
+    defined: HashMap<hir::NodeId, bool>, // id → mutable?
+    /// функције које дирају променљиву глобалну → име те глобалне
+    racy: &'a HashMap<hir::NodeId, String>,
+    /// `Receiver` везивања → дубина петље у којој су уведена
+    receivers: HashMap<hir::NodeId, u32>,
+    /// пријемници већ премештени у неку нит
+    sent: HashSet<hir::NodeId>,
+    /// непроменљива `let x: T;` везивања; да је додела једна проверава `cfg`
+    deferred: HashSet<hir::NodeId>,
+    loop_depth: u32,
+}
+
+impl FnCheck<'_> {
+    fn error(&mut self, msg: String) {
+        self.errs.push(ResolveError { span: Span::default(), msg });
+    }
+
+    fn check_block(&mut self, blk: &hir::Block) {
+        for st in &blk.stmts {
+            match st {
+                hir::Stmt::Let(l) => {
+                    self.check_expr(&l.init);
+                    self.defined.insert(l.id, l.mutable);
+                    if matches!(l.ty, Type::Receiver(_)) {
+                        self.receivers.insert(l.id, self.loop_depth);
+                    }
+                }
+                hir::Stmt::Decl { id, mutable, .. } => {
+                    self.defined.insert(*id, *mutable);
+                    if !mutable {
+                        self.deferred.insert(*id);
                     }
-                    _ => {
-                        // уносимо/ажурирамо запис
-                        defined.insert(l.name.clone(), l.mutable);
+                }
+                hir::Stmt::Assign { id, name, expr } => {
+                    self.check_expr(expr);
+                    if self.defined.get(id) != Some(&true) && !self.deferred.contains(id) {
+                        self.error(format!("cannot reassign immutable binding `{}`", name));
                     }
-                };
+                }
+                hir::Stmt::AssignPlace { target, expr } => {
+                    self.check_expr(expr);
+                    self.check_expr(target);
+                    if let (hir::Expr::Ident { id, name, .. }, _) = target.place_chain()
+                        && self.defined.get(id) != Some(&true)
+                    {
+                        let what = if matches!(target, hir::Expr::Field { .. }) { "a field" } else { "an element" };
+                        self.error(format!("cannot assign to {what} of immutable binding `{name}`"));
+                    }
+                }
+                hir::Stmt::Expr(e) | hir::Stmt::Semi(e) | hir::Stmt::Return(Some(e)) => {
+                    self.check_expr(e)
+                }
+                hir::Stmt::Return(None) | hir::Stmt::Break | hir::Stmt::Continue => {}
+            }
+        }
+    }
+
+    /// Силази у угњеждене блокове унутар израза.
+    fn check_expr(&mut self, e: &hir::Expr) {
+        use hir::Expr::*;
+        match e {
+            Block { block, .. } => self.check_block(block),
+            If {
+                cond,
+                then_blk,
+                else_branch,
+                ..
+            } => {
+                self.check_expr(cond);
+                self.check_block(then_blk);
+                if let Some(e) = else_branch {
+                    self.check_expr(e);
+                }
+            }
+            While { cond, body, .. } => {
+                self.check_expr(cond);
+                self.loop_depth += 1;
+                self.check_block(body);
+                self.loop_depth -= 1;
+            }
+            Call { callee, args, .. } => {
+                self.check_expr(callee);
+                for a in args {
+                    self.check_expr(a);
+                }
+            }
+            Spawn { callee, args, .. } => self.check_spawn(callee, args),
+            Unary { rhs, .. } => self.check_expr(rhs),
+            Binary { lhs, rhs, .. } | Index { base: lhs, index: rhs, .. } => {
+                self.check_expr(lhs);
+                self.check_expr(rhs);
+            }
+            Array { elems, .. } | Tuple { elems, .. } => elems.iter().for_each(|e| self.check_expr(e)),
+            Repeat { elem, .. }
+            | Field { base: elem, .. }
+            | Ref { expr: elem, .. }
+            | Deref { expr: elem, .. }
+            | ToDyn { expr: elem, .. }
+            | Try { expr: elem, .. } => self.check_expr(elem),
+            Variant { value, .. } => value.iter().for_each(|e| self.check_expr(e)),
+            Ident { id, name, .. } => {
+                if self.sent.contains(id) {
+                    self.error(format!(
+                        "use of receiver `{name}` after it was moved into a spawned thread"
+                    ));
+                }
+            }
+            // telo je nova funkcija: `break` ne izlazi iz spoljne petlje
+            Closure { params, body, .. } => {
+                for p in params {
+                    self.defined.insert(p.id, false);
+                }
+                let depth = std::mem::take(&mut self.loop_depth);
+                self.check_block(body);
+                self.loop_depth = depth;
             }
-            hir::Stmt::Assign { name, .. } => {
-                match defined.get(name) {
-                    Some(true) => {},
-                    _ => errs.push(ResolveError {
-                        span: Span { start: 0, end: 0, line: 0, column: 0 },
-                        msg: format!("cannot reassign immutable binding `{}`", name),
-                    }),
+            Int { .. } | Float { .. } | Bool { .. } | Unit { .. } | Str { .. } | Builtin { .. }
+            | Channel { .. } | VecNew { .. } | FnRef { .. } | MethodRef { .. } | DynMethod { .. } => {}
+        }
+    }
+
+    /// Аргументи прелазе у нову нит: позајмице су забрањене, а `Receiver`
+    /// се премешта.
+    fn check_spawn(&mut self, callee: &hir::Expr, args: &[hir::Expr]) {
+        if let hir::Expr::Ident { id, name, .. } = callee
+            && let Some(global) = self.racy.get(id)
+        {
+            self.error(format!("cannot spawn `{name}`: it accesses mutable global `{global}`"));
+        }
+        for a in args {
+            self.check_expr(a);
+            if a.ty().holds_ref() {
+                self.error("borrowed value cannot be sent to a spawned thread".to_string());
+            }
+            if let hir::Expr::Ident { id, name, ty: Type::Receiver(_) } = a {
+                if self.receivers.get(id).is_some_and(|&depth| depth < self.loop_depth) {
+                    self.error(format!(
+                        "receiver `{name}` is moved into a spawned thread inside a loop"
+                    ));
                 }
+                self.sent.insert(*id);
+            }
+        }
+    }
+}
+
+/*────────── функције које дирају променљиве глобалне ──────────*/
+/// Функција је „racy" ако чита или мења `let mut` глобалну, директно или
+/// преко функције коју позива.
+fn racy_fns(m: &hir::HirModule) -> HashMap<hir::NodeId, String> {
+    let mut_globals: HashMap<hir::NodeId, &str> = m
+        .items
+        .iter()
+        .filter_map(|it| match it {
+            hir::Item::Let(g) if g.mutable => Some((g.id, g.name.as_str())),
+            _ => None,
+        })
+        .collect();
+
+    let mut racy = HashMap::new();
+    let mut calls = Vec::new();
+    for it in &m.items {
+        if let hir::Item::Fn(f) = it {
+            let mut refs = Vec::new();
+            block_refs(&f.body, &mut refs);
+            if let Some(g) = refs.iter().find_map(|id| mut_globals.get(id)) {
+                racy.insert(f.id, g.to_string());
+            }
+            calls.push((f.id, refs));
+        }
+    }
+    // позив методе особине може стићи до сваке њене имплементације
+    for imp in &m.impls {
+        calls.push((imp.trait_id, imp.methods.iter().map(|m| m.fn_id).collect()));
+    }
+
+    // ширење уназад кроз позиве до фиксне тачке
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (f, refs) in &calls {
+            if racy.contains_key(f) {
+                continue;
             }
-            hir::Stmt::Expr(_) | hir::Stmt::Semi(_) | hir::Stmt::Return(_) => {}
+            if let Some(g) = refs.iter().find_map(|id| racy.get(id)).cloned() {
+                racy.insert(*f, g);
+                changed = true;
+            }
+        }
+    }
+    racy
+}
+
+/// Сва имена (NodeId) која блок чита или мења, без тела нових нити.
+fn block_refs(b: &hir::Block, out: &mut Vec<hir::NodeId>) {
+    for st in &b.stmts {
+        match st {
+            hir::Stmt::Let(l) => expr_refs(&l.init, out),
+            hir::Stmt::Assign { id, expr, .. } => {
+                out.push(*id);
+                expr_refs(expr, out);
+            }
+            hir::Stmt::AssignPlace { target, expr } => {
+                expr_refs(target, out);
+                expr_refs(expr, out);
+            }
+            hir::Stmt::Expr(e) | hir::Stmt::Semi(e) | hir::Stmt::Return(Some(e)) => {
+                expr_refs(e, out)
+            }
+            hir::Stmt::Decl { .. } | hir::Stmt::Return(None) | hir::Stmt::Break | hir::Stmt::Continue => {}
+        }
+    }
+}
+
+fn expr_refs(e: &hir::Expr, out: &mut Vec<hir::NodeId>) {
+    use hir::Expr::*;
+    match e {
+        Ident { id, .. } => out.push(*id),
+        Block { block, .. } => block_refs(block, out),
+        If {
+            cond,
+            then_blk,
+            else_branch,
+            ..
+        } => {
+            expr_refs(cond, out);
+            block_refs(then_blk, out);
+            if let Some(e) = else_branch {
+                expr_refs(e, out);
+            }
+        }
+        While { cond, body, .. } => {
+            expr_refs(cond, out);
+            block_refs(body, out);
+        }
+        Call { callee, args, .. } => {
+            expr_refs(callee, out);
+            args.iter().for_each(|a| expr_refs(a, out));
+        }
+        // спавнована функција се проверава на свом `spawn`-у
+        Spawn { args, .. } => args.iter().for_each(|a| expr_refs(a, out)),
+        Unary { rhs, .. } => expr_refs(rhs, out),
+        Binary { lhs, rhs, .. } | Index { base: lhs, index: rhs, .. } => {
+            expr_refs(lhs, out);
+            expr_refs(rhs, out);
         }
+        Array { elems, .. } | Tuple { elems, .. } => elems.iter().for_each(|e| expr_refs(e, out)),
+        Repeat { elem, .. }
+        | Field { base: elem, .. }
+        | Ref { expr: elem, .. }
+        | Deref { expr: elem, .. }
+        | ToDyn { expr: elem, .. }
+        | Try { expr: elem, .. } => expr_refs(elem, out),
+        Variant { value, .. } => value.iter().for_each(|e| expr_refs(e, out)),
+        // funkcija kao vrednost može biti pozvana bilo gde
+        FnRef { fn_id, .. } => out.push(*fn_id),
+        // metoda osobine stoji za sve njene implementacije, vidi `racy_fns`
+        MethodRef { trait_id, .. } | DynMethod { trait_id, .. } => out.push(*trait_id),
+        Closure { body, .. } => block_refs(body, out),
+        Int { .. } | Float { .. } | Bool { .. } | Unit { .. } | Str { .. } | Builtin { .. }
+        | Channel { .. } | VecNew { .. } => {}
     }
 }
@@ -0,0 +1,328 @@
+//! cfg.rs – provere nad MIR grafom kontrole toka
+//! * funkcija koja vraća vrednost mora da dodeli `RET_TEMP` na svakom
+//!   putu do `return` terminatora (nedostajući `return`)
+//! * naredbe u blokovima do kojih nijedna grana ne vodi su upozorenja
+//!   (nedostižan kod), na prvoj takvoj naredbi
+//! * promenljiva iz `let x: T;` mora biti dodeljena pre svake upotrebe na
+//!   svakom putu, a ona bez `mut` najviše jednom
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::mir::{self, MirBody, MirType, Operand, Rvalue, Statement, Terminator};
+use crate::{hir, resolver::ResolveError};
+
+#[derive(Debug, Default)]
+pub struct CfgDiagnostics {
+    pub errors: Vec<ResolveError>,
+    pub warnings: Vec<ResolveError>,
+}
+
+/*────────── javni ulaz ──────────*/
+pub fn check_module(m: &hir::HirModule) -> CfgDiagnostics {
+    let mut diags = CfgDiagnostics::default();
+    for it in &m.items {
+        if let hir::Item::Fn(f) = it {
+            // zatvorenja su posebne funkcije `f.closureN`
+            for f in mir::lower_fn_and_closures(f) {
+                check_body(&f.name, &f.body, &mut diags);
+            }
+        }
+    }
+    diags
+}
+
+pub fn check_body(name: &str, body: &MirBody, diags: &mut CfgDiagnostics) {
+    let reachable = reachable_blocks(body);
+
+    // samo blokovi u koje je spušten izvorni kod; spoj posle `if` čije se
+    // obe grane vraćaju ima samo naredbe koje je dodao MIR
+    for bb in (0..body.blocks.len()).filter(|&bb| !reachable[bb]) {
+        if let Some(&span) = body.stmt_spans.get(&(bb as mir::BlockId)) {
+            diags.warnings.push(ResolveError {
+                span,
+                msg: format!("unreachable statement in function `{}`", name),
+            });
+            // jedno upozorenje po funkciji je dovoljno
+            break;
+        }
+    }
+
+    check_init(name, body, &reachable, diags);
+
+    if !matches!(body.ret_ty, MirType::Unit) && !always_returns_value(body, &reachable) {
+        diags.errors.push(ResolveError {
+            span: body.span,
+            msg: format!(
+                "function `{}` may reach its end without a value: expected {:?}, got ()",
+                name, body.ret_ty
+            ),
+        });
+    }
+}
+
+/*────────── pomoćne analize ──────────*/
+fn successors(term: &Terminator) -> Vec<mir::BlockId> {
+    match term {
+        Terminator::Return => Vec::new(),
+        Terminator::Goto(bb) => vec![*bb],
+        Terminator::CondBranch { then_bb, else_bb, .. } => vec![*then_bb, *else_bb],
+    }
+}
+
+/// Blokovi dostižni iz ulaznog bloka `bb0`.
+fn reachable_blocks(body: &MirBody) -> Vec<bool> {
+    let mut seen = vec![false; body.blocks.len()];
+    let mut work = vec![0 as mir::BlockId];
+    while let Some(bb) = work.pop() {
+        if std::mem::replace(&mut seen[bb as usize], true) {
+            continue;
+        }
+        work.extend(successors(&body.blocks[bb as usize].term));
+    }
+    seen
+}
+
+fn assigns_ret(block: &mir::BasicBlock) -> bool {
+    block
+        .stmts
+        .iter()
+        .any(|s| matches!(s, Statement::Assign { dst, .. } if *dst == mir::RET_TEMP))
+}
+
+/// Prethodnici svakog bloka, samo preko dostižnih blokova.
+fn predecessors(body: &MirBody, reachable: &[bool]) -> Vec<Vec<usize>> {
+    let mut preds = vec![Vec::new(); body.blocks.len()];
+    for (bb, block) in body.blocks.iter().enumerate() {
+        if reachable[bb] {
+            for s in successors(&block.term) {
+                preds[s as usize].push(bb);
+            }
+        }
+    }
+    preds
+}
+
+/// „Must" analiza unapred: da li je `RET_TEMP` sigurno dodeljen na izlazu
+/// svakog dostižnog bloka koji se završava sa `return`.
+fn always_returns_value(body: &MirBody, reachable: &[bool]) -> bool {
+    let n = body.blocks.len();
+    let preds = predecessors(body, reachable);
+
+    // optimistički start (true) pa se spušta do fiksne tačke
+    let mut out = vec![true; n];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for bb in (0..n).filter(|&bb| reachable[bb]) {
+            let entry = bb != 0 && preds[bb].iter().all(|&p| out[p]);
+            let exit = entry || assigns_ret(&body.blocks[bb]);
+            if exit != out[bb] {
+                out[bb] = exit;
+                changed = true;
+            }
+        }
+    }
+
+    body.blocks
+        .iter()
+        .enumerate()
+        .filter(|(bb, b)| reachable[*bb] && matches!(b.term, Terminator::Return))
+        .all(|(bb, _)| out[bb])
+}
+
+/*────────── odložena inicijalizacija ──────────*/
+/// Svaka upotreba promenljive iz `let x: T;` mora biti iza dodele na svakom
+/// putu od deklaracije; greška navodi put na kom dodele nema. Promenljiva
+/// bez `mut` ne sme da se dodeli ako na nekom putu već ima vrednost.
+fn check_init(name: &str, body: &MirBody, reachable: &[bool], diags: &mut CfgDiagnostics) {
+    let preds = predecessors(body, reachable);
+    let mut deferred: Vec<_> = body.deferred.iter().collect();
+    deferred.sort_by_key(|(var, _)| **var);
+
+    for (&var, (var_name, mutable)) in deferred {
+        let uninit = may_be(body, reachable, &preds, var, false);
+        let init = may_be(body, reachable, &preds, var, true);
+        // jedna greška po promenljivoj; petlja bi je inače ponavljala
+        'blocks: for (bb, block) in body.blocks.iter().enumerate().filter(|(bb, _)| reachable[*bb]) {
+            let mut u = preds[bb].iter().any(|&p| uninit[p]);
+            let mut i = preds[bb].iter().any(|&p| init[p]);
+            let cond = match &block.term {
+                Terminator::CondBranch { cond, .. } => Some(cond),
+                _ => None,
+            };
+            for s in block.stmts.iter().map(Some).chain([None]) {
+                let used = match s {
+                    Some(s) => stmt_reads(s, var),
+                    None => cond.is_some_and(|c| operand_reads(c, var)),
+                };
+                if u && used {
+                    let msg = if !i {
+                        format!("use of uninitialized variable `{var_name}` in function `{name}`")
+                    } else {
+                        let steps = uninit_path(body, &uninit, var, bb);
+                        let mut msg = format!("use of possibly uninitialized variable `{var_name}` in function `{name}`");
+                        if !steps.is_empty() {
+                            msg += &format!(": it is not assigned when {}", steps.join(" and then "));
+                        }
+                        msg
+                    };
+                    diags.errors.push(ResolveError { span: body.span, msg });
+                    break 'blocks;
+                }
+                match s.and_then(|s| effect(s, var)) {
+                    Some(true) if i && !mutable => {
+                        diags.errors.push(ResolveError {
+                            span: body.span,
+                            msg: format!("cannot assign twice to immutable variable `{var_name}` in function `{name}`"),
+                        });
+                        break 'blocks;
+                    }
+                    Some(assigned) => (u, i) = (!assigned, assigned),
+                    None => {}
+                }
+            }
+        }
+    }
+}
+
+/// `Some(true)` ako naredba dodeljuje `var`, `Some(false)` ako je ostavlja
+/// bez vrednosti (`let x: T;`).
+fn effect(s: &Statement, var: mir::VarId) -> Option<bool> {
+    match s {
+        Statement::AssignVar { var: v, .. } if *v == var => Some(true),
+        Statement::AssignPlace { place: mir::Place { base: Operand::Var(v), proj }, .. } if *v == var && proj.is_empty() => {
+            Some(true)
+        }
+        Statement::Uninit(v) if *v == var => Some(false),
+        _ => None,
+    }
+}
+
+/// „May" analiza unapred: može li `var` na izlazu bloka biti dodeljena
+/// (`assigned`), odnosno bez vrednosti (`!assigned`).
+fn may_be(body: &MirBody, reachable: &[bool], preds: &[Vec<usize>], var: mir::VarId, assigned: bool) -> Vec<bool> {
+    let n = body.blocks.len();
+    // start od false pa se penje do najmanje fiksne tačke
+    let mut out = vec![false; n];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for bb in (0..n).filter(|&bb| reachable[bb]) {
+            let mut state = preds[bb].iter().any(|&p| out[p]);
+            for s in &body.blocks[bb].stmts {
+                if let Some(a) = effect(s, var) {
+                    state = a == assigned;
+                }
+            }
+            if state != out[bb] {
+                out[bb] = state;
+                changed = true;
+            }
+        }
+    }
+    out
+}
+
+/// Najkraći put od deklaracije `var` do bloka `target` kroz blokove iz
+/// kojih ona izlazi bez vrednosti, opisan granama `if` i `while` uslova
+/// čija bi druga strana mogla da je dodeli.
+fn uninit_path(body: &MirBody, uninit: &[bool], var: mir::VarId, target: usize) -> Vec<String> {
+    let mut seen = vec![false; body.blocks.len()];
+    let mut queue = VecDeque::new();
+    for (bb, block) in body.blocks.iter().enumerate() {
+        if block.stmts.iter().any(|s| matches!(s, Statement::Uninit(v) if *v == var)) {
+            seen[bb] = true;
+            queue.push_back(bb);
+        }
+    }
+    let mut parent = HashMap::new();
+    while let Some(bb) = queue.pop_front() {
+        if bb == target {
+            break;
+        }
+        if !uninit[bb] {
+            continue;
+        }
+        for s in successors(&body.blocks[bb].term) {
+            if !std::mem::replace(&mut seen[s as usize], true) {
+                parent.insert(s as usize, bb);
+                queue.push_back(s as usize);
+            }
+        }
+    }
+
+    let mut steps = Vec::new();
+    let mut bb = target;
+    while let Some(&p) = parent.get(&bb) {
+        if let (Some(kw), Terminator::CondBranch { then_bb, else_bb, .. }) =
+            (body.branches.get(&(p as mir::BlockId)), &body.blocks[p].term)
+        {
+            let taken = *then_bb as usize == bb;
+            let other = if taken { *else_bb } else { *then_bb };
+            if assigns_from(body, other as usize, &[p, target], var) {
+                steps.push(format!("the `{kw}` condition is {taken}"));
+            }
+        }
+        bb = p;
+    }
+    steps.reverse();
+    steps
+}
+
+/// Da li neki blok dostižan iz `start` bez prolaska kroz `stop` dodeljuje
+/// `var`.
+fn assigns_from(body: &MirBody, start: usize, stop: &[usize], var: mir::VarId) -> bool {
+    let mut seen = vec![false; body.blocks.len()];
+    let mut work = vec![start];
+    while let Some(bb) = work.pop() {
+        if stop.contains(&bb) || std::mem::replace(&mut seen[bb], true) {
+            continue;
+        }
+        if body.blocks[bb].stmts.iter().any(|s| effect(s, var) == Some(true)) {
+            return true;
+        }
+        work.extend(successors(&body.blocks[bb].term).into_iter().map(|s| s as usize));
+    }
+    false
+}
+
+fn stmt_reads(s: &Statement, var: mir::VarId) -> bool {
+    match s {
+        Statement::Assign { rv, .. } | Statement::AssignVar { rv, .. } => rvalue_reads(rv, var),
+        // upis u deo vrednosti čita ostatak, a kroz referencu čita pokazivač
+        Statement::AssignPlace { place, rv } => {
+            let indices = place.proj.iter().any(|p| matches!(p, mir::Projection::Index(i) if operand_reads(i, var)));
+            (!place.proj.is_empty() && operand_reads(&place.base, var)) || indices || rvalue_reads(rv, var)
+        }
+        Statement::BoundsCheck { index, len, .. } => operand_reads(index, var) || operand_reads(len, var),
+        Statement::NewCell(_) | Statement::Uninit(_) | Statement::StorageLive(_) | Statement::StorageDead(_) => false,
+    }
+}
+
+fn rvalue_reads(rv: &Rvalue, var: mir::VarId) -> bool {
+    let any = |ops: &[Operand]| ops.iter().any(|o| operand_reads(o, var));
+    match rv {
+        Rvalue::Use(o)
+        | Rvalue::UnaryOp { src: o, .. }
+        | Rvalue::Repeat { elem: o, .. }
+        | Rvalue::Field { base: o, .. }
+        | Rvalue::Load(o)
+        | Rvalue::ToDyn { value: o, .. } => operand_reads(o, var),
+        Rvalue::BinaryOp { lhs, rhs, .. } | Rvalue::Index { base: lhs, index: rhs } => {
+            operand_reads(lhs, var) || operand_reads(rhs, var)
+        }
+        Rvalue::Call { args, .. } | Rvalue::Spawn { args, .. } | Rvalue::CallDyn { args, .. } => any(args),
+        Rvalue::CallValue { callee, args, .. } => operand_reads(callee, var) || any(args),
+        Rvalue::Array(ops) | Rvalue::Tuple(ops) => any(ops),
+        // zatvorenje hvata trenutnu vrednost ili samu promenljivu
+        Rvalue::Closure { captures, .. } => captures.contains(&var),
+    }
+}
+
+fn operand_reads(o: &Operand, var: mir::VarId) -> bool {
+    match o {
+        Operand::Var(v) => *v == var,
+        Operand::Addr(inner) => operand_reads(inner, var),
+        Operand::Const(_) | Operand::Temp(_) => false,
+    }
+}
@@ -1,20 +1,22 @@
 use std::collections::HashMap;
-use std::rc::Rc;
 
 use inkwell::{
-    AddressSpace,
+    AddressSpace, FloatPredicate, IntPredicate,
+    attributes::{Attribute, AttributeLoc},
     builder::Builder,
     context::Context,
-    module::Module,
-    types::{BasicType, BasicTypeEnum},
-    values::{BasicValueEnum, FunctionValue},
+    module::{Linkage, Module},
+    types::{ArrayType, BasicMetadataTypeEnum, BasicType, BasicTypeEnum, FunctionType, StructType},
+    values::{BasicMetadataValueEnum, BasicValueEnum, FloatValue, FunctionValue, IntValue, PointerValue},
 };
 
-use crate::hir::BinOp;
+use crate::hir::{BinOp, UnOp};
+use crate::lexer::Span;
 use crate::mir::{
-    BasicBlock, Constant, MirBody, MirType, Operand, RET_TEMP, Rvalue, Statement, TempId,
-    Terminator,
+    BasicBlock, Constant, Env, MirBody, MirModule, MirType, Operand, Place, Projection, RET_TEMP, Rvalue,
+    Statement, TempId, Terminator, VarId, Vtable,
 };
+use crate::type_::NumTy;
 
 // Safe approach: Use Rc to share ownership of the context
 pub struct LlvmCtx<'ctx> {
@@ -34,20 +36,7 @@ impl LlvmCtx<'static> {
         let module = context.create_module(name);
         let builder = context.create_builder();
 
-        let i32_ty = context.i32_type();
-        let i8_ptr = context.i8_type().ptr_type(AddressSpace::default());
-
-        module.add_function(
-            "aethc_print_int",
-            context.void_type().fn_type(&[i32_ty.into()], false),
-            None,
-        );
-
-        module.add_function(
-            "aethc_print_str",
-            context.void_type().fn_type(&[i8_ptr.into()], false),
-            None,
-        );
+        declare_runtime(context, &module);
 
         Self {
             context,
@@ -57,6 +46,71 @@ impl LlvmCtx<'static> {
     }
 }
 
+/// Funkcije iz `runtime.c`.
+fn declare_runtime<'ctx>(context: &'ctx Context, module: &Module<'ctx>) {
+    let void = context.void_type();
+    let i32_ty = context.i32_type();
+    let i64_ty = context.i64_type();
+    let i8_ptr = context.i8_type().ptr_type(AddressSpace::default());
+
+    module.add_function("aethc_print_int", void.fn_type(&[i32_ty.into()], false), None);
+    module.add_function("aethc_print_str", void.fn_type(&[i8_ptr.into()], false), None);
+    module.add_function("aethc_print_i64", void.fn_type(&[i64_ty.into()], false), None);
+    module.add_function("aethc_print_u64", void.fn_type(&[i64_ty.into()], false), None);
+    module.add_function("aethc_print_char", void.fn_type(&[i32_ty.into()], false), None);
+    module.add_function("aethc_print_bytes", void.fn_type(&[i8_ptr.into(), i64_ty.into()], false), None);
+    module.add_function("aethc_str_cmp", i32_ty.fn_type(&[i8_ptr.into(), i8_ptr.into()], false), None);
+
+    // kanali prenose jednu 64-bitnu reč; nit dobija trampolin + kopiju argumenata
+    module.add_function("aethc_chan_new", i8_ptr.fn_type(&[], false), None);
+    module.add_function("aethc_chan_send", void.fn_type(&[i8_ptr.into(), i64_ty.into()], false), None);
+    module.add_function("aethc_chan_recv", i64_ty.fn_type(&[i8_ptr.into()], false), None);
+    module.add_function(
+        "aethc_spawn",
+        void.fn_type(&[i8_ptr.into(), i8_ptr.into(), i64_ty.into()], false),
+        None,
+    );
+    module.add_function("aethc_join_all", void.fn_type(&[], false), None);
+
+    // Vec je ručka na niz reči u runtime-u; elementi idu kao i kroz kanal
+    module.add_function("aethc_vec_new", i8_ptr.fn_type(&[], false), None);
+    module.add_function("aethc_vec_push", void.fn_type(&[i8_ptr.into(), i64_ty.into()], false), None);
+    module.add_function("aethc_vec_pop", i64_ty.fn_type(&[i8_ptr.into()], false), None);
+    module.add_function("aethc_vec_len", i64_ty.fn_type(&[i8_ptr.into()], false), None);
+    module.add_function("aethc_vec_get", i64_ty.fn_type(&[i8_ptr.into(), i64_ty.into()], false), None);
+    module.add_function(
+        "aethc_vec_set",
+        void.fn_type(&[i8_ptr.into(), i64_ty.into(), i64_ty.into()], false),
+        None,
+    );
+
+    // (indeks, dužina, funkcija, red, kolona) – ne vraća se
+    let panic = module.add_function(
+        "aethc_panic_bounds",
+        void.fn_type(&[i64_ty.into(), i64_ty.into(), i8_ptr.into(), i32_ty.into(), i32_ty.into()], false),
+        None,
+    );
+    // (funkcija, delilac, da li je `%`) – deljenje nulom ili `MIN / -1`
+    let panic_div = module.add_function(
+        "aethc_panic_div",
+        void.fn_type(&[i8_ptr.into(), i64_ty.into(), i32_ty.into()], false),
+        None,
+    );
+    // ćelije promenljivih uhvaćenih po referenci i okruženja zatvorenja
+    module.add_function("aethc_alloc", i8_ptr.fn_type(&[i64_ty.into()], false), None);
+
+    let noreturn = Attribute::get_named_enum_kind_id("noreturn");
+    for f in [panic, panic_div] {
+        f.add_attribute(AttributeLoc::Function, context.create_enum_attribute(noreturn, 0));
+    }
+}
+
+impl Default for LlvmContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl LlvmContext {
     pub fn new() -> Self {
         Self {
@@ -68,20 +122,7 @@ impl LlvmContext {
         let module = self.context.create_module(name);
         let builder = self.context.create_builder();
 
-        let i32_ty = self.context.i32_type();
-        let i8_ptr = self.context.i8_type().ptr_type(AddressSpace::default());
-
-        module.add_function(
-            "aethc_print_int",
-            self.context.void_type().fn_type(&[i32_ty.into()], false),
-            None,
-        );
-
-        module.add_function(
-            "aethc_print_str",
-            self.context.void_type().fn_type(&[i8_ptr.into()], false),
-            None,
-        );
+        declare_runtime(&self.context, &module);
 
         LlvmCtx {
             context: &self.context,
@@ -96,236 +137,1152 @@ impl<'ctx> LlvmCtx<'ctx> {
         match ty {
             MirType::Int => self.context.i32_type().into(),
             MirType::Float => self.context.f64_type().into(),
+            MirType::Num(NumTy::F32) => self.context.f32_type().into(),
+            MirType::Num(NumTy::F64) => self.context.f64_type().into(),
+            MirType::Num(n) => self.context.custom_width_int_type(n.bits()).into(),
             MirType::Bool => self.context.bool_type().into(),
-            MirType::Str => self
+            MirType::Str | MirType::Chan | MirType::Vec => self
                 .context
                 .i8_type()
                 .ptr_type(AddressSpace::default())
                 .into(),
-            MirType::Unit => unreachable!("unit type has no LLVM equivalent"),
+            // prazna struktura – vrednost bez sadržaja, da bi i `()` imao slot
+            MirType::Unit => self.context.struct_type(&[], false).into(),
+            MirType::Array(elem, len) => self.ll_ty(elem).array_type(*len as u32).into(),
+            MirType::Tuple(elems) => {
+                let fields: Vec<_> = elems.iter().map(|e| self.ll_ty(e)).collect();
+                self.context.struct_type(&fields, false).into()
+            }
+            // (kod, okruženje); kod uzima okruženje kao prvi parametar.
+            // `&dyn Trait` je isti par (podaci, vtable), po vrednosti
+            MirType::Fn { .. } | MirType::Dyn => {
+                let i8_ptr = self.context.i8_type().ptr_type(AddressSpace::default());
+                self.context.struct_type(&[i8_ptr.into(), i8_ptr.into()], false).into()
+            }
+            MirType::Ptr(inner) => self.ll_ty(inner).ptr_type(AddressSpace::default()).into(),
         }
     }
-}
 
-pub fn codegen_fn<'ctx>(llcx: &mut LlvmCtx<'ctx>, name: &str, mir: &MirBody) {
-    let func = match mir.ret_ty {
-        MirType::Unit => {
-            llcx.module
-                .add_function(name, llcx.context.void_type().fn_type(&[], false), None)
+    /// LLVM type of `ret fn(params)`; with `env`, a leading `i8*` parameter
+    /// takes the closure environment.
+    fn fn_ty(&self, params: &[MirType], ret: &MirType, env: bool) -> FunctionType<'ctx> {
+        let i8_ptr = self.context.i8_type().ptr_type(AddressSpace::default());
+        let mut tys: Vec<BasicMetadataTypeEnum> = Vec::new();
+        if env {
+            tys.push(i8_ptr.into());
         }
-        _ => {
-            let ret_ty = llcx.ll_ty(&mir.ret_ty);
-            llcx.module
-                .add_function(name, ret_ty.fn_type(&[], false), None)
+        tys.extend(params.iter().map(|p| BasicMetadataTypeEnum::from(self.ll_ty(p))));
+        match ret {
+            MirType::Unit => self.context.void_type().fn_type(&tys, false),
+            _ => self.ll_ty(ret).fn_type(&tys, false),
         }
-    };
+    }
 
-    let entry_bb = llcx.context.append_basic_block(func, "bb0");
-    llcx.builder.position_at_end(entry_bb);
+    /// Value of the named function `f`: a thunk `f.fnval` that ignores the
+    /// environment and calls `f`, with a null environment.
+    fn fn_value(&self, name: &str) -> BasicValueEnum<'ctx> {
+        let f = self
+            .module
+            .get_function(name)
+            .unwrap_or_else(|| panic!("unknown function `{name}`"));
+        let i8_ptr = self.context.i8_type().ptr_type(AddressSpace::default());
+        let thunk_name = format!("{name}.fnval");
+        let thunk = self.module.get_function(&thunk_name).unwrap_or_else(|| {
+            let f_ty = f.get_type();
+            let mut params: Vec<BasicMetadataTypeEnum> = vec![i8_ptr.into()];
+            params.extend(f_ty.get_param_types().into_iter().map(BasicMetadataTypeEnum::from));
+            let thunk_ty = match f_ty.get_return_type() {
+                Some(ret) => ret.fn_type(&params, false),
+                None => self.context.void_type().fn_type(&params, false),
+            };
+            let thunk = self.module.add_function(&thunk_name, thunk_ty, Some(Linkage::Private));
 
-    let mut temps: HashMap<TempId, BasicValueEnum<'ctx>> = HashMap::new();
+            let here = self.builder.get_insert_block();
+            self.builder.position_at_end(self.context.append_basic_block(thunk, "entry"));
+            let args: Vec<BasicMetadataValueEnum> =
+                thunk.get_param_iter().skip(1).map(BasicMetadataValueEnum::from).collect();
+            let call = self.builder.build_call(f, &args, "call").expect("call");
+            let _ = match call.try_as_basic_value().left() {
+                Some(v) => self.builder.build_return(Some(&v)),
+                None => self.builder.build_return(None),
+            };
+            if let Some(bb) = here {
+                self.builder.position_at_end(bb);
+            }
+            thunk
+        });
+        let code = thunk.as_global_value().as_pointer_value().const_cast(i8_ptr);
+        self.context.const_struct(&[code.into(), i8_ptr.const_null().into()], false).into()
+    }
 
-    lower_block(
-        llcx,
-        &mir.blocks[0],
-        func,
-        &mut temps,
-        &mir.blocks,
-        &mir.ret_ty,
-    );
+    fn unit_value(&self) -> BasicValueEnum<'ctx> {
+        self.context.const_struct(&[], false).into()
+    }
+
+    fn const_value(&self, c: &Constant) -> BasicValueEnum<'ctx> {
+        match c {
+            Constant::Int(i) => self.context.i32_type().const_int(*i as u64, true).into(),
+            Constant::Float(f) => self.context.f64_type().const_float(*f).into(),
+            Constant::IntN(n, i) => self.ll_ty(&MirType::Num(*n)).into_int_type().const_int(*i as u64, true).into(),
+            Constant::F32(f) => self.context.f32_type().const_float(f64::from(*f)).into(),
+            Constant::Bool(b) => self.context.bool_type().const_int(*b as u64, false).into(),
+            Constant::Unit => self.unit_value(),
+            Constant::Str(s) => {
+                // privatni niz bajtova sa '\0' + konstantni i8* na njegov početak
+                let bytes = self.context.const_string(s.as_bytes(), true);
+                let gv = self.module.add_global(bytes.get_type(), None, "strlit");
+                gv.set_initializer(&bytes);
+                gv.set_constant(true);
+                gv.set_linkage(Linkage::Private);
+                let i8_ptr = self.context.i8_type().ptr_type(AddressSpace::default());
+                gv.as_pointer_value().const_cast(i8_ptr).into()
+            }
+            Constant::Fn(name) => self.fn_value(name),
+            Constant::Zero(ty) => self.ll_ty(ty).const_zero(),
+            Constant::Array(_) | Constant::Tuple(_) => {
+                unreachable!("aggregate constants are typed, see `const_init`")
+            }
+        }
+    }
+
+    /// Initializer of a global of type `ty`. Arrays take their element type
+    /// from `ty`, so even an empty one gets the right LLVM type.
+    fn const_init(&self, c: &Constant, ty: &MirType) -> BasicValueEnum<'ctx> {
+        if let (Constant::Tuple(elems), MirType::Tuple(tys)) = (c, ty) {
+            let vals: Vec<_> = elems.iter().zip(tys).map(|(e, t)| self.const_init(e, t)).collect();
+            return self.context.const_struct(&vals, false).into();
+        }
+        let (Constant::Array(elems), MirType::Array(elem_ty, _)) = (c, ty) else {
+            return match (c, ty) {
+                (Constant::Int(i), MirType::Float) => self.context.f64_type().const_float(*i as f64).into(),
+                (Constant::Int(i), MirType::Num(n)) if n.is_float() => {
+                    self.ll_ty(ty).into_float_type().const_float(*i as f64).into()
+                }
+                (Constant::Int(i), MirType::Num(_)) => self.ll_ty(ty).into_int_type().const_int(*i as u64, true).into(),
+                (Constant::Float(f), MirType::Num(_)) => self.ll_ty(ty).into_float_type().const_float(*f).into(),
+                _ => self.const_value(c),
+            };
+        };
+        let vals: Vec<BasicValueEnum> = elems.iter().map(|e| self.const_init(e, elem_ty)).collect();
+        match self.ll_ty(elem_ty) {
+            BasicTypeEnum::IntType(t) => {
+                let vals: Vec<_> = vals.iter().map(|v| v.into_int_value()).collect();
+                t.const_array(&vals).into()
+            }
+            BasicTypeEnum::FloatType(t) => {
+                let vals: Vec<_> = vals.iter().map(|v| v.into_float_value()).collect();
+                t.const_array(&vals).into()
+            }
+            BasicTypeEnum::PointerType(t) => {
+                let vals: Vec<_> = vals.iter().map(|v| v.into_pointer_value()).collect();
+                t.const_array(&vals).into()
+            }
+            BasicTypeEnum::ArrayType(t) => {
+                let vals: Vec<_> = vals.iter().map(|v| v.into_array_value()).collect();
+                t.const_array(&vals).into()
+            }
+            BasicTypeEnum::StructType(t) => {
+                let vals: Vec<_> = vals.iter().map(|v| v.into_struct_value()).collect();
+                t.const_array(&vals).into()
+            }
+            BasicTypeEnum::VectorType(_) => unreachable!("no vector types"),
+        }
+    }
+
+    /// Declare `name` with parameter and return types taken from `mir`.
+    fn declare_fn(&self, name: &str, mir: &MirBody) -> FunctionValue<'ctx> {
+        let mut params: Vec<MirType> = mir.params.iter().map(|p| mir.vars[p].clone()).collect();
+        if mir.self_ref {
+            params[0] = MirType::Ptr(Box::new(params[0].clone()));
+        }
+        let fn_ty = self.fn_ty(&params, &mir.ret_ty, mir.env.is_some());
+        self.module.add_function(name, fn_ty, None)
+    }
+
+    /// Constant struct of pointers to the functions of `v`, each cast to
+    /// `i8*`, so that a call can index it like an array.
+    fn define_vtable(&self, v: &Vtable) {
+        let i8_ptr = self.context.i8_type().ptr_type(AddressSpace::default());
+        let fns: Vec<BasicValueEnum> = v
+            .fns
+            .iter()
+            .map(|name| {
+                let f = self.module.get_function(name).unwrap_or_else(|| panic!("unknown function `{name}`"));
+                f.as_global_value().as_pointer_value().const_cast(i8_ptr).into()
+            })
+            .collect();
+        let table = self.context.const_struct(&fns, false);
+        let gv = self.module.add_global(table.get_type(), None, &v.name);
+        gv.set_initializer(&table);
+        gv.set_constant(true);
+        gv.set_linkage(Linkage::Private);
+    }
+
+    /// Layout of a closure environment: the captured values, or with
+    /// `by_ref` pointers to their cells.
+    fn env_ty(&self, env: &Env, vars: impl Fn(VarId) -> BasicTypeEnum<'ctx>) -> StructType<'ctx> {
+        let fields: Vec<BasicTypeEnum> = env
+            .captures
+            .iter()
+            .map(|v| match env.by_ref {
+                true => vars(*v).ptr_type(AddressSpace::default()).into(),
+                false => vars(*v),
+            })
+            .collect();
+        self.context.struct_type(&fields, false)
+    }
 }
 
-fn get_or_create_bb<'ctx>(
-    llcx: &mut LlvmCtx<'ctx>,
+/// Promenljiva ili privremena vrednost u memoriji: adresa + tip.
+#[derive(Clone, Copy)]
+struct Slot<'ctx> {
+    ptr: PointerValue<'ctx>,
+    ty: BasicTypeEnum<'ctx>,
+}
+
+/// Stanje generisanja jedne funkcije.
+struct FnCx<'a, 'ctx> {
+    llcx: &'a LlvmCtx<'ctx>,
+    /// globalne promenljive i lokalne (parametri + `let`), po NodeId-u
+    vars: HashMap<VarId, Slot<'ctx>>,
+    /// promenljive iz `MirBody::boxed`: `ptr` je alloca pokazivača na
+    /// ćeliju na heap-u, `ty` tip same promenljive
+    cells: HashMap<VarId, Slot<'ctx>>,
+    temps: HashMap<TempId, Slot<'ctx>>,
+    blocks: Vec<inkwell::basic_block::BasicBlock<'ctx>>,
+    ret_ty: MirType,
     func: FunctionValue<'ctx>,
-    id: u32,
-) -> inkwell::basic_block::BasicBlock<'ctx> {
-    for bb in func.get_basic_blocks() {
-        if bb
-            .get_name()
-            .to_str()
-            .map(|s| s == format!("bb{}", id))
-            .unwrap_or(false)
+}
+
+/// Generate LLVM IR for the whole module: globals, every function and the
+/// global initializer, which `main` calls before anything else.
+pub fn codegen_module<'ctx>(llcx: &mut LlvmCtx<'ctx>, m: &MirModule) {
+    let mut globals = HashMap::new();
+    for g in &m.globals {
+        let ty = llcx.ll_ty(&g.ty);
+        let gv = llcx.module.add_global(ty, None, &g.name);
+        let init = match &g.init {
+            Some(c) => llcx.const_init(c, &g.ty),
+            None => ty.const_zero(),
+        };
+        gv.set_initializer(&init);
+        gv.set_constant(!g.mutable && g.init.is_some());
+        globals.insert(g.id, Slot { ptr: gv.as_pointer_value(), ty });
+    }
+
+    // sve funkcije se deklarišu unapred da bi pozivi mogli da idu unapred
+    let funcs: Vec<_> = m.fns.iter().map(|f| llcx.declare_fn(&f.name, &f.body)).collect();
+    for v in &m.vtables {
+        llcx.define_vtable(v);
+    }
+
+    let init_fn = m.init.as_ref().map(|body| {
+        let func = llcx.declare_fn("__aethc_init_globals", body);
+        define_fn(llcx, func, body, &globals);
+        func
+    });
+
+    for (f, func) in m.fns.iter().zip(funcs) {
+        define_fn(llcx, func, &f.body, &globals);
+        if f.name == "main"
+            && let Some(init_fn) = init_fn
         {
-            return bb;
+            // poziv inicijalizatora ide odmah posle alloca-a u ulaznom bloku
+            let entry = func.get_first_basic_block().unwrap();
+            llcx.builder.position_before(&entry.get_terminator().unwrap());
+            let _ = llcx.builder.build_call(init_fn, &[], "");
         }
     }
-    llcx.context.append_basic_block(func, &format!("bb{}", id))
 }
 
-fn succ_blocks(term: &Terminator) -> Vec<u32> {
-    match term {
-        Terminator::Goto(id) => vec![*id],
-        Terminator::CondBranch {
-            then_bb, else_bb, ..
-        } => vec![*then_bb, *else_bb],
-        _ => Vec::new(),
-    }
+pub fn codegen_fn<'ctx>(llcx: &mut LlvmCtx<'ctx>, name: &str, mir: &MirBody) {
+    let func = llcx.declare_fn(name, mir);
+    define_fn(llcx, func, mir, &HashMap::new());
 }
 
-fn lower_block<'ctx>(
-    llcx: &mut LlvmCtx<'ctx>,
-    bb: &BasicBlock,
+fn define_fn<'ctx>(
+    llcx: &LlvmCtx<'ctx>,
     func: FunctionValue<'ctx>,
-    temps: &mut HashMap<TempId, BasicValueEnum<'ctx>>,
-    all: &[BasicBlock],
-    ret_ty: &MirType,
+    mir: &MirBody,
+    globals: &HashMap<VarId, Slot<'ctx>>,
 ) {
-    for stmt in &bb.stmts {
-        match stmt {
-            Statement::Assign { dst, rv } => {
-                let val = lower_rvalue(llcx, rv, temps);
-                temps.insert(*dst, val);
+    let entry = llcx.context.append_basic_block(func, "entry");
+    let blocks = (0..mir.blocks.len())
+        .map(|i| llcx.context.append_basic_block(func, &format!("bb{i}")))
+        .collect();
+    llcx.builder.position_at_end(entry);
+
+    let mut cx = FnCx {
+        llcx,
+        vars: globals.clone(),
+        cells: HashMap::new(),
+        temps: HashMap::new(),
+        blocks,
+        ret_ty: mir.ret_ty.clone(),
+        func,
+    };
+
+    // svaka promenljiva i privremena vrednost dobija alloca u ulaznom bloku
+    for (id, ty) in &mir.vars {
+        if mir.boxed.contains(id) {
+            let ty = llcx.ll_ty(ty);
+            let ptr_ty = ty.ptr_type(AddressSpace::default());
+            let ptr = llcx.builder.build_alloca(ptr_ty, "cell").expect("alloca");
+            cx.cells.insert(*id, Slot { ptr, ty });
+        } else {
+            let slot = cx.alloca(ty, "var");
+            cx.vars.insert(*id, slot);
+        }
+    }
+    for (id, ty) in mir.temps.iter().enumerate() {
+        let slot = cx.alloca(ty, "tmp");
+        cx.temps.insert(id as TempId, slot);
+    }
+    let ret_slot = cx.alloca(&mir.ret_ty, "ret");
+    cx.temps.insert(RET_TEMP, ret_slot);
+
+    let mut args = func.get_param_iter();
+    if let Some(env) = &mir.env {
+        cx.unpack_env(env, args.next().unwrap().into_pointer_value());
+    }
+    for (i, (p, arg)) in mir.params.iter().zip(args).enumerate() {
+        // primalac `&self` već ima ćeliju – vrednost pozivaoca
+        if i == 0 && mir.self_ref {
+            let _ = llcx.builder.build_store(cx.cells[p].ptr, arg);
+            continue;
+        }
+        if mir.boxed.contains(p) {
+            cx.new_cell(*p);
+        }
+        cx.store(cx.var_slot(*p), arg);
+    }
+    let _ = llcx.builder.build_unconditional_branch(cx.blocks[0]);
+
+    for (i, bb) in mir.blocks.iter().enumerate() {
+        llcx.builder.position_at_end(cx.blocks[i]);
+        cx.lower_block(bb);
+    }
+}
+
+impl<'ctx> FnCx<'_, 'ctx> {
+    fn alloca(&self, ty: &MirType, name: &str) -> Slot<'ctx> {
+        let ty = self.llcx.ll_ty(ty);
+        let ptr = self.llcx.builder.build_alloca(ty, name).expect("alloca");
+        Slot { ptr, ty }
+    }
+
+    fn load(&self, slot: Slot<'ctx>) -> BasicValueEnum<'ctx> {
+        self.llcx.builder.build_load(slot.ty, slot.ptr, "load").expect("load")
+    }
+
+    fn store(&self, slot: Slot<'ctx>, val: BasicValueEnum<'ctx>) {
+        let val = self.coerce(val, slot.ty);
+        let _ = self.llcx.builder.build_store(slot.ptr, val);
+    }
+
+    /// Stores `rv` to `slot`. Arrays are written in place rather than built
+    /// as SSA values, which takes an instruction per element: see
+    /// `fill_array` for `[x; N]`, and a copied array is a `memcpy`.
+    fn assign(&mut self, slot: Slot<'ctx>, rv: &Rvalue) {
+        match rv {
+            Rvalue::Repeat { elem, len } => self.fill_array(slot, elem, *len),
+            Rvalue::Use(op @ (Operand::Var(_) | Operand::Temp(_))) if slot.ty.is_array_type() => {
+                let src = self.place_slot(op);
+                let size = slot.ty.size_of().expect("sized array");
+                let _ = self.llcx.builder.build_memcpy(slot.ptr, 1, src.ptr, 1, size).expect("memcpy");
+            }
+            _ => {
+                let val = self.lower_rvalue(rv, slot.ty);
+                self.store(slot, val);
             }
-            _ => {}
         }
     }
 
-    match &bb.term {
-        Terminator::Return => {
-            // Fix: Use matches! macro or implement PartialEq for MirType
-            if matches!(ret_ty, MirType::Unit) {
-                let _ = llcx.builder.build_return(None);
-            } else {
-                let ret_val = temps.get(&RET_TEMP).expect("ret temp");
-                let _ = llcx.builder.build_return(Some(ret_val));
-            }
-        }
-        Terminator::Goto(id) => {
-            let l_bb = get_or_create_bb(llcx, func, *id);
-            let _ = llcx.builder.build_unconditional_branch(l_bb);
-        }
-        Terminator::CondBranch {
-            cond,
-            then_bb,
-            else_bb,
-        } => {
-            let cond_val = lower_operand(llcx, cond, temps).into_int_value();
-            let then_ll = get_or_create_bb(llcx, func, *then_bb);
-            let else_ll = get_or_create_bb(llcx, func, *else_bb);
-            let _ = llcx
-                .builder
-                .build_conditional_branch(cond_val, then_ll, else_ll);
+    /// `[elem; len]` u `slot`: nula je `memset`, konstantan broj kopija
+    /// konstantnog globalnog niza, a sve ostalo petlja koja upisuje element
+    /// po element.
+    fn fill_array(&self, slot: Slot<'ctx>, elem: &Operand, len: usize) {
+        let llcx = self.llcx;
+        let b = &llcx.builder;
+        let ty = slot.ty.into_array_type();
+        let val = self.coerce(self.lower_operand(elem), ty.get_element_type());
+        let zero = match val {
+            BasicValueEnum::IntValue(v) => v.is_null(),
+            BasicValueEnum::FloatValue(v) => v.is_null(),
+            BasicValueEnum::ArrayValue(v) => v.is_null(),
+            BasicValueEnum::StructValue(v) => v.is_null(),
+            BasicValueEnum::PointerValue(v) => v.is_null(),
+            _ => false,
+        };
+        let size = ty.size_of().expect("sized array");
+        if zero {
+            let _ = b.build_memset(slot.ptr, 1, llcx.context.i8_type().const_zero(), size).expect("memset");
+            return;
+        }
+        let constant: Option<BasicValueEnum> = match val {
+            BasicValueEnum::IntValue(v) if v.is_const() => Some(v.get_type().const_array(&vec![v; len]).into()),
+            BasicValueEnum::FloatValue(v) if v.is_const() => Some(v.get_type().const_array(&vec![v; len]).into()),
+            _ => None,
+        };
+        if let Some(array) = constant {
+            let gv = llcx.module.add_global(ty, None, "repeat");
+            gv.set_initializer(&array);
+            gv.set_constant(true);
+            gv.set_linkage(Linkage::Private);
+            let _ = b.build_memcpy(slot.ptr, 1, gv.as_pointer_value(), 1, size).expect("memcpy");
+            return;
         }
+        if len == 0 {
+            return;
+        }
+        let i32_ty = llcx.context.i32_type();
+        let entry = b.get_insert_block().unwrap();
+        let body = llcx.context.append_basic_block(self.func, "fill");
+        let done = llcx.context.append_basic_block(self.func, "filled");
+        let _ = b.build_unconditional_branch(body);
+
+        b.position_at_end(body);
+        let i = b.build_phi(i32_ty, "i").expect("phi");
+        i.add_incoming(&[(&i32_ty.const_zero(), entry)]);
+        let i_val = i.as_basic_value().into_int_value();
+        // SAFETY: `i` ide od 0 do `len - 1`
+        let ptr = unsafe { b.build_in_bounds_gep(ty, slot.ptr, &[i32_ty.const_zero(), i_val], "elem") }
+            .expect("gep");
+        let _ = b.build_store(ptr, val);
+        let next = b.build_int_add(i_val, i32_ty.const_int(1, false), "next").expect("add");
+        i.add_incoming(&[(&next, body)]);
+        let more = b
+            .build_int_compare(IntPredicate::ULT, next, i32_ty.const_int(len as u64, false), "more")
+            .expect("icmp");
+        let _ = b.build_conditional_branch(more, body, done);
+        b.position_at_end(done);
     }
 
-    for succ in succ_blocks(&bb.term) {
-        if !func.get_basic_blocks().iter().any(|b| {
-            b.get_name()
-                .to_str()
-                .map(|s| s == format!("bb{}", succ))
-                .unwrap_or(false)
-        }) {
-            let new_bb = llcx
-                .context
-                .append_basic_block(func, &format!("bb{}", succ));
-            llcx.builder.position_at_end(new_bb);
-            lower_block(llcx, &all[succ as usize], func, temps, all, ret_ty);
+    /// Int → Float promocija na mestima gde resolver dozvoljava implicitnu
+    /// konverziju (dodela, povratna vrednost, argument).
+    fn coerce(&self, val: BasicValueEnum<'ctx>, ty: BasicTypeEnum<'ctx>) -> BasicValueEnum<'ctx> {
+        if ty.is_float_type() && val.is_int_value() {
+            self.int_to_float(val.into_int_value()).into()
+        } else {
+            val
         }
     }
-}
 
-fn lower_operand<'ctx>(
-    llcx: &LlvmCtx<'ctx>,
-    op: &Operand,
-    temps: &HashMap<TempId, BasicValueEnum<'ctx>>,
-) -> BasicValueEnum<'ctx> {
-    match op {
-        Operand::Const(c) => match c {
-            Constant::Int(i) => llcx.context.i32_type().const_int(*i as u64, true).into(),
-            Constant::Float(f) => llcx.context.f64_type().const_float(*f).into(),
-            Constant::Bool(b) => llcx.context.bool_type().const_int(*b as u64, false).into(),
-            Constant::Str(s) => {
-                let gv = llcx
-                    .builder
-                    .build_global_string_ptr(&format!("{}\0", s), "strlit")
-                    .expect("Failed to build global string ptr");
-                gv.as_pointer_value().into()
+    fn int_to_float(&self, v: IntValue<'ctx>) -> FloatValue<'ctx> {
+        self.llcx
+            .builder
+            .build_signed_int_to_float(v, self.llcx.context.f64_type(), "sitofp")
+            .expect("Failed to build sitofp")
+    }
+
+    fn lower_block(&mut self, bb: &BasicBlock) {
+        for stmt in &bb.stmts {
+            match stmt {
+                Statement::Assign { dst, rv } => {
+                    let slot = self.temps[dst];
+                    self.assign(slot, rv);
+                }
+                Statement::AssignVar { var, rv } => {
+                    let slot = self.var_slot(*var);
+                    self.assign(slot, rv);
+                }
+                Statement::AssignPlace { place, rv } => {
+                    let slot = self.place(place);
+                    self.assign(slot, rv);
+                }
+                Statement::BoundsCheck { index, len, span } => self.bounds_check(index, len, *span),
+                Statement::NewCell(var) => self.new_cell(*var),
+                Statement::Uninit(_) | Statement::StorageLive(_) | Statement::StorageDead(_) => {}
+            }
+        }
+
+        let builder = &self.llcx.builder;
+        match &bb.term {
+            Terminator::Return => {
+                if self.func.get_name().to_bytes() == b"main" {
+                    // program se završava tek kad se završe sve niti
+                    let join = self.llcx.module.get_function("aethc_join_all").unwrap();
+                    let _ = builder.build_call(join, &[], "");
+                }
+                if matches!(self.ret_ty, MirType::Unit) {
+                    let _ = builder.build_return(None);
+                } else {
+                    let ret_val = self.load(self.temps[&RET_TEMP]);
+                    let _ = builder.build_return(Some(&ret_val));
+                }
             }
-            Constant::Unit => panic!("unit is never a value"),
-        },
-        Operand::Temp(t) => *temps.get(t).expect("temp"),
-        Operand::Var(v) => {
-            let ptr = *temps.get(v).expect("var ptr");
-            let ptr_val = ptr.into_pointer_value();
+            Terminator::Goto(id) => {
+                let _ = builder.build_unconditional_branch(self.blocks[*id as usize]);
+            }
+            Terminator::CondBranch {
+                cond,
+                then_bb,
+                else_bb,
+            } => {
+                let cond_val = self.lower_operand(cond).into_int_value();
+                let _ = builder.build_conditional_branch(
+                    cond_val,
+                    self.blocks[*then_bb as usize],
+                    self.blocks[*else_bb as usize],
+                );
+            }
+        }
+    }
 
-            // For LLVM 16+, we need to specify the type explicitly
-            // You'll need to determine the correct type based on your type system
-            let elem_ty = llcx.context.i32_type(); // Adjust this based on your actual type
+    fn lower_operand(&self, op: &Operand) -> BasicValueEnum<'ctx> {
+        match op {
+            Operand::Const(c) => self.llcx.const_value(c),
+            Operand::Temp(t) => self.load(self.temps[t]),
+            Operand::Var(v) => self.load(self.var_slot(*v)),
+            Operand::Addr(place) => self.place_slot(place).ptr.into(),
+        }
+    }
 
-            llcx.builder
-                .build_load(elem_ty, ptr_val, "varload")
-                .expect("Failed to build load")
+    /// Slot of a variable or temporary operand.
+    fn place_slot(&self, op: &Operand) -> Slot<'ctx> {
+        match op {
+            Operand::Var(v) => self.var_slot(*v),
+            Operand::Temp(t) => self.temps[t],
+            Operand::Const(_) | Operand::Addr(_) => unreachable!("not a place: {op:?}"),
         }
     }
-}
 
-fn lower_rvalue<'ctx>(
-    llcx: &mut LlvmCtx<'ctx>,
-    rv: &Rvalue,
-    temps: &HashMap<TempId, BasicValueEnum<'ctx>>,
-) -> BasicValueEnum<'ctx> {
-    match rv {
-        Rvalue::Use(op) => lower_operand(llcx, op, temps),
-        Rvalue::BinaryOp { op, lhs, rhs } => {
-            let l = lower_operand(llcx, lhs, temps);
-            let r = lower_operand(llcx, rhs, temps);
-            match op {
-                BinOp::Plus => {
-                    if l.is_float_value() || r.is_float_value() {
-                        let l_val = if l.is_int_value() {
-                            llcx.builder
-                                .build_signed_int_to_float(
-                                    l.into_int_value(),
-                                    llcx.context.f64_type(),
-                                    "sitofp",
-                                )
-                                .expect("Failed to build sitofp")
-                        } else {
-                            l.into_float_value()
-                        };
-                        let r_val = if r.is_int_value() {
-                            llcx.builder
-                                .build_signed_int_to_float(
-                                    r.into_int_value(),
-                                    llcx.context.f64_type(),
-                                    "sitofp",
-                                )
-                                .expect("Failed to build sitofp")
-                        } else {
-                            r.into_float_value()
+    /// `dst` is the type of the slot the value is stored to.
+    fn lower_rvalue(&mut self, rv: &Rvalue, dst: BasicTypeEnum<'ctx>) -> BasicValueEnum<'ctx> {
+        match rv {
+            Rvalue::Use(op) => self.lower_operand(op),
+            Rvalue::BinaryOp { op, lhs, rhs, ty } => {
+                let l = self.lower_operand(lhs);
+                let r = self.lower_operand(rhs);
+                if l.is_float_value() || r.is_float_value() {
+                    let l = self.coerce(l, self.llcx.context.f64_type().into());
+                    let r = self.coerce(r, self.llcx.context.f64_type().into());
+                    self.float_binop(*op, l.into_float_value(), r.into_float_value())
+                } else if l.is_pointer_value() {
+                    // `Str` se poredi po sadržaju, kao `strcmp`
+                    let cmp = self.runtime_call("aethc_str_cmp", &[l.into(), r.into()]).into_int_value();
+                    self.int_binop(*op, cmp, cmp.get_type().const_zero(), false)
+                } else {
+                    let unsigned = matches!(ty, MirType::Num(n) if n.is_unsigned());
+                    self.int_binop(*op, l.into_int_value(), r.into_int_value(), unsigned)
+                }
+            }
+            Rvalue::UnaryOp { op, src } => {
+                let v = self.lower_operand(src);
+                let b = &self.llcx.builder;
+                match (op, v) {
+                    (UnOp::Negate, BasicValueEnum::FloatValue(f)) => {
+                        b.build_float_neg(f, "fneg").expect("fneg").into()
+                    }
+                    (UnOp::Negate, v) => b.build_int_neg(v.into_int_value(), "neg").expect("neg").into(),
+                    (UnOp::Not | UnOp::BitNot, v) => b.build_not(v.into_int_value(), "not").expect("not").into(),
+                }
+            }
+            Rvalue::Call { fn_name, args } => {
+                match fn_name.as_str() {
+                    "print" => return self.lower_print(&args[0], false),
+                    "print.u" => return self.lower_print(&args[0], true),
+                    "print.char" => {
+                        let c = self.lower_operand(&args[0]);
+                        return self.runtime_call("aethc_print_char", &[c.into()]);
+                    }
+                    // niz bajtova ide preko memorije: pokazivač i dužina
+                    "print.bytes" => {
+                        let bytes = self.lower_operand(&args[0]).into_array_value();
+                        let b = &self.llcx.builder;
+                        let ptr = b.build_alloca(bytes.get_type(), "bytes").expect("alloca");
+                        let _ = b.build_store(ptr, bytes);
+                        let i8_ptr = self.llcx.context.i8_type().ptr_type(AddressSpace::default());
+                        let ptr = b.build_pointer_cast(ptr, i8_ptr, "bytes").expect("cast");
+                        let len = self.llcx.context.i64_type().const_int(bytes.get_type().len().into(), false);
+                        return self.runtime_call("aethc_print_bytes", &[ptr.into(), len.into()]);
+                    }
+                    "channel" => return self.runtime_call("aethc_chan_new", &[]),
+                    "chan.send" => {
+                        let chan = self.lower_operand(&args[0]);
+                        let word = self.to_word(self.lower_operand(&args[1]));
+                        self.runtime_call("aethc_chan_send", &[chan.into(), word.into()]);
+                        return self.llcx.unit_value();
+                    }
+                    "chan.recv" => {
+                        let chan = self.lower_operand(&args[0]);
+                        let word = self.runtime_call("aethc_chan_recv", &[chan.into()]);
+                        return self.word_to_value(word.into_int_value(), dst);
+                    }
+                    "vec.new" => return self.runtime_call("aethc_vec_new", &[]),
+                    "vec.push" => return self.vec_call("aethc_vec_push", args),
+                    "vec.set" => return self.vec_call("aethc_vec_set", args),
+                    "vec.pop" | "vec.len" | "vec.get" => {
+                        let f = match fn_name.as_str() {
+                            "vec.pop" => "aethc_vec_pop",
+                            "vec.len" => "aethc_vec_len",
+                            _ => "aethc_vec_get",
                         };
-
-                        llcx.builder
-                            .build_float_add(l_val, r_val, "faddtmp")
-                            .expect("Failed to build float add")
-                            .into()
-                    } else {
-                        llcx.builder
-                            .build_int_add(l.into_int_value(), r.into_int_value(), "iaddtmp")
-                            .expect("Failed to build int add")
-                            .into()
+                        let word = self.vec_call(f, args);
+                        return self.word_to_value(word.into_int_value(), dst);
                     }
+                    _ => {}
                 }
-                _ => todo!("other binops"),
+                let f = self
+                    .llcx
+                    .module
+                    .get_function(fn_name)
+                    .unwrap_or_else(|| panic!("unknown function `{fn_name}`"));
+                let params = f.get_type().get_param_types();
+                let vals: Vec<BasicMetadataValueEnum> = args
+                    .iter()
+                    .zip(params)
+                    .map(|(a, ty)| self.coerce(self.lower_operand(a), ty).into())
+                    .collect();
+                let call = self.llcx.builder.build_call(f, &vals, "call").expect("call");
+                call.try_as_basic_value().left().unwrap_or_else(|| self.llcx.unit_value())
+            }
+            Rvalue::CallValue { callee, ty, args } => {
+                let MirType::Fn { params, ret } = ty else { unreachable!("call of a {ty:?}") };
+                let llcx = self.llcx;
+                let b = &llcx.builder;
+                let f = self.lower_operand(callee).into_struct_value();
+                let code = b.build_extract_value(f, 0, "code").expect("extractvalue");
+                let env = b.build_extract_value(f, 1, "env").expect("extractvalue");
+                let fn_ty = llcx.fn_ty(params, ret, true);
+                let code = b
+                    .build_pointer_cast(code.into_pointer_value(), fn_ty.ptr_type(AddressSpace::default()), "fn")
+                    .expect("cast");
+                let mut vals: Vec<BasicMetadataValueEnum> = vec![env.into()];
+                vals.extend(
+                    args.iter()
+                        .zip(params)
+                        .map(|(a, ty)| BasicMetadataValueEnum::from(self.coerce(self.lower_operand(a), llcx.ll_ty(ty)))),
+                );
+                let call = b.build_indirect_call(fn_ty, code, &vals, "call").expect("call");
+                call.try_as_basic_value().left().unwrap_or_else(|| llcx.unit_value())
+            }
+            Rvalue::Closure { fn_name, captures, by_ref } => self.lower_closure(fn_name, captures, *by_ref),
+            Rvalue::Spawn { fn_name, args } => self.lower_spawn(fn_name, args),
+            Rvalue::Array(elems) => {
+                let ty = dst.into_array_type();
+                let vals: Vec<_> = elems.iter().map(|e| self.lower_operand(e)).collect();
+                self.build_array(ty, &vals)
+            }
+            Rvalue::Repeat { .. } => unreachable!("`[x; N]` is written in place by `assign`"),
+            Rvalue::Index { base, index } => {
+                let elem = self.element(self.place_slot(base), std::slice::from_ref(index));
+                self.load(elem)
+            }
+            Rvalue::Tuple(elems) => {
+                let ty = dst.into_struct_type();
+                let mut acc = ty.get_undef();
+                for (i, e) in elems.iter().enumerate() {
+                    let field_ty = ty.get_field_type_at_index(i as u32).expect("tuple field");
+                    let v = self.coerce(self.lower_operand(e), field_ty);
+                    acc = self
+                        .llcx
+                        .builder
+                        .build_insert_value(acc, v, i as u32, "field")
+                        .expect("insertvalue")
+                        .into_struct_value();
+                }
+                acc.into()
+            }
+            Rvalue::Field { base, index } => {
+                let val = self.lower_operand(base).into_struct_value();
+                self.llcx
+                    .builder
+                    .build_extract_value(val, *index as u32, "field")
+                    .expect("extractvalue")
+            }
+            Rvalue::Load(ptr) => {
+                let ptr = self.lower_operand(ptr).into_pointer_value();
+                self.load(Slot { ptr, ty: dst })
             }
+            Rvalue::ToDyn { value, vtable } => self.lower_to_dyn(value, vtable),
+            Rvalue::CallDyn { index, ty, args } => self.lower_call_dyn(*index, ty, args),
         }
-        Rvalue::Call { fn_name, args } => {
-            if fn_name == "print" {
-                let arg_val = lower_operand(llcx, &args[0], temps);
-                if arg_val.is_int_value() {
-                    let f = llcx.module.get_function("aethc_print_int").unwrap();
-                    let _ = llcx.builder.build_call(f, &[arg_val.into()], "");
-                } else {
-                    let f = llcx.module.get_function("aethc_print_str").unwrap();
-                    let _ = llcx.builder.build_call(f, &[arg_val.into()], "");
+    }
+
+    /// Objekat osobine: par (adresa vrednosti, vtable) po vrednosti; na
+    /// heap ide tek u `Vec`-u, vidi `to_word`.
+    fn lower_to_dyn(&self, value: &Operand, vtable: &str) -> BasicValueEnum<'ctx> {
+        let llcx = self.llcx;
+        let b = &llcx.builder;
+        let i8_ptr = llcx.context.i8_type().ptr_type(AddressSpace::default());
+        let pair_ty = llcx.context.struct_type(&[i8_ptr.into(), i8_ptr.into()], false);
+        let data = b.build_pointer_cast(self.lower_operand(value).into_pointer_value(), i8_ptr, "data").expect("cast");
+        let table = llcx.module.get_global(vtable).unwrap_or_else(|| panic!("unknown vtable `{vtable}`"));
+        let table = table.as_pointer_value().const_cast(i8_ptr);
+        let mut pair = pair_ty.get_undef();
+        for (i, v) in [data, table].into_iter().enumerate() {
+            pair = b.build_insert_value(pair, v, i as u32, "obj").expect("insertvalue").into_struct_value();
+        }
+        pair.into()
+    }
+
+    /// Poziv `index`-te metode iz vtable-a objekta `args[0]`; metoda dobija
+    /// adresu vrednosti umesto objekta.
+    fn lower_call_dyn(&self, index: usize, ty: &MirType, args: &[Operand]) -> BasicValueEnum<'ctx> {
+        let MirType::Fn { params, ret } = ty else { unreachable!("call of a {ty:?}") };
+        let llcx = self.llcx;
+        let b = &llcx.builder;
+        let i8_ptr = llcx.context.i8_type().ptr_type(AddressSpace::default());
+        let pair = self.lower_operand(&args[0]).into_struct_value();
+        let data = b.build_extract_value(pair, 0, "data").expect("extractvalue");
+        let table = b.build_extract_value(pair, 1, "vtable").expect("extractvalue").into_pointer_value();
+        let table = b.build_pointer_cast(table, i8_ptr.ptr_type(AddressSpace::default()), "vtable").expect("cast");
+        let i64_ty = llcx.context.i64_type();
+        // SAFETY: the vtable has an entry for every method of the trait
+        let slot = unsafe { b.build_in_bounds_gep(i8_ptr, table, &[i64_ty.const_int(index as u64, false)], "method") }
+            .expect("gep");
+        let code = b.build_load(i8_ptr, slot, "method").expect("load").into_pointer_value();
+        // prvi parametar je adresa vrednosti, `i8*` kao okruženje zatvorenja
+        let fn_ty = llcx.fn_ty(&params[1..], ret, true);
+        let code = b.build_pointer_cast(code, fn_ty.ptr_type(AddressSpace::default()), "fn").expect("cast");
+        let mut vals: Vec<BasicMetadataValueEnum> = vec![data.into()];
+        vals.extend(
+            args[1..]
+                .iter()
+                .zip(&params[1..])
+                .map(|(a, ty)| BasicMetadataValueEnum::from(self.coerce(self.lower_operand(a), llcx.ll_ty(ty)))),
+        );
+        let call = b.build_indirect_call(fn_ty, code, &vals, "call").expect("call");
+        call.try_as_basic_value().left().unwrap_or_else(|| llcx.unit_value())
+    }
+
+    /// Slot of a local or global; a boxed variable lives in its current cell.
+    fn var_slot(&self, v: VarId) -> Slot<'ctx> {
+        match self.cells.get(&v) {
+            Some(cell) => {
+                let ptr_ty = cell.ty.ptr_type(AddressSpace::default());
+                let ptr = self.llcx.builder.build_load(ptr_ty, cell.ptr, "cell").expect("load");
+                Slot { ptr: ptr.into_pointer_value(), ty: cell.ty }
+            }
+            None => self.vars[&v],
+        }
+    }
+
+    /// Fresh heap memory for a value of type `ty`.
+    fn alloc(&self, ty: BasicTypeEnum<'ctx>) -> PointerValue<'ctx> {
+        let raw = self.runtime_call("aethc_alloc", &[ty.size_of().unwrap().into()]);
+        self.llcx
+            .builder
+            .build_pointer_cast(raw.into_pointer_value(), ty.ptr_type(AddressSpace::default()), "cell")
+            .expect("cast")
+    }
+
+    fn new_cell(&self, v: VarId) {
+        let cell = self.cells[&v];
+        let ptr = self.alloc(cell.ty);
+        let _ = self.llcx.builder.build_store(cell.ptr, ptr);
+    }
+
+    /// Function value of closure `fn_name`: its environment is copied to
+    /// the heap, since the closure may outlive this call.
+    fn lower_closure(&self, fn_name: &str, captures: &[VarId], by_ref: bool) -> BasicValueEnum<'ctx> {
+        let llcx = self.llcx;
+        let i8_ptr = llcx.context.i8_type().ptr_type(AddressSpace::default());
+        let f = llcx
+            .module
+            .get_function(fn_name)
+            .unwrap_or_else(|| panic!("unknown closure `{fn_name}`"));
+        let env = if captures.is_empty() {
+            i8_ptr.const_null()
+        } else {
+            let layout = Env { captures: captures.to_vec(), by_ref };
+            let env_ty = llcx.env_ty(&layout, |v| self.var_slot(v).ty);
+            let env = self.alloc(env_ty.into());
+            for (i, v) in captures.iter().enumerate() {
+                let val: BasicValueEnum = match by_ref {
+                    true => self.var_slot(*v).ptr.into(),
+                    false => self.load(self.var_slot(*v)),
+                };
+                let field = llcx.builder.build_struct_gep(env_ty, env, i as u32, "capture").expect("gep");
+                let _ = llcx.builder.build_store(field, val);
+            }
+            llcx.builder.build_pointer_cast(env, i8_ptr, "env").expect("cast")
+        };
+        let code = llcx
+            .builder
+            .build_pointer_cast(f.as_global_value().as_pointer_value(), i8_ptr, "code")
+            .expect("cast");
+        let ty = llcx.context.struct_type(&[i8_ptr.into(), i8_ptr.into()], false);
+        let val = llcx.builder.build_insert_value(ty.get_undef(), code, 0, "fn").expect("insertvalue");
+        llcx.builder.build_insert_value(val, env, 1, "fn").expect("insertvalue").into_struct_value().into()
+    }
+
+    /// Entry of a closure body: captured cells become the cells of its
+    /// variables, captured values are copied into its own slots.
+    fn unpack_env(&self, env: &Env, raw: PointerValue<'ctx>) {
+        let llcx = self.llcx;
+        let env_ty = llcx.env_ty(env, |v| match self.cells.get(&v) {
+            Some(cell) => cell.ty,
+            None => self.vars[&v].ty,
+        });
+        let env_ptr = llcx
+            .builder
+            .build_pointer_cast(raw, env_ty.ptr_type(AddressSpace::default()), "env")
+            .expect("cast");
+        for (i, v) in env.captures.iter().enumerate() {
+            let field_ty = env_ty.get_field_type_at_index(i as u32).unwrap();
+            let field = llcx.builder.build_struct_gep(env_ty, env_ptr, i as u32, "capture").expect("gep");
+            let val = llcx.builder.build_load(field_ty, field, "capture").expect("load");
+            if env.by_ref {
+                let _ = llcx.builder.build_store(self.cells[v].ptr, val);
+            } else {
+                if self.cells.contains_key(v) {
+                    self.new_cell(*v);
                 }
-                llcx.context.i32_type().const_int(0, false).into()
+                self.store(self.var_slot(*v), val);
+            }
+        }
+    }
+
+    /// Array value of type `ty` from `vals`; the builder folds constant
+    /// elements into a constant array.
+    fn build_array(&self, ty: ArrayType<'ctx>, vals: &[BasicValueEnum<'ctx>]) -> BasicValueEnum<'ctx> {
+        let elem_ty = ty.get_element_type();
+        let mut acc = ty.get_undef();
+        for (i, v) in vals.iter().enumerate() {
+            let v = self.coerce(*v, elem_ty);
+            acc = self
+                .llcx
+                .builder
+                .build_insert_value(acc, v, i as u32, "elem")
+                .expect("insertvalue")
+                .into_array_value();
+        }
+        acc.into()
+    }
+
+    /// Address and type of `slot[i][j]..`.
+    fn element(&self, slot: Slot<'ctx>, indices: &[Operand]) -> Slot<'ctx> {
+        let i32_ty = self.llcx.context.i32_type();
+        let mut path = vec![i32_ty.const_zero()];
+        let mut ty = slot.ty;
+        for i in indices {
+            path.push(self.lower_operand(i).into_int_value());
+            ty = ty.into_array_type().get_element_type();
+        }
+        // SAFETY: every index passed its `BoundsCheck`
+        let ptr = unsafe { self.llcx.builder.build_in_bounds_gep(slot.ty, slot.ptr, &path, "elem") }
+            .expect("gep");
+        Slot { ptr, ty }
+    }
+
+    /// Slot of a place: the slot of its base, then a GEP for each field or
+    /// element and a load for each pointer followed.
+    fn place(&self, place: &Place) -> Slot<'ctx> {
+        let mut slot = self.place_slot(&place.base);
+        for proj in &place.proj {
+            slot = match proj {
+                Projection::Field(i) => {
+                    let ty = slot.ty.into_struct_type();
+                    let ptr = self.llcx.builder.build_struct_gep(ty, slot.ptr, *i as u32, "field").expect("gep");
+                    Slot { ptr, ty: ty.get_field_type_at_index(*i as u32).expect("tuple field") }
+                }
+                Projection::Index(i) => self.element(slot, std::slice::from_ref(i)),
+                Projection::Deref(ty) => Slot { ptr: self.load(slot).into_pointer_value(), ty: self.llcx.ll_ty(ty) },
+            };
+        }
+        slot
+    }
+
+    /// `if index >= len (unsigned) { aethc_panic_bounds(..) }`
+    fn bounds_check(&self, index: &Operand, len: &Operand, span: Span) {
+        let llcx = self.llcx;
+        let b = &llcx.builder;
+        let i64_ty = llcx.context.i64_type();
+        let index = b
+            .build_int_s_extend(self.lower_operand(index).into_int_value(), i64_ty, "idx")
+            .expect("sext");
+        let len = b
+            .build_int_s_extend(self.lower_operand(len).into_int_value(), i64_ty, "len")
+            .expect("sext");
+        // negativan indeks je posle sext-a ogroman neoznačen broj
+        let oob = b.build_int_compare(IntPredicate::UGE, index, len, "oob").expect("icmp");
+        let fail = llcx.context.append_basic_block(self.func, "oob");
+        let ok = llcx.context.append_basic_block(self.func, "inbounds");
+        let _ = b.build_conditional_branch(oob, fail, ok);
+
+        b.position_at_end(fail);
+        let name = b
+            .build_global_string_ptr(self.func.get_name().to_str().unwrap(), "fn.name")
+            .expect("string")
+            .as_pointer_value();
+        let i32_ty = llcx.context.i32_type();
+        let line = i32_ty.const_int(span.line as u64, false);
+        let column = i32_ty.const_int(span.column as u64, false);
+        let args = [index.into(), len.into(), name.into(), line.into(), column.into()];
+        self.runtime_call("aethc_panic_bounds", &args);
+        let _ = b.build_unreachable();
+        b.position_at_end(ok);
+    }
+
+    fn runtime_call(&self, name: &str, args: &[BasicMetadataValueEnum<'ctx>]) -> BasicValueEnum<'ctx> {
+        let f = self.llcx.module.get_function(name).unwrap();
+        let call = self.llcx.builder.build_call(f, args, "rt").expect("call");
+        call.try_as_basic_value().left().unwrap_or_else(|| self.llcx.unit_value())
+    }
+
+    /// Poziv `aethc_vec_*`: ručka `Vec`-a, pa indeks i vrednost kao reči.
+    fn vec_call(&self, name: &str, args: &[Operand]) -> BasicValueEnum<'ctx> {
+        let mut vals: Vec<BasicMetadataValueEnum> = vec![self.lower_operand(&args[0]).into()];
+        vals.extend(args[1..].iter().map(|a| BasicMetadataValueEnum::from(self.to_word(self.lower_operand(a)))));
+        self.runtime_call(name, &vals)
+    }
+
+    /// Vrednost koja prolazi kroz kanal ili `Vec`, proširena na 64 bita.
+    fn to_word(&self, val: BasicValueEnum<'ctx>) -> IntValue<'ctx> {
+        let b = &self.llcx.builder;
+        let i64_ty = self.llcx.context.i64_type();
+        match val {
+            BasicValueEnum::IntValue(i) if i.get_type().get_bit_width() == 1 => {
+                b.build_int_z_extend(i, i64_ty, "zext").expect("zext")
+            }
+            BasicValueEnum::IntValue(i) => b.build_int_s_extend(i, i64_ty, "sext").expect("sext"),
+            // `f32` ide kroz 32-bitnu reč
+            BasicValueEnum::FloatValue(f) if f.get_type() == self.llcx.context.f32_type() => {
+                let bits = b.build_bitcast(f, self.llcx.context.i32_type(), "bits").expect("bitcast");
+                b.build_int_z_extend(bits.into_int_value(), i64_ty, "zext").expect("zext")
+            }
+            BasicValueEnum::FloatValue(f) => {
+                b.build_bitcast(f, i64_ty, "bits").expect("bitcast").into_int_value()
+            }
+            BasicValueEnum::PointerValue(p) => b.build_ptr_to_int(p, i64_ty, "addr").expect("ptrtoint"),
+            // `&dyn Trait` ne staje u reč, pa ga `Vec` drži na heap-u kao
+            // i svoje elemente
+            BasicValueEnum::StructValue(pair) if pair.get_type().count_fields() == 2 => {
+                let ptr = self.alloc(pair.get_type().into());
+                let _ = b.build_store(ptr, pair);
+                b.build_ptr_to_int(ptr, i64_ty, "addr").expect("ptrtoint")
+            }
+            _ => i64_ty.const_zero(),
+        }
+    }
+
+    /// Obrnuto od `to_word`: reč iz kanala kao vrednost tipa `ty`.
+    fn word_to_value(&self, word: IntValue<'ctx>, ty: BasicTypeEnum<'ctx>) -> BasicValueEnum<'ctx> {
+        let b = &self.llcx.builder;
+        match ty {
+            BasicTypeEnum::IntType(t) => b.build_int_truncate(word, t, "trunc").expect("trunc").into(),
+            BasicTypeEnum::FloatType(t) if t == self.llcx.context.f32_type() => {
+                let bits = b.build_int_truncate(word, self.llcx.context.i32_type(), "trunc").expect("trunc");
+                b.build_bitcast(bits, t, "float").expect("bitcast")
+            }
+            BasicTypeEnum::FloatType(t) => b.build_bitcast(word, t, "float").expect("bitcast"),
+            BasicTypeEnum::PointerType(t) => b.build_int_to_ptr(word, t, "ptr").expect("inttoptr").into(),
+            BasicTypeEnum::StructType(t) if t.count_fields() == 2 => {
+                let ptr = b.build_int_to_ptr(word, t.ptr_type(AddressSpace::default()), "ptr").expect("inttoptr");
+                b.build_load(t, ptr, "obj").expect("load")
+            }
+            _ => self.llcx.unit_value(),
+        }
+    }
+
+    /// `spawn f(args)`: argumenti se pakuju u strukturu koju runtime kopira
+    /// i predaje trampolinu `f.spawn` u novoj niti.
+    fn lower_spawn(&mut self, fn_name: &str, args: &[Operand]) -> BasicValueEnum<'ctx> {
+        let llcx = self.llcx;
+        let f = llcx
+            .module
+            .get_function(fn_name)
+            .unwrap_or_else(|| panic!("unknown function `{fn_name}`"));
+        let param_tys = f.get_type().get_param_types();
+        let env_ty = llcx.context.struct_type(&param_tys, false);
+        let tramp = self.trampoline(f, env_ty);
+
+        // alloca ide u ulazni blok da petlja ne bi rasla stek
+        let here = llcx.builder.get_insert_block().unwrap();
+        let entry = self.func.get_first_basic_block().unwrap();
+        match entry.get_first_instruction() {
+            Some(first) => llcx.builder.position_before(&first),
+            None => llcx.builder.position_at_end(entry),
+        }
+        let env = llcx.builder.build_alloca(env_ty, "spawn.env").expect("alloca");
+        llcx.builder.position_at_end(here);
+
+        for (i, (a, ty)) in args.iter().zip(&param_tys).enumerate() {
+            let val = self.coerce(self.lower_operand(a), *ty);
+            let field = llcx.builder.build_struct_gep(env_ty, env, i as u32, "field").expect("gep");
+            let _ = llcx.builder.build_store(field, val);
+        }
+
+        let i8_ptr = llcx.context.i8_type().ptr_type(AddressSpace::default());
+        let b = &llcx.builder;
+        let tramp_ptr = b
+            .build_pointer_cast(tramp.as_global_value().as_pointer_value(), i8_ptr, "fn")
+            .expect("cast");
+        let env_ptr = b.build_pointer_cast(env, i8_ptr, "env").expect("cast");
+        let size = env_ty.size_of().unwrap();
+        self.runtime_call("aethc_spawn", &[tramp_ptr.into(), env_ptr.into(), size.into()]);
+        llcx.unit_value()
+    }
+
+    /// `void f.spawn(i8* env)` – raspakuje argumente i poziva `f`.
+    fn trampoline(&self, f: FunctionValue<'ctx>, env_ty: StructType<'ctx>) -> FunctionValue<'ctx> {
+        let llcx = self.llcx;
+        let name = format!("{}.spawn", f.get_name().to_str().unwrap());
+        if let Some(t) = llcx.module.get_function(&name) {
+            return t;
+        }
+        let i8_ptr = llcx.context.i8_type().ptr_type(AddressSpace::default());
+        let fn_ty = llcx.context.void_type().fn_type(&[i8_ptr.into()], false);
+        let tramp = llcx.module.add_function(&name, fn_ty, Some(Linkage::Private));
+
+        let here = llcx.builder.get_insert_block().unwrap();
+        let b = &llcx.builder;
+        b.position_at_end(llcx.context.append_basic_block(tramp, "entry"));
+        let raw = tramp.get_nth_param(0).unwrap().into_pointer_value();
+        let env = b
+            .build_pointer_cast(raw, env_ty.ptr_type(AddressSpace::default()), "env")
+            .expect("cast");
+        let vals: Vec<BasicMetadataValueEnum> = env_ty
+            .get_field_types()
+            .iter()
+            .enumerate()
+            .map(|(i, ty)| {
+                let field = b.build_struct_gep(env_ty, env, i as u32, "field").expect("gep");
+                b.build_load(*ty, field, "arg").expect("load").into()
+            })
+            .collect();
+        let _ = b.build_call(f, &vals, "");
+        let _ = b.build_return(None);
+        b.position_at_end(here);
+        tramp
+    }
+
+    /// `print(arg)`; an `unsigned` integer is printed as one.
+    fn lower_print(&self, arg: &Operand, unsigned: bool) -> BasicValueEnum<'ctx> {
+        let llcx = self.llcx;
+        let val = self.lower_operand(arg);
+        if val.is_pointer_value() {
+            let f = llcx.module.get_function("aethc_print_str").unwrap();
+            let _ = llcx.builder.build_call(f, &[val.into()], "");
+        } else if let BasicValueEnum::IntValue(i) = val
+            && (unsigned || !matches!(i.get_type().get_bit_width(), 1 | 32))
+        {
+            // ostale širine se ispisuju kao 64-bitne
+            let i64_ty = llcx.context.i64_type();
+            let (v, f) = if unsigned {
+                (llcx.builder.build_int_z_extend(i, i64_ty, "zext").expect("zext"), "aethc_print_u64")
             } else {
-                todo!("non builtin call")
+                (llcx.builder.build_int_s_extend(i, i64_ty, "sext").expect("sext"), "aethc_print_i64")
+            };
+            let f = llcx.module.get_function(f).unwrap();
+            let _ = llcx.builder.build_call(f, &[v.into()], "");
+        } else {
+            let i32_ty = llcx.context.i32_type();
+            let v = match val {
+                BasicValueEnum::FloatValue(f) => llcx
+                    .builder
+                    .build_float_to_signed_int(f, i32_ty, "fptosi")
+                    .expect("fptosi"),
+                BasicValueEnum::IntValue(i) if i.get_type().get_bit_width() != 32 => {
+                    llcx.builder.build_int_z_extend(i, i32_ty, "zext").expect("zext")
+                }
+                v => v.into_int_value(),
+            };
+            let f = llcx.module.get_function("aethc_print_int").unwrap();
+            let _ = llcx.builder.build_call(f, &[v.into()], "");
+        }
+        llcx.unit_value()
+    }
+
+    /// `unsigned` operands divide, compare and shift right as unsigned.
+    fn int_binop(&self, op: BinOp, l: IntValue<'ctx>, r: IntValue<'ctx>, unsigned: bool) -> BasicValueEnum<'ctx> {
+        let b = &self.llcx.builder;
+        let cmp = |pred, name| b.build_int_compare(pred, l, r, name).expect("icmp").into();
+        let ord = |signed, unsigned_pred| if unsigned { unsigned_pred } else { signed };
+        if matches!(op, BinOp::Slash | BinOp::Percent) {
+            self.div_check(op, l, r, unsigned);
+        }
+        match op {
+            BinOp::Plus => b.build_int_add(l, r, "iaddtmp").expect("add").into(),
+            BinOp::Minus => b.build_int_sub(l, r, "isubtmp").expect("sub").into(),
+            BinOp::Star => b.build_int_mul(l, r, "imultmp").expect("mul").into(),
+            BinOp::Slash if unsigned => b.build_int_unsigned_div(l, r, "udivtmp").expect("udiv").into(),
+            BinOp::Percent if unsigned => b.build_int_unsigned_rem(l, r, "uremtmp").expect("urem").into(),
+            BinOp::Slash => b.build_int_signed_div(l, r, "idivtmp").expect("sdiv").into(),
+            BinOp::Percent => b.build_int_signed_rem(l, r, "iremtmp").expect("srem").into(),
+            BinOp::AndAnd => b.build_and(l, r, "and").expect("and").into(),
+            BinOp::OrOr => b.build_or(l, r, "or").expect("or").into(),
+            BinOp::BitAnd => b.build_and(l, r, "band").expect("and").into(),
+            BinOp::BitOr => b.build_or(l, r, "bor").expect("or").into(),
+            BinOp::BitXor => b.build_xor(l, r, "bxor").expect("xor").into(),
+            BinOp::Shl => b.build_left_shift(l, self.shift_amount(r), "shl").expect("shl").into(),
+            BinOp::Shr if unsigned => b.build_right_shift(l, self.shift_amount(r), false, "lshr").expect("lshr").into(),
+            BinOp::Shr => b.build_right_shift(l, self.shift_amount(r), true, "ashr").expect("ashr").into(),
+            BinOp::EqEq => cmp(IntPredicate::EQ, "eq"),
+            BinOp::NotEq => cmp(IntPredicate::NE, "ne"),
+            BinOp::Lt => cmp(ord(IntPredicate::SLT, IntPredicate::ULT), "lt"),
+            BinOp::Le => cmp(ord(IntPredicate::SLE, IntPredicate::ULE), "le"),
+            BinOp::Gt => cmp(ord(IntPredicate::SGT, IntPredicate::UGT), "gt"),
+            BinOp::Ge => cmp(ord(IntPredicate::SGE, IntPredicate::UGE), "ge"),
+        }
+    }
+
+    fn float_binop(&self, op: BinOp, l: FloatValue<'ctx>, r: FloatValue<'ctx>) -> BasicValueEnum<'ctx> {
+        let b = &self.llcx.builder;
+        let cmp = |pred, name| b.build_float_compare(pred, l, r, name).expect("fcmp").into();
+        match op {
+            BinOp::Plus => b.build_float_add(l, r, "faddtmp").expect("fadd").into(),
+            BinOp::Minus => b.build_float_sub(l, r, "fsubtmp").expect("fsub").into(),
+            BinOp::Star => b.build_float_mul(l, r, "fmultmp").expect("fmul").into(),
+            BinOp::Slash => b.build_float_div(l, r, "fdivtmp").expect("fdiv").into(),
+            BinOp::Percent => b.build_float_rem(l, r, "fremtmp").expect("frem").into(),
+            BinOp::EqEq => cmp(FloatPredicate::OEQ, "eq"),
+            BinOp::NotEq => cmp(FloatPredicate::UNE, "ne"),
+            BinOp::Lt => cmp(FloatPredicate::OLT, "lt"),
+            BinOp::Le => cmp(FloatPredicate::OLE, "le"),
+            BinOp::Gt => cmp(FloatPredicate::OGT, "gt"),
+            BinOp::Ge => cmp(FloatPredicate::OGE, "ge"),
+            BinOp::AndAnd | BinOp::OrOr => unreachable!("logical op on Float"),
+            BinOp::BitAnd | BinOp::BitOr | BinOp::BitXor | BinOp::Shl | BinOp::Shr => {
+                unreachable!("bitwise op on Float")
             }
         }
-        _ => todo!("unary"),
+    }
+
+    /// `if r == 0 || (signed && l == MIN && r == -1) { aethc_panic_div(..) }`;
+    /// LLVM leaves both cases undefined.
+    fn div_check(&self, op: BinOp, l: IntValue<'ctx>, r: IntValue<'ctx>, unsigned: bool) {
+        let llcx = self.llcx;
+        let b = &llcx.builder;
+        let ty = r.get_type();
+        let mut fails = b.build_int_compare(IntPredicate::EQ, r, ty.const_zero(), "divzero").expect("icmp");
+        if !unsigned {
+            let min = ty.const_int(1 << (ty.get_bit_width() - 1), false);
+            let l_min = b.build_int_compare(IntPredicate::EQ, l, min, "lmin").expect("icmp");
+            let r_neg1 = b.build_int_compare(IntPredicate::EQ, r, ty.const_all_ones(), "rneg1").expect("icmp");
+            let overflow = b.build_and(l_min, r_neg1, "divovf").expect("and");
+            fails = b.build_or(fails, overflow, "divfail").expect("or");
+        }
+        let fail = llcx.context.append_basic_block(self.func, "divfail");
+        let ok = llcx.context.append_basic_block(self.func, "divok");
+        let _ = b.build_conditional_branch(fails, fail, ok);
+
+        b.position_at_end(fail);
+        let name = b
+            .build_global_string_ptr(self.func.get_name().to_str().unwrap(), "fn.name")
+            .expect("string")
+            .as_pointer_value();
+        let i64_ty = llcx.context.i64_type();
+        let divisor = if unsigned {
+            b.build_int_z_extend_or_bit_cast(r, i64_ty, "divisor")
+        } else {
+            b.build_int_s_extend_or_bit_cast(r, i64_ty, "divisor")
+        }
+        .expect("ext");
+        let rem = llcx.context.i32_type().const_int(u64::from(op == BinOp::Percent), false);
+        self.runtime_call("aethc_panic_div", &[name.into(), divisor.into(), rem.into()]);
+        let _ = b.build_unreachable();
+        b.position_at_end(ok);
+    }
+
+    /// Only the low bits of a shift amount count, so shifting by the width
+    /// of the type or more, or by a negative amount, wraps instead of
+    /// producing poison.
+    fn shift_amount(&self, r: IntValue<'ctx>) -> IntValue<'ctx> {
+        let mask = r.get_type().const_int(u64::from(r.get_type().get_bit_width()) - 1, false);
+        self.llcx.builder.build_and(r, mask, "shamt").expect("and")
     }
 }
 
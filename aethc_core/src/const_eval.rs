@@ -0,0 +1,482 @@
+//! const_eval.rs – izračunavanje konstantnih izraza nad HIR-om
+//! Koristi se za `const` stavke i inicijalizatore globalnih `let`-ova.
+//! Izvršava aritmetiku, poređenja i pozive čistih funkcija (bez `print`-a
+//! i bez pisanja u globalne promenljive); prekoračenje i deljenje nulom su
+//! greške u vreme prevođenja.
+
+use crate::hir::{self, BinOp, Expr, NodeId, Stmt, UnOp};
+use crate::type_::{NumTy, Type};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConstValue {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Str(String),
+    Unit,
+    Array(Vec<ConstValue>),
+    Tuple(Vec<ConstValue>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConstError {
+    /// Izraz zavisi od nečega što nije poznato u vreme prevođenja.
+    NotConst(String),
+    Overflow,
+    DivByZero,
+    /// `<<` ili `>>` za negativan broj ili bar onoliko bitova kolika je
+    /// širina tipa levog operanda.
+    ShiftOverflow(i64, NumTy),
+    IndexOutOfBounds { index: i64, len: usize },
+    /// Previše koraka ili preduboka rekurzija.
+    TooExpensive,
+}
+
+impl ConstError {
+    pub fn msg(&self) -> String {
+        match self {
+            ConstError::NotConst(why) => why.clone(),
+            ConstError::Overflow => "integer overflow".to_string(),
+            ConstError::DivByZero => "attempt to divide by zero".to_string(),
+            ConstError::ShiftOverflow(n, NumTy::I32) => format!("attempt to shift an Int by {n} bits"),
+            ConstError::ShiftOverflow(n, ty) => format!("attempt to shift a `{}` by {n} bits", ty.name()),
+            ConstError::IndexOutOfBounds { index, len } => {
+                format!("index out of bounds: the len is {len} but the index is {index}")
+            }
+            ConstError::TooExpensive => "evaluation exceeds the step limit".to_string(),
+        }
+    }
+}
+
+const MAX_STEPS: u32 = 1_000_000;
+const MAX_DEPTH: u32 = 256;
+
+/// Poznate vrednosti (konstante, nepromenljivi globalni `let`-ovi) i tela
+/// funkcija koje se smeju pozvati.
+#[derive(Default)]
+pub struct ConstEnv {
+    values: HashMap<NodeId, ConstValue>,
+    fns: HashMap<NodeId, hir::HirFn>,
+}
+
+impl ConstEnv {
+    pub fn add_fn(&mut self, f: &hir::HirFn) {
+        self.fns.insert(f.id, f.clone());
+    }
+
+    pub fn define(&mut self, id: NodeId, value: ConstValue) {
+        self.values.insert(id, value);
+    }
+
+    pub fn eval(&self, e: &Expr) -> Result<ConstValue, ConstError> {
+        let mut it = Interp { env: self, steps: 0, depth: 0, frames: vec![HashMap::new()] };
+        match it.expr(e)? {
+            Flow::Value(v) => Ok(v),
+            // resolver ne dozvoljava `return`/`break` van funkcije i petlje
+            _ => Err(ConstError::NotConst("unexpected control flow".to_string())),
+        }
+    }
+}
+
+/// Ishod izvršavanja: vrednost ili skok.
+enum Flow {
+    Value(ConstValue),
+    Return(ConstValue),
+    Break,
+    Continue,
+}
+
+/// Propagira skok naviše, inače daje vrednost.
+macro_rules! value {
+    ($flow:expr) => {
+        match $flow {
+            Flow::Value(v) => v,
+            other => return Ok(other),
+        }
+    };
+}
+
+struct Interp<'a> {
+    env: &'a ConstEnv,
+    steps: u32,
+    depth: u32,
+    /// lokalne promenljive – jedan okvir po pozivu funkcije
+    frames: Vec<HashMap<NodeId, ConstValue>>,
+}
+
+impl Interp<'_> {
+    fn tick(&mut self) -> Result<(), ConstError> {
+        self.steps += 1;
+        if self.steps > MAX_STEPS {
+            return Err(ConstError::TooExpensive);
+        }
+        Ok(())
+    }
+
+    fn expr(&mut self, e: &Expr) -> Result<Flow, ConstError> {
+        self.tick()?;
+        let v = match e {
+            Expr::Int { value, .. } => ConstValue::Int(*value),
+            Expr::Float { value, .. } => ConstValue::Float(*value),
+            Expr::Bool { value, .. } => ConstValue::Bool(*value),
+            Expr::Str { value, .. } => ConstValue::Str(value.clone()),
+            Expr::Unit { .. } => ConstValue::Unit,
+            Expr::Ident { id, name, .. } => {
+                let local = self.frames.last().unwrap().get(id);
+                match local.or_else(|| self.env.values.get(id)) {
+                    Some(v) => v.clone(),
+                    None => {
+                        return Err(ConstError::NotConst(format!("`{name}` is not a constant")));
+                    }
+                }
+            }
+            Expr::Builtin { kind, .. } => {
+                return Err(ConstError::NotConst(format!("`{}` is not a constant", kind.name())));
+            }
+            Expr::Spawn { .. } => {
+                return Err(ConstError::NotConst("`spawn` cannot run in a constant".to_string()));
+            }
+            Expr::Channel { .. } => {
+                return Err(ConstError::NotConst("a channel is not a constant".to_string()));
+            }
+            Expr::VecNew { .. } => {
+                return Err(ConstError::NotConst("a Vec is not a constant".to_string()));
+            }
+            Expr::FnRef { .. } | Expr::MethodRef { .. } | Expr::DynMethod { .. } | Expr::Closure { .. } => {
+                return Err(ConstError::NotConst("a function value is not a constant".to_string()));
+            }
+            Expr::Ref { .. } | Expr::Deref { .. } | Expr::ToDyn { .. } => {
+                return Err(ConstError::NotConst("a reference is not a constant".to_string()));
+            }
+            Expr::Variant { .. } | Expr::Try { .. } => {
+                return Err(ConstError::NotConst("an `Option` or a `Result` is not a constant".to_string()));
+            }
+            Expr::Array { elems, .. } => {
+                let mut vals = Vec::with_capacity(elems.len());
+                for e in elems {
+                    vals.push(value!(self.expr(e)?));
+                }
+                ConstValue::Array(vals)
+            }
+            Expr::Repeat { elem, len, .. } => {
+                let v = value!(self.expr(elem)?);
+                ConstValue::Array(vec![v; *len])
+            }
+            Expr::Tuple { elems, .. } => {
+                let mut vals = Vec::with_capacity(elems.len());
+                for e in elems {
+                    vals.push(value!(self.expr(e)?));
+                }
+                ConstValue::Tuple(vals)
+            }
+            Expr::Field { base, index, .. } => {
+                let ConstValue::Tuple(mut vals) = value!(self.expr(base)?) else {
+                    unreachable!("resolver only projects tuples")
+                };
+                vals.swap_remove(*index)
+            }
+            Expr::Index { base, index, .. } => {
+                let ConstValue::Array(mut vals) = value!(self.expr(base)?) else {
+                    unreachable!("resolver only indexes arrays")
+                };
+                let i = value!(self.expr(index)?);
+                let i = checked_index(&i, vals.len())?;
+                vals.swap_remove(i)
+            }
+            Expr::Unary { op, rhs, ty, .. } => {
+                let v = value!(self.expr(rhs)?);
+                let v = match (op, v) {
+                    (UnOp::Negate, ConstValue::Int(i)) => {
+                        ConstValue::Int(i.checked_neg().ok_or(ConstError::Overflow)?)
+                    }
+                    (UnOp::Negate, ConstValue::Float(f)) => ConstValue::Float(-f),
+                    (UnOp::Not, ConstValue::Bool(b)) => ConstValue::Bool(!b),
+                    (UnOp::BitNot, ConstValue::Int(i)) => ConstValue::Int(!i),
+                    (_, v) => return Err(unsupported(&v)),
+                };
+                sized(ty, *op == UnOp::BitNot, v)?
+            }
+            Expr::Binary { op: BinOp::AndAnd, lhs, rhs, .. } => {
+                match value!(self.expr(lhs)?) {
+                    ConstValue::Bool(false) => ConstValue::Bool(false),
+                    _ => value!(self.expr(rhs)?),
+                }
+            }
+            Expr::Binary { op: BinOp::OrOr, lhs, rhs, .. } => {
+                match value!(self.expr(lhs)?) {
+                    ConstValue::Bool(true) => ConstValue::Bool(true),
+                    _ => value!(self.expr(rhs)?),
+                }
+            }
+            Expr::Binary { op, lhs, rhs, ty, .. } => {
+                let l = value!(self.expr(lhs)?);
+                let r = value!(self.expr(rhs)?);
+                let width = lhs.ty().num().map_or(NumTy::I32, |n| n);
+                let bitwise = matches!(op, BinOp::BitAnd | BinOp::BitOr | BinOp::BitXor | BinOp::Shl | BinOp::Shr);
+                sized(ty, bitwise, binary(*op, width, l, r)?)?
+            }
+            Expr::Call { callee, args, .. } => {
+                let mut vals = Vec::new();
+                for a in args {
+                    vals.push(value!(self.expr(a)?));
+                }
+                self.call(callee, vals)?
+            }
+            Expr::Block { block, .. } => value!(self.block(block)?),
+            Expr::If { cond, then_blk, else_branch, .. } => {
+                match value!(self.expr(cond)?) {
+                    ConstValue::Bool(true) => value!(self.block(then_blk)?),
+                    _ => match else_branch {
+                        Some(e) => value!(self.expr(e)?),
+                        None => ConstValue::Unit,
+                    },
+                }
+            }
+            Expr::While { cond, body, .. } => {
+                while value!(self.expr(cond)?) == ConstValue::Bool(true) {
+                    match self.block(body)? {
+                        Flow::Return(v) => return Ok(Flow::Return(v)),
+                        Flow::Break => break,
+                        Flow::Value(_) | Flow::Continue => {}
+                    }
+                }
+                ConstValue::Unit
+            }
+        };
+        Ok(Flow::Value(v))
+    }
+
+    fn call(&mut self, callee: &Expr, args: Vec<ConstValue>) -> Result<ConstValue, ConstError> {
+        let f = match callee {
+            Expr::Ident { id, .. } => self.env.fns.get(id),
+            Expr::Builtin { kind, .. } => {
+                return Err(ConstError::NotConst(format!(
+                    "`{}` cannot be called in a constant",
+                    kind.name()
+                )));
+            }
+            _ => None,
+        };
+        let Some(f) = f else {
+            return Err(ConstError::NotConst("only functions can be called in a constant".to_string()));
+        };
+        if self.depth >= MAX_DEPTH {
+            return Err(ConstError::TooExpensive);
+        }
+
+        let frame = f.params.iter().map(|p| p.id).zip(args).collect();
+        self.frames.push(frame);
+        self.depth += 1;
+        let out = self.block(&f.body);
+        self.depth -= 1;
+        self.frames.pop();
+
+        let v = match out? {
+            Flow::Value(v) | Flow::Return(v) => v,
+            Flow::Break | Flow::Continue => ConstValue::Unit,
+        };
+        // Int → Float promocija povratne vrednosti, kao u resolver-u
+        Ok(match (&f.return_ty, v) {
+            (crate::type_::Type::Float, ConstValue::Int(i)) => ConstValue::Float(i as f64),
+            (_, v) => v,
+        })
+    }
+
+    fn block(&mut self, b: &hir::Block) -> Result<Flow, ConstError> {
+        for st in &b.stmts {
+            self.tick()?;
+            match st {
+                Stmt::Let(l) => {
+                    let v = value!(self.expr(&l.init)?);
+                    self.frames.last_mut().unwrap().insert(l.id, v);
+                }
+                // mesto za kasniju dodelu; `cfg` ne pušta čitanje pre nje
+                Stmt::Decl { id, .. } => {
+                    self.frames.last_mut().unwrap().insert(*id, ConstValue::Unit);
+                }
+                Stmt::Assign { id, name, expr } => {
+                    let v = value!(self.expr(expr)?);
+                    let frame = self.frames.last_mut().unwrap();
+                    let Some(slot) = frame.get_mut(id) else {
+                        return Err(ConstError::NotConst(format!(
+                            "cannot assign to global `{name}` in a constant"
+                        )));
+                    };
+                    *slot = match (&*slot, v) {
+                        (ConstValue::Float(_), ConstValue::Int(i)) => ConstValue::Float(i as f64),
+                        (_, v) => v,
+                    };
+                }
+                Stmt::AssignPlace { target, expr } => {
+                    let v = value!(self.expr(expr)?);
+                    let (root, nodes) = target.place_chain();
+                    // polje torke je indeks koji je uvek u granicama
+                    let mut indices = Vec::new();
+                    for node in nodes {
+                        indices.push(match node {
+                            Expr::Index { index, .. } => value!(self.expr(index)?),
+                            Expr::Field { index, .. } => ConstValue::Int(*index as i64),
+                            _ => unreachable!(),
+                        });
+                    }
+                    let Expr::Ident { id, name, .. } = root else {
+                        return Err(ConstError::NotConst("assignment through a reference in a constant".to_string()));
+                    };
+                    let Some(mut slot) = self.frames.last_mut().unwrap().remove(id) else {
+                        return Err(ConstError::NotConst(format!(
+                            "cannot assign to global `{name}` in a constant"
+                        )));
+                    };
+                    let res = store_at(&mut slot, &indices, v);
+                    self.frames.last_mut().unwrap().insert(*id, slot);
+                    res?;
+                }
+                Stmt::Semi(e) => {
+                    value!(self.expr(e)?);
+                }
+                Stmt::Expr(e) => return self.expr(e),
+                Stmt::Return(e) => {
+                    let v = match e {
+                        Some(e) => value!(self.expr(e)?),
+                        None => ConstValue::Unit,
+                    };
+                    return Ok(Flow::Return(v));
+                }
+                Stmt::Break => return Ok(Flow::Break),
+                Stmt::Continue => return Ok(Flow::Continue),
+            }
+        }
+        Ok(Flow::Value(ConstValue::Unit))
+    }
+}
+
+/// Indeks `i` u nizu dužine `len`, ili greška granica.
+fn checked_index(i: &ConstValue, len: usize) -> Result<usize, ConstError> {
+    let ConstValue::Int(i) = *i else { return Err(unsupported(i)) };
+    if i < 0 || i as usize >= len {
+        return Err(ConstError::IndexOutOfBounds { index: i, len });
+    }
+    Ok(i as usize)
+}
+
+/// Upis `v` u element `slot`-a na putanji `indices`.
+fn store_at(slot: &mut ConstValue, indices: &[ConstValue], v: ConstValue) -> Result<(), ConstError> {
+    let Some((first, rest)) = indices.split_first() else {
+        *slot = match (&*slot, v) {
+            (ConstValue::Float(_), ConstValue::Int(i)) => ConstValue::Float(i as f64),
+            (_, v) => v,
+        };
+        return Ok(());
+    };
+    let (ConstValue::Array(vals) | ConstValue::Tuple(vals)) = slot else {
+        unreachable!("resolver only indexes arrays and tuples")
+    };
+    let i = checked_index(first, vals.len())?;
+    store_at(&mut vals[i], rest, v)
+}
+
+fn unsupported(v: &ConstValue) -> ConstError {
+    ConstError::NotConst(format!("unsupported operand {v:?} in a constant"))
+}
+
+/// `v` as a value of type `ty`: an integer that doesn't fit its type, `Int`
+/// being 32-bit, overflows, or wraps when it comes from a bitwise operator;
+/// an `f32` is rounded.
+fn sized(ty: &Type, bitwise: bool, v: ConstValue) -> Result<ConstValue, ConstError> {
+    let n = match ty {
+        Type::Int => &NumTy::I32,
+        Type::Num(n) => n,
+        _ => return Ok(v),
+    };
+    Ok(match v {
+        ConstValue::Int(i) if bitwise => ConstValue::Int(n.wrap(i)),
+        ConstValue::Int(i) if !n.fits(i) => return Err(ConstError::Overflow),
+        ConstValue::Float(f) if *n == NumTy::F32 => ConstValue::Float(f64::from(f as f32)),
+        v => v,
+    })
+}
+
+/// `l op r`; shifts are limited to the bits of `width`, the type of `l`.
+fn binary(op: BinOp, width: NumTy, l: ConstValue, r: ConstValue) -> Result<ConstValue, ConstError> {
+    use ConstValue::*;
+    Ok(match (l, r) {
+        (Int(a), Int(b)) if width == NumTy::U64 && !matches!(op, BinOp::BitAnd | BinOp::BitOr | BinOp::BitXor) => {
+            binary_u64(op, a as u64, b as u64)?
+        }
+        (Int(a), Int(b)) => match op {
+            BinOp::Plus => Int(a.checked_add(b).ok_or(ConstError::Overflow)?),
+            BinOp::Minus => Int(a.checked_sub(b).ok_or(ConstError::Overflow)?),
+            BinOp::Star => Int(a.checked_mul(b).ok_or(ConstError::Overflow)?),
+            BinOp::Slash | BinOp::Percent if b == 0 => return Err(ConstError::DivByZero),
+            BinOp::Slash => Int(a.checked_div(b).ok_or(ConstError::Overflow)?),
+            BinOp::Percent => Int(a.checked_rem(b).ok_or(ConstError::Overflow)?),
+            BinOp::BitAnd => Int(a & b),
+            BinOp::BitOr => Int(a | b),
+            BinOp::BitXor => Int(a ^ b),
+            BinOp::Shl | BinOp::Shr if !(0..i64::from(width.bits())).contains(&b) => {
+                return Err(ConstError::ShiftOverflow(b, width));
+            }
+            BinOp::Shl => Int(a << b),
+            BinOp::Shr => Int(a >> b),
+            _ => Bool(compare(op, a.cmp(&b))),
+        },
+        (Float(a), Int(b)) => float(op, a, b as f64),
+        (Int(a), Float(b)) => float(op, a as f64, b),
+        (Float(a), Float(b)) => float(op, a, b),
+        (Bool(a), Bool(b)) if matches!(op, BinOp::EqEq | BinOp::NotEq) => {
+            Bool((a == b) == (op == BinOp::EqEq))
+        }
+        (Str(a), Str(b)) if matches!(op, BinOp::EqEq | BinOp::NotEq) => {
+            Bool((a == b) == (op == BinOp::EqEq))
+        }
+        (l, _) => return Err(unsupported(&l)),
+    })
+}
+
+/// `a op b` for `u64`, whose values above `i64::MAX` are kept as their bits.
+fn binary_u64(op: BinOp, a: u64, b: u64) -> Result<ConstValue, ConstError> {
+    use ConstValue::*;
+    let int = |v: Option<u64>| v.map(|v| Int(v as i64)).ok_or(ConstError::Overflow);
+    Ok(match op {
+        BinOp::Plus => int(a.checked_add(b))?,
+        BinOp::Minus => int(a.checked_sub(b))?,
+        BinOp::Star => int(a.checked_mul(b))?,
+        BinOp::Slash | BinOp::Percent if b == 0 => return Err(ConstError::DivByZero),
+        BinOp::Slash => Int((a / b) as i64),
+        BinOp::Percent => Int((a % b) as i64),
+        BinOp::Shl | BinOp::Shr if b >= 64 => return Err(ConstError::ShiftOverflow(b as i64, NumTy::U64)),
+        BinOp::Shl => Int((a << b) as i64),
+        BinOp::Shr => Int((a >> b) as i64),
+        _ => Bool(compare(op, a.cmp(&b))),
+    })
+}
+
+fn float(op: BinOp, a: f64, b: f64) -> ConstValue {
+    match op {
+        BinOp::Plus => ConstValue::Float(a + b),
+        BinOp::Minus => ConstValue::Float(a - b),
+        BinOp::Star => ConstValue::Float(a * b),
+        BinOp::Slash => ConstValue::Float(a / b),
+        BinOp::Percent => ConstValue::Float(a % b),
+        BinOp::EqEq => ConstValue::Bool(a == b),
+        BinOp::NotEq => ConstValue::Bool(a != b),
+        _ => match a.partial_cmp(&b) {
+            Some(ord) => ConstValue::Bool(compare(op, ord)),
+            None => ConstValue::Bool(false), // NaN
+        },
+    }
+}
+
+fn compare(op: BinOp, ord: std::cmp::Ordering) -> bool {
+    use std::cmp::Ordering::*;
+    match op {
+        BinOp::EqEq => ord == Equal,
+        BinOp::NotEq => ord != Equal,
+        BinOp::Lt => ord == Less,
+        BinOp::Le => ord != Greater,
+        BinOp::Gt => ord == Greater,
+        BinOp::Ge => ord != Less,
+        _ => unreachable!("not a comparison: {op:?}"),
+    }
+}
@@ -2,26 +2,122 @@
 //! Potpuno usklađeno sa novim UnOp + Binary/Unary operacijama
 
 pub use crate::ast::BinOp;
+pub use crate::ast::SelfMode;
 pub use crate::ast::UnOp;
+use crate::const_eval::ConstValue;
+use crate::lexer::Span;
 use crate::type_::Type;
+use std::collections::HashMap;
 
 pub type NodeId = u32; // simple counter assigned by resolver
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Builtin {
     Print,
+    /// `send(tx, v)` – šalje vrednost kroz kanal
+    Send,
+    /// `recv(rx)` – čeka sledeću vrednost iz kanala
+    Recv,
+    /// `push(v, x)` – dodaje element na kraj `Vec`-a
+    Push,
+    /// `pop(v)` – uklanja i vraća poslednji element; prazan `Vec` je panika
+    Pop,
+    /// `len(v)` – broj elemenata `Vec`-a
+    Len,
+}
+
+impl Builtin {
+    pub fn name(self) -> &'static str {
+        match self {
+            Builtin::Print => "print",
+            Builtin::Send => "send",
+            Builtin::Recv => "recv",
+            Builtin::Push => "push",
+            Builtin::Pop => "pop",
+            Builtin::Len => "len",
+        }
+    }
+
+    /// Name of the call in MIR; apart from `print`, a dot keeps it from
+    /// clashing with a user function of the same name.
+    pub fn mir_name(self) -> &'static str {
+        match self {
+            Builtin::Print => "print",
+            Builtin::Send => "chan.send",
+            Builtin::Recv => "chan.recv",
+            Builtin::Push => "vec.push",
+            Builtin::Pop => "vec.pop",
+            Builtin::Len => "vec.len",
+        }
+    }
+
+    pub fn arity(self) -> usize {
+        match self {
+            Builtin::Send | Builtin::Push => 2,
+            Builtin::Print | Builtin::Recv | Builtin::Pop | Builtin::Len => 1,
+        }
+    }
 }
 
 /*─────────── HIR root ───────────*/
 #[derive(Debug, Clone)]
 pub struct HirModule {
     pub items: Vec<Item>,
+    /// Vrednosti globalnih `let`-ova čiji je inicijalizator izračunat u
+    /// vreme prevođenja.
+    pub global_values: HashMap<NodeId, ConstValue>,
+    /// Implementacije osobina; njihove metode su funkcije među `items`.
+    pub impls: Vec<HirImpl>,
+    /// Implementacije jezičkih osobina za ugrađene tipove (`impl Add for
+    /// Int`) i funkcije njihovih metoda, koje se prevode samo ako su pozvane.
+    pub lang_impls: Vec<HirImpl>,
+    pub lang_fns: Vec<HirFn>,
+}
+
+/// `impl Trait for self_ty`; u generičkoj implementaciji `self_ty` sadrži
+/// njene tip-parametre.
+#[derive(Debug, Clone)]
+pub struct HirImpl {
+    pub trait_id: NodeId,
+    pub type_params: Vec<String>,
+    pub self_ty: Type,
+    pub methods: Vec<ImplMethod>,
+}
+
+/// Metoda implementacije i funkcija koja je sprovodi.
+#[derive(Debug, Clone)]
+pub struct ImplMethod {
+    pub name: String,
+    pub fn_id: NodeId,
+    pub fn_name: String,
+}
+
+impl HirImpl {
+    /// Types of the impl's parameters if it implements its trait for `ty`.
+    pub fn matches(&self, ty: &Type) -> Option<HashMap<String, Type>> {
+        let mut map = HashMap::new();
+        self.self_ty.matches(ty, &mut map).then_some(map)
+    }
+
+    pub fn method(&self, name: &str) -> &ImplMethod {
+        self.methods.iter().find(|m| m.name == name).expect("resolver checks that impls are complete")
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum Item {
     Fn(HirFn),
     Let(HirLet), // global let 0.1
+    Const(HirConst),
+}
+
+/// `const` stavka; upotrebe su već zamenjene literalom vrednosti.
+#[derive(Debug, Clone)]
+pub struct HirConst {
+    pub id: NodeId,
+    pub name: String,
+    pub ty: Type,
+    pub value: ConstValue,
 }
 
 /*─────────── functions ──────────*/
@@ -29,8 +125,16 @@ pub enum Item {
 pub struct HirFn {
     pub id: NodeId,
     pub name: String,
+    pub file: usize, // index of the source file, see `loader::ModuleTree`
+    pub span: Span,  // the function's name
+    /// Imena tip-parametara; generička funkcija se prevodi tek za konkretne
+    /// tipove, u `mir::lower_module`.
+    pub type_params: Vec<String>,
     pub params: Vec<Param>,
     pub return_ty: Type,
+    /// Metoda sa `&self` ili `&mut self` dobija adresu pozivaočeve
+    /// vrednosti umesto kopije.
+    pub self_mode: Option<SelfMode>,
     pub body: Block,
 }
 
@@ -41,6 +145,14 @@ pub struct Param {
     pub ty: Type,
 }
 
+/// Spoljašnja lokalna promenljiva koju koristi telo zatvorenja.
+#[derive(Debug, Clone)]
+pub struct Capture {
+    pub id: NodeId,
+    pub name: String,
+    pub ty: Type,
+}
+
 /*─────────── let binding ────────*/
 #[derive(Debug, Clone)]
 pub struct HirLet {
@@ -56,15 +168,52 @@ pub struct HirLet {
 pub struct Block {
     pub id: NodeId,
     pub stmts: Vec<Stmt>,
+    /// Start of the source statement each of `stmts` comes from; empty for
+    /// blocks the resolver builds itself.
+    pub spans: Vec<Span>,
 }
 
 #[derive(Debug, Clone)]
 pub enum Stmt {
     Let(HirLet),
+    /// `let x: T;` – promenljiva bez vrednosti do prve dodele
+    Decl { id: NodeId, mutable: bool, name: String, ty: Type },
     Assign { id: NodeId, name: String, expr: Expr },
-    Expr(Expr), // value used
+    /// `a[i].0 = expr;`, `*r = expr;` – `target` je lanac `Index` i `Field`
+    /// izraza nad promenljivom ili nad `Deref`-om reference
+    AssignPlace { target: Expr, expr: Expr },
+    Expr(Expr), // value used – only as the last stmt (block tail)
     Semi(Expr), // value ignored
     Return(Option<Expr>),
+    Break,
+    Continue,
+}
+
+impl Block {
+    /// Trailing expression the block evaluates to, if any.
+    pub fn tail(&self) -> Option<&Expr> {
+        match self.stmts.last() {
+            Some(Stmt::Expr(e)) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// Type of the block's value: the tail's type, or Unit without one.
+    pub fn ty(&self) -> Type {
+        self.tail().map_or(Type::Unit, |e| e.ty().clone())
+    }
+
+    /// True if control never reaches the end of the block because an
+    /// unconditional `return`, `break` or `continue` comes first.
+    pub fn diverges(&self) -> bool {
+        self.stmts.iter().any(|s| match s {
+            Stmt::Return(_) | Stmt::Break | Stmt::Continue => true,
+            Stmt::Decl { .. } => false,
+            Stmt::Let(l) => l.init.diverges(),
+            Stmt::Assign { expr, .. } | Stmt::Expr(expr) | Stmt::Semi(expr) => expr.diverges(),
+            Stmt::AssignPlace { target, expr } => target.diverges() || expr.diverges(),
+        })
+    }
 }
 
 /*─────────── expressions ───────*/
@@ -123,6 +272,175 @@ pub enum Expr {
         rhs: Box<Expr>,
         ty: Type,
     },
+    Block {
+        id: NodeId,
+        block: Block,
+        ty: Type,
+    },
+    If {
+        id: NodeId,
+        cond: Box<Expr>,
+        then_blk: Block,
+        else_branch: Option<Box<Expr>>, // Block or nested If
+        ty: Type,
+    },
+    While {
+        id: NodeId,
+        cond: Box<Expr>,
+        body: Block,
+        ty: Type,
+    },
+    /// Poziv funkcije u novoj niti; vrednost je `()`.
+    Spawn {
+        id: NodeId,
+        callee: Box<Expr>,
+        args: Vec<Expr>,
+        ty: Type,
+    },
+    /// Novi kanal; `ty` je `(Sender<T>, Receiver<T>)` – oba kraja dele isti
+    /// objekat.
+    Channel {
+        id: NodeId,
+        ty: Type,
+    },
+    /// `vec::<T>()`; `ty` je `Vec<T>`
+    VecNew {
+        id: NodeId,
+        ty: Type,
+    },
+    /// `(a, b)`
+    Tuple {
+        id: NodeId,
+        elems: Vec<Expr>,
+        ty: Type,
+    },
+    /// `base.index`
+    Field {
+        id: NodeId,
+        base: Box<Expr>,
+        index: usize,
+        ty: Type,
+    },
+    /// `[a, b, c]`
+    Array {
+        id: NodeId,
+        elems: Vec<Expr>,
+        ty: Type,
+    },
+    /// `[elem; len]`
+    Repeat {
+        id: NodeId,
+        elem: Box<Expr>,
+        len: usize,
+        ty: Type,
+    },
+    /// `base[index]` nad nizom ili `Vec`-om, sa proverom granica u vreme
+    /// izvršavanja
+    Index {
+        id: NodeId,
+        base: Box<Expr>,
+        index: Box<Expr>,
+        span: Span,
+        ty: Type,
+    },
+    /// Imenovana funkcija upotrebljena kao vrednost, a ne pozvana; kao
+    /// pozvana funkcija samo generička, sa `type_args` za njene parametre
+    FnRef {
+        id: NodeId,
+        fn_id: NodeId,
+        name: String,
+        type_args: Vec<Type>,
+        ty: Type,
+    },
+    /// `Trait::method` pozvana za `self_ty`; implementacija se bira kad je
+    /// `self_ty` konkretan, najkasnije pri monomorfizaciji
+    MethodRef {
+        id: NodeId,
+        trait_id: NodeId,
+        trait_name: String,
+        method: String,
+        self_ty: Type,
+        ty: Type,
+    },
+    /// `&expr` / `&mut expr`, i automatska referenca na primaoca metode sa
+    /// `&self` ili `&mut self`
+    Ref {
+        id: NodeId,
+        mutable: bool,
+        expr: Box<Expr>,
+        ty: Type,
+    },
+    /// `*expr`; i primalac metode pozvane preko reference
+    Deref {
+        id: NodeId,
+        expr: Box<Expr>,
+        ty: Type,
+    },
+    /// Referenca na konkretan tip pretvorena u `&dyn Trait`; `methods` su
+    /// metode osobine redom kojim stoje u vtable-u
+    ToDyn {
+        id: NodeId,
+        expr: Box<Expr>,
+        trait_id: NodeId,
+        trait_name: String,
+        methods: Vec<String>,
+        ty: Type,
+    },
+    /// Metoda pozvana preko `&dyn Trait`: `index`-ta u vtable-u. Stoji samo
+    /// kao pozvana funkcija, a primalac je prvi argument poziva
+    DynMethod {
+        id: NodeId,
+        trait_id: NodeId,
+        trait_name: String,
+        method: String,
+        index: usize,
+        ty: Type,
+    },
+    /// `|params| body`; `captures` su spoljašnje lokalne promenljive iz
+    /// tela – po referenci, a uz `move` po vrednosti
+    Closure {
+        id: NodeId,
+        params: Vec<Param>,
+        ret: Type,
+        body: Block,
+        captures: Vec<Capture>,
+        by_move: bool,
+        ty: Type,
+    },
+    /// `Some(x)`, `None`, `Ok(x)` ili `Err(e)`; deo `ty` koji vrednost ne
+    /// određuje je `_` dok ga ne popuni očekivani tip
+    Variant {
+        id: NodeId,
+        variant: Variant,
+        value: Option<Box<Expr>>,
+        ty: Type,
+    },
+    /// `expr?`; `ty` je tip vrednosti u `Some` ili `Ok`
+    Try {
+        id: NodeId,
+        expr: Box<Expr>,
+        ty: Type,
+    },
+}
+
+/// Varijante ugrađenih `Option` i `Result`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    Some,
+    None,
+    Ok,
+    Err,
+}
+
+impl Variant {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Variant::Some => "Some",
+            Variant::None => "None",
+            Variant::Ok => "Ok",
+            Variant::Err => "Err",
+        }
+    }
 }
 
 impl Expr {
@@ -139,12 +457,89 @@ impl Expr {
             | Builtin { ty, .. }
             | Call { ty, .. }
             | Unary { ty, .. }
-            | Binary { ty, .. } => ty,
+            | Binary { ty, .. }
+            | Block { ty, .. }
+            | If { ty, .. }
+            | While { ty, .. }
+            | Spawn { ty, .. }
+            | Channel { ty, .. }
+            | VecNew { ty, .. }
+            | Tuple { ty, .. }
+            | Field { ty, .. }
+            | Array { ty, .. }
+            | Repeat { ty, .. }
+            | Index { ty, .. }
+            | FnRef { ty, .. }
+            | MethodRef { ty, .. }
+            | Ref { ty, .. }
+            | Deref { ty, .. }
+            | ToDyn { ty, .. }
+            | DynMethod { ty, .. }
+            | Closure { ty, .. }
+            | Variant { ty, .. }
+            | Try { ty, .. } => ty,
         }
     }
 
-    /// Treat a block as Unit expression (placeholder until we have real value)
+    /// Wrap a block as an expression; its value is the block's tail.
     pub fn from_block(b: Block) -> Self {
-        Expr::Unit { id: b.id, ty: Type::Unit }
+        Expr::Block {
+            id: b.id,
+            ty: b.ty(),
+            block: b,
+        }
+    }
+
+    /// Root of a place `a[i].0` and its `Index` and `Field` nodes in the
+    /// order they apply (`a[i]`, then `a[i].0`). The root of `(*r).0` is
+    /// the `Deref`.
+    pub fn place_chain(&self) -> (&Expr, Vec<&Expr>) {
+        let mut nodes = Vec::new();
+        let mut cur = self;
+        while let Expr::Index { base, .. } | Expr::Field { base, .. } = cur {
+            nodes.push(cur);
+            cur = base;
+        }
+        nodes.reverse();
+        (cur, nodes)
+    }
+
+    /// True if evaluating the expression never completes normally.
+    pub fn diverges(&self) -> bool {
+        use Expr::*;
+        match self {
+            Block { block, .. } => block.diverges(),
+            If {
+                cond,
+                then_blk,
+                else_branch,
+                ..
+            } => {
+                cond.diverges()
+                    || (then_blk.diverges() && else_branch.as_ref().is_some_and(|e| e.diverges()))
+            }
+            // the body may run zero times
+            While { cond, .. } => cond.diverges(),
+            Unary { rhs, .. } => rhs.diverges(),
+            Binary { lhs, rhs, .. } | Index { base: lhs, index: rhs, .. } => {
+                lhs.diverges() || rhs.diverges()
+            }
+            Array { elems, .. } | Tuple { elems, .. } => elems.iter().any(Expr::diverges),
+            Repeat { elem: e, .. }
+            | Field { base: e, .. }
+            | Ref { expr: e, .. }
+            | Deref { expr: e, .. }
+            | ToDyn { expr: e, .. }
+            | Try { expr: e, .. } => e.diverges(),
+            Variant { value, .. } => value.as_ref().is_some_and(|e| e.diverges()),
+            Call { callee, args, .. } | Spawn { callee, args, .. } => {
+                callee.diverges() || args.iter().any(Expr::diverges)
+            }
+            Ident { .. } | Int { .. } | Float { .. } | Bool { .. } | Unit { .. } | Str { .. }
+            | Builtin { .. } | Channel { .. } | VecNew { .. } | FnRef { .. } | MethodRef { .. }
+            | DynMethod { .. } => false,
+            // the body runs only when the closure is called
+            Closure { .. } => false,
+        }
     }
 }
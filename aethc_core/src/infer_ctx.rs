@@ -1,4 +1,5 @@
 use crate::lexer::Span;
+use crate::type_::{NumTy, Type};
 use std::collections::{HashMap, VecDeque};
 
 pub type TypeVarId = u32;
@@ -13,11 +14,26 @@ pub struct TypeVar {
 pub enum Ty {
     Int,
     Float,
+    Num(NumTy),
     Bool,
+    Char,
     Str,
     Unit,
     Error,
     Var(TypeVarId),
+    /// Type parameter of the function being checked; equal only to itself.
+    Param(String),
+    Sender(Box<Ty>),
+    Receiver(Box<Ty>),
+    Array(Box<Ty>, usize),
+    Vec(Box<Ty>),
+    Tuple(Vec<Ty>),
+    Fn(Vec<Ty>, Box<Ty>),
+    /// `&T`, or `&mut T` when the flag is set.
+    Ref(bool, Box<Ty>),
+    Dyn(String),
+    Option(Box<Ty>),
+    Result(Box<Ty>, Box<Ty>),
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -54,6 +70,12 @@ pub struct InferCtx {
     pub errors: Vec<TypeError>,
 }
 
+impl Default for InferCtx {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl InferCtx {
     pub fn new() -> Self {
         Self {
@@ -72,13 +94,36 @@ impl InferCtx {
         TvOrTy::Var(id)
     }
 
+    /// Require `left` and `right` to be the same type.
+    pub fn constrain(&mut self, left: Ty, right: Ty) {
+        let side = |t| match t {
+            Ty::Var(v) => TvOrTy::Var(v),
+            t => TvOrTy::Ty(t),
+        };
+        self.constraints.push_back(Constraint {
+            left: side(left),
+            right: side(right),
+            left_span: Span::default(),
+            right_span: Span::default(),
+        });
+    }
+
     fn apply_ty(&self, ty: Ty) -> Ty {
+        let app = |t: Box<Ty>| Box::new(self.apply_ty(*t));
         match ty {
             Ty::Var(v) => match self.subst.get(&v) {
                 Some(t) => self.apply_ty(t.clone()),
                 None => Ty::Var(v),
             },
-            Ty::Error => Ty::Error,
+            Ty::Sender(t) => Ty::Sender(app(t)),
+            Ty::Receiver(t) => Ty::Receiver(app(t)),
+            Ty::Array(t, n) => Ty::Array(app(t), n),
+            Ty::Vec(t) => Ty::Vec(app(t)),
+            Ty::Option(t) => Ty::Option(app(t)),
+            Ty::Result(t, e) => Ty::Result(app(t), app(e)),
+            Ty::Ref(m, t) => Ty::Ref(m, app(t)),
+            Ty::Tuple(ts) => Ty::Tuple(ts.into_iter().map(|t| self.apply_ty(t)).collect()),
+            Ty::Fn(ps, r) => Ty::Fn(ps.into_iter().map(|t| self.apply_ty(t)).collect(), app(r)),
             other => other,
         }
     }
@@ -104,52 +149,168 @@ impl InferCtx {
 
             match (a_res, b_res) {
                 (TvOrTy::Var(v), TvOrTy::Ty(ty)) | (TvOrTy::Ty(ty), TvOrTy::Var(v)) => {
-                    self.subst.insert(v, ty);
+                    self.bind(v, ty, &c);
                 }
                 (TvOrTy::Var(v1), TvOrTy::Var(v2)) => {
                     self.subst.insert(v1, Ty::Var(v2));
                 }
-                (TvOrTy::Ty(ty1), TvOrTy::Ty(ty2)) => {
-                    let unified = match (ty1.clone(), ty2.clone()) {
-                        (Ty::Int, Ty::Float) | (Ty::Float, Ty::Int) => Some(Ty::Float),
-                        (ref x, ref y) if x == y => Some(x.clone()),
-                        (found, expected) => {
-                            self.errors.push(TypeError {
-                                kind: TypeErrorKind::Mismatch { found, expected },
-                                primary_span: c.left_span,
-                                secondary_span: c.right_span,
-                            });
-                            Some(Ty::Error)
-                        }
-                    };
-
-                    if let Some(t) = unified {
-                        if t == Ty::Float {
-                            self.subst_promote_float();
+                (TvOrTy::Ty(ty1), TvOrTy::Ty(ty2)) => match (&ty1, &ty2) {
+                    // Int se na vrhu penje u Float: promenljiva vezana za Int
+                    // postaje Float
+                    (Ty::Int, Ty::Float) => self.promote_float(&c.left),
+                    (Ty::Float, Ty::Int) => self.promote_float(&c.right),
+                    _ => {
+                        if !self.unify_exact(ty1.clone(), ty2.clone()) {
+                            self.mismatch(ty1, ty2, &c);
                         }
                     }
-                }
+                },
             }
         }
     }
 
-    fn subst_promote_float(&mut self) {
-        for v in self.subst.values_mut() {
-            if matches!(v, Ty::Int) {
-                *v = Ty::Float;
+    fn mismatch(&mut self, found: Ty, expected: Ty, c: &Constraint) {
+        self.errors.push(TypeError {
+            kind: TypeErrorKind::Mismatch { found, expected },
+            primary_span: c.left_span,
+            secondary_span: c.right_span,
+        });
+    }
+
+    fn bind(&mut self, v: TypeVarId, ty: Ty, c: &Constraint) {
+        if ty.mentions(v) {
+            self.mismatch(Ty::Var(v), ty, c);
+        } else {
+            self.subst.insert(v, ty);
+        }
+    }
+
+    /// Unify the components of structural types; inside them `Int` and
+    /// `Float` stay different types.
+    fn unify_exact(&mut self, a: Ty, b: Ty) -> bool {
+        match (self.apply_ty(a), self.apply_ty(b)) {
+            (x, y) if x == y => true,
+            // `_` u tipu `None` ili `Ok(x)` ne ograničava ništa
+            (Ty::Error, _) | (_, Ty::Error) => true,
+            (Ty::Var(v), t) | (t, Ty::Var(v)) => {
+                if t.mentions(v) {
+                    return false;
+                }
+                self.subst.insert(v, t);
+                true
+            }
+            (Ty::Sender(x), Ty::Sender(y))
+            | (Ty::Receiver(x), Ty::Receiver(y))
+            | (Ty::Vec(x), Ty::Vec(y))
+            | (Ty::Option(x), Ty::Option(y)) => self.unify_exact(*x, *y),
+            (Ty::Result(x, e), Ty::Result(y, f)) => self.unify_exact(*x, *y) && self.unify_exact(*e, *f),
+            (Ty::Array(x, n), Ty::Array(y, m)) => n == m && self.unify_exact(*x, *y),
+            (Ty::Ref(a, x), Ty::Ref(b, y)) => a == b && self.unify_exact(*x, *y),
+            (Ty::Tuple(xs), Ty::Tuple(ys)) => {
+                xs.len() == ys.len() && xs.into_iter().zip(ys).all(|(x, y)| self.unify_exact(x, y))
             }
+            (Ty::Fn(xs, r1), Ty::Fn(ys, r2)) => {
+                xs.len() == ys.len()
+                    && xs.into_iter().zip(ys).all(|(x, y)| self.unify_exact(x, y))
+                    && self.unify_exact(*r1, *r2)
+            }
+            _ => false,
+        }
+    }
+
+    /// The Int side of an Int/Float constraint: if it is a type variable,
+    /// the variable becomes Float.
+    fn promote_float(&mut self, side: &TvOrTy) {
+        let &TvOrTy::Var(mut v) = side else { return };
+        while let Some(Ty::Var(next)) = self.subst.get(&v) {
+            v = *next;
+        }
+        if matches!(self.subst.get(&v), Some(Ty::Int)) {
+            self.subst.insert(v, Ty::Float);
         }
     }
 }
 
 impl Ty {
+    /// Type of the resolver as a `Ty`; type parameters named in `vars` are
+    /// the variables being inferred, the others stay rigid.
+    pub fn from_type(t: &Type, vars: &HashMap<String, TypeVarId>) -> Ty {
+        let from = |t: &Type| Ty::from_type(t, vars);
+        match t {
+            Type::Int => Ty::Int,
+            Type::Float => Ty::Float,
+            Type::Num(n) => Ty::Num(*n),
+            Type::Bool => Ty::Bool,
+            Type::Char => Ty::Char,
+            Type::Str => Ty::Str,
+            Type::Unit => Ty::Unit,
+            Type::Param(name) => match vars.get(name) {
+                Some(v) => Ty::Var(*v),
+                None => Ty::Param(name.clone()),
+            },
+            Type::Sender(t) => Ty::Sender(Box::new(from(t))),
+            Type::Receiver(t) => Ty::Receiver(Box::new(from(t))),
+            Type::Array(t, n) => Ty::Array(Box::new(from(t)), *n),
+            Type::Vec(t) => Ty::Vec(Box::new(from(t))),
+            Type::Option(t) => Ty::Option(Box::new(from(t))),
+            Type::Result(t, e) => Ty::Result(Box::new(from(t)), Box::new(from(e))),
+            Type::Tuple(ts) => Ty::Tuple(ts.iter().map(from).collect()),
+            Type::Fn { params, ret } => Ty::Fn(params.iter().map(from).collect(), Box::new(from(ret))),
+            Type::Ref { mutability, inner, .. } => Ty::Ref(*mutability, Box::new(from(inner))),
+            Type::Dyn(name) => Ty::Dyn(name.clone()),
+            Type::Custom(_) | Type::Infer => Ty::Error,
+        }
+    }
+
+    /// Back to a resolver type; `None` while a type variable is left.
+    pub fn to_type(&self) -> Option<Type> {
+        let to = |t: &Ty| t.to_type().map(Box::new);
+        Some(match self {
+            Ty::Int => Type::Int,
+            Ty::Float => Type::Float,
+            Ty::Num(n) => Type::Num(*n),
+            Ty::Bool => Type::Bool,
+            Ty::Char => Type::Char,
+            Ty::Str => Type::Str,
+            Ty::Unit => Type::Unit,
+            Ty::Param(name) => Type::Param(name.clone()),
+            Ty::Sender(t) => Type::Sender(to(t)?),
+            Ty::Receiver(t) => Type::Receiver(to(t)?),
+            Ty::Array(t, n) => Type::Array(to(t)?, *n),
+            Ty::Vec(t) => Type::Vec(to(t)?),
+            Ty::Option(t) => Type::Option(to(t)?),
+            Ty::Result(t, e) => Type::Result(to(t)?, to(e)?),
+            Ty::Tuple(ts) => Type::Tuple(ts.iter().map(Ty::to_type).collect::<Option<_>>()?),
+            Ty::Fn(ps, r) => Type::Fn { params: ps.iter().map(Ty::to_type).collect::<Option<_>>()?, ret: to(r)? },
+            Ty::Ref(m, t) => Type::Ref { mutability: *m, inner: to(t)?, lifetime: None },
+            Ty::Dyn(name) => Type::Dyn(name.clone()),
+            Ty::Error | Ty::Var(_) => return None,
+        })
+    }
+
+    fn mentions(&self, v: TypeVarId) -> bool {
+        match self {
+            Ty::Var(w) => *w == v,
+            Ty::Sender(t) | Ty::Receiver(t) | Ty::Array(t, _) | Ty::Vec(t) | Ty::Option(t) | Ty::Ref(_, t) => {
+                t.mentions(v)
+            }
+            Ty::Result(t, e) => t.mentions(v) || e.mentions(v),
+            Ty::Tuple(ts) => ts.iter().any(|t| t.mentions(v)),
+            Ty::Fn(ps, r) => ps.iter().any(|t| t.mentions(v)) || r.mentions(v),
+            _ => false,
+        }
+    }
+
+    #[allow(clippy::result_unit_err)]
     pub fn unify(a: &Ty, b: &Ty) -> Result<Ty, ()> {
         use Ty::*;
         match (a, b) {
             (Int, Float) | (Float, Int) => Ok(Float),
             (Int, Int) => Ok(Int),
             (Float, Float) => Ok(Float),
+            (Num(x), Num(y)) if x == y => Ok(Num(*x)),
             (Bool, Bool) => Ok(Bool),
+            (Char, Char) => Ok(Char),
             (Str, Str) => Ok(Str),
             (Unit, Unit) => Ok(Unit),
             (Error, _) | (_, Error) => Ok(Error),
@@ -1,5 +1,8 @@
 // lexer.rs – v0.1-final (floats, escapes, byte-strings, nested comments)
 
+use crate::ast::BinOp;
+use crate::type_::NumTy;
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
 pub struct Span {
     pub start: usize,
@@ -13,6 +16,7 @@ pub enum TokenKind {
     // Keywords
     Let,
     Mut,
+    Const,
     Fn,
     Match,
     If,
@@ -21,24 +25,45 @@ pub enum TokenKind {
     For,
     In,
     Return,
+    Break,
+    Continue,
     Spawn,
     Channel,
     Use,
+    Mod,
+    Pub,
+    Trait,
+    Impl,
+    Dyn,
     // Ident & literals
     Ident(String),
+    Lifetime(String), // `'a`, bez apostrofa
     Int(i64),
     Float(f64),
     Bool(bool),
     Str(String),
     ByteStr(Vec<u8>),
+    Char(char),
+    /// `b'a'`
+    Byte(u8),
+    /// `10u8`, `7i64` – ceo broj sa sufiksom tipa
+    TypedInt(i64, NumTy),
+    /// `2.5f32`, `1f64`
+    TypedFloat(f64, NumTy),
     // Operators & punctuation
     Plus,
     Minus,
     Star,
     Slash,
     Percent,
+    Caret,
+    Tilde,
+    Shl,
+    Shr,
     AndAnd,
     OrOr,
+    Pipe,
+    Amp,
     EqEq,
     NotEq,
     Lt,
@@ -46,7 +71,10 @@ pub enum TokenKind {
     Le,
     Ge,
     Bang,
+    Question,
     Assign,
+    /// `+=`, `-=`, `<<=` … – operator of a compound assignment
+    OpAssign(BinOp),
     Arrow,
     FatArrow,
     Colon,
@@ -54,13 +82,16 @@ pub enum TokenKind {
     Semicolon,
     Comma,
     Dot,
+    DotDot,
+    DotDotEq,
     LParen,
     RParen,
     LBrace,
     RBrace,
     LBracket,
     RBracket,
-    Neg,
+    /// Neispravan token, sa porukom o grešci.
+    Error(String),
     // End of file
     Eof,
 }
@@ -95,14 +126,36 @@ impl<'a> Lexer<'a> {
             return self.make_tok(TokenKind::Eof, 0);
         }
         let ch = self.peek();
+        // `b"` mora pre identifikatora, inače bi `b` bio ime
+        if ch == '"' || (ch == 'b' && self.peek_ahead(1) == Some('"')) {
+            return self.string_like();
+        }
+        if ch == 'b' && self.peek_ahead(1) == Some('\'') {
+            return self.char_like();
+        }
+        // `'a'` je znak, a `'a` bez zatvarajućeg apostrofa lifetime
+        if ch == '\'' && (matches!(self.peek_ahead(1), Some('\\' | '\'')) || self.peek_ahead(2) == Some('\'')) {
+            return self.char_like();
+        }
         if ch.is_ascii_alphabetic() || ch == '_' {
             return self.ident_or_kw();
         }
         if ch.is_ascii_digit() {
             return self.number();
         }
-        if ch == '"' || (ch == 'b' && self.peek_ahead(1) == Some('"')) {
-            return self.string_like();
+        if ch == '\'' && self.peek_ahead(1).is_some_and(|c| c.is_ascii_alphabetic() || c == '_') {
+            let start = self.pos + 1;
+            self.bump(1);
+            while self.peek().is_ascii_alphanumeric() || self.peek() == '_' {
+                self.bump(1);
+            }
+            let name = self.input[start..self.pos].to_string();
+            if self.peek() == '\'' {
+                self.bump(1);
+                let msg = "character literal may only contain one character".to_string();
+                return self.make_tok(TokenKind::Error(msg), self.pos - start + 1);
+            }
+            return self.make_tok(TokenKind::Lifetime(name), self.pos - start + 1);
         }
         self.operator_or_punct()
     }
@@ -165,6 +218,7 @@ impl<'a> Lexer<'a> {
         let kind = match text {
             "let" => TokenKind::Let,
             "mut" => TokenKind::Mut,
+            "const" => TokenKind::Const,
             "fn" => TokenKind::Fn,
             "match" => TokenKind::Match,
             "if" => TokenKind::If,
@@ -173,9 +227,16 @@ impl<'a> Lexer<'a> {
             "for" => TokenKind::For,
             "in" => TokenKind::In,
             "return" => TokenKind::Return,
+            "break" => TokenKind::Break,
+            "continue" => TokenKind::Continue,
             "spawn" => TokenKind::Spawn,
             "channel" => TokenKind::Channel,
             "use" => TokenKind::Use,
+            "mod" => TokenKind::Mod,
+            "pub" => TokenKind::Pub,
+            "trait" => TokenKind::Trait,
+            "impl" => TokenKind::Impl,
+            "dyn" => TokenKind::Dyn,
             "true" => TokenKind::Bool(true),
             "false" => TokenKind::Bool(false),
             _ => TokenKind::Ident(text.to_string()),
@@ -183,119 +244,206 @@ impl<'a> Lexer<'a> {
         self.make_tok(kind, self.pos - start)
     }
 
+    /// Broj: `0x`, `0o` ili `0b` prefiks, `_` između cifara i sufiks tipa
+    /// (`10u8`, `2.5f32`). Nevažeća cifra ili sufiks daje `TokenKind::Error`.
     fn number(&mut self) -> Token {
         let start = self.pos;
-        while self.peek().is_ascii_digit() {
-            self.bump(1);
-        }
-        let mut is_float = false;
-        if self.peek() == '.' && self.peek_ahead(1).map_or(false, |c| c.is_ascii_digit()) {
-            is_float = true;
-            self.bump(1);
+        // `t.0.1` – indeks polja torke je uvek ceo broj, nikad `0.1`
+        let bytes = self.input.as_bytes();
+        let field = start >= 1 && bytes[start - 1] == b'.' && (start < 2 || bytes[start - 2] != b'.');
+        if field {
             while self.peek().is_ascii_digit() {
                 self.bump(1);
             }
+            let v = self.input[start..self.pos].parse::<i64>().unwrap_or(0);
+            return self.make_tok(TokenKind::Int(v), self.pos - start);
         }
-        if matches!(self.peek(), 'e' | 'E') {
-            is_float = true;
+        let radix = match (self.peek(), self.peek_ahead(1)) {
+            ('0', Some('x')) => 16,
+            ('0', Some('o')) => 8,
+            ('0', Some('b')) => 2,
+            _ => 10,
+        };
+        if radix != 10 {
+            self.bump(2);
+        }
+        // cifre svih osnova do 16, da bi `0b12` bila greška, a ne `0b1` i `2`
+        let digits_start = self.pos;
+        while self.peek().is_ascii_digit() || self.peek() == '_' || (radix == 16 && self.peek().is_ascii_hexdigit()) {
             self.bump(1);
-            if matches!(self.peek(), '+' | '-') {
+        }
+        let mut text = self.input[digits_start..self.pos].replace('_', "");
+        let mut is_float = false;
+        if radix == 10 {
+            if self.peek() == '.' && self.peek_ahead(1).is_some_and(|c| c.is_ascii_digit()) {
+                is_float = true;
                 self.bump(1);
+                while self.peek().is_ascii_digit() || self.peek() == '_' {
+                    self.bump(1);
+                }
             }
-            while self.peek().is_ascii_digit() {
-                self.bump(1);
+            let exp_digit = |c: Option<char>| c.is_some_and(|c| c.is_ascii_digit());
+            if matches!(self.peek(), 'e' | 'E')
+                && (exp_digit(self.peek_ahead(1))
+                    || (matches!(self.peek_ahead(1), Some('+' | '-')) && exp_digit(self.peek_ahead(2))))
+            {
+                is_float = true;
+                self.bump(2);
+                while self.peek().is_ascii_digit() || self.peek() == '_' {
+                    self.bump(1);
+                }
             }
+            text = self.input[digits_start..self.pos].replace('_', "");
         }
-        let text = &self.input[start..self.pos].replace('_', "");
-        if is_float {
-            let v = text.parse::<f64>().unwrap_or(0.0);
-            self.make_tok(TokenKind::Float(v), self.pos - start)
-        } else {
-            let v = text.parse::<i64>().unwrap_or(0);
-            self.make_tok(TokenKind::Int(v), self.pos - start)
+        let suffix_start = self.pos;
+        while self.peek().is_ascii_alphanumeric() || self.peek() == '_' {
+            self.bump(1);
         }
+        let suffix = &self.input[suffix_start..self.pos];
+        let kind = match literal(&text, radix, is_float, suffix) {
+            Ok(kind) => kind,
+            Err(msg) => TokenKind::Error(msg),
+        };
+        self.make_tok(kind, self.pos - start)
     }
 
+    /// `"..."` ili `b"..."`; bajtovi smeju biti samo ASCII ili `\xNN`.
     fn string_like(&mut self) -> Token {
+        let start = self.pos;
         let is_bytes = self.peek() == 'b';
         if is_bytes {
             self.bump(1);
         }
         self.bump(1); // opening quote
-        let start = self.pos;
         let mut value = String::new();
+        let mut bytes = Vec::new();
+        let mut error = None;
         while self.peek() != '"' && self.peek() != '\0' {
-            match self.peek() {
-                '\\' => {
-                    // escape
+            let c = match self.peek() {
+                '\\' => self.escape(is_bytes),
+                c if is_bytes && !c.is_ascii() => {
                     self.bump(1);
-                    let esc = self.peek();
-                    match esc {
-                        'n' => {
-                            value.push('\n');
-                            self.bump(1);
-                        }
-                        't' => {
-                            value.push('\t');
-                            self.bump(1);
-                        }
-                        'r' => {
-                            value.push('\r');
-                            self.bump(1);
-                        }
-                        '\\' => {
-                            value.push('\\');
-                            self.bump(1);
-                        }
-                        '"' => {
-                            value.push('"');
-                            self.bump(1);
-                        }
-                        'u' if self.peek_ahead(1) == Some('{') => {
-                            // \u{XXXX}
-                            self.bump(2);
-                            let mut hex = String::new();
-                            while let c @ '0'..='9' | c @ 'a'..='f' | c @ 'A'..='F' = self.peek() {
-                                hex.push(c);
-                                self.bump(1);
-                            }
-                            if self.peek() == '}' {
-                                self.bump(1);
-                            }
-                            if let Ok(code) = u32::from_str_radix(&hex, 16) {
-                                if let Some(ch) = std::char::from_u32(code) {
-                                    value.push(ch);
-                                }
-                            }
-                        }
-                        other => {
-                            value.push(other);
-                            self.bump(1);
-                        }
-                    }
+                    Err("non-ASCII character in a byte string literal".to_string())
                 }
-                ch => {
-                    value.push(ch);
+                c => {
                     self.bump(1);
+                    Ok(c)
                 }
+            };
+            match c {
+                Ok(c) if is_bytes => bytes.push(c as u8),
+                Ok(c) => value.push(c),
+                Err(msg) => error = error.or(Some(msg)),
             }
         }
         self.bump(1); // closing quote
-        if is_bytes {
-            let bytes = value.into_bytes();
-            self.make_tok(
-                TokenKind::ByteStr(bytes),
-                self.pos - start + if is_bytes { 2 } else { 2 },
-            )
+        let kind = match error {
+            Some(msg) => TokenKind::Error(msg),
+            None if is_bytes => TokenKind::ByteStr(bytes),
+            None => TokenKind::Str(value),
+        };
+        self.make_tok(kind, self.pos - start)
+    }
+
+    /// `'a'` ili `b'a'`, sa istim escape-ovima kao u nizovima.
+    fn char_like(&mut self) -> Token {
+        let start = self.pos;
+        let is_byte = self.peek() == 'b';
+        if is_byte {
+            self.bump(1);
+        }
+        self.bump(1); // opening quote
+        let c = match self.peek() {
+            '\'' => Err("empty character literal".to_string()),
+            '\\' => self.escape(is_byte),
+            c if is_byte && !c.is_ascii() => Err("non-ASCII character in a byte literal".to_string()),
+            c => {
+                self.bump(1);
+                Ok(c)
+            }
+        };
+        let c = c.and_then(|c| match self.peek() {
+            '\'' => Ok(c),
+            _ => Err("unterminated character literal: expected `'` after one character".to_string()),
+        });
+        if c.is_ok() {
+            self.bump(1); // closing quote
         } else {
-            self.make_tok(TokenKind::Str(value), self.pos - start + 2)
+            // posle greške se preskače do `'` u istom redu, ako ga ima
+            let rest: String = self.input[self.pos..].chars().take_while(|&c| c != '\n').collect();
+            self.bump(rest.find('\'').map_or(0, |i| rest[..=i].chars().count()));
         }
+        let kind = match c {
+            Ok(c) if is_byte => TokenKind::Byte(c as u8),
+            Ok(c) => TokenKind::Char(c),
+            Err(msg) => TokenKind::Error(msg),
+        };
+        self.make_tok(kind, self.pos - start)
+    }
+
+    /// Escape koji počinje `\` u literalu znaka, niza ili bajta. Bajtovima
+    /// `\xNN` ide do `\xFF`, a znakovima i nizovima samo do `\x7F`.
+    fn escape(&mut self, byte: bool) -> Result<char, String> {
+        self.bump(1); // `\`
+        let esc = self.peek();
+        self.bump(1);
+        Ok(match esc {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '0' => '\0',
+            'x' => {
+                let mut hex = String::new();
+                while hex.len() < 2 && self.peek().is_ascii_hexdigit() {
+                    hex.push(self.peek());
+                    self.bump(1);
+                }
+                let v = match u8::from_str_radix(&hex, 16) {
+                    Ok(v) if hex.len() == 2 => v,
+                    _ => return Err("invalid escape `\\x`: expected two hexadecimal digits".to_string()),
+                };
+                if !byte && v > 0x7F {
+                    return Err(format!("out of range escape `\\x{hex}`: only a byte may go above `\\x7F`"));
+                }
+                char::from(v)
+            }
+            'u' if self.peek() == '{' => {
+                // \u{XXXX}
+                self.bump(1);
+                let mut hex = String::new();
+                while let c @ ('0'..='9' | 'a'..='f' | 'A'..='F') = self.peek() {
+                    hex.push(c);
+                    self.bump(1);
+                }
+                if self.peek() == '}' {
+                    self.bump(1);
+                }
+                if byte {
+                    return Err("unicode escape `\\u{..}` in a byte literal".to_string());
+                }
+                match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                    Some(ch) => ch,
+                    None => return Err(format!("invalid unicode escape `\\u{{{hex}}}`")),
+                }
+            }
+            // `\\`, `\"`, `\'` i ostali znače sami sebe
+            other => other,
+        })
     }
 
     fn operator_or_punct(&mut self) -> Token {
         let start = self.pos;
         let ch1 = self.peek();
         let ch2 = self.peek_ahead(1);
+        // `<<=` and `>>=` before `<<`, `<=` …
+        if let (Some(op), Some('=')) = (shift(ch1, ch2), self.peek_ahead(2)) {
+            self.bump(3);
+            return self.make_tok(TokenKind::OpAssign(op), 3);
+        }
+        if let (Some(op), Some('=')) = (arith(ch1), ch2) {
+            self.bump(2);
+            return self.make_tok(TokenKind::OpAssign(op), 2);
+        }
         let kind = match (ch1, ch2) {
             ('=', Some('=')) => {
                 self.bump(2);
@@ -333,10 +481,21 @@ impl<'a> Lexer<'a> {
                 self.bump(2);
                 TokenKind::DoubleColon
             }
-            // Add this new match arm for the `Neg` token
-            ('~', _) => {
-                self.bump(1);
-                TokenKind::Neg
+            ('.', Some('.')) if self.peek_ahead(2) == Some('=') => {
+                self.bump(3);
+                TokenKind::DotDotEq
+            }
+            ('.', Some('.')) => {
+                self.bump(2);
+                TokenKind::DotDot
+            }
+            ('<', Some('<')) => {
+                self.bump(2);
+                TokenKind::Shl
+            }
+            ('>', Some('>')) => {
+                self.bump(2);
+                TokenKind::Shr
             }
             _ => {
                 let k = match ch1 {
@@ -345,8 +504,13 @@ impl<'a> Lexer<'a> {
                     '*' => TokenKind::Star,
                     '/' => TokenKind::Slash,
                     '%' => TokenKind::Percent,
+                    '^' => TokenKind::Caret,
+                    '~' => TokenKind::Tilde,
                     '=' => TokenKind::Assign,
                     '!' => TokenKind::Bang,
+                    '?' => TokenKind::Question,
+                    '|' => TokenKind::Pipe,
+                    '&' => TokenKind::Amp,
                     '<' => TokenKind::Lt,
                     '>' => TokenKind::Gt,
                     ':' => TokenKind::Colon,
@@ -395,3 +559,80 @@ impl<'a> Lexer<'a> {
         }
     }
 }
+
+/// `<<` or `>>` starting at `a`, `b`.
+fn shift(a: char, b: Option<char>) -> Option<BinOp> {
+    match (a, b) {
+        ('<', Some('<')) => Some(BinOp::Shl),
+        ('>', Some('>')) => Some(BinOp::Shr),
+        _ => None,
+    }
+}
+
+/// One-character operator that may be followed by `=` to assign.
+fn arith(c: char) -> Option<BinOp> {
+    Some(match c {
+        '+' => BinOp::Plus,
+        '-' => BinOp::Minus,
+        '*' => BinOp::Star,
+        '/' => BinOp::Slash,
+        '%' => BinOp::Percent,
+        '&' => BinOp::BitAnd,
+        '|' => BinOp::BitOr,
+        '^' => BinOp::BitXor,
+        _ => return None,
+    })
+}
+
+/// Value of a number literal: its digits without `_` and the prefix, and the
+/// suffix naming its type, if any.
+fn literal(digits: &str, radix: u32, is_float: bool, suffix: &str) -> Result<TokenKind, String> {
+    let base = match radix {
+        2 => "a binary",
+        8 => "an octal",
+        16 => "a hexadecimal",
+        _ => "a decimal",
+    };
+    if let (true, Some(c)) = (digits.is_empty() && radix == 16, suffix.chars().next()) {
+        return Err(format!("invalid digit `{c}` in {base} literal"));
+    }
+    if digits.is_empty() {
+        let prefix = match radix {
+            2 => "0b",
+            8 => "0o",
+            _ => "0x",
+        };
+        return Err(format!("missing digits after the `{prefix}` prefix"));
+    }
+    if let Some(c) = digits.chars().find(|c| !c.is_digit(radix)).filter(|_| !is_float) {
+        return Err(format!("invalid digit `{c}` in {base} literal"));
+    }
+    let ty = match suffix {
+        "" => None,
+        s => match NumTy::from_name(s) {
+            Some(n) if is_float && !n.is_float() => return Err(format!("invalid suffix `{s}` for a float literal")),
+            Some(n) if radix != 10 && n.is_float() => {
+                return Err(format!("invalid suffix `{s}` for {base} literal"));
+            }
+            Some(n) => Some(n),
+            None => return Err(format!("invalid suffix `{s}` for a number literal")),
+        },
+    };
+    if is_float || ty.is_some_and(NumTy::is_float) {
+        let v = digits.parse::<f64>().map_err(|e| e.to_string())?;
+        return Ok(match ty {
+            Some(n) => TokenKind::TypedFloat(v, n),
+            None => TokenKind::Float(v),
+        });
+    }
+    // `u64` iznad `i64::MAX` se čuva kao svoji bitovi
+    let max = if ty == Some(NumTy::U64) { u64::MAX.into() } else { i64::MAX as u128 };
+    let v = u128::from_str_radix(digits, radix)
+        .ok()
+        .filter(|&v| v <= max)
+        .ok_or_else(|| "integer literal is too large".to_string())? as u64 as i64;
+    Ok(match ty {
+        Some(n) => TokenKind::TypedInt(v, n),
+        None => TokenKind::Int(v),
+    })
+}
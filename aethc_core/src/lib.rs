@@ -6,10 +6,14 @@ pub mod parser;
 pub mod resolver;
 pub mod borrowck;
 pub mod borrow;
+pub mod region;
 pub mod infer_ctx;
 pub mod type_inference;
 pub mod test_harness;
 pub mod mir;
+pub mod cfg;
+pub mod const_eval;
+pub mod loader;
 pub mod codegen;
 
 use lexer::Span;
@@ -21,9 +25,7 @@ pub struct LexError {
 }
 
 pub fn parse(src: &str) -> (ast::Module, Vec<LexError>) {
-    // Current lexer does not report errors, so return empty list
-    let module = parser::Parser::new(src).parse_module();
-    (module, Vec::new())
+    parser::Parser::new(src).parse()
 }
 
 pub fn lower_to_hir(ast: &ast::Module, _src: &str) -> (hir::HirModule, Vec<resolver::ResolveError>) {
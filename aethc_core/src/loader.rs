@@ -0,0 +1,112 @@
+//! loader.rs – učitavanje stabla modula sa diska
+//! `mod foo;` u fajlu `dir/main.ae` (ili `dir/…/mod.ae`) traži
+//! `dir/foo.ae` pa `dir/foo/mod.ae`; u fajlu `dir/bar.ae` traži
+//! `dir/bar/foo.ae` pa `dir/bar/foo/mod.ae`.
+
+use crate::lexer::Span;
+use crate::resolver::{FileError, ModuleAst, ResolveError};
+use crate::{ast, parser::Parser};
+use std::path::{Path, PathBuf};
+
+pub struct SourceFile {
+    pub path: PathBuf,
+    pub src: String,
+}
+
+pub struct LoadedModule {
+    pub path: Vec<String>,
+    pub file: usize,
+    pub ast: ast::Module,
+}
+
+/// All files of a program; `modules[0]` is the root.
+pub struct ModuleTree {
+    pub files: Vec<SourceFile>,
+    pub modules: Vec<LoadedModule>,
+}
+
+impl ModuleTree {
+    /// View for `resolver::resolve_modules`.
+    pub fn module_asts(&self) -> Vec<ModuleAst<'_>> {
+        self.modules
+            .iter()
+            .map(|m| ModuleAst { path: m.path.clone(), file: m.file, ast: &m.ast })
+            .collect()
+    }
+}
+
+/// Load `root` and, recursively, every module it declares with `mod`.
+/// On failure the tree holds the files read so far, so errors can name them.
+pub fn load(root: &Path) -> Result<ModuleTree, (ModuleTree, Vec<FileError>)> {
+    let mut tree = ModuleTree { files: Vec::new(), modules: Vec::new() };
+    let mut errors = Vec::new();
+    match std::fs::read_to_string(root) {
+        Ok(src) => load_module(&mut tree, &mut errors, Vec::new(), root.to_path_buf(), src),
+        Err(e) => {
+            tree.files.push(SourceFile { path: root.to_path_buf(), src: String::new() });
+            errors.push(error(0, format!("cannot read `{}`: {e}", root.display())));
+        }
+    }
+    if errors.is_empty() { Ok(tree) } else { Err((tree, errors)) }
+}
+
+fn load_module(
+    tree: &mut ModuleTree,
+    errors: &mut Vec<FileError>,
+    path: Vec<String>,
+    file_path: PathBuf,
+    src: String,
+) {
+    let (ast, lex_errors) = Parser::new(&src).parse();
+    let file = tree.files.len();
+    errors.extend(lex_errors.into_iter().map(|e| FileError { file, err: ResolveError { span: e.span, msg: e.msg } }));
+    tree.files.push(SourceFile { path: file_path.clone(), src });
+
+    // direktorijum u kome se traže podmoduli
+    let dir = file_path.parent().unwrap_or(Path::new("")).to_path_buf();
+    let child_dir = match file_path.file_stem().and_then(|s| s.to_str()) {
+        _ if path.is_empty() => dir,
+        Some("mod") => dir,
+        Some(stem) => dir.join(stem),
+        None => dir,
+    };
+
+    let mut children: Vec<String> = Vec::new();
+    for it in &ast.items {
+        let ast::Item::Mod(name) = it else { continue };
+        if children.contains(name) {
+            errors.push(error(file, format!("module `{name}` is declared more than once")));
+            continue;
+        }
+        children.push(name.clone());
+    }
+    tree.modules.push(LoadedModule { path: path.clone(), file, ast });
+
+    for name in children {
+        let candidates = [
+            child_dir.join(format!("{name}.ae")),
+            child_dir.join(&name).join("mod.ae"),
+        ];
+        let Some((child_path, src)) = candidates
+            .iter()
+            .find_map(|p| std::fs::read_to_string(p).ok().map(|s| (p.clone(), s)))
+        else {
+            errors.push(error(
+                file,
+                format!(
+                    "file not found for module `{name}`: expected `{}` or `{}`",
+                    candidates[0].display(),
+                    candidates[1].display()
+                ),
+            ));
+            continue;
+        };
+        let mut child = path.clone();
+        child.push(name);
+        load_module(tree, errors, child, child_path, src);
+    }
+}
+
+fn error(file: usize, msg: String) -> FileError {
+    FileError { file, err: ResolveError { span: Span::default(), msg } }
+}
@@ -1,26 +1,57 @@
 //! mir.rs - Minimal MIR representation and lowering from HIR
+use crate::const_eval::ConstValue;
 use crate::hir::{self, Expr, Stmt};
+use crate::lexer::Span;
+use crate::type_::{NumTy, Type};
+use std::collections::{HashMap, HashSet};
 
 pub type BlockId = u32;
 pub type TempId = u32;
 pub type VarId = hir::NodeId;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum MirType {
     Int,
     Float,
+    /// `i8` … `u64`, `f32`
+    Num(NumTy),
     Bool,
     Str,
     Unit,
+    /// Either end of a channel – a handle to the runtime channel.
+    Chan,
+    Array(Box<MirType>, usize),
+    /// Handle to a runtime-allocated vector of machine words.
+    Vec,
+    Tuple(Vec<MirType>),
+    /// Function value: pointer to the code and to the closure environment.
+    Fn { params: Vec<MirType>, ret: Box<MirType> },
+    /// Address of a value: a reference, or the receiver of a `&self` method.
+    Ptr(Box<MirType>),
+    /// `&dyn Trait`: handle to the pair of the value's address and the
+    /// vtable of its type.
+    Dyn,
 }
 
 #[derive(Debug, Clone)]
 pub enum Constant {
     Int(i64),
     Float(f64),
+    /// An integer of a type other than `Int`.
+    IntN(NumTy, i64),
+    F32(f32),
     Bool(bool),
     Str(String),
     Unit,
+    /// Only initializes globals; arrays in function bodies are built by
+    /// `Rvalue::Array`.
+    Array(Vec<Constant>),
+    Tuple(Vec<Constant>),
+    /// A named function used as a value.
+    Fn(String),
+    /// All-zero value of a type: the unused payload slot of `None` or of
+    /// the other variant of a `Result`.
+    Zero(MirType),
 }
 
 #[derive(Debug, Clone)]
@@ -28,19 +59,81 @@ pub enum Operand {
     Const(Constant),
     Var(VarId),
     Temp(TempId),
+    /// Address of a variable or temporary: the receiver passed to a
+    /// `&self` or `&mut self` method.
+    Addr(Box<Operand>),
 }
 
 #[derive(Debug, Clone)]
 pub enum Rvalue {
     Use(Operand),
-    BinaryOp { op: hir::BinOp, lhs: Operand, rhs: Operand },
+    /// `ty` is the type of the operands; it picks signed or unsigned
+    /// division, comparison and shift.
+    BinaryOp { op: hir::BinOp, lhs: Operand, rhs: Operand, ty: MirType },
     UnaryOp { op: hir::UnOp, src: Operand },
     Call { fn_name: String, args: Vec<Operand> },
+    /// Call through a function value; `ty` is the callee's `MirType::Fn`.
+    CallValue { callee: Operand, ty: MirType, args: Vec<Operand> },
+    /// Function value of the closure `fn_name`, with an environment holding
+    /// the captured variables – their cells when `by_ref`, else copies.
+    Closure { fn_name: String, captures: Vec<VarId>, by_ref: bool },
+    /// Run `fn_name(args)` on a new thread.
+    Spawn { fn_name: String, args: Vec<Operand> },
+    /// `[a, b, c]`
+    Array(Vec<Operand>),
+    /// `[elem; len]`
+    Repeat { elem: Operand, len: usize },
+    /// `(a, b, c)`
+    Tuple(Vec<Operand>),
+    /// `base.index` of a tuple operand.
+    Field { base: Operand, index: usize },
+    /// Element of an array held in a variable or temporary; the index was
+    /// checked by a preceding `BoundsCheck`.
+    Index { base: Operand, index: Operand },
+    /// Value behind a pointer operand: `*r`.
+    Load(Operand),
+    /// Trait object made of the pointer `value` and the vtable `vtable`.
+    ToDyn { value: Operand, vtable: String },
+    /// Call of the `index`-th method in the vtable of the trait object
+    /// `args[0]`; `ty` is the method's `MirType::Fn`.
+    CallDyn { index: usize, ty: MirType, args: Vec<Operand> },
+}
+
+/// A location in memory: the slot of a variable or temporary, then the
+/// projections in the order they apply.
+#[derive(Debug, Clone)]
+pub struct Place {
+    pub base: Operand,
+    pub proj: Vec<Projection>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Projection {
+    /// Field of a tuple.
+    Field(usize),
+    /// Element of an array; a `BoundsCheck` comes first.
+    Index(Operand),
+    /// What the pointer in the place points to, a value of this type.
+    Deref(MirType),
 }
 
 #[derive(Debug, Clone)]
 pub enum Statement {
     Assign { dst: TempId, rv: Rvalue },
+    /// Write to a local or global variable.
+    AssignVar { var: VarId, rv: Rvalue },
+    /// Write to a field, an element or the target of a reference.
+    AssignPlace { place: Place, rv: Rvalue },
+    /// Panic unless `0 <= index < len`; `span` is where the program reports
+    /// the failure. `len` is a constant for arrays and the result of `len`
+    /// for a `Vec`.
+    BoundsCheck { index: Operand, len: Operand, span: Span },
+    /// Give a variable in `MirBody::boxed` a fresh heap cell, so that the
+    /// closures created from here on share this instance of the variable.
+    NewCell(VarId),
+    /// `let x: T;`: from here on the variable holds no value until it is
+    /// assigned; `cfg::check_body` proves no use comes before that.
+    Uninit(VarId),
     StorageLive(TempId),
     StorageDead(TempId),
 }
@@ -62,39 +155,214 @@ pub struct BasicBlock {
 pub struct MirBody {
     pub blocks: Vec<BasicBlock>,
     pub ret_ty: MirType,
+    pub params: Vec<VarId>,
+    /// Types of parameters and locals.
+    pub vars: HashMap<VarId, MirType>,
+    /// Types of temporaries, indexed by `TempId`.
+    pub temps: Vec<MirType>,
+    /// Variables captured by reference; they live in heap cells, because a
+    /// closure may outlive the call that created it.
+    pub boxed: HashSet<VarId>,
+    /// Set for the body of a closure, which takes its environment as a
+    /// hidden first parameter.
+    pub env: Option<Env>,
+    /// The first parameter is a `&self` or `&mut self` receiver: the
+    /// caller passes the address of its value, which becomes the cell of
+    /// the parameter (it is in `boxed`).
+    pub self_ref: bool,
+    /// Variables declared by `let x: T;`, with their names and whether
+    /// they are `mut`.
+    pub deferred: HashMap<VarId, (String, bool)>,
+    /// Keyword of the `if` or `while` whose condition the `CondBranch`
+    /// ending a block tests, for describing paths in diagnostics.
+    pub branches: HashMap<BlockId, &'static str>,
+    /// Name of the function the body belongs to, or of the function a
+    /// closure is in; diagnostics of `cfg` point at it.
+    pub span: Span,
+    /// Start of the first source statement lowered into each block; `cfg`
+    /// points unreachable code at it.
+    pub stmt_spans: HashMap<BlockId, Span>,
+}
+
+/// Captured variables in the order of the closure's environment.
+#[derive(Debug, Clone)]
+pub struct Env {
+    pub captures: Vec<VarId>,
+    pub by_ref: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct MirGlobal {
+    pub id: VarId,
+    pub name: String,
+    pub ty: MirType,
+    pub mutable: bool,
+    /// Constant-evaluated initializer; `None` means the value is computed
+    /// by `MirModule::init`.
+    pub init: Option<Constant>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MirFn {
+    pub name: String,
+    pub body: MirBody,
+}
+
+/// Functions implementing the methods of a trait for one type, in the
+/// order the trait declares them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Vtable {
+    pub name: String,
+    pub fns: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MirModule {
+    pub globals: Vec<MirGlobal>,
+    pub fns: Vec<MirFn>,
+    /// Stores non-constant global initializers in declaration order; runs
+    /// before `main`.
+    pub init: Option<MirBody>,
+    /// Vtables of the trait objects the functions create.
+    pub vtables: Vec<Vtable>,
 }
 
 pub const RET_TEMP: TempId = u32::MAX;
 
-struct LowerCtx<'a> {
-    hir: &'a hir::Block,
+struct LowerCtx {
     blocks: Vec<BasicBlock>,
     cur_block: BlockId,
-    next_temp: TempId,
+    temps: Vec<MirType>,
+    vars: HashMap<VarId, MirType>,
+    /// The current block already got its terminator (`return`, `break`,
+    /// `continue`); code lowered after it goes into a fresh block that no
+    /// edge leads to.
+    terminated: bool,
+    loops: Vec<LoopTargets>,
+    /// Name of the function being lowered; closures are named after it.
+    fn_name: String,
+    /// Ids of globals; a call of anything that is not a local or a global
+    /// calls a function by name.
+    globals: HashSet<VarId>,
+    boxed: HashSet<VarId>,
+    /// Functions made of the closures in this body.
+    closures: Vec<MirFn>,
+    /// Concrete types of the type parameters when lowering an instance of
+    /// a generic function.
+    subst: HashMap<String, crate::type_::Type>,
+    /// Instances of generic functions this body calls.
+    instances: Vec<Instance>,
+    /// Impls of the module, for calls of trait methods.
+    impls: Vec<hir::HirImpl>,
+    /// Vtables of the trait objects made in this body.
+    vtables: Vec<Vtable>,
+    /// Return type of the body, for the early return of `?`.
+    ret_ty: crate::type_::Type,
+    deferred: HashMap<VarId, (String, bool)>,
+    branches: HashMap<BlockId, &'static str>,
+    span: Span,
+    stmt_spans: HashMap<BlockId, Span>,
+}
+
+/// A generic function with concrete type arguments; `name` is the mangled
+/// name its body is lowered under.
+#[derive(Debug, Clone)]
+struct Instance {
+    fn_id: hir::NodeId,
+    name: String,
+    type_args: Vec<crate::type_::Type>,
+}
+
+/// Body of a function together with what has to be lowered after it.
+struct Lowered {
+    body: MirBody,
+    closures: Vec<MirFn>,
+    instances: Vec<Instance>,
+    vtables: Vec<Vtable>,
+}
+
+#[derive(Clone, Copy)]
+struct LoopTargets {
+    continue_bb: BlockId,
+    break_bb: BlockId,
 }
 
-impl<'a> LowerCtx<'a> {
-    fn new(hir: &'a hir::Block) -> Self {
+impl LowerCtx {
+    fn new(fn_name: &str, globals: &HashSet<VarId>) -> Self {
         Self {
-            hir,
             blocks: vec![BasicBlock { stmts: Vec::new(), term: Terminator::Return }],
             cur_block: 0,
-            next_temp: 0,
+            temps: Vec::new(),
+            vars: HashMap::new(),
+            terminated: false,
+            loops: Vec::new(),
+            fn_name: fn_name.to_string(),
+            globals: globals.clone(),
+            boxed: HashSet::new(),
+            closures: Vec::new(),
+            subst: HashMap::new(),
+            instances: Vec::new(),
+            impls: Vec::new(),
+            vtables: Vec::new(),
+            ret_ty: crate::type_::Type::Unit,
+            deferred: HashMap::new(),
+            branches: HashMap::new(),
+            span: Span::default(),
+            stmt_spans: HashMap::new(),
         }
     }
 
-    fn fresh_temp(&mut self) -> TempId {
-        let t = self.next_temp;
-        self.next_temp += 1;
-        t
+    /// Type of a value in this body; type parameters are replaced first.
+    fn mir_ty(&self, ty: &crate::type_::Type) -> MirType {
+        MirType::from(&ty.subst(&self.subst))
     }
 
-    fn push_stmt(&mut self, stmt: Statement) {
-        self.blocks[self.cur_block as usize].stmts.push(stmt);
+    fn is_unit(&self, ty: &crate::type_::Type) -> bool {
+        ty.subst(&self.subst) == crate::type_::Type::Unit
     }
 
-    fn set_term(&mut self, term: Terminator) {
-        self.blocks[self.cur_block as usize].term = term;
+    /// Lower a function or closure body taking `params`.
+    fn lower_body(mut self, params: &[hir::Param], ret_ty: &crate::type_::Type, body: &hir::Block) -> Lowered {
+        for p in params {
+            self.vars.insert(p.id, self.mir_ty(&p.ty));
+        }
+        by_ref_captures(body, &mut self.boxed);
+        self.ret_ty = ret_ty.subst(&self.subst);
+        let value = self.lower_block(body);
+        if !self.terminated {
+            // falling off the end returns the body's value; without a tail
+            // there is none, which `cfg::check_fn` reports for non-Unit
+            // functions
+            if !self.is_unit(ret_ty) && body.tail().is_some() {
+                self.push_stmt(Statement::Assign { dst: RET_TEMP, rv: Rvalue::Use(value) });
+            }
+            self.set_term(Terminator::Return);
+        }
+        let body = MirBody {
+            ret_ty: self.mir_ty(ret_ty),
+            blocks: self.blocks,
+            params: params.iter().map(|p| p.id).collect(),
+            vars: self.vars,
+            temps: self.temps,
+            boxed: self.boxed,
+            env: None,
+            self_ref: false,
+            deferred: self.deferred,
+            branches: self.branches,
+            span: self.span,
+            stmt_spans: self.stmt_spans,
+        };
+        Lowered { body, closures: self.closures, instances: self.instances, vtables: self.vtables }
+    }
+
+    /// A local variable or global rather than a function name.
+    fn is_variable(&self, id: VarId) -> bool {
+        self.vars.contains_key(&id) || self.globals.contains(&id)
+    }
+
+    fn fresh_temp(&mut self, ty: &crate::type_::Type) -> TempId {
+        self.temps.push(self.mir_ty(ty));
+        (self.temps.len() - 1) as TempId
     }
 
     fn new_block(&mut self) -> BlockId {
@@ -103,47 +371,368 @@ impl<'a> LowerCtx<'a> {
         id
     }
 
+    /// Continue lowering into `bb`.
+    fn switch_to(&mut self, bb: BlockId) {
+        self.cur_block = bb;
+        self.terminated = false;
+    }
+
+    /// Make sure the current block can still take statements.
+    fn ensure_open(&mut self) {
+        if self.terminated {
+            let bb = self.new_block();
+            self.switch_to(bb);
+        }
+    }
+
+    fn push_stmt(&mut self, stmt: Statement) {
+        self.ensure_open();
+        self.blocks[self.cur_block as usize].stmts.push(stmt);
+    }
+
+    fn set_term(&mut self, term: Terminator) {
+        self.ensure_open();
+        self.blocks[self.cur_block as usize].term = term;
+    }
+
+    /// Set the terminator and close the current block.
+    fn terminate(&mut self, term: Terminator) {
+        self.set_term(term);
+        self.terminated = true;
+    }
+
     fn lower_expr(&mut self, e: &Expr) -> Operand {
         use Expr::*;
         match e {
-            Int { value, .. } => Operand::Const(Constant::Int(*value)),
-            Float { value, .. } => Operand::Const(Constant::Float(*value)),
+            Int { value, ty, .. } => Operand::Const(Constant::int(ty, *value)),
+            Float { value, ty, .. } => Operand::Const(Constant::float(ty, *value)),
             Bool { value, .. } => Operand::Const(Constant::Bool(*value)),
             Str { value, .. } => Operand::Const(Constant::Str(value.clone())),
             Unit { .. } => Operand::Const(Constant::Unit),
             Builtin { .. } => Operand::Const(Constant::Unit),
             Ident { id, .. } => Operand::Var(*id),
-            Binary { op, lhs, rhs, .. } => {
+            Binary { op, lhs, rhs, ty, .. } => {
                 let l = self.lower_expr(lhs);
                 let r = self.lower_expr(rhs);
-                let t = self.fresh_temp();
+                let operands = self.mir_ty(lhs.ty());
+                let t = self.fresh_temp(ty);
                 self.push_stmt(Statement::StorageLive(t));
-                self.push_stmt(Statement::Assign { dst: t, rv: Rvalue::BinaryOp { op: *op, lhs: l, rhs: r } });
+                self.push_stmt(Statement::Assign { dst: t, rv: Rvalue::BinaryOp { op: *op, lhs: l, rhs: r, ty: operands } });
                 Operand::Temp(t)
             }
-            Unary { op, rhs, .. } => {
+            Unary { op, rhs, ty, .. } => {
                 let src = self.lower_expr(rhs);
-                let t = self.fresh_temp();
+                let t = self.fresh_temp(ty);
                 self.push_stmt(Statement::StorageLive(t));
                 self.push_stmt(Statement::Assign { dst: t, rv: Rvalue::UnaryOp { op: *op, src } });
                 Operand::Temp(t)
             }
-            Call { callee, args, .. } => {
+            Call { callee, args, ty, .. } => {
                 let name = match &**callee {
-                    Ident { name, .. } => name.clone(),
-                    Builtin { kind: hir::Builtin::Print, .. } => "print".to_string(),
-                    _ => "<fn>".to_string(),
+                    Ident { id, name, .. } if !self.is_variable(*id) => name.clone(),
+                    // neoznačeni celi brojevi se ispisuju kao takvi
+                    Builtin { kind: hir::Builtin::Print, .. } if args[0].ty().num().is_some_and(NumTy::is_unsigned) => {
+                        "print.u".to_string()
+                    }
+                    Builtin { kind: hir::Builtin::Print, .. } if args[0].ty() == &Type::Char => "print.char".to_string(),
+                    Builtin { kind: hir::Builtin::Print, .. } if matches!(args[0].ty(), Type::Array(..)) => {
+                        "print.bytes".to_string()
+                    }
+                    Builtin { kind, .. } => kind.mir_name().to_string(),
+                    // generička funkcija – poziva se njena instanca
+                    FnRef { fn_id, name, type_args, .. } if !type_args.is_empty() => {
+                        let type_args: Vec<_> = type_args.iter().map(|t| t.subst(&self.subst)).collect();
+                        self.instance(*fn_id, name, type_args)
+                    }
+                    MethodRef { trait_id, trait_name, method, self_ty, .. } => {
+                        self.method_callee(*trait_id, trait_name, method, self_ty)
+                    }
+                    DynMethod { index, .. } => {
+                        let a = args.iter().map(|arg| self.lower_expr(arg)).collect();
+                        let rv = Rvalue::CallDyn { index: *index, ty: self.mir_ty(callee.ty()), args: a };
+                        return self.assign_temp(ty, rv);
+                    }
+                    _ => {
+                        let f = self.lower_expr(callee);
+                        let a = args.iter().map(|arg| self.lower_expr(arg)).collect();
+                        let rv = Rvalue::CallValue { callee: f, ty: self.mir_ty(callee.ty()), args: a };
+                        return self.assign_temp(ty, rv);
+                    }
                 };
                 let mut a = Vec::new();
                 for arg in args {
                     a.push(self.lower_expr(arg));
                 }
-                let t = self.fresh_temp();
+                let t = self.fresh_temp(ty);
                 self.push_stmt(Statement::StorageLive(t));
                 self.push_stmt(Statement::Assign { dst: t, rv: Rvalue::Call { fn_name: name, args: a } });
                 Operand::Temp(t)
             }
+            Spawn { callee, args, ty, .. } => {
+                let Ident { name, .. } = &**callee else {
+                    unreachable!("resolver only spawns named functions")
+                };
+                let a = args.iter().map(|arg| self.lower_expr(arg)).collect();
+                let t = self.fresh_temp(ty);
+                self.push_stmt(Statement::StorageLive(t));
+                let rv = Rvalue::Spawn { fn_name: name.clone(), args: a };
+                self.push_stmt(Statement::Assign { dst: t, rv });
+                Operand::Temp(t)
+            }
+            // both ends are the same runtime handle
+            Channel { ty, .. } => {
+                let crate::type_::Type::Tuple(ends) = ty else { unreachable!("channel of type {ty:?}") };
+                let rv = Rvalue::Call { fn_name: "channel".to_string(), args: Vec::new() };
+                let c = self.assign_temp(&ends[0], rv);
+                self.assign_temp(ty, Rvalue::Tuple(vec![c.clone(), c]))
+            }
+            Tuple { elems, ty, .. } => {
+                let ops = elems.iter().map(|e| self.lower_expr(e)).collect();
+                self.assign_temp(ty, Rvalue::Tuple(ops))
+            }
+            // (tag, Some/Ok vrednost[, Err vrednost]); slot druge varijante
+            // ostaje nula
+            Variant { variant, value, ty, .. } => {
+                let ty = ty.subst(&self.subst);
+                let mut slots: Vec<Operand> = match &ty {
+                    crate::type_::Type::Option(t) => vec![Operand::Const(Constant::Zero(MirType::from(&**t)))],
+                    crate::type_::Type::Result(t, e) => {
+                        vec![MirType::from(&**t), MirType::from(&**e)].into_iter().map(|t| Operand::Const(Constant::Zero(t))).collect()
+                    }
+                    _ => unreachable!("variant of type {ty:?}"),
+                };
+                let tag = matches!(variant, hir::Variant::Some | hir::Variant::Ok);
+                if let Some(v) = value {
+                    let slot = if *variant == hir::Variant::Err { 1 } else { 0 };
+                    slots[slot] = self.lower_expr(v);
+                }
+                slots.insert(0, Operand::Const(Constant::Bool(tag)));
+                self.assign_temp(&ty, Rvalue::Tuple(slots))
+            }
+            // `e?`: po tagu ili vrednost iz `Some`/`Ok`, ili povratak sa
+            // `None`/`Err(e)` tipa koji funkcija vraća
+            Try { expr, ty, .. } => {
+                let value = self.lower_expr(expr);
+                let tag = self.assign_temp(&crate::type_::Type::Bool, Rvalue::Field { base: value.clone(), index: 0 });
+                let ok_bb = self.new_block();
+                let err_bb = self.new_block();
+                self.terminate(Terminator::CondBranch { cond: tag, then_bb: ok_bb, else_bb: err_bb });
+
+                self.switch_to(err_bb);
+                let ret_ty = self.ret_ty.clone();
+                let fields = match (&ret_ty, expr.ty()) {
+                    (crate::type_::Type::Option(t), _) => vec![Operand::Const(Constant::Zero(MirType::from(&**t)))],
+                    (crate::type_::Type::Result(t, err_ty), crate::type_::Type::Result(..)) => {
+                        let err = self.assign_temp(err_ty, Rvalue::Field { base: value.clone(), index: 2 });
+                        vec![Operand::Const(Constant::Zero(MirType::from(&**t))), err]
+                    }
+                    _ => unreachable!("resolver checks the return type of `?`"),
+                };
+                let fields = std::iter::once(Operand::Const(Constant::Bool(false))).chain(fields).collect();
+                let ret = self.assign_temp(&ret_ty, Rvalue::Tuple(fields));
+                self.push_stmt(Statement::Assign { dst: RET_TEMP, rv: Rvalue::Use(ret) });
+                self.terminate(Terminator::Return);
+
+                self.switch_to(ok_bb);
+                self.assign_temp(ty, Rvalue::Field { base: value, index: 1 })
+            }
+            Field { base, index, ty, .. } => {
+                let base = self.lower_expr(base);
+                self.assign_temp(ty, Rvalue::Field { base, index: *index })
+            }
+            Array { elems, ty, .. } => {
+                let ops = elems.iter().map(|e| self.lower_expr(e)).collect();
+                self.assign_temp(ty, Rvalue::Array(ops))
+            }
+            Repeat { elem, len, ty, .. } => {
+                let elem = self.lower_expr(elem);
+                self.assign_temp(ty, Rvalue::Repeat { elem, len: *len })
+            }
+            FnRef { name, .. } => Operand::Const(Constant::Fn(name.clone())),
+            MethodRef { .. } | DynMethod { .. } => unreachable!("resolver only calls trait methods"),
+            // `&*r` je isti pokazivač kao `r`
+            Ref { expr, .. } if matches!(**expr, Deref { .. }) => {
+                let Deref { expr, .. } = &**expr else { unreachable!() };
+                self.lower_expr(expr)
+            }
+            // adresa promenljive, a vrednost drugog izraza se prvo smešta u
+            // privremenu
+            Ref { expr, .. } => {
+                let op = match self.lower_expr(expr) {
+                    Operand::Const(c) => self.assign_temp(expr.ty(), Rvalue::Use(Operand::Const(c))),
+                    op => op,
+                };
+                Operand::Addr(Box::new(op))
+            }
+            Deref { expr, ty, .. } => {
+                let ptr = self.lower_expr(expr);
+                self.assign_temp(ty, Rvalue::Load(ptr))
+            }
+            ToDyn { expr, trait_id, trait_name, methods, .. } => {
+                let crate::type_::Type::Ref { inner, .. } = expr.ty() else { unreachable!("object of a reference") };
+                let self_ty = inner.subst(&self.subst);
+                let value = self.lower_expr(expr);
+                let fns = methods.iter().map(|m| self.method_callee(*trait_id, trait_name, m, &self_ty)).collect();
+                let name = format!("<{self_ty:?} as {trait_name}>::vtable");
+                let vtable = Vtable { name: name.clone(), fns };
+                if !self.vtables.contains(&vtable) {
+                    self.vtables.push(vtable);
+                }
+                self.assign_temp(e.ty(), Rvalue::ToDyn { value, vtable: name })
+            }
+            Closure { params, ret, body, captures, by_move, ty, .. } => {
+                let fn_name = format!("{}.closure{}", self.fn_name, self.closures.len());
+                let mut cx = LowerCtx::new(&fn_name, &self.globals);
+                cx.subst = self.subst.clone();
+                cx.impls = self.impls.clone();
+                cx.span = self.span;
+                let ids: Vec<VarId> = captures.iter().map(|c| c.id).collect();
+                for c in captures {
+                    cx.vars.insert(c.id, self.mir_ty(&c.ty));
+                }
+                if !by_move {
+                    cx.boxed.extend(&ids);
+                }
+                let mut lowered = cx.lower_body(params, ret, body);
+                lowered.body.env = Some(Env { captures: ids.clone(), by_ref: !by_move });
+                self.closures.push(MirFn { name: fn_name.clone(), body: lowered.body });
+                self.closures.extend(lowered.closures);
+                self.instances.extend(lowered.instances);
+                self.vtables.extend(lowered.vtables);
+                self.assign_temp(ty, Rvalue::Closure { fn_name, captures: ids, by_ref: !by_move })
+            }
+            VecNew { ty, .. } => {
+                self.assign_temp(ty, Rvalue::Call { fn_name: "vec.new".to_string(), args: Vec::new() })
+            }
+            Index { base, index, span, ty, .. } => {
+                let base_ty = base.ty();
+                let base = self.lower_expr(base);
+                let index = self.lower_expr(index);
+                if let crate::type_::Type::Vec(_) = base_ty {
+                    self.vec_bounds_check(&base, &index, *span);
+                    let rv = Rvalue::Call { fn_name: "vec.get".to_string(), args: vec![base, index] };
+                    return self.assign_temp(ty, rv);
+                }
+                self.push_stmt(Statement::BoundsCheck { index: index.clone(), len: array_len(base_ty), span: *span });
+                self.assign_temp(ty, Rvalue::Index { base, index })
+            }
+            Block { block, .. } => self.lower_block(block),
+            If {
+                cond,
+                then_blk,
+                else_branch,
+                ty,
+                ..
+            } => {
+                let c = self.lower_expr(cond);
+                let then_bb = self.new_block();
+                let join_bb = self.new_block();
+                let else_bb = if else_branch.is_some() { self.new_block() } else { join_bb };
+                self.branches.insert(self.cur_block, "if");
+                self.terminate(Terminator::CondBranch { cond: c, then_bb, else_bb });
+
+                // both branches write the result into the same temp
+                let result = (!self.is_unit(ty)).then(|| self.fresh_temp(ty));
+
+                self.switch_to(then_bb);
+                let v = self.lower_block(then_blk);
+                self.finish_branch(result, v, join_bb);
+
+                if let Some(e) = else_branch {
+                    self.switch_to(else_bb);
+                    let v = self.lower_expr(e);
+                    self.finish_branch(result, v, join_bb);
+                }
+
+                self.switch_to(join_bb);
+                match result {
+                    Some(t) => Operand::Temp(t),
+                    None => Operand::Const(Constant::Unit),
+                }
+            }
+            While { cond, body, .. } => {
+                let head_bb = self.new_block();
+                let body_bb = self.new_block();
+                let exit_bb = self.new_block();
+                self.terminate(Terminator::Goto(head_bb));
+
+                self.switch_to(head_bb);
+                let c = self.lower_expr(cond);
+                self.branches.insert(self.cur_block, "while");
+                self.terminate(Terminator::CondBranch { cond: c, then_bb: body_bb, else_bb: exit_bb });
+
+                self.loops.push(LoopTargets { continue_bb: head_bb, break_bb: exit_bb });
+                self.switch_to(body_bb);
+                self.lower_block(body);
+                if !self.terminated {
+                    self.terminate(Terminator::Goto(head_bb));
+                }
+                self.loops.pop();
+
+                self.switch_to(exit_bb);
+                Operand::Const(Constant::Unit)
+            }
+        }
+    }
+
+    /// Name of the instance of generic function `fn_id` for `type_args`;
+    /// the instance is lowered later, by `lower_module`.
+    fn instance(&mut self, fn_id: hir::NodeId, name: &str, type_args: Vec<crate::type_::Type>) -> String {
+        let name = mangle(name, &type_args);
+        self.instances.push(Instance { fn_id, name: name.clone(), type_args });
+        name
+    }
+
+    /// Function implementing `Trait::method` for `self_ty`, which is
+    /// concrete once the type parameters are substituted. A generic body
+    /// lowered as is calls `Trait::method` itself, see
+    /// `lower_fn_and_closures`.
+    fn method_callee(&mut self, trait_id: hir::NodeId, trait_name: &str, method: &str, self_ty: &crate::type_::Type) -> String {
+        let self_ty = self_ty.subst(&self.subst);
+        let found = self
+            .impls
+            .iter()
+            .filter(|imp| imp.trait_id == trait_id)
+            .find_map(|imp| Some((imp, imp.matches(&self_ty)?)));
+        let Some((imp, map)) = found else {
+            return format!("{trait_name}::{method}");
+        };
+        let m = imp.method(method);
+        if imp.type_params.is_empty() {
+            // već preveden, osim ako je metoda jezičke osobine ugrađenog tipa
+            self.instances.push(Instance { fn_id: m.fn_id, name: m.fn_name.clone(), type_args: Vec::new() });
+            return m.fn_name.clone();
         }
+        let type_args = imp.type_params.iter().map(|p| map[p].clone()).collect();
+        let (fn_id, fn_name) = (m.fn_id, m.fn_name.clone());
+        self.instance(fn_id, &fn_name, type_args)
+    }
+
+    /// Fresh temporary of type `ty` holding `rv`.
+    fn assign_temp(&mut self, ty: &crate::type_::Type, rv: Rvalue) -> Operand {
+        let t = self.fresh_temp(ty);
+        self.push_stmt(Statement::StorageLive(t));
+        self.push_stmt(Statement::Assign { dst: t, rv });
+        Operand::Temp(t)
+    }
+
+    /// `BoundsCheck` against the current length of the vector `vec`.
+    fn vec_bounds_check(&mut self, vec: &Operand, index: &Operand, span: Span) {
+        let rv = Rvalue::Call { fn_name: "vec.len".to_string(), args: vec![vec.clone()] };
+        let len = self.assign_temp(&crate::type_::Type::Int, rv);
+        self.push_stmt(Statement::BoundsCheck { index: index.clone(), len, span });
+    }
+
+    /// End an `if` branch: store its value and jump to the join block,
+    /// unless the branch already left via `return`/`break`/`continue`.
+    fn finish_branch(&mut self, result: Option<TempId>, value: Operand, join_bb: BlockId) {
+        if self.terminated {
+            return;
+        }
+        if let Some(t) = result {
+            self.push_stmt(Statement::Assign { dst: t, rv: Rvalue::Use(value) });
+        }
+        self.terminate(Terminator::Goto(join_bb));
     }
 
     fn lower_stmt(&mut self, s: &Stmt) {
@@ -151,11 +740,27 @@ impl<'a> LowerCtx<'a> {
         match s {
             Let(l) => {
                 let op = self.lower_expr(&l.init);
-                self.push_stmt(Statement::Assign { dst: l.id, rv: Rvalue::Use(op) });
+                self.vars.insert(l.id, self.mir_ty(&l.ty));
+                if self.boxed.contains(&l.id) {
+                    self.push_stmt(Statement::NewCell(l.id));
+                }
+                self.push_stmt(Statement::AssignVar { var: l.id, rv: Rvalue::Use(op) });
+            }
+            Decl { id, mutable, name, ty } => {
+                self.vars.insert(*id, self.mir_ty(ty));
+                self.deferred.insert(*id, (name.clone(), *mutable));
+                if self.boxed.contains(id) {
+                    self.push_stmt(Statement::NewCell(*id));
+                }
+                self.push_stmt(Statement::Uninit(*id));
             }
             Assign { id, expr, .. } => {
                 let op = self.lower_expr(expr);
-                self.push_stmt(Statement::Assign { dst: *id, rv: Rvalue::Use(op) });
+                self.push_stmt(Statement::AssignVar { var: *id, rv: Rvalue::Use(op) });
+            }
+            AssignPlace { target, expr } => {
+                let op = self.lower_expr(expr);
+                self.assign_place(target, op);
             }
             Expr(e) | Semi(e) => {
                 self.lower_expr(e);
@@ -165,27 +770,327 @@ impl<'a> LowerCtx<'a> {
                     let op = self.lower_expr(e);
                     self.push_stmt(Statement::Assign { dst: RET_TEMP, rv: Rvalue::Use(op) });
                 }
-                self.set_term(Terminator::Return);
+                self.terminate(Terminator::Return);
+            }
+            Break | Continue => {
+                let targets = *self.loops.last().expect("resolver rejects break outside loops");
+                let bb = if matches!(s, Break) { targets.break_bb } else { targets.continue_bb };
+                self.terminate(Terminator::Goto(bb));
             }
         }
     }
 
-    fn lower_block(&mut self, block: &hir::Block) {
-        for stmt in &block.stmts {
-            self.lower_stmt(stmt);
-            if matches!(self.blocks[self.cur_block as usize].term, Terminator::Return) {
-                break;
+    /// Store `value` into the place `target`. An element of a `Vec` has no
+    /// address in the body, so `v[i] = x` is a runtime call.
+    fn assign_place(&mut self, target: &Expr, value: Operand) {
+        if let Expr::Index { base, index, span, .. } = target
+            && let crate::type_::Type::Vec(_) = base.ty()
+        {
+            let vec = self.lower_expr(base);
+            let index = self.lower_expr(index);
+            self.vec_bounds_check(&vec, &index, *span);
+            let rv = Rvalue::Call { fn_name: "vec.set".to_string(), args: vec![vec, index, value] };
+            self.assign_temp(&crate::type_::Type::Unit, rv);
+            return;
+        }
+        let place = self.place(target);
+        self.push_stmt(Statement::AssignPlace { place, rv: Rvalue::Use(value) });
+    }
+
+    /// The place `e` names. A `Vec` only holds values of one word, so a
+    /// place inside an element is behind a reference the element holds,
+    /// and a copy of the element is as good as the element.
+    fn place(&mut self, e: &Expr) -> Place {
+        match e {
+            Expr::Ident { id, .. } => Place { base: Operand::Var(*id), proj: Vec::new() },
+            Expr::Field { base, index, .. } => {
+                let mut place = self.place(base);
+                place.proj.push(Projection::Field(*index));
+                place
+            }
+            Expr::Index { base, .. } if matches!(base.ty(), crate::type_::Type::Vec(_)) => {
+                Place { base: self.lower_expr(e), proj: Vec::new() }
+            }
+            Expr::Index { base, index, span, .. } => {
+                let mut place = self.place(base);
+                let index = self.lower_expr(index);
+                self.push_stmt(Statement::BoundsCheck { index: index.clone(), len: array_len(base.ty()), span: *span });
+                place.proj.push(Projection::Index(index));
+                place
             }
+            // `*&mut x` je sama promenljiva
+            Expr::Deref { expr, ty, .. } => match self.lower_expr(expr) {
+                Operand::Addr(inner) => Place { base: *inner, proj: Vec::new() },
+                ptr => Place { base: ptr, proj: vec![Projection::Deref(self.mir_ty(ty))] },
+            },
+            _ => unreachable!("resolver only assigns to places"),
         }
     }
+
+    /// Lower the statements of `block` and return the operand holding its
+    /// value (the tail), or Unit for a block without tail.
+    fn lower_block(&mut self, block: &hir::Block) -> Operand {
+        for (i, stmt) in block.stmts.iter().enumerate() {
+            // code after `return`, `break` or `continue` starts a block no
+            // branch leads to, even when it lowers to no statement
+            if let Some(&span) = block.spans.get(i) {
+                self.ensure_open();
+                self.stmt_spans.entry(self.cur_block).or_insert(span);
+            }
+            if let Stmt::Expr(tail) = stmt {
+                return self.lower_expr(tail);
+            }
+            self.lower_stmt(stmt);
+        }
+        Operand::Const(Constant::Unit)
+    }
 }
 
+/// Lower one function. Closures in its body are lowered too, but only
+/// `lower_fn_and_closures` and `lower_module` keep them.
 pub fn lower_fn(hir_fn: &hir::HirFn) -> MirBody {
-    let mut cx = LowerCtx::new(&hir_fn.body);
-    cx.lower_block(&hir_fn.body);
-    MirBody {
-        blocks: cx.blocks,
-        ret_ty: MirType::from(&hir_fn.return_ty),
+    lower_fn_with(hir_fn, &HashSet::new(), &[], &hir_fn.name, HashMap::new()).body
+}
+
+/// The function followed by the functions made of its closures. A generic
+/// function is lowered as is, with its type parameters as `()`; this is
+/// enough for checks of the control flow, not for code generation.
+pub fn lower_fn_and_closures(hir_fn: &hir::HirFn) -> Vec<MirFn> {
+    let lowered = lower_fn_with(hir_fn, &HashSet::new(), &[], &hir_fn.name, HashMap::new());
+    let mut fns = vec![MirFn { name: hir_fn.name.clone(), body: lowered.body }];
+    fns.extend(lowered.closures);
+    fns
+}
+
+fn lower_fn_with(
+    hir_fn: &hir::HirFn,
+    globals: &HashSet<VarId>,
+    impls: &[hir::HirImpl],
+    name: &str,
+    subst: HashMap<String, crate::type_::Type>,
+) -> Lowered {
+    let mut cx = LowerCtx::new(name, globals);
+    cx.subst = subst;
+    cx.impls = impls.to_vec();
+    cx.span = hir_fn.span;
+    let self_ref = matches!(hir_fn.self_mode, Some(hir::SelfMode::Ref | hir::SelfMode::RefMut));
+    if self_ref {
+        cx.boxed.insert(hir_fn.params[0].id);
+    }
+    let mut lowered = cx.lower_body(&hir_fn.params, &hir_fn.return_ty, &hir_fn.body);
+    lowered.body.self_ref = self_ref;
+    lowered
+}
+
+/// Symbol of the instance of generic function `name` for `type_args`, e.g.
+/// `max<Int>` or `pair<Int,(Bool,String)>`.
+pub fn mangle(name: &str, type_args: &[crate::type_::Type]) -> String {
+    let args: Vec<String> = type_args.iter().map(|t| format!("{t:?}").replace(' ', "")).collect();
+    format!("{name}<{}>", args.join(","))
+}
+
+/// Variables that non-`move` closures directly in `block` capture; closures
+/// nested deeper are handled when their parent closure is lowered.
+fn by_ref_captures(block: &hir::Block, out: &mut HashSet<VarId>) {
+    for st in &block.stmts {
+        match st {
+            Stmt::Let(l) => expr_captures(&l.init, out),
+            Stmt::Assign { expr, .. } | Stmt::Expr(expr) | Stmt::Semi(expr) | Stmt::Return(Some(expr)) => {
+                expr_captures(expr, out)
+            }
+            Stmt::AssignPlace { target, expr } => {
+                expr_captures(target, out);
+                expr_captures(expr, out);
+            }
+            Stmt::Decl { .. } | Stmt::Return(None) | Stmt::Break | Stmt::Continue => {}
+        }
+    }
+}
+
+fn expr_captures(e: &hir::Expr, out: &mut HashSet<VarId>) {
+    use hir::Expr::*;
+    match e {
+        Closure { captures, by_move, .. } => {
+            if !by_move {
+                out.extend(captures.iter().map(|c| c.id));
+            }
+        }
+        Block { block, .. } => by_ref_captures(block, out),
+        If { cond, then_blk, else_branch, .. } => {
+            expr_captures(cond, out);
+            by_ref_captures(then_blk, out);
+            if let Some(e) = else_branch {
+                expr_captures(e, out);
+            }
+        }
+        While { cond, body, .. } => {
+            expr_captures(cond, out);
+            by_ref_captures(body, out);
+        }
+        Call { callee, args, .. } | Spawn { callee, args, .. } => {
+            expr_captures(callee, out);
+            args.iter().for_each(|a| expr_captures(a, out));
+        }
+        Unary { rhs, .. } => expr_captures(rhs, out),
+        Binary { lhs, rhs, .. } | Index { base: lhs, index: rhs, .. } => {
+            expr_captures(lhs, out);
+            expr_captures(rhs, out);
+        }
+        Array { elems, .. } | Tuple { elems, .. } => elems.iter().for_each(|e| expr_captures(e, out)),
+        Repeat { elem, .. }
+        | Field { base: elem, .. }
+        | Ref { expr: elem, .. }
+        | Deref { expr: elem, .. }
+        | ToDyn { expr: elem, .. }
+        | Try { expr: elem, .. } => expr_captures(elem, out),
+        Variant { value, .. } => value.iter().for_each(|e| expr_captures(e, out)),
+        Ident { .. } | Int { .. } | Float { .. } | Bool { .. } | Unit { .. } | Str { .. } | Builtin { .. }
+        | Channel { .. } | VecNew { .. } | FnRef { .. } | MethodRef { .. } | DynMethod { .. } => {}
+    }
+}
+
+/// Lower the whole module. Global `let`s whose initializer the resolver
+/// evaluated at compile time become initialized globals; the rest are
+/// zero-initialized and assigned by the generated `init` body. `const` items
+/// need no storage, their uses are already literals. A generic function is
+/// lowered once for every list of type arguments it is called with.
+pub fn lower_module(m: &hir::HirModule) -> MirModule {
+    let mut globals = Vec::new();
+    let mut fns = Vec::new();
+    let mut init_cx: Option<LowerCtx> = None;
+    let mut instances = Vec::new();
+    let mut vtables = Vec::new();
+    // generičke funkcije i metode jezičkih osobina se prevode tek kad zatrebaju
+    let generic: HashMap<hir::NodeId, &hir::HirFn> = m
+        .items
+        .iter()
+        .filter_map(|it| match it {
+            hir::Item::Fn(f) if !f.type_params.is_empty() => Some((f.id, f)),
+            _ => None,
+        })
+        .chain(m.lang_fns.iter().map(|f| (f.id, f)))
+        .collect();
+    let impls: Vec<hir::HirImpl> = m.impls.iter().chain(&m.lang_impls).cloned().collect();
+    let global_ids: HashSet<VarId> = m
+        .items
+        .iter()
+        .filter_map(|it| match it {
+            hir::Item::Let(g) => Some(g.id),
+            _ => None,
+        })
+        .collect();
+
+    for it in &m.items {
+        match it {
+            hir::Item::Fn(f) if generic.contains_key(&f.id) => {}
+            hir::Item::Fn(f) => {
+                let lowered = lower_fn_with(f, &global_ids, &impls, &f.name, HashMap::new());
+                fns.push(MirFn { name: f.name.clone(), body: lowered.body });
+                fns.extend(lowered.closures);
+                instances.extend(lowered.instances);
+                vtables.extend(lowered.vtables);
+            }
+            hir::Item::Let(g) => {
+                let init = m.global_values.get(&g.id).map(Constant::from);
+                if init.is_none() {
+                    let cx = init_cx.get_or_insert_with(|| {
+                        let mut cx = LowerCtx::new("init", &global_ids);
+                        cx.impls = impls.clone();
+                        cx
+                    });
+                    let op = cx.lower_expr(&g.init);
+                    cx.push_stmt(Statement::AssignVar { var: g.id, rv: Rvalue::Use(op) });
+                }
+                globals.push(MirGlobal {
+                    id: g.id,
+                    name: g.name.clone(),
+                    ty: MirType::from(&g.ty),
+                    mutable: g.mutable,
+                    init,
+                });
+            }
+            hir::Item::Const(_) => {}
+        }
+    }
+
+    let init = init_cx.map(|mut cx| {
+        cx.set_term(Terminator::Return);
+        fns.append(&mut cx.closures);
+        instances.append(&mut cx.instances);
+        vtables.append(&mut cx.vtables);
+        MirBody {
+            blocks: cx.blocks,
+            ret_ty: MirType::Unit,
+            params: Vec::new(),
+            vars: cx.vars,
+            temps: cx.temps,
+            boxed: cx.boxed,
+            env: None,
+            self_ref: false,
+            deferred: cx.deferred,
+            branches: cx.branches,
+            span: Span::default(),
+            stmt_spans: cx.stmt_spans,
+        }
+    });
+
+    // instance može tražiti nove instance; svaka se prevodi jednom
+    let mut done: HashSet<String> = fns.iter().map(|f| f.name.clone()).collect();
+    while let Some(inst) = instances.pop() {
+        if !done.insert(inst.name.clone()) {
+            continue;
+        }
+        let f = generic[&inst.fn_id];
+        let subst = f.type_params.iter().cloned().zip(inst.type_args).collect();
+        let lowered = lower_fn_with(f, &global_ids, &impls, &inst.name, subst);
+        fns.push(MirFn { name: inst.name, body: lowered.body });
+        fns.extend(lowered.closures);
+        instances.extend(lowered.instances);
+        vtables.extend(lowered.vtables);
+    }
+    // isti tip pretvoren u isti objekat na više mesta deli vtable
+    let mut seen = HashSet::new();
+    vtables.retain(|v: &Vtable| seen.insert(v.name.clone()));
+    MirModule { globals, fns, init, vtables }
+}
+
+/// Length of an array type as a `BoundsCheck` operand.
+fn array_len(ty: &crate::type_::Type) -> Operand {
+    let crate::type_::Type::Array(_, len) = ty else { unreachable!("not an array: {ty:?}") };
+    Operand::Const(Constant::Int(*len as i64))
+}
+
+impl Constant {
+    /// Integer constant of type `ty`.
+    pub fn int(ty: &crate::type_::Type, v: i64) -> Constant {
+        match ty.num() {
+            Some(NumTy::F32) => Constant::F32(v as f32),
+            Some(NumTy::F64) => Constant::Float(v as f64),
+            Some(n) if n != NumTy::I32 => Constant::IntN(n, v),
+            _ => Constant::Int(v),
+        }
+    }
+
+    /// Float constant of type `ty`.
+    pub fn float(ty: &crate::type_::Type, v: f64) -> Constant {
+        match ty.num() {
+            Some(NumTy::F32) => Constant::F32(v as f32),
+            _ => Constant::Float(v),
+        }
+    }
+}
+
+impl From<&ConstValue> for Constant {
+    fn from(v: &ConstValue) -> Self {
+        match v {
+            ConstValue::Int(i) => Constant::Int(*i),
+            ConstValue::Float(f) => Constant::Float(*f),
+            ConstValue::Bool(b) => Constant::Bool(*b),
+            ConstValue::Str(s) => Constant::Str(s.clone()),
+            ConstValue::Unit => Constant::Unit,
+            ConstValue::Array(vals) => Constant::Array(vals.iter().map(Constant::from).collect()),
+            ConstValue::Tuple(vals) => Constant::Tuple(vals.iter().map(Constant::from).collect()),
+        }
     }
 }
 
@@ -195,10 +1100,32 @@ impl From<&crate::type_::Type> for MirType {
         match t {
             Int => MirType::Int,
             Float => MirType::Float,
+            Num(n) => MirType::Num(*n),
             Bool => MirType::Bool,
+            // Unicode skalar staje u i32
+            Char => MirType::Int,
             Str => MirType::Str,
             Unit => MirType::Unit,
-            Custom(_) | Ref { .. } => MirType::Unit,
+            Sender(_) | Receiver(_) => MirType::Chan,
+            Array(elem, len) => MirType::Array(Box::new(MirType::from(&**elem)), *len),
+            Vec(_) => MirType::Vec,
+            Tuple(elems) => MirType::Tuple(elems.iter().map(MirType::from).collect()),
+            // (tag, vrednost u `Some`) i (tag, vrednost u `Ok`, greška)
+            Option(t) => MirType::Tuple(vec![MirType::Bool, MirType::from(&**t)]),
+            Result(t, e) => MirType::Tuple(vec![MirType::Bool, MirType::from(&**t), MirType::from(&**e)]),
+            Fn { params, ret } => MirType::Fn {
+                params: params.iter().map(MirType::from).collect(),
+                ret: Box::new(MirType::from(&**ret)),
+            },
+            Ref { inner, .. } => match &**inner {
+                Dyn(_) => MirType::Dyn,
+                inner => MirType::Ptr(Box::new(MirType::from(inner))),
+            },
+            // a type parameter left after substitution: the generic body
+            // itself, see `lower_fn_and_closures`; `dyn Trait` is only used
+            // behind a reference; the unknown part of the type of a `None`
+            // nothing reads
+            Custom(_) | Param(_) | Dyn(_) | Infer => MirType::Unit,
         }
     }
 }
@@ -1,30 +1,42 @@
 //! parser.rs – рекурзивни‑десцент + Pratt (са унарним операцијама)
 //! Подешено да подржи -x и !x као префикс операторе.
 
+use crate::LexError;
 use crate::ast;
 use crate::lexer::{Lexer, Token, TokenKind};
+use crate::type_::NumTy;
 
 /*──────── Parser ───────*/
 
 pub struct Parser<'a> {
     lexer: Lexer<'a>,
     lookahead: Token,
+    /// greške leksera; parsiranje se nastavlja kao da je na tom mestu `0`
+    errors: Vec<LexError>,
 }
 
 impl<'a> Parser<'a> {
     pub fn new(src: &'a str) -> Self {
-        let mut lexer = Lexer::new(src);
-        let lookahead = lexer.next_token();
-        Self { lexer, lookahead }
+        let lexer = Lexer::new(src);
+        let lookahead = Token { kind: TokenKind::Eof, span: Default::default() };
+        let mut parser = Self { lexer, lookahead, errors: Vec::new() };
+        parser.bump();
+        parser
     }
 
     /*──────── module ─────*/
-    pub fn parse_module(mut self) -> ast::Module {
+    /// The module and the errors of the lexer.
+    pub fn parse(mut self) -> (ast::Module, Vec<LexError>) {
         let mut items = Vec::new();
         while self.lookahead.kind != TokenKind::Eof {
             items.push(self.parse_item());
         }
-        ast::Module { items }
+        (ast::Module { items }, self.errors)
+    }
+
+    /// The module alone, for sources the lexer accepts; see `parse`.
+    pub fn parse_module(self) -> ast::Module {
+        self.parse().0
     }
 
     /*──────── items ──────*/
@@ -32,16 +44,68 @@ impl<'a> Parser<'a> {
         match self.lookahead.kind {
             TokenKind::Fn => ast::Item::Function(self.parse_function()),
             TokenKind::Let => ast::Item::Let(self.parse_global_let()),
+            TokenKind::Const => ast::Item::Const(self.parse_const()),
+            TokenKind::Trait => ast::Item::Trait(self.parse_trait()),
+            TokenKind::Impl => ast::Item::Impl(self.parse_impl()),
+            TokenKind::Mod => {
+                self.bump();
+                let name = self.expect_ident();
+                self.expect(TokenKind::Semicolon);
+                ast::Item::Mod(name)
+            }
+            TokenKind::Use => {
+                self.bump();
+                let path = self.parse_path_segments();
+                self.expect(TokenKind::Semicolon);
+                ast::Item::Use(path)
+            }
+            TokenKind::Pub => {
+                self.bump();
+                let mut item = self.parse_item();
+                match &mut item {
+                    ast::Item::Function(f) => f.is_pub = true,
+                    ast::Item::Let(g) => g.is_pub = true,
+                    ast::Item::Const(c) => c.is_pub = true,
+                    ast::Item::Trait(t) => t.is_pub = true,
+                    other => panic!("`pub` is not allowed on {other:?}"),
+                }
+                item
+            }
             _ => panic!("unexpected token {:?}", self.lookahead.kind),
         }
     }
 
     /*──────── function ───*/
     fn parse_function(&mut self) -> ast::Function {
+        let ast::FnDecl { name, span, lifetimes, type_params, params, return_ty, self_mode } = self.parse_fn_decl();
+        let body = self.parse_block();
+        ast::Function { name, span, is_pub: false, lifetimes, type_params, params, return_ty, self_mode, body }
+    }
+
+    /// `fn name<'a, T>(params) -> R` – the signature up to the body.
+    fn parse_fn_decl(&mut self) -> ast::FnDecl {
         self.expect(TokenKind::Fn);
+        let span = self.lookahead.span;
         let name = self.expect_ident();
+        let (lifetimes, type_params) = self.parse_type_params();
         self.expect(TokenKind::LParen);
 
+        // `self`, `&self` ili `&mut self` na početku
+        let mut self_mode = None;
+        if self.lookahead.kind == TokenKind::Amp {
+            self.bump();
+            let mutable = self.lookahead.kind == TokenKind::Mut;
+            if mutable {
+                self.bump();
+            }
+            self_mode = Some(if mutable { ast::SelfMode::RefMut } else { ast::SelfMode::Ref });
+            if !matches!(&self.lookahead.kind, TokenKind::Ident(n) if n == "self") {
+                panic!("expected `self` after `&`, got {:?}", self.lookahead.kind);
+            }
+        } else if matches!(&self.lookahead.kind, TokenKind::Ident(n) if n == "self") {
+            self_mode = Some(ast::SelfMode::Value);
+        }
+
         // params
         let mut params = Vec::new();
         if self.lookahead.kind != TokenKind::RParen {
@@ -49,7 +113,7 @@ impl<'a> Parser<'a> {
                 let pname = self.expect_ident();
                 let pty = if self.lookahead.kind == TokenKind::Colon {
                     self.expect(TokenKind::Colon);
-                    Some(self.expect_ident())
+                    Some(self.parse_type())
                 } else {
                     None
                 };
@@ -68,27 +132,127 @@ impl<'a> Parser<'a> {
 
         let return_ty = if self.lookahead.kind == TokenKind::Arrow {
             self.bump();
-            Some(self.expect_ident())
+            Some(self.parse_type())
         } else {
             None
         };
+        ast::FnDecl { name, span, lifetimes, type_params, params, return_ty, self_mode }
+    }
 
-        let body = self.parse_fn_body();
+    /// `<'a, T, U: Show + Eq>`, or nothing. Lifetimes come first.
+    fn parse_type_params(&mut self) -> (Vec<String>, Vec<ast::TypeParam>) {
+        let mut lifetimes = Vec::new();
+        let mut type_params = Vec::new();
+        if self.lookahead.kind != TokenKind::Lt {
+            return (lifetimes, type_params);
+        }
+        self.bump();
+        while self.lookahead.kind != TokenKind::Gt {
+            if let TokenKind::Lifetime(name) = &self.lookahead.kind {
+                if !type_params.is_empty() {
+                    panic!("lifetime parameters must be declared before type parameters");
+                }
+                lifetimes.push(name.clone());
+                self.bump();
+                if self.lookahead.kind != TokenKind::Comma {
+                    break;
+                }
+                self.bump();
+                continue;
+            }
+            let name = self.expect_ident();
+            let mut bounds = Vec::new();
+            if self.lookahead.kind == TokenKind::Colon {
+                self.bump();
+                bounds.push(self.parse_path_segments());
+                while self.lookahead.kind == TokenKind::Plus {
+                    self.bump();
+                    bounds.push(self.parse_path_segments());
+                }
+            }
+            type_params.push(ast::TypeParam { name, bounds });
+            if self.lookahead.kind != TokenKind::Comma {
+                break;
+            }
+            self.bump();
+        }
+        self.expect(TokenKind::Gt);
+        (lifetimes, type_params)
+    }
 
-        ast::Function { name, params, return_ty, body }
+    /*──────── traits ─────*/
+    /// `trait Name { fn m(self) -> T; .. }`
+    fn parse_trait(&mut self) -> ast::Trait {
+        self.expect(TokenKind::Trait);
+        let name = self.expect_ident();
+        self.expect(TokenKind::LBrace);
+        let mut methods = Vec::new();
+        while self.lookahead.kind != TokenKind::RBrace {
+            methods.push(self.parse_fn_decl());
+            self.expect(TokenKind::Semicolon);
+        }
+        self.expect(TokenKind::RBrace);
+        ast::Trait { name, is_pub: false, methods }
     }
 
-    fn parse_fn_body(&mut self) -> Vec<ast::Stmt> {
+    /// `impl<T> path::Trait for Type { fn .. }` or `impl<T> Type { fn .. }`
+    fn parse_impl(&mut self) -> ast::Impl {
+        self.expect(TokenKind::Impl);
+        let (lifetimes, type_params) = self.parse_type_params();
+        let (trait_path, self_ty) = if matches!(self.lookahead.kind, TokenKind::Ident(_)) {
+            let mut path = self.parse_path_segments();
+            if self.lookahead.kind == TokenKind::For {
+                self.bump();
+                (Some(path), self.parse_type())
+            } else if path.len() == 1 {
+                let args = self.parse_type_args();
+                (None, ast::TypeExpr::Named { name: path.pop().unwrap(), args })
+            } else {
+                panic!("expected `for` after trait `{}`", path.join("::"));
+            }
+        } else {
+            (None, self.parse_type())
+        };
         self.expect(TokenKind::LBrace);
-        let mut body = Vec::new();
+        let mut methods = Vec::new();
         while self.lookahead.kind != TokenKind::RBrace {
-            body.push(self.parse_stmt());
+            methods.push(self.parse_function());
         }
-        if !matches!(body.last(), Some(ast::Stmt::Return(_))) {
-            body.push(ast::Stmt::Return(Some(ast::Expr::Unit)));
+        self.expect(TokenKind::RBrace);
+        ast::Impl { lifetimes, type_params, trait_path, self_ty, methods }
+    }
+
+    /*──────── block ──────*/
+    /// `{ stmt* expr? }` – a final expression without `;` becomes the
+    /// block's value.
+    fn parse_block(&mut self) -> ast::Block {
+        self.expect(TokenKind::LBrace);
+        let mut stmts = Vec::new();
+        let mut tail = None;
+        let mut spans = Vec::new();
+        while self.lookahead.kind != TokenKind::RBrace {
+            spans.push(self.lookahead.span);
+            if !self.at_expr_stmt() {
+                stmts.push(self.parse_stmt());
+                continue;
+            }
+            let expr = self.parse_stmt_expr();
+            if matches!(self.lookahead.kind, TokenKind::Assign | TokenKind::OpAssign(_)) {
+                stmts.push(self.parse_place_assign(expr));
+                continue;
+            }
+            match self.lookahead.kind {
+                TokenKind::Semicolon => {
+                    self.bump();
+                    stmts.push(ast::Stmt::Expr(expr));
+                }
+                TokenKind::RBrace => tail = Some(Box::new(expr)),
+                _ if ends_with_block(&expr) => stmts.push(ast::Stmt::Expr(expr)),
+                _ => panic!("expected {:?}, got {:?}", TokenKind::Semicolon, self.lookahead.kind),
+            }
         }
         self.expect(TokenKind::RBrace);
-        body
+        ast::Block { stmts, tail, spans }
     }
 
     /*──────── statements ─*/
@@ -96,6 +260,16 @@ impl<'a> Parser<'a> {
         match self.lookahead.kind {
             TokenKind::Let => self.parse_let(),
             TokenKind::Return => self.parse_return(),
+            TokenKind::Break => {
+                self.bump();
+                self.expect(TokenKind::Semicolon);
+                ast::Stmt::Break
+            }
+            TokenKind::Continue => {
+                self.bump();
+                self.expect(TokenKind::Semicolon);
+                ast::Stmt::Continue
+            }
             TokenKind::Ident(_) if self.peek_next(TokenKind::Assign) => {
                 let name = self.expect_ident();
                 self.expect(TokenKind::Assign);
@@ -104,13 +278,148 @@ impl<'a> Parser<'a> {
                 ast::Stmt::Assign { name, expr }
             }
             _ => {
-                let expr = self.parse_expr(0);
-                self.expect(TokenKind::Semicolon);
+                let expr = self.parse_stmt_expr();
+                if matches!(self.lookahead.kind, TokenKind::Assign | TokenKind::OpAssign(_)) {
+                    return self.parse_place_assign(expr);
+                }
+                // block-like statements don't need a trailing `;`
+                if !ends_with_block(&expr) || self.lookahead.kind == TokenKind::Semicolon {
+                    self.expect(TokenKind::Semicolon);
+                }
                 ast::Stmt::Expr(expr)
             }
         }
     }
 
+    /// `a[i] = expr;`, `p.0 = expr;` or `*r = expr;` after the target has
+    /// been parsed, or a compound assignment `target op= expr;` to any of
+    /// those or a variable; the resolver checks that the target is a place.
+    fn parse_place_assign(&mut self, target: ast::Expr) -> ast::Stmt {
+        let op = match self.lookahead.kind {
+            TokenKind::OpAssign(op) => Some(op),
+            _ => None,
+        };
+        self.bump();
+        let expr = self.parse_expr(0);
+        self.expect(TokenKind::Semicolon);
+        match op {
+            Some(op) => ast::Stmt::AssignOp { target, op, expr },
+            None => ast::Stmt::AssignPlace { target, expr },
+        }
+    }
+
+    /// Does the lookahead start an expression statement (as opposed to
+    /// `let`, `return`, `break`, `continue` or an assignment)?
+    fn at_expr_stmt(&self) -> bool {
+        match self.lookahead.kind {
+            TokenKind::Let | TokenKind::Return | TokenKind::Break | TokenKind::Continue => false,
+            TokenKind::Ident(_) => !self.peek_next(TokenKind::Assign),
+            _ => true,
+        }
+    }
+
+    /// Expression in statement position. A leading block, `if` or `while`
+    /// is a complete statement, so `{ .. } - 1` is not parsed as a
+    /// subtraction.
+    fn parse_stmt_expr(&mut self) -> ast::Expr {
+        match self.lookahead.kind {
+            TokenKind::LBrace => ast::Expr::Block(self.parse_block()),
+            TokenKind::If => self.parse_if(),
+            TokenKind::Match => self.parse_match(),
+            TokenKind::While => self.parse_while(),
+            TokenKind::For => self.parse_for(),
+            _ => self.parse_expr(0),
+        }
+    }
+
+    /*──────── control flow ─*/
+    fn parse_if(&mut self) -> ast::Expr {
+        self.expect(TokenKind::If);
+        let cond = self.parse_expr(0);
+        let then_branch = self.parse_block();
+        let else_branch = if self.lookahead.kind == TokenKind::Else {
+            self.bump();
+            if self.lookahead.kind == TokenKind::If {
+                Some(Box::new(self.parse_if()))
+            } else {
+                Some(Box::new(ast::Expr::Block(self.parse_block())))
+            }
+        } else {
+            None
+        };
+        ast::Expr::If {
+            cond: Box::new(cond),
+            then_branch,
+            else_branch,
+        }
+    }
+
+    /// `match e { Some(x) => a, None => { .. } }`; zarez posle grane
+    /// koja je blok nije obavezan.
+    fn parse_match(&mut self) -> ast::Expr {
+        self.expect(TokenKind::Match);
+        let scrutinee = self.parse_expr(0);
+        self.expect(TokenKind::LBrace);
+        let mut arms = Vec::new();
+        while self.lookahead.kind != TokenKind::RBrace {
+            let pattern = match self.lookahead.kind.clone() {
+                TokenKind::Ident(name) if name == "_" => {
+                    self.bump();
+                    ast::MatchPattern::Wildcard
+                }
+                TokenKind::Ident(name) => {
+                    self.bump();
+                    let inner = if self.lookahead.kind == TokenKind::LParen {
+                        self.bump();
+                        let inner = self.parse_pattern();
+                        self.expect(TokenKind::RParen);
+                        Some(inner)
+                    } else {
+                        None
+                    };
+                    ast::MatchPattern::Variant { name, inner }
+                }
+                other => panic!("expected a pattern in `match`, got {other:?}"),
+            };
+            self.expect(TokenKind::FatArrow);
+            let body = self.parse_stmt_expr();
+            let block_like = ends_with_block(&body);
+            arms.push(ast::MatchArm { pattern, body });
+            if self.lookahead.kind == TokenKind::Comma {
+                self.bump();
+            } else if !block_like {
+                break;
+            }
+        }
+        self.expect(TokenKind::RBrace);
+        ast::Expr::Match { scrutinee: Box::new(scrutinee), arms }
+    }
+
+    fn parse_while(&mut self) -> ast::Expr {
+        self.expect(TokenKind::While);
+        let cond = self.parse_expr(0);
+        let body = self.parse_block();
+        ast::Expr::While {
+            cond: Box::new(cond),
+            body,
+        }
+    }
+
+    fn parse_for(&mut self) -> ast::Expr {
+        let span = self.lookahead.span;
+        self.expect(TokenKind::For);
+        let var = self.expect_ident();
+        self.expect(TokenKind::In);
+        let iter = self.parse_expr(0);
+        let body = self.parse_block();
+        ast::Expr::For {
+            var,
+            iter: Box::new(iter),
+            body,
+            span,
+        }
+    }
+
     /*──────── локални let */
     fn parse_let(&mut self) -> ast::Stmt {
         self.expect(TokenKind::Let);
@@ -121,18 +430,72 @@ impl<'a> Parser<'a> {
         } else {
             false
         };
+        if !mutable && self.lookahead.kind == TokenKind::LParen {
+            let ast::Pattern::Tuple(pats) = self.parse_pattern() else { unreachable!() };
+            self.expect(TokenKind::Assign);
+            let expr = self.parse_expr(0);
+            self.expect(TokenKind::Semicolon);
+            return ast::Stmt::LetTuple { pats, expr };
+        }
         let name = self.expect_ident();
+        let ty = if self.lookahead.kind == TokenKind::Colon {
+            self.bump();
+            Some(self.parse_type())
+        } else {
+            None
+        };
+        if let Some(ty) = &ty
+            && self.lookahead.kind == TokenKind::Semicolon
+        {
+            self.bump();
+            return ast::Stmt::Decl { name, ty: ty.clone(), mutable };
+        }
         self.expect(TokenKind::Assign);
         let expr = self.parse_expr(0);
         self.expect(TokenKind::Semicolon);
 
         ast::Stmt::Let {
             name,
+            ty,
             expr,
             mutable,
         }
     }
 
+    /// `name`, `mut name`, `_` ili `(p, ..)`; zagrade bez zareza samo
+    /// grupišu.
+    fn parse_pattern(&mut self) -> ast::Pattern {
+        match &self.lookahead.kind {
+            TokenKind::LParen => {
+                self.bump();
+                let mut pats = Vec::new();
+                let mut trailing_comma = false;
+                while self.lookahead.kind != TokenKind::RParen {
+                    pats.push(self.parse_pattern());
+                    trailing_comma = self.lookahead.kind == TokenKind::Comma;
+                    if !trailing_comma {
+                        break;
+                    }
+                    self.bump();
+                }
+                self.expect(TokenKind::RParen);
+                if pats.len() == 1 && !trailing_comma {
+                    return pats.pop().unwrap();
+                }
+                ast::Pattern::Tuple(pats)
+            }
+            TokenKind::Mut => {
+                self.bump();
+                ast::Pattern::Ident { name: self.expect_ident(), mutable: true }
+            }
+            TokenKind::Ident(name) if name == "_" => {
+                self.bump();
+                ast::Pattern::Wildcard
+            }
+            _ => ast::Pattern::Ident { name: self.expect_ident(), mutable: false },
+        }
+    }
+
     fn parse_return(&mut self) -> ast::Stmt {
         self.expect(TokenKind::Return);
         if self.lookahead.kind == TokenKind::Semicolon {
@@ -148,7 +511,8 @@ impl<'a> Parser<'a> {
     /*──────── expressions – Pratt ─*/
     fn parse_expr(&mut self, min_bp: u8) -> ast::Expr {
         use ast::BinOp::{
-            AndAnd, EqEq, Ge, Gt, Le, Lt, Minus, NotEq, OrOr, Percent, Plus, Slash, Star,
+            AndAnd, BitAnd, BitOr, BitXor, EqEq, Ge, Gt, Le, Lt, Minus, NotEq, OrOr, Percent, Plus, Shl, Shr, Slash,
+            Star,
         };
 
         //── prefix / unary ────────────────────────────────────────
@@ -169,25 +533,69 @@ impl<'a> Parser<'a> {
                     expr: Box::new(rhs),
                 }
             }
+            TokenKind::Tilde => {
+                self.bump();
+                let rhs = self.parse_expr(ast::UnOp::BitNot.binding_power());
+                ast::Expr::Unary {
+                    op: ast::UnOp::BitNot,
+                    expr: Box::new(rhs),
+                }
+            }
+            TokenKind::Amp => {
+                self.bump();
+                let mutable = self.lookahead.kind == TokenKind::Mut;
+                if mutable {
+                    self.bump();
+                }
+                let rhs = self.parse_expr(ast::UnOp::Not.binding_power());
+                ast::Expr::Ref { mutable, expr: Box::new(rhs) }
+            }
+            TokenKind::Star => {
+                self.bump();
+                let rhs = self.parse_expr(ast::UnOp::Not.binding_power());
+                ast::Expr::Deref(Box::new(rhs))
+            }
             _ => self.parse_primary(),
         };
 
         //── infix / binary ────────────────────────────────────────
+        // operator of the comparison that built `lhs`, if it was one
+        let mut compared: Option<ast::BinOp> = None;
         loop {
-            let (l_bp, r_bp, op) = match self.lookahead.kind {
-                TokenKind::Plus => (1, 2, Plus),
-                TokenKind::Minus => (1, 2, Minus),
-                TokenKind::Star => (3, 4, Star),
-                TokenKind::Slash => (3, 4, Slash),
-                TokenKind::Percent => (3, 4, Percent),
-                TokenKind::EqEq => (0, 0, EqEq),
-                TokenKind::NotEq => (0, 0, NotEq),
-                TokenKind::Lt => (0, 0, Lt),
-                TokenKind::Le => (0, 0, Le),
-                TokenKind::Gt => (0, 0, Gt),
-                TokenKind::Ge => (0, 0, Ge),
-                TokenKind::AndAnd => (0, 1, AndAnd),
-                TokenKind::OrOr => (0, 1, OrOr),
+            let op = match self.lookahead.kind {
+                TokenKind::Plus => Plus,
+                TokenKind::Minus => Minus,
+                TokenKind::Star => Star,
+                TokenKind::Slash => Slash,
+                TokenKind::Percent => Percent,
+                TokenKind::EqEq => EqEq,
+                TokenKind::NotEq => NotEq,
+                TokenKind::Lt => Lt,
+                TokenKind::Le => Le,
+                TokenKind::Gt => Gt,
+                TokenKind::Ge => Ge,
+                TokenKind::AndAnd => AndAnd,
+                TokenKind::OrOr => OrOr,
+                TokenKind::Amp => BitAnd,
+                TokenKind::Pipe => BitOr,
+                TokenKind::Caret => BitXor,
+                TokenKind::Shl => Shl,
+                TokenKind::Shr => Shr,
+                TokenKind::DotDot | TokenKind::DotDotEq => {
+                    // opseg vezuje najslabije i nije asocijativan
+                    if min_bp > 0 {
+                        break;
+                    }
+                    let inclusive = self.lookahead.kind == TokenKind::DotDotEq;
+                    self.bump();
+                    let end = self.parse_expr(1);
+                    lhs = ast::Expr::Range {
+                        start: Box::new(lhs),
+                        end: Box::new(end),
+                        inclusive,
+                    };
+                    break;
+                }
 
                 TokenKind::LParen => {
                     // call
@@ -198,14 +606,61 @@ impl<'a> Parser<'a> {
                     };
                     continue;
                 }
+                TokenKind::Dot => {
+                    // polje torke `t.0` ili poziv metode `x.len()`
+                    self.bump();
+                    match self.lookahead.kind.clone() {
+                        TokenKind::Int(index) => {
+                            self.bump();
+                            lhs = ast::Expr::Field { base: Box::new(lhs), index: index as usize };
+                        }
+                        TokenKind::Ident(method) => {
+                            self.bump();
+                            if self.lookahead.kind != TokenKind::LParen {
+                                panic!("expected `(` after method `{method}`, got {:?}", self.lookahead.kind);
+                            }
+                            let args = self.parse_call_args();
+                            lhs = ast::Expr::MethodCall { receiver: Box::new(lhs), method, args };
+                        }
+                        other => panic!("expected tuple index or method, got {other:?}"),
+                    }
+                    continue;
+                }
+                TokenKind::Question => {
+                    self.bump();
+                    lhs = ast::Expr::Try(Box::new(lhs));
+                    continue;
+                }
+                TokenKind::LBracket => {
+                    // indeksiranje
+                    let span = self.lookahead.span;
+                    self.bump();
+                    let index = self.parse_expr(0);
+                    self.expect(TokenKind::RBracket);
+                    lhs = ast::Expr::Index {
+                        base: Box::new(lhs),
+                        index: Box::new(index),
+                        span,
+                    };
+                    continue;
+                }
                 _ => break,
             };
 
+            let (l_bp, r_bp) = op.binding_power();
             if l_bp < min_bp {
                 break;
             }
+            if let Some(prev) = compared.filter(|_| op.is_comparison()) {
+                panic!(
+                    "comparison operators cannot be chained: `a {} b {} c` needs parentheses or `&&`",
+                    prev.symbol(),
+                    op.symbol()
+                );
+            }
             self.bump();
             let rhs = self.parse_expr(r_bp);
+            compared = Some(op).filter(|op| op.is_comparison());
             lhs = ast::Expr::Binary {
                 op,
                 lhs: Box::new(lhs),
@@ -223,10 +678,32 @@ impl<'a> Parser<'a> {
             return ast::Expr::Unit;
         }
         match &self.lookahead.kind {
-            TokenKind::Ident(n) => {
-                let s = n.clone();
+            TokenKind::Pipe | TokenKind::OrOr => self.parse_closure(false),
+            // `move |..| ..`; inače je `move` obično ime
+            TokenKind::Ident(name)
+                if name == "move" && (self.peek_next(TokenKind::Pipe) || self.peek_next(TokenKind::OrOr)) =>
+            {
                 self.bump();
-                ast::Expr::Ident(s)
+                self.parse_closure(true)
+            }
+            // `vec::<T>()`; `vec` ostaje obično ime kad ga ne prati `::<`
+            TokenKind::Ident(name) if name == "vec" && self.peek_turbofish() => {
+                self.bump();
+                self.expect(TokenKind::DoubleColon);
+                self.expect(TokenKind::Lt);
+                let elem = self.parse_type();
+                self.expect_gt();
+                self.expect(TokenKind::LParen);
+                self.expect(TokenKind::RParen);
+                ast::Expr::VecNew(elem)
+            }
+            TokenKind::Ident(_) => {
+                let mut path = self.parse_path_segments();
+                if path.len() == 1 {
+                    ast::Expr::Ident(path.pop().unwrap())
+                } else {
+                    ast::Expr::Path(path)
+                }
             }
             TokenKind::Int(v) => {
                 let v = *v;
@@ -238,6 +715,16 @@ impl<'a> Parser<'a> {
                 self.bump();
                 ast::Expr::Float(v)
             }
+            TokenKind::TypedInt(v, ty) => {
+                let (v, ty) = (*v, *ty);
+                self.bump();
+                ast::Expr::TypedInt(v, ty)
+            }
+            TokenKind::TypedFloat(v, ty) => {
+                let (v, ty) = (*v, *ty);
+                self.bump();
+                ast::Expr::TypedFloat(v, ty)
+            }
             TokenKind::Bool(b) => {
                 let val = *b;
                 self.bump();
@@ -248,21 +735,138 @@ impl<'a> Parser<'a> {
                 self.bump();
                 ast::Expr::Str(s)
             }
+            TokenKind::Char(c) => {
+                let c = *c;
+                self.bump();
+                ast::Expr::Char(c)
+            }
+            TokenKind::Byte(b) => {
+                let b = *b;
+                self.bump();
+                ast::Expr::TypedInt(b.into(), NumTy::U8)
+            }
+            TokenKind::ByteStr(bytes) => {
+                let bytes = bytes.clone();
+                self.bump();
+                ast::Expr::ByteStr(bytes)
+            }
+            TokenKind::LBrace => ast::Expr::Block(self.parse_block()),
+            TokenKind::If => self.parse_if(),
+            TokenKind::Match => self.parse_match(),
+            TokenKind::While => self.parse_while(),
+            TokenKind::For => self.parse_for(),
+            TokenKind::Spawn => {
+                self.bump();
+                let callee = self.parse_primary();
+                let args = self.parse_call_args();
+                ast::Expr::Spawn { callee: Box::new(callee), args }
+            }
+            TokenKind::Channel => {
+                self.bump();
+                // `channel::<T>()`; bez tipa javlja grešku resolver
+                let elem = if self.lookahead.kind == TokenKind::DoubleColon {
+                    self.bump();
+                    self.expect(TokenKind::Lt);
+                    let ty = self.parse_type();
+                    self.expect_gt();
+                    Some(ty)
+                } else {
+                    None
+                };
+                self.expect(TokenKind::LParen);
+                self.expect(TokenKind::RParen);
+                ast::Expr::Channel(elem)
+            }
+            TokenKind::LBracket => self.parse_array(),
             TokenKind::LParen => {
                 self.bump();
                 if self.lookahead.kind == TokenKind::RParen {
                     self.bump();
-                    ast::Expr::Unit
-                } else {
-                    let e = self.parse_expr(0);
+                    return ast::Expr::Unit;
+                }
+                let first = self.parse_expr(0);
+                if self.lookahead.kind != TokenKind::Comma {
                     self.expect(TokenKind::RParen);
-                    e
+                    return first;
+                }
+                // `(a, b)`, `(a,)` – zarez pravi torku
+                let mut elems = vec![first];
+                while self.lookahead.kind == TokenKind::Comma {
+                    self.bump();
+                    if self.lookahead.kind == TokenKind::RParen {
+                        break;
+                    }
+                    elems.push(self.parse_expr(0));
                 }
+                self.expect(TokenKind::RParen);
+                ast::Expr::Tuple(elems)
             }
             _ => panic!("unexpected token {:?}", self.lookahead.kind),
         }
     }
 
+    /// `|a: Int, b: Int| body`, `|| body` or `|a: Int| -> T { .. }`; with
+    /// a return type the body must be a block.
+    fn parse_closure(&mut self, by_move: bool) -> ast::Expr {
+        let mut params = Vec::new();
+        if self.lookahead.kind == TokenKind::OrOr {
+            self.bump();
+        } else {
+            self.expect(TokenKind::Pipe);
+            while self.lookahead.kind != TokenKind::Pipe {
+                let name = self.expect_ident();
+                let ty = if self.lookahead.kind == TokenKind::Colon {
+                    self.bump();
+                    Some(self.parse_type())
+                } else {
+                    None
+                };
+                params.push(ast::Param { name, ty });
+                if self.lookahead.kind != TokenKind::Comma {
+                    break;
+                }
+                self.bump();
+            }
+            self.expect(TokenKind::Pipe);
+        }
+        let (ret, body) = if self.lookahead.kind == TokenKind::Arrow {
+            self.bump();
+            let ret = self.parse_type();
+            (Some(ret), ast::Expr::Block(self.parse_block()))
+        } else {
+            (None, self.parse_expr(0))
+        };
+        ast::Expr::Closure { params, ret, body: Box::new(body), by_move }
+    }
+
+    /// `[a, b, c]` or `[elem; len]`
+    fn parse_array(&mut self) -> ast::Expr {
+        self.expect(TokenKind::LBracket);
+        let mut elems = Vec::new();
+        if self.lookahead.kind != TokenKind::RBracket {
+            let first = self.parse_expr(0);
+            if self.lookahead.kind == TokenKind::Semicolon {
+                self.bump();
+                let len = self.parse_expr(0);
+                self.expect(TokenKind::RBracket);
+                return ast::Expr::Repeat {
+                    elem: Box::new(first),
+                    len: Box::new(len),
+                };
+            }
+            elems.push(first);
+            while self.lookahead.kind == TokenKind::Comma {
+                self.bump();
+                if self.lookahead.kind == TokenKind::RBracket {
+                    break; // završni zarez
+                }
+                elems.push(self.parse_expr(0));
+            }
+        }
+        self.expect(TokenKind::RBracket);
+        ast::Expr::Array(elems)
+    }
+
     /*──────── call args ───*/
     fn parse_call_args(&mut self) -> Vec<ast::Expr> {
         self.expect(TokenKind::LParen);
@@ -285,6 +889,19 @@ impl<'a> Parser<'a> {
         }
         self.bump();
     }
+    /// `>` closing type arguments; of `>>` in `Vec<Vec<Int>>` only the
+    /// first half is taken.
+    fn expect_gt(&mut self) {
+        let rest = match self.lookahead.kind {
+            TokenKind::Shr => TokenKind::Gt,
+            TokenKind::Ge => TokenKind::Assign,
+            TokenKind::OpAssign(ast::BinOp::Shr) => TokenKind::Ge,
+            _ => return self.expect(TokenKind::Gt),
+        };
+        self.lookahead.kind = rest;
+        self.lookahead.span.start += 1;
+        self.lookahead.span.column += 1;
+    }
     fn expect_ident(&mut self) -> String {
         if let TokenKind::Ident(s) = &self.lookahead.kind {
             let n = s.clone();
@@ -295,7 +912,15 @@ impl<'a> Parser<'a> {
         }
     }
     fn bump(&mut self) {
-        self.lookahead = self.lexer.next_token();
+        let tok = self.lexer.next_token();
+        self.lookahead = self.checked(tok);
+    }
+
+    /// A lexer error is recorded and replaced by the literal `0`.
+    fn checked(&mut self, tok: Token) -> Token {
+        let TokenKind::Error(msg) = tok.kind else { return tok };
+        self.errors.push(LexError { span: tok.span, msg });
+        Token { kind: TokenKind::Int(0), ..tok }
     }
 
     fn peek(&self, kind: TokenKind) -> bool {
@@ -308,6 +933,12 @@ impl<'a> Parser<'a> {
         std::mem::discriminant(&tok.kind) == std::mem::discriminant(&kind)
     }
 
+    /// `::<` posle tekućeg tokena
+    fn peek_turbofish(&self) -> bool {
+        let mut lx = self.lexer.clone();
+        lx.next_token().kind == TokenKind::DoubleColon && lx.next_token().kind == TokenKind::Lt
+    }
+
     /*──────── global let ───*/
     fn parse_global_let(&mut self) -> ast::GlobalLet {
         self.expect(TokenKind::Let);
@@ -324,10 +955,136 @@ impl<'a> Parser<'a> {
 
         ast::GlobalLet {
             name,
+            is_pub: false,
             expr,
             mutable,
         }
     }
+
+    /// `Int`, `Sender<Int>`, `[Int; 4]`, `(Int, Bool)`, `fn(Int) -> Int`,
+    /// `&'a mut T`
+    fn parse_type(&mut self) -> ast::TypeExpr {
+        if self.lookahead.kind == TokenKind::Amp {
+            self.bump();
+            let lifetime = match &self.lookahead.kind {
+                TokenKind::Lifetime(name) => {
+                    let name = name.clone();
+                    self.bump();
+                    Some(name)
+                }
+                _ => None,
+            };
+            let mutable = self.lookahead.kind == TokenKind::Mut;
+            if mutable {
+                self.bump();
+            }
+            return ast::TypeExpr::Ref { mutable, lifetime, inner: Box::new(self.parse_type()) };
+        }
+        if self.lookahead.kind == TokenKind::Dyn {
+            self.bump();
+            return ast::TypeExpr::Dyn(self.parse_path_segments());
+        }
+        if self.lookahead.kind == TokenKind::Fn {
+            self.bump();
+            self.expect(TokenKind::LParen);
+            let mut params = Vec::new();
+            while self.lookahead.kind != TokenKind::RParen {
+                params.push(self.parse_type());
+                if self.lookahead.kind != TokenKind::Comma {
+                    break;
+                }
+                self.bump();
+            }
+            self.expect(TokenKind::RParen);
+            let ret = if self.lookahead.kind == TokenKind::Arrow {
+                self.bump();
+                Some(Box::new(self.parse_type()))
+            } else {
+                None
+            };
+            return ast::TypeExpr::Fn { params, ret };
+        }
+        if self.lookahead.kind == TokenKind::LParen {
+            self.bump();
+            let mut elems = Vec::new();
+            let mut trailing_comma = false;
+            while self.lookahead.kind != TokenKind::RParen {
+                elems.push(self.parse_type());
+                trailing_comma = self.lookahead.kind == TokenKind::Comma;
+                if !trailing_comma {
+                    break;
+                }
+                self.bump();
+            }
+            self.expect(TokenKind::RParen);
+            if elems.len() == 1 && !trailing_comma {
+                return elems.pop().unwrap();
+            }
+            return ast::TypeExpr::Tuple(elems);
+        }
+        if self.lookahead.kind == TokenKind::LBracket {
+            self.bump();
+            let elem = self.parse_type();
+            self.expect(TokenKind::Semicolon);
+            let len = self.parse_expr(0);
+            self.expect(TokenKind::RBracket);
+            return ast::TypeExpr::Array {
+                elem: Box::new(elem),
+                len: Box::new(len),
+            };
+        }
+        let name = self.expect_ident();
+        let args = self.parse_type_args();
+        ast::TypeExpr::Named { name, args }
+    }
+
+    /// `<A, B>` after a type name, or nothing.
+    fn parse_type_args(&mut self) -> Vec<ast::TypeExpr> {
+        let mut args = Vec::new();
+        if self.lookahead.kind == TokenKind::Lt {
+            self.bump();
+            args.push(self.parse_type());
+            while self.lookahead.kind == TokenKind::Comma {
+                self.bump();
+                args.push(self.parse_type());
+            }
+            self.expect_gt();
+        }
+        args
+    }
+
+    /// `a::b::c` – at least one identifier.
+    fn parse_path_segments(&mut self) -> Vec<String> {
+        let mut path = vec![self.expect_ident()];
+        while self.lookahead.kind == TokenKind::DoubleColon {
+            self.bump();
+            path.push(self.expect_ident());
+        }
+        path
+    }
+
+    fn parse_const(&mut self) -> ast::Const {
+        self.expect(TokenKind::Const);
+        let name = self.expect_ident();
+        self.expect(TokenKind::Colon);
+        let ty = self.parse_type();
+        self.expect(TokenKind::Assign);
+        let expr = self.parse_expr(0);
+        self.expect(TokenKind::Semicolon);
+        ast::Const { name, is_pub: false, ty, expr }
+    }
+}
+
+/// Expressions ending in a `}` may be used as statements without `;`.
+fn ends_with_block(e: &ast::Expr) -> bool {
+    matches!(
+        e,
+        ast::Expr::Block(_)
+            | ast::Expr::If { .. }
+            | ast::Expr::Match { .. }
+            | ast::Expr::While { .. }
+            | ast::Expr::For { .. }
+    )
 }
 
 /*──────── utility parsers ───*/
@@ -0,0 +1,312 @@
+// region.rs - references may not outlive what they borrow
+//
+// Every reference value is followed back to the loans it comes from: a
+// local variable or a temporary, which dies at the end of its block, or a
+// region the caller provides, named by a lifetime of the signature. An
+// unnamed `&T` parameter has the lifetime elision gave it, named after the
+// parameter's position, and a global lives for `'static`.
+//
+// A value holding references may not be stored in a variable declared
+// outside the block of what it borrows, be the value of that block, or be
+// returned from the function unless it borrows only from lifetimes of the
+// return type. A call returns what the arguments whose parameter types
+// share a lifetime with the return type borrow; without one, what any
+// argument borrows. A closure borrows what its captured variables do, so it
+// escapes under the same rules as the references it holds.
+
+use std::collections::HashMap;
+
+use crate::hir::{self, Expr, Stmt};
+use crate::lexer::Span;
+use crate::resolver::ResolveError;
+use crate::type_::{Type, is_elided};
+
+type VarId = hir::NodeId;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Loan {
+    /// a local variable, or a temporary when `var` is `None`, that dies at
+    /// the end of the block at `depth`; parameters are at depth 0
+    Local { var: Option<VarId>, depth: usize },
+    /// a region of the caller, named by a lifetime of the signature
+    Region(String),
+}
+
+struct RegionCx<'f> {
+    f: &'f hir::HirFn,
+    errors: Vec<ResolveError>,
+    depth: usize,
+    /// block depth each local variable is declared at
+    depths: HashMap<VarId, usize>,
+    names: HashMap<VarId, String>,
+    /// what the references a variable holds borrow
+    loans: HashMap<VarId, Vec<Loan>>,
+    /// `self` of a `&self` or `&mut self` method
+    self_ref: Option<VarId>,
+    /// lifetimes of the return type; `None` inside a closure, whose
+    /// `return` doesn't leave the function
+    ret: Option<Vec<String>>,
+}
+
+pub fn check_fn(f: &hir::HirFn) -> Vec<ResolveError> {
+    let mut ret = Vec::new();
+    f.return_ty.lifetimes(&mut ret);
+    let mut cx = RegionCx {
+        f,
+        errors: Vec::new(),
+        depth: 0,
+        depths: HashMap::new(),
+        names: HashMap::new(),
+        loans: HashMap::new(),
+        self_ref: None,
+        ret: Some(ret),
+    };
+    for p in &f.params {
+        let mut lifetimes = Vec::new();
+        p.ty.lifetimes(&mut lifetimes);
+        cx.depths.insert(p.id, 0);
+        cx.names.insert(p.id, p.name.clone());
+        cx.loans.insert(p.id, lifetimes.into_iter().map(Loan::Region).collect());
+    }
+    if matches!(f.self_mode, Some(hir::SelfMode::Ref | hir::SelfMode::RefMut)) {
+        cx.self_ref = Some(f.params[0].id);
+    }
+    let returned = cx.block(&f.body, true);
+    cx.returned(returned);
+    cx.errors
+}
+
+impl RegionCx<'_> {
+    fn error(&mut self, msg: String) {
+        self.errors.push(ResolveError { span: Span::default(), msg });
+    }
+
+    /// The loans of the block's value. Those of the function's body are
+    /// returned before its locals die.
+    fn block(&mut self, b: &hir::Block, fn_body: bool) -> Vec<Loan> {
+        self.depth += 1;
+        let mut value = Vec::new();
+        for st in &b.stmts {
+            match st {
+                Stmt::Let(l) => {
+                    let loans = self.expr(&l.init);
+                    self.depths.insert(l.id, self.depth);
+                    self.names.insert(l.id, l.name.clone());
+                    self.loans.insert(l.id, loans);
+                }
+                Stmt::Decl { id, name, .. } => {
+                    self.depths.insert(*id, self.depth);
+                    self.names.insert(*id, name.clone());
+                }
+                Stmt::Assign { id, expr, .. } => {
+                    let loans = self.expr(expr);
+                    self.store(*id, loans);
+                }
+                Stmt::AssignPlace { target, expr } => {
+                    let loans = self.expr(expr);
+                    self.expr(target);
+                    if let (Expr::Ident { id, .. }, _) = target.place_chain() {
+                        self.store(*id, loans);
+                    }
+                }
+                Stmt::Expr(e) => value = self.expr(e),
+                Stmt::Semi(e) => {
+                    self.expr(e);
+                }
+                Stmt::Return(Some(e)) => {
+                    let loans = self.expr(e);
+                    self.returned(loans);
+                }
+                Stmt::Return(None) | Stmt::Break | Stmt::Continue => {}
+            }
+        }
+        if fn_body {
+            self.returned(std::mem::take(&mut value));
+        }
+        // ono što vrednost bloka pozajmljuje mora da ga nadživi
+        let depth = self.depth;
+        let (dead, live): (Vec<_>, Vec<_>) =
+            value.into_iter().partition(|l| matches!(l, Loan::Local { depth: d, .. } if *d >= depth));
+        for loan in dead {
+            self.dead(&loan, "the block's value borrows it");
+        }
+        self.depth -= 1;
+        live
+    }
+
+    /// The loans of the references `e` evaluates to.
+    fn expr(&mut self, e: &Expr) -> Vec<Loan> {
+        use Expr::*;
+        let loans = match e {
+            Ident { id, .. } => self.loans.get(id).cloned().unwrap_or_default(),
+            Ref { expr, .. } => self.borrow(expr),
+            Field { base: inner, .. }
+            | Deref { expr: inner, .. }
+            | ToDyn { expr: inner, .. }
+            | Try { expr: inner, .. }
+            | Unary { rhs: inner, .. }
+            | Repeat { elem: inner, .. } => self.expr(inner),
+            Index { base, index, .. } => {
+                self.expr(index);
+                self.expr(base)
+            }
+            Binary { lhs, rhs, .. } => {
+                let mut loans = self.expr(lhs);
+                loans.extend(self.expr(rhs));
+                loans
+            }
+            Tuple { elems, .. } | Array { elems, .. } => elems.iter().flat_map(|e| self.expr(e)).collect(),
+            Variant { value, .. } => value.iter().flat_map(|e| self.expr(e)).collect(),
+            Block { block, .. } => self.block(block, false),
+            If { cond, then_blk, else_branch, .. } => {
+                self.expr(cond);
+                let mut loans = self.block(then_blk, false);
+                if let Some(e) = else_branch {
+                    loans.extend(self.expr(e));
+                }
+                loans
+            }
+            While { cond, body, .. } => {
+                self.expr(cond);
+                self.block(body, false);
+                Vec::new()
+            }
+            Call { callee, args, .. } => self.call(callee, args, e.ty()),
+            Spawn { callee, args, .. } => {
+                self.expr(callee);
+                for a in args {
+                    self.expr(a);
+                }
+                Vec::new()
+            }
+            // parametri zatvorenja žive koliko i njegovo telo
+            Closure { params, body, captures, .. } => {
+                for p in params {
+                    self.depths.insert(p.id, self.depth + 1);
+                    self.names.insert(p.id, p.name.clone());
+                }
+                let ret = self.ret.take();
+                self.block(body, false);
+                self.ret = ret;
+                // zatvorenje nosi reference koje su uhvaćene promenljive imale
+                captures.iter().flat_map(|c| self.loans.get(&c.id).cloned().unwrap_or_default()).collect()
+            }
+            Int { .. } | Float { .. } | Bool { .. } | Unit { .. } | Str { .. } | Builtin { .. } | Channel { .. }
+            | VecNew { .. } | FnRef { .. } | MethodRef { .. } | DynMethod { .. } => Vec::new(),
+        };
+        if e.ty().holds_ref() || matches!(e.ty(), Type::Fn { .. }) { loans } else { Vec::new() }
+    }
+
+    /// What `&place` borrows.
+    fn borrow(&mut self, place: &Expr) -> Vec<Loan> {
+        match place {
+            Expr::Ident { id, .. } if self.self_ref == Some(*id) => vec![Loan::Region("0".to_string())],
+            Expr::Ident { id, .. } => match self.depths.get(id) {
+                Some(&depth) => vec![Loan::Local { var: Some(*id), depth }],
+                None => vec![Loan::Region("static".to_string())],
+            },
+            // a field of a referenced value is borrowed from where the reference is
+            Expr::Field { base, .. } if matches!(base.ty(), Type::Ref { .. }) => self.expr(base),
+            Expr::Field { base, .. } => self.borrow(base),
+            Expr::Index { base, index, .. } => {
+                self.expr(index);
+                if matches!(base.ty(), Type::Ref { .. }) { self.expr(base) } else { self.borrow(base) }
+            }
+            Expr::Deref { expr, .. } => self.expr(expr),
+            e => {
+                self.expr(e);
+                vec![Loan::Local { var: None, depth: self.depth }]
+            }
+        }
+    }
+
+    /// `callee(args)` borrows what the arguments its return type borrows
+    /// from do; `push` stores its value in the `Vec`.
+    fn call(&mut self, callee: &Expr, args: &[Expr], ret: &Type) -> Vec<Loan> {
+        self.expr(callee);
+        let args_loans: Vec<Vec<Loan>> = args.iter().map(|a| self.expr(a)).collect();
+        if let (Expr::Builtin { kind: hir::Builtin::Push, .. }, [Expr::Ident { id, .. }, _]) = (callee, args) {
+            self.store(*id, args_loans[1].clone());
+        }
+        let mut lifetimes = Vec::new();
+        ret.lifetimes(&mut lifetimes);
+        let Type::Fn { params, .. } = callee.ty() else { return args_loans.concat() };
+        if lifetimes.is_empty() || ret.clone().name_lifetimes("_") {
+            return args_loans.concat();
+        }
+        let mut loans = Vec::new();
+        for l in lifetimes.iter().filter(|l| *l != "static") {
+            let mut from: Vec<usize> = (0..args.len())
+                .filter(|&i| {
+                    let mut ls = Vec::new();
+                    params.get(i).is_some_and(|t| {
+                        t.lifetimes(&mut ls);
+                        ls.contains(l)
+                    })
+                })
+                .collect();
+            // `&self` nema referencu u tipu, ali joj elizija daje `'0`
+            if from.is_empty() && is_elided(l) {
+                from.extend(l.parse::<usize>().ok().filter(|&i| i < args.len()));
+            }
+            loans.extend(from.into_iter().flat_map(|i| args_loans[i].clone()));
+        }
+        loans
+    }
+
+    /// `loans` are stored in variable `id`, so they have to live as long.
+    fn store(&mut self, id: VarId, loans: Vec<Loan>) {
+        let depth = self.depths.get(&id).copied().unwrap_or(0);
+        for loan in &loans {
+            if matches!(loan, Loan::Local { depth: d, .. } if *d > depth) {
+                let why = format!("`{}` still holds a reference to it", self.names.get(&id).map_or("_", |n| n.as_str()));
+                self.dead(loan, &why);
+            }
+        }
+        self.loans.entry(id).or_default().extend(loans);
+    }
+
+    fn dead(&mut self, loan: &Loan, why: &str) {
+        match loan {
+            Loan::Local { var: Some(v), .. } => {
+                let name = self.names.get(v).cloned().unwrap_or_default();
+                self.error(format!("`{name}` does not live long enough: {why}"));
+            }
+            Loan::Local { var: None, .. } => self.error(format!("temporary value does not live long enough: {why}")),
+            Loan::Region(_) => {}
+        }
+    }
+
+    /// `loans` leave the function in its return value.
+    fn returned(&mut self, loans: Vec<Loan>) {
+        let Some(ret) = self.ret.clone() else { return };
+        for loan in loans {
+            match loan {
+                Loan::Local { var: Some(v), depth } => {
+                    let what = if depth == 0 { "function parameter" } else { "local variable" };
+                    let name = self.names.get(&v).cloned().unwrap_or_default();
+                    self.error(format!("cannot return a reference to {what} `{name}`"));
+                }
+                Loan::Local { var: None, .. } => self.error("cannot return a reference to a temporary value".to_string()),
+                Loan::Region(l) if l == "static" || ret.contains(&l) => {}
+                Loan::Region(l) => {
+                    let to: Vec<String> = ret.iter().map(|l| self.describe(l)).collect();
+                    let to = if to.is_empty() { "nothing".to_string() } else { to.join(" and ") };
+                    self.error(format!(
+                        "lifetime mismatch: `{}` returns a value borrowed from {}, but its return type borrows from {to}",
+                        self.f.name,
+                        self.describe(&l)
+                    ));
+                }
+            }
+        }
+    }
+
+    /// `'a`, or the parameter an elided lifetime belongs to.
+    fn describe(&self, lifetime: &str) -> String {
+        match lifetime.parse::<usize>().ok().and_then(|i| self.f.params.get(i)) {
+            Some(p) if is_elided(lifetime) => format!("parameter `{}`", p.name),
+            _ => format!("`'{lifetime}`"),
+        }
+    }
+}
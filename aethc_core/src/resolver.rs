@@ -2,10 +2,71 @@
 //! Ulaz: &ast::Module  →  Izlaz: hir::HirModule + Vec<ResolveError>
 //! 2025‑06: mešani Int/Float, Unary, „already defined" dup‑check.
 
+use crate::const_eval::{ConstEnv, ConstError, ConstValue};
+use crate::infer_ctx::{InferCtx, TvOrTy, Ty};
 use crate::lexer::Span;
-use crate::type_::Type;
+use crate::parser::Parser;
+use crate::type_::{NumTy, Type};
 use crate::{ast, hir};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+/// Items every module sees. Language traits: a type that implements one can
+/// be used with its operator, e.g. `a + b` becomes `Add::add(a, b)`. The
+/// built-in types implement them too, so they satisfy bounds like
+/// `T: Add`; on those the operators keep their built-in meaning. And the
+/// methods of `Option` and `Result`, whose variants the resolver knows.
+const PRELUDE: &str = "
+    pub trait Add { fn add(self, rhs: Self) -> Self; }
+    pub trait Sub { fn sub(self, rhs: Self) -> Self; }
+    pub trait Mul { fn mul(self, rhs: Self) -> Self; }
+    pub trait Div { fn div(self, rhs: Self) -> Self; }
+    pub trait Rem { fn rem(self, rhs: Self) -> Self; }
+    pub trait Neg { fn neg(self) -> Self; }
+    pub trait BitAnd { fn bitand(self, rhs: Self) -> Self; }
+    pub trait BitOr { fn bitor(self, rhs: Self) -> Self; }
+    pub trait BitXor { fn bitxor(self, rhs: Self) -> Self; }
+    pub trait Shl { fn shl(self, rhs: Self) -> Self; }
+    pub trait Shr { fn shr(self, rhs: Self) -> Self; }
+    pub trait Eq { fn eq(&self, other: &Self) -> Bool; }
+    pub trait Ord { fn cmp(&self, other: &Self) -> Int; }
+
+    impl Add for Int { fn add(self, rhs: Self) -> Self { self + rhs } }
+    impl Sub for Int { fn sub(self, rhs: Self) -> Self { self - rhs } }
+    impl Mul for Int { fn mul(self, rhs: Self) -> Self { self * rhs } }
+    impl Div for Int { fn div(self, rhs: Self) -> Self { self / rhs } }
+    impl Rem for Int { fn rem(self, rhs: Self) -> Self { self % rhs } }
+    impl Neg for Int { fn neg(self) -> Self { -self } }
+    impl BitAnd for Int { fn bitand(self, rhs: Self) -> Self { self & rhs } }
+    impl BitOr for Int { fn bitor(self, rhs: Self) -> Self { self | rhs } }
+    impl BitXor for Int { fn bitxor(self, rhs: Self) -> Self { self ^ rhs } }
+    impl Shl for Int { fn shl(self, rhs: Self) -> Self { self << rhs } }
+    impl Shr for Int { fn shr(self, rhs: Self) -> Self { self >> rhs } }
+    impl Eq for Int { fn eq(&self, other: &Self) -> Bool { self == *other } }
+    impl Ord for Int { fn cmp(&self, other: &Self) -> Int { if self < *other { -1 } else if self > *other { 1 } else { 0 } } }
+
+    impl Add for Float { fn add(self, rhs: Self) -> Self { self + rhs } }
+    impl Sub for Float { fn sub(self, rhs: Self) -> Self { self - rhs } }
+    impl Mul for Float { fn mul(self, rhs: Self) -> Self { self * rhs } }
+    impl Div for Float { fn div(self, rhs: Self) -> Self { self / rhs } }
+    impl Neg for Float { fn neg(self) -> Self { -self } }
+    impl Eq for Float { fn eq(&self, other: &Self) -> Bool { self == *other } }
+    impl Ord for Float { fn cmp(&self, other: &Self) -> Int { if self < *other { -1 } else if self > *other { 1 } else { 0 } } }
+
+    impl Eq for Bool { fn eq(&self, other: &Self) -> Bool { self == *other } }
+
+    impl<T> Option<T> {
+        fn is_some(&self) -> Bool { match self { Some(_) => true, None => false } }
+        fn is_none(&self) -> Bool { match self { Some(_) => false, None => true } }
+        fn unwrap_or(self, default: T) -> T { match self { Some(x) => x, None => default } }
+    }
+
+    impl<T, E> Result<T, E> {
+        fn is_ok(&self) -> Bool { match self { Ok(_) => true, Err(_) => false } }
+        fn is_err(&self) -> Bool { match self { Ok(_) => false, Err(_) => true } }
+        fn unwrap_or(self, default: T) -> T { match self { Ok(x) => x, Err(_) => default } }
+        fn ok(self) -> Option<T> { match self { Ok(x) => Some(x), Err(_) => None } }
+    }
+";
 
 /*──────────── error type ───────────*/
 #[derive(Debug, Clone)]
@@ -14,25 +75,100 @@ pub struct ResolveError {
     pub msg: String,
 }
 
+/// Greška zajedno sa indeksom fajla iz kog potiče.
+#[derive(Debug, Clone)]
+pub struct FileError {
+    pub file: usize,
+    pub err: ResolveError,
+}
+
+/// Greške i upozorenja razrešavanja, po fajlu.
+#[derive(Debug, Default)]
+pub struct ResolveDiagnostics {
+    pub errors: Vec<FileError>,
+    pub warnings: Vec<FileError>,
+}
+
+/// Jedan modul stabla: put od korena (`[]` za koren), fajl i AST.
+pub struct ModuleAst<'a> {
+    pub path: Vec<String>,
+    pub file: usize,
+    pub ast: &'a ast::Module,
+}
+
 /*──────────── entry point ──────────*/
 pub fn resolve(m: &ast::Module) -> (hir::HirModule, Vec<ResolveError>) {
-    let mut cx = Cx::default();
-    cx.push_scope(); // global scope
+    let root = ModuleAst { path: Vec::new(), file: 0, ast: m };
+    let (hir, diags) = resolve_modules(&[root]);
+    (hir, diags.errors.into_iter().map(|e| e.err).collect())
+}
 
+/// Resolve a whole module tree into one flat HIR module. Names are only
+/// visible after their declaration, so modules are lowered in dependency
+/// order (the modules a module imports from come first); a cycle among
+/// those dependencies is an error. Private items that are never used are
+/// reported as warnings.
+pub fn resolve_modules(mods: &[ModuleAst]) -> (hir::HirModule, ResolveDiagnostics) {
+    let mut cx = Cx {
+        module_paths: mods.iter().map(|m| m.path.clone()).collect(),
+        ..Cx::default()
+    };
     let mut items = Vec::new();
-    for it in &m.items {
-        match it {
-            ast::Item::Function(f) => match cx.lower_fn(f) {
-                Ok(h) => items.push(hir::Item::Fn(h)),
-                Err(e) => cx.errors.push(e),
-            },
-            ast::Item::Let(gl) => match cx.lower_global_let(gl) {
-                Ok(h) => items.push(hir::Item::Let(h)),
-                Err(e) => cx.errors.push(e),
-            },
+    let mut errors = Vec::new();
+
+    let order = match module_order(mods, &cx.module_paths) {
+        Ok(order) => order,
+        Err(e) => {
+            errors.push(e);
+            Vec::new()
         }
+    };
+
+    // prelud se razrešava pre svih modula, kao da ga je svaki uvezao
+    cx.scopes = vec![HashMap::new()];
+    let mut lang_items = Vec::new();
+    cx.lower_items(&Parser::new(PRELUDE).parse_module(), &mut lang_items);
+    cx.prelude = cx.scopes.pop().unwrap();
+    let lang_count = cx.impls.len();
+    debug_assert!(cx.errors.is_empty(), "{:?}", cx.errors);
+
+    for i in order {
+        let m = &mods[i];
+        cx.cur_module = m.path.clone();
+        cx.cur_file = m.file;
+        cx.aliases.clear();
+        cx.scopes = vec![HashMap::new()]; // module scope
+        cx.lower_items(m.ast, &mut items);
+        errors.extend(cx.errors.drain(..).map(|err| FileError { file: m.file, err }));
+        let scope = cx.scopes.pop().unwrap();
+        cx.module_scopes.insert(m.path.clone(), scope);
     }
-    (hir::HirModule { items }, cx.errors)
+
+    let warnings = cx
+        .private_items
+        .iter()
+        .filter(|it| !cx.used.contains(&it.id))
+        .map(|it| FileError {
+            file: it.file,
+            err: ResolveError {
+                span: Span::default(),
+                msg: format!("{} `{}` is never used", it.kind, it.name),
+            },
+        })
+        .collect();
+
+    let global_values = std::mem::take(&mut cx.global_values);
+    let mut impls: Vec<_> = cx.impls.into_iter().map(|i| i.hir).collect();
+    let lang_impls = impls.drain(..lang_count).collect();
+    let lang_fns = lang_items
+        .into_iter()
+        .filter_map(|it| match it {
+            hir::Item::Fn(f) => Some(f),
+            _ => None,
+        })
+        .collect();
+    let hir = hir::HirModule { items, global_values, impls, lang_impls, lang_fns };
+    (hir, ResolveDiagnostics { errors, warnings })
 }
 
 /*──────────── context ──────────────*/
@@ -42,16 +178,277 @@ struct Cx {
     scopes: Vec<HashMap<String, Symbol>>, // stack of scopes
     errors: Vec<ResolveError>,
     current_ret_ty: Option<Type>,
+    loop_depth: u32, // number of enclosing `while` loops
+    closures: Vec<ClosureFrame>, // closures whose body is being lowered, innermost last
+    fn_sigs: HashMap<hir::NodeId, FnSig>,
+    type_params: Vec<TypeParam>, // of the generic function, trait or impl being lowered
+    lifetimes: Vec<String>,      // in scope: those of the impl, then of the function
+    deferred: HashSet<hir::NodeId>, // immutable `let x: T;` bindings, assigned once
+    typed_literals: HashSet<hir::NodeId>, // `10u8` and the like keep their type
+    wide_literals: HashSet<hir::NodeId>, // unsuffixed literals too big for Int, see `check_wide_literals`
+    generic_fn: Option<hir::NodeId>,
+    traits: HashMap<hir::NodeId, TraitDef>,
+    impls: Vec<ImplDef>,
+    inherent: Vec<InherentImpl>,
+    self_ty: Option<Type>,      // what `Self` and `self` stand for in a trait or impl
+    cur_impl: Option<usize>,    // index in `impls` of the impl being lowered
+    self_ref: Option<hir::NodeId>, // `self` of the `&self` method being lowered
+    consts: ConstEnv, // known values and pure fns for the const evaluator
+    global_values: HashMap<hir::NodeId, ConstValue>,
+
+    /*── modules ─*/
+    module_paths: Vec<Vec<String>>, // every module in the tree
+    module_scopes: HashMap<Vec<String>, HashMap<String, Symbol>>, // already lowered
+    cur_module: Vec<String>,
+    cur_file: usize,
+    aliases: HashMap<String, Vec<String>>, // `use a::b;` → `b` names module `a::b`
+    prelude: HashMap<String, Symbol>, // language traits, visible in every module
+
+    /*── dead code ─*/
+    private_items: Vec<PrivateItem>,
+    used: HashSet<hir::NodeId>, // every item referred to by name or path
+}
+
+/// Item without `pub`; warned about if nothing refers to it.
+struct PrivateItem {
+    id: hir::NodeId,
+    kind: &'static str,
+    name: String,
+    file: usize,
+}
+
+/// Zatvorenje čije se telo upravo razrešava.
+struct ClosureFrame {
+    depth: usize, // index of the closure's own scope; names below it are captured
+    by_move: bool,
+    ret: Option<Type>, // `None` – the closure returns its body's type
+    captures: Vec<hir::Capture>,
+}
+
+/// Potpis funkcije – tipovi parametara i povratni tip, za proveru poziva.
+#[derive(Clone)]
+struct FnSig {
+    name: String, // qualified, e.g. `geom::area`
+    type_params: Vec<TypeParam>,
+    params: Vec<Type>,
+    ret: Type,
+    self_mode: Option<ast::SelfMode>, // of a method
+}
+
+impl FnSig {
+    /// Parameter types with the type parameters left unknown, for filling
+    /// `None` and the like among the arguments before they are inferred.
+    fn holed_params(&self) -> Vec<Type> {
+        let holes = self.type_params.iter().map(|p| (p.name.clone(), Type::Infer)).collect();
+        self.params.iter().map(|t| t.subst(&holes)).collect()
+    }
+}
+
+/// Tip-parametar generičke funkcije. Telo ga ne poznaje, ali ga može staviti
+/// u `Vec` ili kanal, pa konkretan tip mora stati u mašinsku reč, i može
+/// pozivati metode osobina iz `bounds`.
+#[derive(Clone)]
+struct TypeParam {
+    name: String,
+    in_vec: bool,
+    in_channel: bool,
+    bounds: Vec<hir::NodeId>, // traits
+}
+
+/// Osobina: potpisi metoda, u kojima je `Self` jedini tip-parametar.
+#[derive(Clone)]
+struct TraitDef {
+    name: String, // qualified
+    methods: Vec<(String, FnSig)>,
+}
+
+/// Implementacija osobine; `type_params` nose granice i zahteve tela metoda.
+struct ImplDef {
+    hir: hir::HirImpl,
+    type_params: Vec<TypeParam>,
+}
+
+/// Metode tipa bez osobine, `impl Type { .. }`.
+struct InherentImpl {
+    self_ty: Type,
+    methods: Vec<(String, hir::NodeId)>,
+}
+
+impl InherentImpl {
+    fn method(&self, name: &str) -> Option<hir::NodeId> {
+        self.methods.iter().find(|(n, _)| n == name).map(|&(_, id)| id)
+    }
 }
 
+/// Index of an impl in `Cx::impls` with the types its parameters stand for.
+type ImplMatch = (usize, HashMap<String, Type>);
+
+/// Provera `T: Trait` kroz generičke implementacije sme ići samo ovoliko
+/// duboko.
+const MAX_IMPL_DEPTH: u32 = 32;
+
 #[derive(Clone)]
 struct Symbol {
     id: hir::NodeId,
     ty: Type,
     is_mut: bool,
+    value: Option<ConstValue>, // `const` – uses are replaced by the value
+    is_pub: bool,              // visible outside of its module
 }
 
 impl Cx {
+    /*── items of one module ─*/
+    fn lower_items(&mut self, m: &ast::Module, items: &mut Vec<hir::Item>) {
+        for it in &m.items {
+            match it {
+                ast::Item::Function(f) => match self.lower_fn(f) {
+                    Ok(h) => {
+                        // `main` is used by the runtime
+                        let entry = self.cur_module.is_empty() && f.name == "main";
+                        self.declare(f.is_pub || entry, h.id, "function", &f.name);
+                        self.consts.add_fn(&h);
+                        items.push(hir::Item::Fn(h));
+                    }
+                    Err(e) => self.errors.push(e),
+                },
+                ast::Item::Const(c) => match self.lower_const(c) {
+                    Ok(h) => {
+                        self.declare(c.is_pub, h.id, "constant", &c.name);
+                        items.push(hir::Item::Const(h));
+                    }
+                    Err(e) => self.errors.push(e),
+                },
+                ast::Item::Let(gl) => match self.lower_global_let(gl) {
+                    Ok(h) => {
+                        self.declare(gl.is_pub, h.id, "global", &gl.name);
+                        items.push(hir::Item::Let(h));
+                    }
+                    Err(e) => self.errors.push(e),
+                },
+                ast::Item::Use(path) => {
+                    if let Err(e) = self.lower_use(path) {
+                        self.errors.push(e);
+                    }
+                }
+                ast::Item::Trait(t) => {
+                    if let Err(e) = self.lower_trait(t) {
+                        self.errors.push(e);
+                    }
+                }
+                ast::Item::Impl(imp) => {
+                    let result = self.lower_impl(imp, items);
+                    self.type_params.clear();
+                    self.lifetimes.clear();
+                    self.self_ty = None;
+                    self.cur_impl = None;
+                    self.generic_fn = None;
+                    if let Err(e) = result {
+                        self.errors.push(e);
+                    }
+                }
+                // the module tree is built by `loader`
+                ast::Item::Mod(_) => {}
+            }
+            self.check_wide_literals();
+        }
+    }
+
+    /// An unsuffixed literal that doesn't fit in `Int` is only allowed where
+    /// `adapt_literal` gives it a wider type, e.g. `let x: i64 = 3000000000;`.
+    fn check_wide_literals(&mut self) {
+        if !self.wide_literals.is_empty() {
+            self.wide_literals.clear();
+            self.errors.push(ResolveError { span: Span::default(), msg: "literal out of range for `Int`".to_string() });
+        }
+    }
+
+    fn declare(&mut self, is_pub: bool, id: hir::NodeId, kind: &'static str, name: &str) {
+        if !is_pub {
+            let (name, file) = (name.to_owned(), self.cur_file);
+            self.private_items.push(PrivateItem { id, kind, name, file });
+        }
+    }
+
+    /// Name of an item of the current module as seen from the crate root.
+    fn qualify(&self, name: &str) -> String {
+        let mut path = self.cur_module.clone();
+        path.push(name.to_owned());
+        path.join("::")
+    }
+
+    /// `use a::b::item;` imports an item, `use a::b;` the module itself.
+    fn lower_use(&mut self, path: &[String]) -> Result<(), ResolveError> {
+        let (last, prefix) = path.split_last().unwrap();
+        let is_item = !prefix.is_empty()
+            && self
+                .find_module(prefix)
+                .and_then(|m| self.module_scope(&m))
+                .is_some_and(|scope| scope.contains_key(last));
+        if is_item {
+            let sym = self.lookup_path(path)?;
+            self.used.insert(sym.id);
+            // an import is private to the importing module
+            return self.insert(last, Symbol { is_pub: false, ..sym }, Span::default());
+        }
+        match self.find_module(path) {
+            Some(m) => {
+                self.aliases.insert(last.clone(), m);
+                Ok(())
+            }
+            None => Err(ResolveError {
+                span: Span::default(),
+                msg: format!("unresolved import `{}`", path.join("::")),
+            }),
+        }
+    }
+
+    /// Module named by `segs`, taking `use` aliases into account.
+    fn find_module(&self, segs: &[String]) -> Option<Vec<String>> {
+        match segs.first().and_then(|s| self.aliases.get(s)) {
+            Some(target) => {
+                let mut abs = target.clone();
+                abs.extend_from_slice(&segs[1..]);
+                self.module_paths.contains(&abs).then_some(abs)
+            }
+            None => find_module(&self.module_paths, &self.cur_module, segs),
+        }
+    }
+
+    /// Item named by a qualified path `a::b::item`.
+    fn lookup_path(&self, path: &[String]) -> Result<Symbol, ResolveError> {
+        let (last, prefix) = path.split_last().unwrap();
+        let Some(module) = self.find_module(prefix) else {
+            return Err(ResolveError {
+                span: Span::default(),
+                msg: format!("unresolved module `{}`", prefix.join("::")),
+            });
+        };
+        let sym = self.module_scope(&module).and_then(|s| s.get(last)).cloned();
+        let Some(sym) = sym else {
+            return Err(ResolveError {
+                span: Span::default(),
+                msg: format!("cannot find `{last}` in module `{}`", module_name(&module)),
+            });
+        };
+        // private items are visible in their module and its submodules
+        if !sym.is_pub && !self.cur_module.starts_with(&module) {
+            return Err(ResolveError {
+                span: Span::default(),
+                msg: format!("`{last}` is private to module `{}`", module_name(&module)),
+            });
+        }
+        Ok(sym)
+    }
+
+    /// Top-level names of a module: the one being lowered or an earlier one.
+    fn module_scope(&self, module: &[String]) -> Option<&HashMap<String, Symbol>> {
+        if module == self.cur_module {
+            self.scopes.first()
+        } else {
+            self.module_scopes.get(module)
+        }
+    }
+
     /*── id & scope helpers ─*/
     fn fresh(&mut self) -> hir::NodeId {
         let id = self.next_id;
@@ -66,26 +463,75 @@ impl Cx {
     }
 
     /*── symbol table insert ─*/
+    /// Declare a function, global or parameter. Such names must be unique
+    /// within their scope.
     fn insert(&mut self, name: &str, sym: Symbol, span: Span) -> Result<(), ResolveError> {
         let top = self.scopes.last_mut().unwrap();
-
-        if let Some(prev) = top.get(name) {
-            if prev.is_mut {
-                top.insert(name.to_owned(), sym);
-                return Ok(());
-            } else {
-                return Err(ResolveError {
-                    span,
-                    msg: format!("cannot redeclare immutable binding `{}`", name),
-                });
-            }
+        if top.contains_key(name) {
+            return Err(ResolveError {
+                span,
+                msg: format!("`{name}` is already defined in this scope"),
+            });
         }
         top.insert(name.to_owned(), sym);
         Ok(())
     }
 
+    /// Introduce a local `let` binding. Every `let` creates a fresh binding
+    /// that shadows any earlier one with the same name until the end of the
+    /// enclosing block; the shadowed binding keeps its own id and mutability.
+    fn shadow(&mut self, name: &str, sym: Symbol) {
+        self.scopes
+            .last_mut()
+            .unwrap()
+            .insert(name.to_owned(), sym);
+    }
+
     fn lookup(&self, name: &str) -> Option<&Symbol> {
-        self.scopes.iter().rev().find_map(|s| s.get(name))
+        self.scopes.iter().rev().find_map(|s| s.get(name)).or_else(|| self.prelude.get(name))
+    }
+
+    /// Records `name` as a capture of every closure being lowered that it
+    /// is declared outside of. Items of the module scope are not captured.
+    fn capture(&mut self, name: &str) {
+        let Some(depth) = self.scopes.iter().rposition(|s| s.contains_key(name)) else { return };
+        let sym = &self.scopes[depth][name];
+        if depth == 0 || sym.value.is_some() {
+            return;
+        }
+        let cap = hir::Capture { id: sym.id, name: name.to_owned(), ty: sym.ty.clone() };
+        for frame in self.closures.iter_mut().filter(|f| f.depth > depth) {
+            if !frame.captures.iter().any(|c| c.id == cap.id) {
+                frame.captures.push(cap.clone());
+            }
+        }
+    }
+
+    /// A `move` closure works on its own copy of a captured variable, so
+    /// assigning to the variable inside it would be lost.
+    fn check_assign_capture(&self, name: &str) -> Result<(), ResolveError> {
+        let Some(depth) = self.scopes.iter().rposition(|s| s.contains_key(name)) else { return Ok(()) };
+        if depth > 0 && self.closures.iter().any(|f| f.by_move && f.depth > depth) {
+            return Err(ResolveError {
+                span: Span::default(),
+                msg: format!("cannot assign to `{name}`: it is captured by value in a `move` closure"),
+            });
+        }
+        Ok(())
+    }
+
+    /// An immutable `let x: T;` is assigned once, which `cfg` can't prove
+    /// for a closure that may run any number of times.
+    fn check_deferred_capture(&self, name: &str) -> Result<(), ResolveError> {
+        let Some(depth) = self.scopes.iter().rposition(|s| s.contains_key(name)) else { return Ok(()) };
+        let deferred = self.scopes[depth].get(name).is_some_and(|s| self.deferred.contains(&s.id));
+        if deferred && self.closures.iter().any(|f| f.depth > depth) {
+            return Err(ResolveError {
+                span: Span::default(),
+                msg: format!("cannot assign to immutable `{name}` inside a closure; declare it `let mut {name}`"),
+            });
+        }
+        Ok(())
     }
 
     fn lookup_mut(&mut self, name: &str) -> Option<&mut Symbol> {
@@ -107,130 +553,1263 @@ impl Cx {
     }
 
     /*──────── type lookup ───────*/
-    fn resolve_type(&mut self, name: &str, span: Span) -> Result<Type, ResolveError> {
+    fn resolve_type(&mut self, t: &ast::TypeExpr, span: Span) -> Result<Type, ResolveError> {
+        let (name, args) = match t {
+            ast::TypeExpr::Named { name, args } if args.is_empty() && self.type_params.iter().any(|p| p.name == *name) => {
+                return Ok(Type::Param(name.clone()));
+            }
+            ast::TypeExpr::Named { name, args } if name == "Self" && args.is_empty() && self.self_ty.is_some() => {
+                return Ok(self.self_ty.clone().unwrap());
+            }
+            ast::TypeExpr::Named { name, args } => (name.as_str(), args),
+            ast::TypeExpr::Array { elem, len } => {
+                let elem = self.resolve_type(elem, span)?;
+                let elem = self.array_elem(elem, span)?;
+                return Ok(Type::Array(Box::new(elem), self.array_len(len)?));
+            }
+            ast::TypeExpr::Tuple(elems) if elems.is_empty() => return Ok(Type::Unit),
+            ast::TypeExpr::Tuple(elems) => {
+                let elems = elems.iter().map(|t| self.resolve_type(t, span)).collect::<Result<_, _>>()?;
+                return Ok(Type::Tuple(elems));
+            }
+            ast::TypeExpr::Fn { params, ret } => {
+                let params = params.iter().map(|t| self.resolve_type(t, span)).collect::<Result<_, _>>()?;
+                let ret = match ret {
+                    Some(t) => self.resolve_type(t, span)?,
+                    None => Type::Unit,
+                };
+                return Ok(Type::Fn { params, ret: Box::new(ret) });
+            }
+            ast::TypeExpr::Ref { mutable, lifetime, inner } => {
+                if let Some(l) = lifetime.as_ref().filter(|l| *l != "static" && !self.lifetimes.contains(l)) {
+                    return Err(ResolveError { span, msg: format!("use of undeclared lifetime name `'{l}`") });
+                }
+                let inner = match &**inner {
+                    ast::TypeExpr::Dyn(path) => self.dyn_type(path)?,
+                    t => self.resolve_type(t, span)?,
+                };
+                return Ok(Type::Ref { mutability: *mutable, inner: Box::new(inner), lifetime: lifetime.clone() });
+            }
+            ast::TypeExpr::Dyn(path) => {
+                let path = path.join("::");
+                return Err(ResolveError {
+                    span,
+                    msg: format!("a trait object must be behind a reference: `&dyn {path}`"),
+                });
+            }
+        };
+        let arity = match name {
+            "Sender" | "Receiver" | "Vec" | "Option" => 1,
+            "Result" => 2,
+            _ => 0,
+        };
+        if args.len() != arity {
+            return Err(ResolveError {
+                span,
+                msg: format!("`{name}` takes {arity} type argument(s), got {}", args.len()),
+            });
+        }
         match name {
             "Int" | "int" => Ok(Type::Int),
             "Float" | "float" => Ok(Type::Float),
             "Bool" | "bool" => Ok(Type::Bool),
+            "Char" | "char" => Ok(Type::Char),
             "Str" | "String" => Ok(Type::Str),
-            _ => Err(ResolveError {
+            "Sender" => Ok(Type::Sender(Box::new(self.channel_elem(&args[0], span)?))),
+            "Receiver" => Ok(Type::Receiver(Box::new(self.channel_elem(&args[0], span)?))),
+            "Vec" => Ok(Type::Vec(Box::new(self.vec_elem(&args[0], span)?))),
+            "Option" => Ok(Type::Option(Box::new(self.resolve_type(&args[0], span)?))),
+            "Result" => {
+                let ok = self.resolve_type(&args[0], span)?;
+                Ok(Type::Result(Box::new(ok), Box::new(self.resolve_type(&args[1], span)?)))
+            }
+            _ => NumTy::from_name(name).map(NumTy::ty).ok_or_else(|| ResolveError {
                 span,
                 msg: format!("unknown type `{name}`"),
             }),
         }
     }
 
+    /// Element type of a channel; the runtime passes one machine word.
+    fn channel_elem(&mut self, t: &ast::TypeExpr, span: Span) -> Result<Type, ResolveError> {
+        let elem = self.resolve_type(t, span)?;
+        self.word_elem(&elem, false).map_err(|what| ResolveError {
+            span,
+            msg: format!("a channel cannot carry {what}, got {elem:?}"),
+        })?;
+        Ok(elem)
+    }
+
+    /// Element type of a `Vec`; the runtime stores one machine word per
+    /// element, and a `Vec` owns its elements outright.
+    fn vec_elem(&mut self, t: &ast::TypeExpr, span: Span) -> Result<Type, ResolveError> {
+        let elem = self.resolve_type(t, span)?;
+        self.word_elem(&elem, true).map_err(|what| ResolveError {
+            span,
+            msg: format!("a Vec cannot hold {what}, got {elem:?}"),
+        })?;
+        Ok(elem)
+    }
+
+    /// Check that `elem` fits one word of a `Vec` (`in_vec`) or a channel; a
+    /// type parameter of the current function remembers the requirement,
+    /// which its instances are checked against.
+    fn word_elem(&mut self, elem: &Type, in_vec: bool) -> Result<(), &'static str> {
+        match elem {
+            Type::Array(..) => Err("an array"),
+            Type::Tuple(_) => Err("a tuple"),
+            Type::Option(_) => Err("an Option"),
+            Type::Result(..) => Err("a Result"),
+            Type::Fn { .. } => Err("a function value"),
+            Type::Vec(_) if in_vec => Err("another Vec"),
+            // nit bi mogla nadživeti promenljivu na koju referenca pokazuje
+            Type::Ref { .. } if !in_vec => Err("a reference"),
+            Type::Param(name) => {
+                let p = self.type_params.iter_mut().find(|p| p.name == *name).expect("type parameter in scope");
+                if in_vec {
+                    p.in_vec = true;
+                } else {
+                    p.in_channel = true;
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Arrays are copied, so they must not share a `Vec` between copies.
+    fn array_elem(&self, elem: Type, span: Span) -> Result<Type, ResolveError> {
+        fn holds_vec(t: &Type) -> bool {
+            match t {
+                Type::Vec(_) => true,
+                Type::Tuple(elems) => elems.iter().any(holds_vec),
+                Type::Option(t) => holds_vec(t),
+                Type::Result(t, e) => holds_vec(t) || holds_vec(e),
+                _ => false,
+            }
+        }
+        if holds_vec(&elem) {
+            return Err(ResolveError {
+                span,
+                msg: format!("an array cannot hold a Vec, got elements of type {elem:?}"),
+            });
+        }
+        Ok(elem)
+    }
+
+    /// Length of `[T; len]` or `[elem; len]`, computed by the const evaluator.
+    fn array_len(&mut self, len: &ast::Expr) -> Result<usize, ResolveError> {
+        let len = self.lower_expr(len)?;
+        match self.consts.eval(&len) {
+            Ok(ConstValue::Int(n)) if n >= 0 => Ok(n as usize),
+            Ok(ConstValue::Int(n)) => Err(ResolveError {
+                span: Span::default(),
+                msg: format!("array length must not be negative, got {n}"),
+            }),
+            Ok(_) => Err(ResolveError {
+                span: Span::default(),
+                msg: format!("array length must be Int, got {:?}", len.ty()),
+            }),
+            Err(e) => Err(ResolveError {
+                span: Span::default(),
+                msg: format!("array length must be a constant: {}", e.msg()),
+            }),
+        }
+    }
+
     /*──────── lower fn ──────────*/
     fn lower_fn(&mut self, f: &ast::Function) -> Result<hir::HirFn, ResolveError> {
         let id = self.fresh();
+        let result = self.enter_type_params(&f.type_params).and_then(|()| {
+            self.generic_fn = (!f.type_params.is_empty()).then_some(id);
+            // Register function name in current scope before processing body
+            self.insert(
+                &f.name,
+                Symbol {
+                    id,
+                    ty: Type::Unit, // Functions have Unit type for now
+                    is_mut: false,
+                    value: None,
+                    is_pub: f.is_pub,
+                },
+                Span::default(),
+            )?;
+            self.lower_fn_body(id, f, self.qualify(&f.name))
+        });
+        self.type_params.clear();
+        self.generic_fn = None;
+        result
+    }
 
-        // Register function name in current scope before processing body
-        self.insert(
-            &f.name,
-            Symbol {
-                id,
-                ty: Type::Unit, // Functions have Unit type for now
-                is_mut: false,
-            },
-            Span::default(),
-        )?;
+    /// Make `tps` the type parameters in scope, with their bounds resolved.
+    fn enter_type_params(&mut self, tps: &[ast::TypeParam]) -> Result<(), ResolveError> {
+        self.type_params.clear();
+        for tp in tps {
+            if self.type_params.iter().any(|p| p.name == tp.name) {
+                return Err(ResolveError {
+                    span: Span::default(),
+                    msg: format!("type parameter `{}` is declared twice", tp.name),
+                });
+            }
+            let bounds = tp.bounds.iter().map(|b| self.lookup_trait(b)).collect::<Result<_, _>>()?;
+            self.type_params.push(TypeParam { name: tp.name.clone(), in_vec: false, in_channel: false, bounds });
+        }
+        Ok(())
+    }
 
-        self.push_scope();
+    /// Add the lifetime parameters `names` to those in scope.
+    fn enter_lifetimes(&mut self, names: &[String]) -> Result<(), ResolveError> {
+        for name in names {
+            let msg = if name == "static" {
+                "`'static` is a reserved lifetime name".to_string()
+            } else if self.lifetimes.contains(name) {
+                format!("lifetime `'{name}` is declared twice")
+            } else {
+                self.lifetimes.push(name.clone());
+                continue;
+            };
+            return Err(ResolveError { span: Span::default(), msg });
+        }
+        Ok(())
+    }
+
+    /// Declared type of a parameter; `self` stands for `Self` of the
+    /// enclosing trait or impl.
+    fn param_ty(&mut self, p: &ast::Param) -> Result<Type, ResolveError> {
+        match (&p.ty, &self.self_ty) {
+            (None, Some(self_ty)) if p.name == "self" => Ok(self_ty.clone()),
+            (None, None) if p.name == "self" => Err(ResolveError {
+                span: Span::default(),
+                msg: "`self` parameter is only allowed in methods".to_string(),
+            }),
+            (Some(_), _) if p.name == "self" => Err(ResolveError {
+                span: Span::default(),
+                msg: "`self` parameter cannot have a type annotation".to_string(),
+            }),
+            (Some(t), _) => self.resolve_type(t, Span::default()),
+            (None, _) => Ok(Type::Unit),
+        }
+    }
+
+    /// Body and signature of function `f`, which is lowered under `name`.
+    /// An item function is already in scope under its own name.
+    fn lower_fn_body(&mut self, id: hir::NodeId, f: &ast::Function, name: String) -> Result<hir::HirFn, ResolveError> {
+        let outer = self.lifetimes.len();
+        let result = self.enter_lifetimes(&f.lifetimes).and_then(|()| self.lower_fn_with_lifetimes(id, f, name));
+        self.lifetimes.truncate(outer);
+        result
+    }
 
-        let return_ty = if let Some(name) = &f.return_ty {
+    fn lower_fn_with_lifetimes(&mut self, id: hir::NodeId, f: &ast::Function, name: String) -> Result<hir::HirFn, ResolveError> {
+        let mut return_ty = if let Some(name) = &f.return_ty {
             self.resolve_type(name, Span::default())?
         } else {
             Type::Unit
         };
 
-        self.current_ret_ty = Some(return_ty.clone());
-
         // params
         let mut params = Vec::new();
-        for p in &f.params {
-            let ty = if let Some(tname) = &p.ty {
-                self.resolve_type(tname, Span::default())?
-            } else {
-                Type::Unit
-            };
-            let pid = self.fresh();
-            self.insert(
-                &p.name,
-                Symbol {
-                    id: pid,
-                    ty: ty.clone(),
-                    is_mut: false,
-                },
-                Span::default(),
-            )?;
+        for (i, p) in f.params.iter().enumerate() {
+            if i > 0 && p.name == "self" {
+                return Err(ResolveError {
+                    span: Span::default(),
+                    msg: format!("`self` must be the first parameter of `{}`", f.name),
+                });
+            }
+            let ty = self.param_ty(p)?;
             params.push(hir::Param {
-                id: pid,
+                id: self.fresh(),
                 name: p.name.clone(),
                 ty,
             });
         }
+        let mut param_tys: Vec<Type> = params.iter().map(|p| p.ty.clone()).collect();
+        elide(&f.name, f.self_mode, &mut param_tys, &mut return_ty)?;
+        for (p, ty) in params.iter_mut().zip(param_tys) {
+            p.ty = ty;
+        }
 
-        // body
-        let mut stmts = Vec::new();
-        for s in &f.body {
-            stmts.push(self.lower_stmt(s)?);
+        let sig = FnSig {
+            name: name.clone(),
+            type_params: self.type_params.clone(),
+            params: params.iter().map(|p| p.ty.clone()).collect(),
+            ret: return_ty.clone(),
+            self_mode: f.self_mode,
+        };
+        // used as a value the function has a `fn(..)` type
+        let fn_ty = Type::Fn { params: sig.params.clone(), ret: Box::new(sig.ret.clone()) };
+        if let Some(sym) = self.scopes.last_mut().unwrap().get_mut(&f.name).filter(|s| s.id == id) {
+            sym.ty = fn_ty;
+        }
+        self.fn_sigs.insert(id, sig);
+
+        // `&mut self` menja vrednost pozivaoca
+        let by_ref = matches!(f.self_mode, Some(ast::SelfMode::Ref | ast::SelfMode::RefMut));
+        self.self_ref = by_ref.then(|| params[0].id);
+        self.push_scope();
+        for p in &params {
+            let sym = Symbol {
+                id: p.id,
+                ty: p.ty.clone(),
+                is_mut: p.name == "self" && f.self_mode == Some(ast::SelfMode::RefMut),
+                value: None,
+                is_pub: false,
+            };
+            if let Err(e) = self.insert(&p.name, sym, Span::default()) {
+                self.pop_scope();
+                return Err(e);
+            }
         }
+        self.current_ret_ty = Some(return_ty.clone());
+
+        // body
+        let body = self.lower_block(&f.body);
         self.pop_scope();
         self.current_ret_ty = None;
+        self.self_ref = None;
+        let mut body = body?;
+        // telo je možda stavilo tip-parametre u `Vec` ili kanal
+        self.fn_sigs.get_mut(&id).unwrap().type_params = self.type_params.clone();
+        if let Some(hir::Stmt::Expr(tail)) = body.stmts.last_mut() {
+            let e = std::mem::replace(tail, hir::Expr::Unit { id: 0, ty: Type::Unit });
+            *tail = self.coerce(&return_ty, e)?;
+        }
+
+        // the tail's value is returned implicitly, unless control never
+        // gets past a `return` to produce one; a body without tail that
+        // can reach its end is reported by `cfg::check_module`
+        let tail_returns = body.tail().is_some_and(|tail| !tail.diverges());
+        if tail_returns && !self.compatible(&return_ty, &body.ty()) {
+            return Err(ResolveError {
+                span: Span::default(),
+                msg: format!("expected {:?}, got {:?}", return_ty, body.ty()),
+            });
+        }
 
         Ok(hir::HirFn {
             id,
-            name: f.name.clone(),
+            name,
+            file: self.cur_file,
+            span: f.span,
+            type_params: self.type_params.iter().map(|p| p.name.clone()).collect(),
             params,
             return_ty,
-            body: hir::Block { id, stmts },
+            self_mode: f.self_mode,
+            body,
         })
     }
 
-    /*──────── lower global let ─*/
-    fn lower_global_let(&mut self, g: &ast::GlobalLet) -> Result<hir::HirLet, ResolveError> {
-        let id = self.fresh();
-        let init = self.lower_expr(&g.expr)?;
-        let ty = init.ty().clone();
-        self.insert(
-            &g.name,
-            Symbol {
-                id,
-                ty: ty.clone(),
-                is_mut: g.mutable,
-            },
-            Span::default(),
-        )?;
-        Ok(hir::HirLet {
-            id,
-            mutable: g.mutable,
-            name: g.name.clone(),
-            ty,
-            init,
-        })
+    /*──────── traits ────────────*/
+    /// Trait named by `path`.
+    fn lookup_trait(&mut self, path: &[String]) -> Result<hir::NodeId, ResolveError> {
+        let sym = match path {
+            [name] => self.lookup(name).cloned().ok_or_else(|| ResolveError {
+                span: Span::default(),
+                msg: format!("unknown trait `{name}`"),
+            })?,
+            _ => self.lookup_path(path)?,
+        };
+        if !self.traits.contains_key(&sym.id) {
+            return Err(ResolveError {
+                span: Span::default(),
+                msg: format!("`{}` is not a trait", path.join("::")),
+            });
+        }
+        self.used.insert(sym.id);
+        Ok(sym.id)
     }
 
-    /*──────── lower stmt ────────*/
-    fn lower_stmt(&mut self, s: &ast::Stmt) -> Result<hir::Stmt, ResolveError> {
+    /// `trait Name { .. }` – the method signatures are checked with `Self`
+    /// as a type parameter.
+    fn lower_trait(&mut self, t: &ast::Trait) -> Result<(), ResolveError> {
+        let id = self.fresh();
+        let name = self.qualify(&t.name);
+        self.type_params = vec![TypeParam { name: "Self".to_string(), in_vec: false, in_channel: false, bounds: Vec::new() }];
+        self.self_ty = Some(Type::Param("Self".to_string()));
+        let methods = self.trait_methods(t, &name);
+        self.type_params.clear();
+        self.lifetimes.clear();
+        self.self_ty = None;
+        let methods = methods?;
+
+        let sym = Symbol { id, ty: Type::Unit, is_mut: false, value: None, is_pub: t.is_pub };
+        self.insert(&t.name, sym, Span::default())?;
+        self.traits.insert(id, TraitDef { name, methods });
+        self.declare(t.is_pub, id, "trait", &t.name);
+        Ok(())
+    }
+
+    fn trait_methods(&mut self, t: &ast::Trait, name: &str) -> Result<Vec<(String, FnSig)>, ResolveError> {
+        let mut methods: Vec<(String, FnSig)> = Vec::new();
+        for m in &t.methods {
+            if methods.iter().any(|(n, _)| *n == m.name) {
+                return Err(ResolveError {
+                    span: Span::default(),
+                    msg: format!("method `{}` is declared twice in trait `{name}`", m.name),
+                });
+            }
+            check_method(&m.name, &m.type_params, &m.params)?;
+            self.lifetimes.clear();
+            self.enter_lifetimes(&m.lifetimes)?;
+            let mut params: Vec<Type> = m.params.iter().map(|p| self.param_ty(p)).collect::<Result<_, _>>()?;
+            let mut ret = match &m.return_ty {
+                Some(t) => self.resolve_type(t, Span::default())?,
+                None => Type::Unit,
+            };
+            elide(&m.name, m.self_mode, &mut params, &mut ret)?;
+            // `Self` je možda u `Vec`-u ili kanalu
+            let type_params = self.type_params.clone();
+            let sig = FnSig { name: format!("{name}::{}", m.name), type_params, params, ret, self_mode: m.self_mode };
+            methods.push((m.name.clone(), sig));
+        }
+        Ok(methods)
+    }
+
+    /// `impl<T> Trait for Type { .. }`. The impl may not overlap another
+    /// impl of the trait, and it has to define exactly the trait's methods,
+    /// with `Self` replaced by `Type`. Each method becomes a function named
+    /// `<Type as Trait>::method`, generic over the impl's type parameters.
+    fn lower_impl(&mut self, imp: &ast::Impl, items: &mut Vec<hir::Item>) -> Result<(), ResolveError> {
+        let Some(trait_path) = &imp.trait_path else {
+            return self.lower_inherent_impl(imp, items);
+        };
+        let trait_id = self.lookup_trait(trait_path)?;
+        self.enter_type_params(&imp.type_params)?;
+        self.enter_lifetimes(&imp.lifetimes)?;
+        let self_ty = self.resolve_type(&imp.self_ty, Span::default())?;
+        let tr = self.traits[&trait_id].clone();
+        // the type parameters are inferred from the type the method is called for
+        if let Some(p) = self.type_params.iter().find(|p| !self_ty.mentions(&p.name)) {
+            return Err(ResolveError {
+                span: Span::default(),
+                msg: format!("type parameter `{}` of the impl of `{}` is not used by `{self_ty:?}`", p.name, tr.name),
+            });
+        }
+        let other = self.impls.iter().find(|i| i.hir.trait_id == trait_id && i.hir.self_ty.overlaps(&self_ty));
+        if let Some(other) = other {
+            return Err(ResolveError {
+                span: Span::default(),
+                msg: format!(
+                    "conflicting implementations of `{}` for `{:?}` and `{self_ty:?}`",
+                    tr.name, other.hir.self_ty
+                ),
+            });
+        }
+
+        // registered before the bodies, which may call their own methods
+        let index = self.impls.len();
+        let type_params = self.type_params.iter().map(|p| p.name.clone()).collect();
+        let hir = hir::HirImpl { trait_id, type_params, self_ty: self_ty.clone(), methods: Vec::new() };
+        self.impls.push(ImplDef { hir, type_params: self.type_params.clone() });
+        self.self_ty = Some(self_ty.clone());
+        self.cur_impl = Some(index);
+
+        let subst = HashMap::from([("Self".to_string(), self_ty.clone())]);
+        for f in &imp.methods {
+            let Some((_, sig)) = tr.methods.iter().find(|(n, _)| *n == f.name) else {
+                return Err(ResolveError {
+                    span: Span::default(),
+                    msg: format!("method `{}` is not a member of trait `{}`", f.name, tr.name),
+                });
+            };
+            if self.impls[index].hir.methods.iter().any(|m| m.name == f.name) {
+                return Err(ResolveError {
+                    span: Span::default(),
+                    msg: format!("method `{}` is defined twice in the impl of `{}` for `{self_ty:?}`", f.name, tr.name),
+                });
+            }
+            check_method(&f.name, &f.type_params, &f.params)?;
+            if f.self_mode != sig.self_mode {
+                let mode = |m: Option<ast::SelfMode>| m.map_or("self", ast::SelfMode::as_str);
+                return Err(ResolveError {
+                    span: Span::default(),
+                    msg: format!(
+                        "method `{}` of the impl of `{}` for `{self_ty:?}` takes `{}`, but the trait declares `{}`",
+                        f.name,
+                        tr.name,
+                        mode(f.self_mode),
+                        mode(sig.self_mode)
+                    ),
+                });
+            }
+            let id = self.fresh();
+            let fn_name = format!("<{self_ty:?} as {}>::{}", tr.name, f.name);
+            self.generic_fn = (!self.type_params.is_empty()).then_some(id);
+            let h = self.lower_fn_body(id, f, fn_name.clone())?;
+
+            let expected = Type::Fn {
+                params: sig.params.iter().map(|t| t.subst(&subst)).collect(),
+                ret: Box::new(sig.ret.subst(&subst)),
+            };
+            let actual = Type::Fn { params: h.params.iter().map(|p| p.ty.clone()).collect(), ret: Box::new(h.return_ty.clone()) };
+            if actual != expected {
+                return Err(ResolveError {
+                    span: Span::default(),
+                    msg: format!(
+                        "method `{}` of the impl of `{}` for `{self_ty:?}` has type {actual:?}, but the trait declares {expected:?}",
+                        f.name, tr.name
+                    ),
+                });
+            }
+            let imp = &mut self.impls[index];
+            imp.hir.methods.push(hir::ImplMethod { name: f.name.clone(), fn_id: id, fn_name });
+            // telo je možda stavilo tip-parametre u `Vec` ili kanal
+            imp.type_params = self.type_params.clone();
+            items.push(hir::Item::Fn(h));
+        }
+        if let Some((missing, _)) = tr.methods.iter().find(|(n, _)| !self.impls[index].hir.methods.iter().any(|m| m.name == *n)) {
+            return Err(ResolveError {
+                span: Span::default(),
+                msg: format!("missing method `{missing}` in the impl of `{}` for `{self_ty:?}`", tr.name),
+            });
+        }
+        Ok(())
+    }
+
+    /// `impl<T> Type { .. }` – methods and associated functions of `Type`,
+    /// called as `x.method()` and `Type::function()`. Each becomes a function
+    /// named `Type::method`, generic over the impl's type parameters. Two
+    /// impls that may apply to the same type cannot define the same name.
+    fn lower_inherent_impl(&mut self, imp: &ast::Impl, items: &mut Vec<hir::Item>) -> Result<(), ResolveError> {
+        self.enter_type_params(&imp.type_params)?;
+        self.enter_lifetimes(&imp.lifetimes)?;
+        let self_ty = self.resolve_type(&imp.self_ty, Span::default())?;
+        if let Type::Param(p) = &self_ty {
+            return Err(ResolveError {
+                span: Span::default(),
+                msg: format!("cannot define methods for type parameter `{p}`; use a trait"),
+            });
+        }
+        if let Some(p) = self.type_params.iter().find(|p| !self_ty.mentions(&p.name)) {
+            return Err(ResolveError {
+                span: Span::default(),
+                msg: format!("type parameter `{}` of the impl for `{self_ty:?}` is not used by it", p.name),
+            });
+        }
+        let index = self.inherent.len();
+        self.inherent.push(InherentImpl { self_ty: self_ty.clone(), methods: Vec::new() });
+        self.self_ty = Some(self_ty.clone());
+
+        for f in &imp.methods {
+            if !f.type_params.is_empty() {
+                return Err(ResolveError {
+                    span: Span::default(),
+                    msg: format!("method `{}` cannot have type parameters of its own", f.name),
+                });
+            }
+            let other = self.inherent.iter().find(|i| i.self_ty.overlaps(&self_ty) && i.method(&f.name).is_some());
+            if other.is_some() {
+                return Err(ResolveError {
+                    span: Span::default(),
+                    msg: format!("duplicate definitions of method `{}` for `{self_ty:?}`", f.name),
+                });
+            }
+            // vidljiva je već u svom telu, radi rekurzije
+            let id = self.fresh();
+            self.inherent[index].methods.push((f.name.clone(), id));
+            self.generic_fn = (!self.type_params.is_empty()).then_some(id);
+            let h = self.lower_fn_body(id, f, format!("{self_ty:?}::{}", f.name))?;
+            items.push(hir::Item::Fn(h));
+        }
+        Ok(())
+    }
+
+    /// Callee of `recv.method(..)`: a method of an inherent impl for the
+    /// receiver's type, else the only trait method of that name the type
+    /// implements.
+    fn method_callee(&mut self, recv: &hir::Expr, method: &str) -> Result<hir::Expr, ResolveError> {
+        let ty = recv.ty().clone();
+        if let Type::Ref { inner, .. } = &ty
+            && let Type::Dyn(_) = **inner
+        {
+            return self.dyn_method(&ty, method);
+        }
+        let inherent = self.inherent.iter().filter(|i| i.self_ty.matches(&ty, &mut HashMap::new()));
+        if let Some(fn_id) = inherent.filter_map(|i| i.method(method)).next() {
+            return Ok(self.method_fn(fn_id));
+        }
+        let mut traits: Vec<hir::NodeId> = self
+            .traits
+            .iter()
+            .filter(|(_, t)| t.methods.iter().any(|(n, _)| n == method))
+            .map(|(&id, _)| id)
+            .filter(|id| match &ty {
+                Type::Param(p) => self.type_params.iter().any(|tp| tp.name == *p && tp.bounds.contains(id)),
+                _ => self.impls.iter().any(|i| i.hir.trait_id == *id && i.hir.matches(&ty).is_some()),
+            })
+            .collect();
+        traits.sort();
+        match traits.as_slice() {
+            &[trait_id] => {
+                let id = self.fresh();
+                self.method_ref(id, trait_id, method)
+            }
+            [] => Err(ResolveError {
+                span: Span::default(),
+                msg: format!("no method named `{method}` for type `{ty:?}`"),
+            }),
+            _ => {
+                let names: Vec<String> = traits.iter().map(|id| format!("`{}`", self.traits[id].name)).collect();
+                Err(ResolveError {
+                    span: Span::default(),
+                    msg: format!(
+                        "multiple traits provide method `{method}` for `{ty:?}`: {}; call it as `Trait::{method}(..)`",
+                        names.join(", ")
+                    ),
+                })
+            }
+        }
+    }
+
+    /// `Type::function` of an inherent impl; `None` unless `ty_name` names a
+    /// type, or is `Self` inside an impl.
+    fn assoc_fn(&mut self, ty_name: &str, method: &str) -> Option<Result<hir::Expr, ResolveError>> {
+        let self_ty = self.self_ty.clone().filter(|_| ty_name == "Self");
+        let head = type_head_name(ty_name);
+        if self_ty.is_none() && head.is_none() {
+            return None;
+        }
+        let found: Vec<(hir::NodeId, &Type)> = self
+            .inherent
+            .iter()
+            .filter(|i| match &self_ty {
+                Some(t) => i.self_ty.matches(t, &mut HashMap::new()),
+                None => type_head(&i.self_ty) == head,
+            })
+            .filter_map(|i| Some((i.method(method)?, &i.self_ty)))
+            .collect();
+        Some(match found.as_slice() {
+            [(fn_id, _)] => Ok(self.method_fn(*fn_id)),
+            [] => Err(ResolveError {
+                span: Span::default(),
+                msg: format!("no function or method named `{method}` for type `{ty_name}`"),
+            }),
+            _ => {
+                let tys: Vec<String> = found.iter().map(|(_, t)| format!("`{t:?}`")).collect();
+                Err(ResolveError {
+                    span: Span::default(),
+                    msg: format!(
+                        "`{ty_name}::{method}` is ambiguous: it is defined for {}; call it as a method",
+                        tys.join(" and ")
+                    ),
+                })
+            }
+        })
+    }
+
+    /// Callee naming the function of an inherent method.
+    fn method_fn(&self, fn_id: hir::NodeId) -> hir::Expr {
+        let sig = &self.fn_sigs[&fn_id];
+        let ty = Type::Fn { params: sig.params.clone(), ret: Box::new(sig.ret.clone()) };
+        hir::Expr::Ident { id: fn_id, name: sig.name.clone(), ty }
+    }
+
+    /// A `&self` or `&mut self` method gets the address of its receiver,
+    /// the first of `args`; for `&mut self` it has to be a `mut` variable
+    /// or behind a `&mut` reference. A receiver reached through a reference
+    /// is passed as that reference.
+    fn auto_ref(&mut self, mode: Option<ast::SelfMode>, name: &str, args: &mut [hir::Expr]) -> Result<(), ResolveError> {
+        let mutable = match mode {
+            Some(ast::SelfMode::Ref) => false,
+            Some(ast::SelfMode::RefMut) => true,
+            _ => return Ok(()),
+        };
+        let Some(recv) = args.first_mut() else { return Ok(()) };
+        if mutable && !self.check_mut_borrow(recv, &format!(" for `{name}`"))? {
+            return Err(ResolveError {
+                span: Span::default(),
+                msg: format!("`{name}` takes `&mut self`, so its receiver must be a variable"),
+            });
+        }
+        let expr = std::mem::replace(recv, hir::Expr::Unit { id: 0, ty: Type::Unit });
+        *recv = match expr {
+            hir::Expr::Deref { expr, .. } => *expr,
+            expr => {
+                let ty = Type::Ref { mutability: mutable, inner: Box::new(expr.ty().clone()), lifetime: None };
+                hir::Expr::Ref { id: self.fresh(), mutable, expr: Box::new(expr), ty }
+            }
+        };
+        Ok(())
+    }
+
+    /// Check that `ty` implements the trait: a type parameter through its
+    /// bounds, any other type through an impl whose own bounds hold for the
+    /// types its parameters stand for. Returns the impl and those types.
+    fn check_impl(
+        &mut self,
+        trait_id: hir::NodeId,
+        ty: &Type,
+        depth: u32,
+    ) -> Result<Option<ImplMatch>, ResolveError> {
+        let trait_name = self.traits[&trait_id].name.clone();
+        if let Type::Param(p) = ty {
+            if self.type_params.iter().any(|tp| tp.name == *p && tp.bounds.contains(&trait_id)) {
+                return Ok(None);
+            }
+            return Err(ResolveError {
+                span: Span::default(),
+                msg: format!("the trait `{trait_name}` is not implemented for `{p}`; add the bound `{p}: {trait_name}`"),
+            });
+        }
+        if depth > MAX_IMPL_DEPTH {
+            return Err(ResolveError {
+                span: Span::default(),
+                msg: format!("overflow while checking whether `{ty:?}` implements `{trait_name}`"),
+            });
+        }
+        let found = self
+            .impls
+            .iter()
+            .enumerate()
+            .filter(|(_, i)| i.hir.trait_id == trait_id)
+            .find_map(|(index, i)| Some((index, i.hir.matches(ty)?)));
+        let Some((index, map)) = found else {
+            return Err(ResolveError {
+                span: Span::default(),
+                msg: format!("the trait `{trait_name}` is not implemented for `{ty:?}`"),
+            });
+        };
+        let params = self.impls[index].type_params.clone();
+        let impl_name = format!("<{:?} as {trait_name}>", self.impls[index].hir.self_ty);
+        for p in &params {
+            let arg = map[&p.name].clone();
+            self.check_instance(&impl_name, p, &arg)?;
+            for &b in &p.bounds {
+                self.check_impl(b, &arg, depth + 1)?;
+            }
+        }
+        Ok(Some((index, map)))
+    }
+
+    /// `Trait::method` before the call fixes what `Self` stands for.
+    fn method_ref(&self, id: hir::NodeId, trait_id: hir::NodeId, method: &str) -> Result<hir::Expr, ResolveError> {
+        let tr = &self.traits[&trait_id];
+        let Some((_, sig)) = tr.methods.iter().find(|(n, _)| n == method) else {
+            return Err(ResolveError {
+                span: Span::default(),
+                msg: format!("trait `{}` has no method `{method}`", tr.name),
+            });
+        };
+        Ok(hir::Expr::MethodRef {
+            id,
+            trait_id,
+            trait_name: tr.name.clone(),
+            method: method.to_owned(),
+            self_ty: Type::Param("Self".to_string()),
+            ty: Type::Fn { params: sig.params.clone(), ret: Box::new(sig.ret.clone()) },
+        })
+    }
+
+    /// Call of `Trait::method`: `Self` is inferred from the arguments like a
+    /// type parameter, and the type it stands for must implement the trait.
+    fn call_method(
+        &mut self,
+        id: hir::NodeId,
+        trait_id: hir::NodeId,
+        method: &str,
+        args: &mut [hir::Expr],
+    ) -> Result<hir::Expr, ResolveError> {
+        let tr = self.traits[&trait_id].clone();
+        let sig = &tr.methods.iter().find(|(n, _)| n == method).expect("checked by `method_ref`").1;
+        let hir::Expr::FnRef { type_args, ty, .. } = self.instantiate(trait_id, sig, args)? else {
+            unreachable!("instance of a fn")
+        };
+        let self_ty = type_args.into_iter().next().expect("`Self` is the only type parameter");
+        let found = self.check_impl(trait_id, &self_ty, 0)?;
+        // metoda generičke implementacije ne sme tražiti veću instancu sebe
+        if let Some((index, map)) = found
+            && self.cur_impl == Some(index)
+            && self.type_params.iter().any(|p| map[&p.name] != Type::Param(p.name.clone()))
+        {
+            return Err(ResolveError {
+                span: Span::default(),
+                msg: format!(
+                    "recursive call of `{}` for `{self_ty:?}` must use the impl's own type parameters",
+                    sig.name
+                ),
+            });
+        }
+        Ok(hir::Expr::MethodRef { id, trait_id, trait_name: tr.name, method: method.to_owned(), self_ty, ty })
+    }
+
+    /*──────── operators ─────────*/
+    /// `l op r` for operands `op` has no built-in meaning for: a call of the
+    /// method of its language trait, e.g. `Add::add(l, r)`. `==` and the
+    /// comparisons take references and look at the result, so `l < r` is
+    /// `Ord::cmp(&l, &r) < 0`.
+    fn overloaded_op(&mut self, id: hir::NodeId, op: ast::BinOp, l: hir::Expr, r: hir::Expr) -> Result<hir::Expr, ResolveError> {
+        use ast::BinOp::*;
+        let (trait_name, method) = op_trait(op).expect("an overloadable operator");
+        if !matches!(op, EqEq | NotEq | Lt | Le | Gt | Ge) {
+            return self.lang_call(id, trait_name, method, vec![l, r]);
+        }
+        let ty = Type::Ref { mutability: false, inner: Box::new(r.ty().clone()), lifetime: None };
+        let r = hir::Expr::Ref { id: self.fresh(), mutable: false, expr: Box::new(r), ty };
+        let call_id = if op == EqEq { id } else { self.fresh() };
+        let call = Box::new(self.lang_call(call_id, trait_name, method, vec![l, r])?);
+        Ok(match op {
+            EqEq => *call,
+            NotEq => hir::Expr::Unary { id, op: hir::UnOp::Not, rhs: call, ty: Type::Bool },
+            _ => {
+                let zero = Box::new(hir::Expr::Int { id: self.fresh(), value: 0, ty: Type::Int });
+                hir::Expr::Binary { id, lhs: call, op: hir::BinOp::from_ast(op), rhs: zero, ty: Type::Bool }
+            }
+        })
+    }
+
+    /// `-e` for a value that is not a number: `Neg::neg(e)`.
+    fn overloaded_neg(&mut self, id: hir::NodeId, e: hir::Expr) -> Result<hir::Expr, ResolveError> {
+        let msg = format!("cannot negate type `{:?}`", e.ty());
+        self.lang_call(id, "Neg", "neg", vec![e])
+            .map_err(|err| ResolveError { span: err.span, msg: format!("{msg}: {}", err.msg) })
+    }
+
+    /// Call `id` of `method` of the language trait `trait_name`, even where
+    /// a trait of the module shadows its name.
+    fn lang_call(&mut self, id: hir::NodeId, trait_name: &str, method: &str, args: Vec<hir::Expr>) -> Result<hir::Expr, ResolveError> {
+        let (trait_id, ref_id) = (self.prelude[trait_name].id, self.fresh());
+        let callee = self.method_ref(ref_id, trait_id, method)?;
+        self.lower_call(id, callee, args)
+    }
+
+    /*──────── trait objects ─────*/
+    /// `dyn Trait`. Every method is called through the vtable with the
+    /// address of the value, so it has to take `&self` or `&mut self`, and
+    /// the concrete type is forgotten, so `Self` may not appear elsewhere.
+    fn dyn_type(&mut self, path: &[String]) -> Result<Type, ResolveError> {
+        let trait_id = self.lookup_trait(path)?;
+        let tr = &self.traits[&trait_id];
+        for (name, sig) in &tr.methods {
+            let why = if !matches!(sig.self_mode, Some(ast::SelfMode::Ref | ast::SelfMode::RefMut)) {
+                "takes `self` by value"
+            } else if sig.params[1..].iter().chain([&sig.ret]).any(|t| t.mentions("Self")) {
+                "uses `Self` outside of its receiver"
+            } else {
+                continue;
+            };
+            return Err(ResolveError {
+                span: Span::default(),
+                msg: format!("the trait `{}` cannot be made into an object: method `{name}` {why}", tr.name),
+            });
+        }
+        Ok(Type::Dyn(tr.name.clone()))
+    }
+
+    /// Trait of `dyn name`.
+    fn dyn_trait(&self, name: &str) -> hir::NodeId {
+        let found = self.traits.iter().find(|(_, t)| t.name == name);
+        *found.expect("`dyn` of a known trait").0
+    }
+
+    /// Callee of `r.method(..)` for `r: &dyn Trait` or `&mut dyn Trait`.
+    fn dyn_method(&mut self, recv_ty: &Type, method: &str) -> Result<hir::Expr, ResolveError> {
+        let Type::Ref { mutability, inner, .. } = recv_ty else { unreachable!("receiver is a reference") };
+        let Type::Dyn(name) = &**inner else { unreachable!("receiver is a trait object") };
+        let trait_id = self.dyn_trait(name);
+        let tr = &self.traits[&trait_id];
+        let Some(index) = tr.methods.iter().position(|(n, _)| n == method) else {
+            return Err(ResolveError {
+                span: Span::default(),
+                msg: format!("no method named `{method}` for type `{recv_ty:?}`"),
+            });
+        };
+        let sig = &tr.methods[index].1;
+        if sig.self_mode == Some(ast::SelfMode::RefMut) && !mutability {
+            return Err(ResolveError {
+                span: Span::default(),
+                msg: format!("`{}` takes `&mut self`, so it cannot be called through `{recv_ty:?}`", sig.name),
+            });
+        }
+        let mut params = vec![recv_ty.clone()];
+        params.extend(sig.params[1..].iter().cloned());
+        let ty = Type::Fn { params, ret: Box::new(sig.ret.clone()) };
+        let (id, trait_name) = (self.fresh(), name.clone());
+        Ok(hir::Expr::DynMethod { id, trait_id, trait_name, method: method.to_owned(), index, ty })
+    }
+
+    /// `e` where a value of type `expected` is needed: `&T` becomes a
+    /// `&dyn Trait` if `T` implements the trait, and `None`, `Ok(x)` or
+    /// `Err(e)` get the rest of their type from `expected`. Anything else is
+    /// returned as is, for the caller to check.
+    fn coerce(&mut self, expected: &Type, e: hir::Expr) -> Result<hir::Expr, ResolveError> {
+        let mut e = self.adapt_literal(expected, e)?;
+        fill_holes(&mut e, expected);
+        let (Type::Ref { mutability, inner: target, .. }, Type::Ref { mutability: from_mut, inner, .. }) = (expected, e.ty())
+        else {
+            return Ok(e);
+        };
+        let Type::Dyn(name) = &**target else { return Ok(e) };
+        if matches!(**inner, Type::Dyn(_)) || (*mutability && !from_mut) {
+            return Ok(e);
+        }
+        let (name, inner) = (name.clone(), (**inner).clone());
+        let trait_id = self.dyn_trait(&name);
+        self.check_impl(trait_id, &inner, 0)?;
+        let methods = self.traits[&trait_id].methods.iter().map(|(n, _)| n.clone()).collect();
+        let id = self.fresh();
+        Ok(hir::Expr::ToDyn { id, expr: Box::new(e), trait_id, trait_name: name, methods, ty: expected.clone() })
+    }
+
+    /// A literal without a suffix where a value of a sized number type is
+    /// expected takes that type: `let b: u8 = 200;`. Elements of array and
+    /// tuple literals do too. The literal must fit in the type.
+    fn adapt_literal(&mut self, expected: &Type, e: hir::Expr) -> Result<hir::Expr, ResolveError> {
+        Ok(match (expected, e) {
+            (Type::Num(n), hir::Expr::Int { id, value, .. }) if !self.typed_literals.contains(&id) => {
+                self.wide_literals.remove(&id);
+                int_literal(id, value, *n, false)?
+            }
+            (Type::Num(n), hir::Expr::Float { id, value, .. })
+                if !self.typed_literals.contains(&id) && n.is_float() =>
+            {
+                hir::Expr::Float { id, value: float_value(value, *n), ty: expected.clone() }
+            }
+            (Type::Num(n), hir::Expr::Unary { id, op: hir::UnOp::Negate, rhs, ty })
+                if matches!(&*rhs, hir::Expr::Int { id, .. } | hir::Expr::Float { id, .. }
+                    if !self.typed_literals.contains(id)) =>
+            {
+                if n.is_unsigned() {
+                    return Err(ResolveError { span: Span::default(), msg: format!("cannot negate unsigned type `{}`", n.name()) });
+                }
+                let rhs = match *rhs {
+                    hir::Expr::Int { id, value, .. } => {
+                        self.wide_literals.remove(&id);
+                        int_literal(id, value, *n, true)?
+                    }
+                    rhs => self.adapt_literal(expected, rhs)?,
+                };
+                let ty = if rhs.ty() == expected { expected.clone() } else { ty };
+                hir::Expr::Unary { id, op: hir::UnOp::Negate, rhs: Box::new(rhs), ty }
+            }
+            // `-(1 << 7)` i slični izrazi od samih literala
+            (Type::Num(_), hir::Expr::Unary { id, op: op @ (hir::UnOp::Negate | hir::UnOp::BitNot), rhs, ty }) => {
+                let rhs = self.adapt_literal(expected, *rhs)?;
+                match (op, expected.num()) {
+                    (hir::UnOp::Negate, Some(n)) if n.is_unsigned() && rhs.ty() == expected => {
+                        return Err(ResolveError {
+                            span: Span::default(),
+                            msg: format!("cannot negate unsigned type `{}`", n.name()),
+                        });
+                    }
+                    _ => {}
+                }
+                let ty = if rhs.ty() == expected { expected.clone() } else { ty };
+                hir::Expr::Unary { id, op, rhs: Box::new(rhs), ty }
+            }
+            (Type::Num(_), hir::Expr::Binary { id, lhs, op, rhs, ty })
+                if !op.is_comparison() && !matches!(op, hir::BinOp::AndAnd | hir::BinOp::OrOr) =>
+            {
+                let lhs = self.adapt_literal(expected, *lhs)?;
+                let rhs = self.adapt_literal(expected, *rhs)?;
+                let ty = if lhs.ty() == expected && rhs.ty() == expected { expected.clone() } else { ty };
+                hir::Expr::Binary { id, lhs: Box::new(lhs), op, rhs: Box::new(rhs), ty }
+            }
+            (Type::Array(elem, _), hir::Expr::Array { id, elems, ty }) => {
+                let elems: Vec<hir::Expr> = elems.into_iter().map(|e| self.adapt_literal(elem, e)).collect::<Result<_, _>>()?;
+                let ty = if elems.iter().all(|e| e.ty() == &**elem) { Type::Array(elem.clone(), elems.len()) } else { ty };
+                hir::Expr::Array { id, elems, ty }
+            }
+            (Type::Array(elem_ty, _), hir::Expr::Repeat { id, elem, len, ty }) => {
+                let elem = self.adapt_literal(elem_ty, *elem)?;
+                let ty = if elem.ty() == &**elem_ty { Type::Array(elem_ty.clone(), len) } else { ty };
+                hir::Expr::Repeat { id, elem: Box::new(elem), len, ty }
+            }
+            (Type::Tuple(tys), hir::Expr::Tuple { id, elems, ty }) if tys.len() == elems.len() => {
+                let elems: Vec<hir::Expr> =
+                    elems.into_iter().zip(tys).map(|(e, t)| self.adapt_literal(t, e)).collect::<Result<_, _>>()?;
+                let ty = match ty {
+                    Type::Tuple(_) => Type::Tuple(elems.iter().map(|e| e.ty().clone()).collect()),
+                    ty => ty,
+                };
+                hir::Expr::Tuple { id, elems, ty }
+            }
+            (_, e) => e,
+        })
+    }
+
+    /// `coerce` each argument to its parameter's type.
+    fn coerce_args(&mut self, params: &[Type], args: &mut [hir::Expr]) -> Result<(), ResolveError> {
+        for (p, a) in params.iter().zip(args) {
+            let e = std::mem::replace(a, hir::Expr::Unit { id: 0, ty: Type::Unit });
+            *a = self.coerce(p, e)?;
+        }
+        Ok(())
+    }
+
+    /// `*e`: the value a reference points to. A trait object has no type to
+    /// read it as.
+    fn deref(&mut self, e: hir::Expr) -> Result<hir::Expr, ResolveError> {
+        let ty = match e.ty() {
+            Type::Ref { inner, .. } if matches!(**inner, Type::Dyn(_)) => {
+                return Err(ResolveError {
+                    span: Span::default(),
+                    msg: format!("cannot dereference `{:?}`; call its methods instead", e.ty()),
+                });
+            }
+            Type::Ref { inner, .. } => (**inner).clone(),
+            ty => {
+                return Err(ResolveError {
+                    span: Span::default(),
+                    msg: format!("cannot dereference a value of type {ty:?}"),
+                });
+            }
+        };
+        Ok(hir::Expr::Deref { id: self.fresh(), expr: Box::new(e), ty })
+    }
+
+    /// `e` itself, or the value it points to if it is a reference; a method
+    /// call, field access or index sees through references. A trait object
+    /// stays as it is.
+    fn auto_deref(&mut self, e: hir::Expr) -> Result<hir::Expr, ResolveError> {
+        match e.ty() {
+            Type::Ref { inner, .. } if !matches!(**inner, Type::Dyn(_)) => self.deref(e),
+            _ => Ok(e),
+        }
+    }
+
+    /// Check that `e` may be borrowed mutably: a `mut` variable, or a value
+    /// behind a `&mut` reference. `Ok(false)` for any other expression,
+    /// which the caller reports; `what` ends the messages, e.g. " for `f`".
+    fn check_mut_borrow(&mut self, e: &hir::Expr, what: &str) -> Result<bool, ResolveError> {
+        match e {
+            hir::Expr::Ident { id, name: var, .. } => {
+                if !self.lookup(var).is_some_and(|s| s.id == *id && s.is_mut) || self.fn_sigs.contains_key(id) {
+                    return Err(ResolveError {
+                        span: Span::default(),
+                        msg: format!("cannot borrow `{var}` as mutable{what}, as it is not declared `mut`"),
+                    });
+                }
+                self.check_assign_capture(&var.clone())?;
+                Ok(true)
+            }
+            hir::Expr::Deref { expr, .. } => match expr.ty() {
+                Type::Ref { mutability: true, .. } => Ok(true),
+                _ => Err(ResolveError {
+                    span: Span::default(),
+                    msg: format!("cannot borrow data behind a `&` reference as mutable{what}"),
+                }),
+            },
+            _ => Ok(false),
+        }
+    }
+
+    /// Check that `target` is a place that may be written: its path of
+    /// fields and elements ends at a `mut` variable or behind a `&mut`
+    /// reference.
+    fn check_assign_place(&mut self, target: &hir::Expr) -> Result<(), ResolveError> {
+        let err = |msg: String| Err(ResolveError { span: Span::default(), msg });
+        let invalid = "invalid left-hand side of assignment: expected a variable, a field, an element or `*reference`";
+        match target.place_chain() {
+            // `m::G` je globalna promenljiva drugog modula
+            (hir::Expr::Ident { id, name, .. }, nodes) if !nodes.is_empty() || name.contains("::") => {
+                let sym = if name.contains("::") {
+                    self.lookup_path(&name.split("::").map(str::to_string).collect::<Vec<_>>()).ok()
+                } else {
+                    self.check_assign_capture(name)?;
+                    self.lookup(name).cloned()
+                };
+                let what = match target {
+                    hir::Expr::Field { .. } => "a field of ",
+                    hir::Expr::Index { .. } => "an element of ",
+                    _ => "",
+                };
+                match sym.filter(|s| s.id == *id && !self.fn_sigs.contains_key(id)) {
+                    Some(s) if s.is_mut => Ok(()),
+                    Some(_) if what.is_empty() => err(format!("cannot reassign immutable binding `{name}`")),
+                    Some(_) => err(format!("cannot assign to {what}immutable binding `{name}`")),
+                    None => err(invalid.to_string()),
+                }
+            }
+            (hir::Expr::Deref { expr, .. }, _) => match expr.ty() {
+                Type::Ref { mutability: true, .. } => Ok(()),
+                _ => err("cannot assign to data behind a `&` reference".to_string()),
+            },
+            _ => err(invalid.to_string()),
+        }
+    }
+
+    /// `target op= expr` is `target = target op expr`. The indices of the
+    /// place and the reference a `*` goes through are evaluated once: those
+    /// that aren't names or literals are bound to temporaries first, in a
+    /// block around the assignment.
+    fn lower_assign_op(&mut self, target: &ast::Expr, op: ast::BinOp, expr: &ast::Expr) -> Result<hir::Stmt, ResolveError> {
+        let mut stmts = Vec::new();
+        let target = hoist_place(target, &mut stmts);
+        let value = ast::Expr::Binary { op, lhs: Box::new(target.clone()), rhs: Box::new(expr.clone()) };
+        let assign = match target {
+            ast::Expr::Ident(name) => ast::Stmt::Assign { name, expr: value },
+            target => ast::Stmt::AssignPlace { target, expr: value },
+        };
+        if stmts.is_empty() {
+            return self.lower_stmt(&assign);
+        }
+        stmts.push(assign);
+        let block = self.lower_block(&ast::Block { stmts, tail: None, spans: Vec::new() })?;
+        Ok(hir::Stmt::Semi(hir::Expr::from_block(block)))
+    }
+
+    /*──────── lower block ───────*/
+    /// Lower a block in a new lexical scope; bindings introduced inside are
+    /// no longer visible once the block ends. The tail becomes a trailing
+    /// `hir::Stmt::Expr`, every other expression statement a `Semi`.
+    fn lower_block(&mut self, b: &ast::Block) -> Result<hir::Block, ResolveError> {
+        let id = self.fresh();
+        self.push_scope();
+        let stmts = self.lower_block_stmts(b);
+        self.pop_scope();
+        let (stmts, spans) = stmts?;
+        Ok(hir::Block { id, stmts, spans })
+    }
+
+    /// The statements of `b` with the span of the statement each comes from.
+    fn lower_block_stmts(&mut self, b: &ast::Block) -> Result<(Vec<hir::Stmt>, Vec<Span>), ResolveError> {
+        let mut stmts = Vec::new();
+        let mut spans = Vec::new();
+        let mut at = b.spans.iter().copied();
+        for s in &b.stmts {
+            match s {
+                ast::Stmt::LetTuple { pats, expr } => stmts.extend(self.lower_let_tuple(pats, expr)?),
+                _ => stmts.push(self.lower_stmt(s)?),
+            }
+            let span = at.next().unwrap_or_default();
+            spans.resize(stmts.len(), span);
+        }
+        if let Some(tail) = &b.tail {
+            stmts.push(hir::Stmt::Expr(self.lower_expr(tail)?));
+            spans.push(at.next().unwrap_or_default());
+        }
+        Ok((stmts, spans))
+    }
+
+    /*──────── lower global let ─*/
+    fn lower_global_let(&mut self, g: &ast::GlobalLet) -> Result<hir::HirLet, ResolveError> {
+        let id = self.fresh();
+        let init = self.lower_expr(&g.expr)?;
+        let ty = init.ty().clone();
+        check_inferred(&g.name, &ty)?;
+
+        // a constant initializer is stored directly in the global; the rest
+        // run at startup, so only errors that would fail there are reported
+        match self.consts.eval(&init) {
+            Ok(v) => {
+                if !g.mutable {
+                    self.consts.define(id, v.clone());
+                }
+                self.global_values.insert(id, v);
+            }
+            Err(
+                e @ (ConstError::Overflow
+                | ConstError::DivByZero
+                | ConstError::IndexOutOfBounds { .. }),
+            ) => {
+                return Err(ResolveError {
+                    span: Span::default(),
+                    msg: format!("initializer of `{}` always fails: {}", g.name, e.msg()),
+                });
+            }
+            Err(_) => {}
+        }
+
+        self.insert(
+            &g.name,
+            Symbol {
+                id,
+                ty: ty.clone(),
+                is_mut: g.mutable,
+                value: None,
+                is_pub: g.is_pub,
+            },
+            Span::default(),
+        )?;
+        Ok(hir::HirLet {
+            id,
+            mutable: g.mutable,
+            name: self.qualify(&g.name),
+            ty,
+            init,
+        })
+    }
+
+    /*──────── lower const ───────*/
+    fn lower_const(&mut self, c: &ast::Const) -> Result<hir::HirConst, ResolveError> {
+        let id = self.fresh();
+        let ty = self.resolve_type(&c.ty, Span::default())?;
+        let init = self.lower_expr(&c.expr)?;
+        let init = self.adapt_literal(&ty, init)?;
+        if !self.compatible(&ty, init.ty()) {
+            return Err(ResolveError {
+                span: Span::default(),
+                msg: format!("expected {:?}, got {:?}", ty, init.ty()),
+            });
+        }
+        let value = match self.consts.eval(&init) {
+            Ok(ConstValue::Int(i)) if ty == Type::Float => ConstValue::Float(i as f64),
+            Ok(v) => v,
+            Err(e) => {
+                return Err(ResolveError {
+                    span: Span::default(),
+                    msg: format!("evaluation of constant `{}` failed: {}", c.name, e.msg()),
+                });
+            }
+        };
+        self.insert(
+            &c.name,
+            Symbol {
+                id,
+                ty: ty.clone(),
+                is_mut: false,
+                value: Some(value.clone()),
+                is_pub: c.is_pub,
+            },
+            Span::default(),
+        )?;
+        Ok(hir::HirConst {
+            id,
+            name: self.qualify(&c.name),
+            ty,
+            value,
+        })
+    }
+
+    /*──────── lower stmt ────────*/
+    fn lower_stmt(&mut self, s: &ast::Stmt) -> Result<hir::Stmt, ResolveError> {
         use ast::Stmt::*;
         match s {
             Let {
                 name,
+                ty,
                 expr,
                 mutable,
             } => {
                 let id = self.fresh();
-                let rhs = self.lower_expr(expr)?;
-                let ty = rhs.ty().clone();
-                self.insert(
+                // initializer is resolved before the new binding is visible,
+                // so `let x = x + 1;` refers to the previous `x`
+                let mut rhs = self.lower_expr(expr)?;
+                let ty = match ty {
+                    Some(ty) => {
+                        let ty = self.resolve_type(ty, Span::default())?;
+                        rhs = self.coerce(&ty, rhs)?;
+                        if !self.compatible(&ty, rhs.ty()) {
+                            return Err(ResolveError {
+                                span: Span::default(),
+                                msg: format!("expected {:?}, got {:?}", ty, rhs.ty()),
+                            });
+                        }
+                        ty
+                    }
+                    None => rhs.ty().clone(),
+                };
+                check_inferred(name, &ty)?;
+                self.shadow(
                     name,
                     Symbol {
                         id,
                         ty: ty.clone(),
                         is_mut: *mutable,
+                        value: None,
+                        is_pub: false,
                     },
-                    Span::default(),
-                )?;
+                );
                 Ok(hir::Stmt::Let(hir::HirLet {
                     id,
                     mutable: *mutable,
@@ -239,10 +1818,22 @@ impl Cx {
                     init: rhs,
                 }))
             }
+            Decl { name, ty, mutable } => {
+                let id = self.fresh();
+                let ty = self.resolve_type(ty, Span::default())?;
+                if !*mutable {
+                    self.deferred.insert(id);
+                }
+                self.shadow(name, Symbol { id, ty: ty.clone(), is_mut: *mutable, value: None, is_pub: false });
+                Ok(hir::Stmt::Decl { id, mutable: *mutable, name: name.clone(), ty })
+            }
             Assign { name, expr } => {
                 let rhs = self.lower_expr(expr)?;
+                self.check_assign_capture(name)?;
+                self.check_deferred_capture(name)?;
+                self.capture(name);
                 let info_ty = if let Some(sym) = self.lookup(name) {
-                    if !sym.is_mut {
+                    if !sym.is_mut && !self.deferred.contains(&sym.id) {
                         return Err(ResolveError {
                             span: Span::default(),
                             msg: format!("cannot reassign immutable binding `{name}`"),
@@ -256,6 +1847,7 @@ impl Cx {
                     });
                 };
 
+                let rhs = self.coerce(&info_ty, rhs)?;
                 let new_ty = match Type::unify(&info_ty, rhs.ty()) {
                     Ok(t) => t,
                     Err(_) => {
@@ -270,7 +1862,21 @@ impl Cx {
                 sym.ty = new_ty.clone();
                 Ok(hir::Stmt::Assign { id: sym.id, name: name.clone(), expr: rhs })
             }
-            Expr(e) => Ok(hir::Stmt::Expr(self.lower_expr(e)?)),
+            AssignPlace { target, expr } => {
+                let rhs = self.lower_expr(expr)?;
+                let target = self.lower_expr(target)?;
+                self.check_assign_place(&target)?;
+                let rhs = self.coerce(&target.ty().clone(), rhs)?;
+                if !self.compatible(target.ty(), rhs.ty()) {
+                    return Err(ResolveError {
+                        span: Span::default(),
+                        msg: format!("expected {:?}, got {:?}", target.ty(), rhs.ty()),
+                    });
+                }
+                Ok(hir::Stmt::AssignPlace { target, expr: rhs })
+            }
+            AssignOp { target, op, expr } => self.lower_assign_op(target, *op, expr),
+            Expr(e) => Ok(hir::Stmt::Semi(self.lower_expr(e)?)),
             Return(opt) => {
                 let expr = match opt {
                     Some(e) => self.lower_expr(e)?,
@@ -279,53 +1885,841 @@ impl Cx {
                         ty: Type::Unit,
                     },
                 };
-                if let Some(expected) = &self.current_ret_ty {
-                    if !self.compatible(expected, expr.ty()) {
-                        return Err(ResolveError {
-                            span: Span::default(),
-                            msg: format!("expected {:?}, got {:?}", expected, expr.ty()),
-                        });
-                    }
+                if self.closures.last().is_some_and(|c| c.ret.is_none()) {
+                    return Err(ResolveError {
+                        span: Span::default(),
+                        msg: "`return` in a closure needs an explicit return type: `|..| -> T { .. }`"
+                            .to_string(),
+                    });
+                }
+                let expr = match self.current_ret_ty.clone() {
+                    Some(expected) => self.coerce(&expected, expr)?,
+                    None => expr,
+                };
+                if let Some(expected) = &self.current_ret_ty
+                    && !self.compatible(expected, expr.ty())
+                {
+                    return Err(ResolveError {
+                        span: Span::default(),
+                        msg: format!("expected {:?}, got {:?}", expected, expr.ty()),
+                    });
                 }
                 Ok(hir::Stmt::Return(Some(expr)))
             }
+            LetTuple { .. } => unreachable!("lowered by `lower_block_stmts`"),
+            Break | Continue => {
+                if self.loop_depth == 0 {
+                    let kw = if matches!(s, Break) { "break" } else { "continue" };
+                    return Err(ResolveError {
+                        span: Span::default(),
+                        msg: format!("`{kw}` outside of a loop"),
+                    });
+                }
+                Ok(if matches!(s, Break) {
+                    hir::Stmt::Break
+                } else {
+                    hir::Stmt::Continue
+                })
+            }
+        }
+    }
+
+    /// Lower a loop or `if` condition and make sure it is a Bool.
+    fn lower_cond(&mut self, cond: &ast::Expr, what: &str) -> Result<hir::Expr, ResolveError> {
+        let c = self.lower_expr(cond)?;
+        if c.ty() != &Type::Bool {
+            return Err(ResolveError {
+                span: Span::default(),
+                msg: format!("`{what}` condition must be Bool, got {:?}", c.ty()),
+            });
+        }
+        Ok(c)
+    }
+
+    /// Use of a resolved name: constants become their value, functions are
+    /// referred to by their qualified name.
+    fn symbol_expr(&self, id: hir::NodeId, name: &str, sym: &Symbol) -> hir::Expr {
+        if let Some(v) = &sym.value {
+            return const_expr(id, v, sym.ty.clone());
+        }
+        let name = match self.fn_sigs.get(&sym.id) {
+            Some(sig) => sig.name.clone(),
+            None => name.to_owned(),
+        };
+        hir::Expr::Ident {
+            id: sym.id,
+            name,
+            ty: sym.ty.clone(),
+        }
+    }
+
+    /// Check the arguments of a call to `name` against its parameter types.
+    /// Call of the generic function `fn_id`: infer its type arguments from
+    /// the argument types and return the callee as a `FnRef` to the instance.
+    fn instantiate(&mut self, fn_id: hir::NodeId, sig: &FnSig, args: &mut [hir::Expr]) -> Result<hir::Expr, ResolveError> {
+        if sig.params.len() != args.len() {
+            self.check_args(&sig.name, &sig.params, args)?;
+        }
+        let mut icx = InferCtx::new();
+        let mut vars = HashMap::new();
+        for p in &sig.type_params {
+            let TvOrTy::Var(v) = icx.fresh(Span::default()) else { unreachable!("fresh gives a variable") };
+            vars.insert(p.name.clone(), v);
+        }
+        for (p, a) in sig.params.iter().zip(args.iter()) {
+            icx.constrain(Ty::from_type(a.ty(), &HashMap::new()), Ty::from_type(p, &vars));
+        }
+        icx.solve();
+
+        let mut map = HashMap::new();
+        let mut type_args = Vec::new();
+        for p in &sig.type_params {
+            let ty = match icx.apply(TvOrTy::Var(vars[&p.name])) {
+                TvOrTy::Ty(t) => t.to_type(),
+                TvOrTy::Var(_) => None,
+            };
+            let ty = match ty {
+                Some(ty) => {
+                    self.check_instance(&sig.name, p, &ty)?;
+                    for &b in &p.bounds {
+                        self.check_impl(b, &ty, 0).map_err(|e| ResolveError {
+                            msg: format!("{}, required by `{}` of `{}`", e.msg, p.name, sig.name),
+                            ..e
+                        })?;
+                    }
+                    ty
+                }
+                // neslaganje argumenata prijavljuje `check_args`
+                None if !icx.errors.is_empty() => Type::Param(p.name.clone()),
+                None => {
+                    return Err(ResolveError {
+                        span: Span::default(),
+                        msg: format!("cannot infer type parameter `{}` of `{}`", p.name, sig.name),
+                    });
+                }
+            };
+            map.insert(p.name.clone(), ty.clone());
+            type_args.push(ty);
+        }
+        let params: Vec<Type> = sig.params.iter().map(|t| t.subst(&map)).collect();
+        self.coerce_args(&params, args)?;
+        self.check_args(&sig.name, &params, args)?;
+
+        // svaka instanca sme da traži samo sebe, inače ih ima beskonačno
+        let own: Vec<Type> = sig.type_params.iter().map(|p| Type::Param(p.name.clone())).collect();
+        if self.generic_fn == Some(fn_id) && type_args != own {
+            return Err(ResolveError {
+                span: Span::default(),
+                msg: format!("recursive call of `{}` must use its own type parameters, got {type_args:?}", sig.name),
+            });
+        }
+        let ty = Type::Fn { params, ret: Box::new(sig.ret.subst(&map)) };
+        Ok(hir::Expr::FnRef { id: self.fresh(), fn_id, name: sig.name.clone(), type_args, ty })
+    }
+
+    /// A generic function that puts a type parameter in a `Vec` or channel
+    /// needs a type argument that fits one machine word.
+    fn check_instance(&mut self, name: &str, p: &TypeParam, ty: &Type) -> Result<(), ResolveError> {
+        for (needed, in_vec, place) in [(p.in_vec, true, "a Vec"), (p.in_channel, false, "a channel")] {
+            if needed && let Err(what) = self.word_elem(ty, in_vec) {
+                return Err(ResolveError {
+                    span: Span::default(),
+                    msg: format!("`{name}` puts `{}` in {place}, so it cannot be {what}, got {ty:?}", p.name),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn check_args(&self, name: &str, params: &[Type], args: &[hir::Expr]) -> Result<(), ResolveError> {
+        if params.len() != args.len() {
+            return Err(ResolveError {
+                span: Span::default(),
+                msg: format!("`{name}` takes {} argument(s), got {}", params.len(), args.len()),
+            });
+        }
+        for (p, arg) in params.iter().zip(args) {
+            if !self.compatible(p, arg.ty()) {
+                return Err(ResolveError {
+                    span: Span::default(),
+                    msg: format!("expected {:?}, got {:?}", p, arg.ty()),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Argument checks and result type of a builtin call.
+    fn builtin_call(&self, kind: hir::Builtin, args: &[hir::Expr]) -> Result<Type, ResolveError> {
+        let err = |msg: String| Err(ResolveError { span: Span::default(), msg });
+        let tys: Vec<&Type> = args.iter().map(|a| a.ty()).collect();
+        match (kind, tys.as_slice()) {
+            (hir::Builtin::Print, [Type::Int | Type::Char | Type::Str]) => Ok(Type::Unit),
+            // niz bajtova, npr. `b"..."`
+            (hir::Builtin::Print, [Type::Array(elem, _)]) if **elem == Type::Num(NumTy::U8) => Ok(Type::Unit),
+            (hir::Builtin::Print, [Type::Num(n)]) if !n.is_float() => Ok(Type::Unit),
+            (hir::Builtin::Print, _) => err("print unsupported type".to_string()),
+            (hir::Builtin::Send, [Type::Sender(elem), _]) => {
+                self.check_args("send", &[Type::Sender(elem.clone()), (**elem).clone()], args)?;
+                Ok(Type::Unit)
+            }
+            (hir::Builtin::Send, [other, _]) => {
+                err(format!("`send` expects a Sender as its first argument, got {other:?}"))
+            }
+            (hir::Builtin::Recv, [Type::Receiver(elem)]) => Ok((**elem).clone()),
+            (hir::Builtin::Recv, [other]) => {
+                err(format!("`recv` expects a Receiver, got {other:?}"))
+            }
+            (hir::Builtin::Push, [Type::Vec(elem), _]) => {
+                self.check_args("push", &[Type::Vec(elem.clone()), (**elem).clone()], args)?;
+                Ok(Type::Unit)
+            }
+            (hir::Builtin::Pop, [Type::Vec(elem)]) => Ok((**elem).clone()),
+            (hir::Builtin::Len, [Type::Vec(_)]) => Ok(Type::Int),
+            (hir::Builtin::Push | hir::Builtin::Pop | hir::Builtin::Len, [other, ..])
+                if args.len() == kind.arity() =>
+            {
+                err(format!("`{}` expects a Vec, got {other:?}", kind.name()))
+            }
+            (kind, _) => {
+                let n = kind.arity();
+                err(format!("`{}` takes {n} argument(s), got {}", kind.name(), args.len()))
+            }
+        }
+    }
+
+    /// `push` and `pop` change the `Vec` in place, so a named one must be
+    /// declared `mut`.
+    fn check_mutable_vec(&self, kind: hir::Builtin, v: &hir::Expr) -> Result<(), ResolveError> {
+        if let hir::Expr::Ident { id, name, .. } = v
+            && !self.lookup(name).is_some_and(|s| s.id == *id && s.is_mut)
+        {
+            return Err(ResolveError {
+                span: Span::default(),
+                msg: format!("`{}` needs a mutable Vec, but `{name}` is not declared `mut`", kind.name()),
+            });
+        }
+        Ok(())
+    }
+
+    /// `let (a, (b, _)) = expr;` evaluates `expr` once into a hidden
+    /// binding and gives every name its own `let` of a field of it. The
+    /// names become visible after the whole statement.
+    fn lower_let_tuple(&mut self, pats: &[ast::Pattern], expr: &ast::Expr) -> Result<Vec<hir::Stmt>, ResolveError> {
+        let init = self.lower_expr(expr)?;
+        let mut stmts = Vec::new();
+        let mut binds = Vec::new();
+        self.bind_tuple(pats, init, &mut stmts, &mut binds)?;
+        for (name, sym) in binds {
+            self.shadow(&name, sym);
+        }
+        Ok(stmts)
+    }
+
+    fn bind_tuple(
+        &mut self,
+        pats: &[ast::Pattern],
+        init: hir::Expr,
+        stmts: &mut Vec<hir::Stmt>,
+        binds: &mut Vec<(String, Symbol)>,
+    ) -> Result<(), ResolveError> {
+        let ty = init.ty().clone();
+        let Type::Tuple(elems) = &ty else {
+            return Err(ResolveError {
+                span: Span::default(),
+                msg: format!("cannot destructure a value of type {ty:?} as a tuple"),
+            });
+        };
+        if elems.len() != pats.len() {
+            return Err(ResolveError {
+                span: Span::default(),
+                msg: format!("tuple pattern has {} element(s), but the value has type {ty:?}", pats.len()),
+            });
+        }
+        // a named tuple is read in place
+        let base = match init {
+            hir::Expr::Ident { .. } => init,
+            _ => {
+                let id = self.fresh();
+                let name = "$tuple".to_string();
+                stmts.push(hir::Stmt::Let(hir::HirLet { id, mutable: false, name: name.clone(), ty: ty.clone(), init }));
+                hir::Expr::Ident { id, name, ty: ty.clone() }
+            }
+        };
+        for (index, (pat, elem)) in pats.iter().zip(elems).enumerate() {
+            let field = hir::Expr::Field { id: self.fresh(), base: Box::new(base.clone()), index, ty: elem.clone() };
+            self.bind_pattern(pat, field, stmts, binds)?;
+        }
+        Ok(())
+    }
+
+    /// Bind `pat` to `value`: a name gets its own `let`, which becomes
+    /// visible when the caller adds `binds` to the scope.
+    fn bind_pattern(
+        &mut self,
+        pat: &ast::Pattern,
+        value: hir::Expr,
+        stmts: &mut Vec<hir::Stmt>,
+        binds: &mut Vec<(String, Symbol)>,
+    ) -> Result<(), ResolveError> {
+        match pat {
+            ast::Pattern::Wildcard => {}
+            ast::Pattern::Tuple(sub) => self.bind_tuple(sub, value, stmts, binds)?,
+            ast::Pattern::Ident { name, mutable } => {
+                if binds.iter().any(|(n, _)| n == name) {
+                    return Err(ResolveError {
+                        span: Span::default(),
+                        msg: format!("identifier `{name}` is bound more than once in the same pattern"),
+                    });
+                }
+                let ty = value.ty().clone();
+                check_inferred(name, &ty)?;
+                let id = self.fresh();
+                stmts.push(hir::Stmt::Let(hir::HirLet {
+                    id,
+                    mutable: *mutable,
+                    name: name.clone(),
+                    ty: ty.clone(),
+                    init: value,
+                }));
+                let sym = Symbol { id, ty, is_mut: *mutable, value: None, is_pub: false };
+                binds.push((name.clone(), sym));
+            }
+        }
+        Ok(())
+    }
+
+    /// `Some(x)`, `Ok(x)` or `Err(e)`; the other half of a `Result` is
+    /// unknown until the value meets the type it is used as.
+    fn lower_variant(&mut self, id: hir::NodeId, name: &str, args: &[ast::Expr]) -> Result<hir::Expr, ResolveError> {
+        let [arg] = args else {
+            return Err(ResolveError {
+                span: Span::default(),
+                msg: format!("`{name}` takes 1 argument(s), got {}", args.len()),
+            });
+        };
+        let value = self.lower_expr(arg)?;
+        let t = Box::new(value.ty().clone());
+        let (variant, ty) = match name {
+            "Some" => (hir::Variant::Some, Type::Option(t)),
+            "Ok" => (hir::Variant::Ok, Type::Result(t, Box::new(Type::Infer))),
+            "Err" => (hir::Variant::Err, Type::Result(Box::new(Type::Infer), t)),
+            _ => unreachable!("not a variant with a value: {name}"),
+        };
+        Ok(hir::Expr::Variant { id, variant, value: Some(Box::new(value)), ty })
+    }
+
+    /// `e?`: the value inside `Some` or `Ok`; `None` or the error of `Err`
+    /// is returned from the enclosing function, whose return type must be
+    /// an `Option`, or a `Result` with the same error type.
+    fn lower_try(&mut self, id: hir::NodeId, inner: &ast::Expr) -> Result<hir::Expr, ResolveError> {
+        let err = |msg: String| Err(ResolveError { span: Span::default(), msg });
+        let mut e = self.lower_expr(inner)?;
+        if self.closures.last().is_some_and(|c| c.ret.is_none()) {
+            return err("`?` in a closure needs an explicit return type: `|..| -> T { .. }`".to_string());
+        }
+        let ret = self.current_ret_ty.clone().unwrap_or(Type::Unit);
+        let ty = match (e.ty().clone(), &ret) {
+            (Type::Option(t), Type::Option(_)) => *t,
+            (Type::Result(t, _), Type::Result(_, ret_err)) => {
+                fill_holes(&mut e, &Type::Result(Box::new(Type::Infer), ret_err.clone()));
+                let Type::Result(_, e_err) = e.ty() else { unreachable!("filled `Result`") };
+                if e_err != ret_err {
+                    return err(format!(
+                        "`?` cannot return an error of type {e_err:?} from a function returning {ret:?}"
+                    ));
+                }
+                *t
+            }
+            (t @ (Type::Option(_) | Type::Result(..)), _) => {
+                let kind = if matches!(t, Type::Option(_)) { "an `Option`" } else { "a `Result`" };
+                return err(format!("`?` on {kind} can only be used in a function that returns {kind}, not {ret:?}"));
+            }
+            (t, _) => return err(format!("`?` can only be applied to an `Option` or a `Result`, got {t:?}")),
+        };
+        if ty.has_holes() {
+            return err(format!("cannot infer the type of the value `?` takes out of {:?}", e.ty()));
+        }
+        Ok(hir::Expr::Try { id, expr: Box::new(e), ty })
+    }
+
+    /// `match` over an `Option` or a `Result` becomes an `if` on its tag:
+    ///
+    /// ```text
+    /// { let $match = scrutinee;
+    ///   if $match.0 { let x = $match.1; some_arm } else { none_arm } }
+    /// ```
+    ///
+    /// The error of `Err` is field 2. A named value is read in place, so
+    /// the `match` moves only the parts its patterns bind; one behind a
+    /// reference is read through it.
+    fn lower_match(&mut self, id: hir::NodeId, scrutinee: &ast::Expr, arms: &[ast::MatchArm]) -> Result<hir::Expr, ResolveError> {
+        let err = |msg: String| Err(ResolveError { span: Span::default(), msg });
+        let scrut = self.lower_expr(scrutinee)?;
+        let scrut = self.auto_deref(scrut)?;
+        let ty = scrut.ty().clone();
+        let (variants, payloads) = match &ty {
+            Type::Option(t) => ([hir::Variant::Some, hir::Variant::None], [(**t).clone(), Type::Unit]),
+            Type::Result(t, e) => ([hir::Variant::Ok, hir::Variant::Err], [(**t).clone(), (**e).clone()]),
+            _ => return err(format!("`match` needs an `Option` or a `Result`, got {ty:?}")),
+        };
+        if ty.has_holes() {
+            return err(format!("cannot infer the type of the matched value, got {ty:?}"));
+        }
+
+        // arm of the first and of the second variant
+        let mut branches: [Option<(Option<&ast::Pattern>, &ast::Expr)>; 2] = [None, None];
+        for arm in arms {
+            let (covers, inner, shown) = match &arm.pattern {
+                ast::MatchPattern::Wildcard => (vec![0, 1], None, "_".to_string()),
+                ast::MatchPattern::Variant { name, inner } => {
+                    let Some(i) = variants.iter().position(|v| v.as_str() == name) else {
+                        return err(format!("`{name}` is not a variant of {ty:?}"));
+                    };
+                    match (variants[i], inner) {
+                        (hir::Variant::None, Some(_)) => return err("`None` has no value to bind".to_string()),
+                        (hir::Variant::None, None) | (_, Some(_)) => {}
+                        (_, None) => return err(format!("`{name}` holds a value; match it with `{name}(_)`")),
+                    }
+                    (vec![i], inner.as_ref(), name.clone())
+                }
+            };
+            let new: Vec<usize> = covers.into_iter().filter(|&i| branches[i].is_none()).collect();
+            if new.is_empty() {
+                return err(format!("unreachable pattern `{shown}`: its values are matched by an earlier arm"));
+            }
+            for i in new {
+                branches[i] = Some((inner, &arm.body));
+            }
+        }
+        let [Some(first), Some(second)] = branches else {
+            let i = branches.iter().position(Option::is_none).unwrap();
+            let v = variants[i];
+            let shown = if v == hir::Variant::None { "None".to_string() } else { format!("{}(_)", v.as_str()) };
+            return err(format!("non-exhaustive patterns in `match`: `{shown}` not covered"));
+        };
+
+        let mut stmts = Vec::new();
+        let base = match scrut {
+            hir::Expr::Ident { .. } => scrut,
+            hir::Expr::Deref { ref expr, .. } if matches!(**expr, hir::Expr::Ident { .. }) => scrut,
+            _ => {
+                let let_id = self.fresh();
+                let name = "$match".to_string();
+                stmts.push(hir::Stmt::Let(hir::HirLet { id: let_id, mutable: false, name: name.clone(), ty: ty.clone(), init: scrut }));
+                hir::Expr::Ident { id: let_id, name, ty: ty.clone() }
+            }
+        };
+        let value = if std::ptr::eq(first.1, second.1) {
+            // `_` pokriva obe varijante
+            hir::Expr::from_block(self.lower_arm(&base, 1, &payloads[0], None, first.1)?)
+        } else {
+            let cond = hir::Expr::Field { id: self.fresh(), base: Box::new(base.clone()), index: 0, ty: Type::Bool };
+            let mut then_blk = self.lower_arm(&base, 1, &payloads[0], first.0, first.1)?;
+            let err_field = if variants[1] == hir::Variant::Err { 2 } else { 1 };
+            let mut else_e = hir::Expr::from_block(self.lower_arm(&base, err_field, &payloads[1], second.0, second.1)?);
+            let ty = join_branches(&mut then_blk, &mut else_e, "`match` arms have")?;
+            hir::Expr::If { id: self.fresh(), cond: Box::new(cond), then_blk, else_branch: Some(Box::new(else_e)), ty }
+        };
+        if stmts.is_empty() {
+            return Ok(value);
+        }
+        let ty = value.ty().clone();
+        stmts.push(hir::Stmt::Expr(value));
+        Ok(hir::Expr::Block { id, block: hir::Block { id: self.fresh(), stmts, spans: Vec::new() }, ty })
+    }
+
+    /// Body of a `match` arm in its own scope, after `pat` is bound to field
+    /// `index` of the matched value.
+    fn lower_arm(
+        &mut self,
+        base: &hir::Expr,
+        index: usize,
+        payload: &Type,
+        pat: Option<&ast::Pattern>,
+        body: &ast::Expr,
+    ) -> Result<hir::Block, ResolveError> {
+        self.push_scope();
+        let mut stmts = Vec::new();
+        let value = (|| {
+            if let Some(pat) = pat {
+                let mut binds = Vec::new();
+                let field = hir::Expr::Field { id: self.fresh(), base: Box::new(base.clone()), index, ty: payload.clone() };
+                self.bind_pattern(pat, field, &mut stmts, &mut binds)?;
+                for (name, sym) in binds {
+                    self.shadow(&name, sym);
+                }
+            }
+            self.lower_expr(body)
+        })();
+        self.pop_scope();
+        stmts.push(hir::Stmt::Expr(value?));
+        Ok(hir::Block { id: self.fresh(), stmts, spans: Vec::new() })
+    }
+
+    /// `for x in a..b { body }` becomes a counted `while` loop:
+    ///
+    /// ```text
+    /// { let mut i = a; let end = b;
+    ///   while i < end { let x = i; i = i + 1; { body } } }
+    /// ```
+    ///
+    /// The counter is advanced before the body runs, so `continue` needs no
+    /// special handling. `a..=b` compares with `<=`.
+    ///
+    /// An array or a `Vec` is walked by index over `0..len`, with
+    /// `let x = seq[i]`. A named sequence is read in place, so the loop
+    /// doesn't consume it; a named `Vec` through a shared reference held
+    /// for the whole loop. Any other expression is evaluated once first.
+    fn lower_for(
+        &mut self,
+        id: hir::NodeId,
+        var: &str,
+        iter: &ast::Expr,
+        body: &ast::Block,
+        span: Span,
+    ) -> Result<hir::Expr, ResolveError> {
+        let ident = |id, name: &str, ty: &Type| hir::Expr::Ident { id, name: name.to_owned(), ty: ty.clone() };
+        let (counter, limit, var_id) = (self.fresh(), self.fresh(), self.fresh());
+        let mut stmts = Vec::new();
+
+        let (start, end, inclusive, seq) = if let ast::Expr::Range { start, end, inclusive } = iter {
+            let start = self.lower_expr(start)?;
+            let end = self.lower_expr(end)?;
+            // literal bez sufiksa uzima tip druge granice, kao kod operatora
+            let (start, end) = match (start.ty(), end.ty()) {
+                (t @ Type::Num(_), _) => {
+                    let t = t.clone();
+                    (start, self.adapt_literal(&t, end)?)
+                }
+                (_, t @ Type::Num(_)) => {
+                    let t = t.clone();
+                    (self.adapt_literal(&t, start)?, end)
+                }
+                _ => (start, end),
+            };
+            for bound in [&start, &end] {
+                if bound.ty().num().is_none_or(|n| n.is_float()) {
+                    return Err(ResolveError {
+                        span: Span::default(),
+                        msg: format!("range bounds must be integers, got {:?}", bound.ty()),
+                    });
+                }
+            }
+            if start.ty() != end.ty() {
+                return Err(ResolveError {
+                    span: Span::default(),
+                    msg: format!("range bounds have different types `{:?}` and `{:?}`", start.ty(), end.ty()),
+                });
+            }
+            (start, end, *inclusive, None)
+        } else {
+            let seq = self.lower_expr(iter)?;
+            let seq = match seq {
+                // `let $seq = &v` borrows a named Vec for the whole loop, so
+                // the body can't push to it or pop from it
+                hir::Expr::Ident { .. } if matches!(seq.ty(), Type::Vec(_)) => {
+                    let seq_id = self.fresh();
+                    let ty = Type::Ref { mutability: false, inner: Box::new(seq.ty().clone()), lifetime: None };
+                    let init = hir::Expr::Ref { id: self.fresh(), mutable: false, expr: Box::new(seq), ty: ty.clone() };
+                    stmts.push(hir::Stmt::Let(hir::HirLet {
+                        id: seq_id,
+                        mutable: false,
+                        name: "$seq".to_string(),
+                        ty: ty.clone(),
+                        init,
+                    }));
+                    self.deref(ident(seq_id, "$seq", &ty))?
+                }
+                hir::Expr::Ident { .. } => seq,
+                _ => {
+                    let seq_id = self.fresh();
+                    let ty = seq.ty().clone();
+                    stmts.push(hir::Stmt::Let(hir::HirLet {
+                        id: seq_id,
+                        mutable: false,
+                        name: "$seq".to_string(),
+                        ty: ty.clone(),
+                        init: seq,
+                    }));
+                    ident(seq_id, "$seq", &ty)
+                }
+            };
+            let (elem, end) = match seq.ty() {
+                Type::Array(elem, len) => {
+                    (elem, hir::Expr::Int { id: self.fresh(), value: *len as i64, ty: Type::Int })
+                }
+                Type::Vec(elem) => {
+                    let len = hir::Expr::Builtin { id: self.fresh(), kind: hir::Builtin::Len, ty: Type::Unit };
+                    let call = hir::Expr::Call {
+                        id: self.fresh(),
+                        callee: Box::new(len),
+                        args: vec![seq.clone()],
+                        ty: Type::Int,
+                    };
+                    (elem, call)
+                }
+                other => {
+                    return Err(ResolveError {
+                        span: Span::default(),
+                        msg: format!(
+                            "`for` can only iterate over a range `a..b` or `a..=b`, an array or a Vec, got {other:?}"
+                        ),
+                    });
+                }
+            };
+            let elem = (**elem).clone();
+            let start = hir::Expr::Int { id: self.fresh(), value: 0, ty: Type::Int };
+            (start, end, false, Some((seq, elem)))
+        };
+        // brojač ima tip granica opsega; niz se prolazi Int indeksom
+        let idx_ty = if seq.is_some() { Type::Int } else { start.ty().clone() };
+        let var_ty = seq.as_ref().map_or(idx_ty.clone(), |(_, elem)| elem.clone());
+
+        // telo petlje vidi samo promenljivu `var`
+        self.push_scope();
+        let sym = Symbol { id: var_id, ty: var_ty.clone(), is_mut: false, value: None, is_pub: false };
+        self.shadow(var, sym);
+        self.loop_depth += 1;
+        let user_body = self.lower_block(body);
+        self.loop_depth -= 1;
+        self.pop_scope();
+        let user_body = user_body?;
+        if user_body.ty() != Type::Unit {
+            return Err(ResolveError {
+                span: Span::default(),
+                msg: format!("`for` body must have type (), got {:?}", user_body.ty()),
+            });
+        }
+
+        let compare = |this: &mut Self, op| hir::Expr::Binary {
+            id: this.fresh(),
+            lhs: Box::new(ident(counter, "$i", &idx_ty)),
+            op,
+            rhs: Box::new(ident(limit, "$end", &idx_ty)),
+            ty: Type::Bool,
+        };
+        // `a..=b` ne sme da poveća `$i` preko `b`, koje može biti najveći
+        // Int; zato `$more` pamti da li ima još prolaza
+        let more = inclusive.then(|| (self.fresh(), compare(self, hir::BinOp::Le)));
+        let cond = match &more {
+            Some((more, _)) => ident(*more, "$more", &Type::Bool),
+            None => compare(self, hir::BinOp::Lt),
+        };
+        let step = hir::Expr::Binary {
+            id: self.fresh(),
+            lhs: Box::new(ident(counter, "$i", &idx_ty)),
+            op: hir::BinOp::Plus,
+            rhs: Box::new(hir::Expr::Int { id: self.fresh(), value: 1, ty: idx_ty.clone() }),
+            ty: idx_ty.clone(),
+        };
+        let var_init = match seq {
+            Some((seq, elem)) => hir::Expr::Index {
+                id: self.fresh(),
+                base: Box::new(seq),
+                index: Box::new(ident(counter, "$i", &idx_ty)),
+                span,
+                ty: elem,
+            },
+            None => ident(counter, "$i", &idx_ty),
+        };
+        let mut body_stmts = vec![hir::Stmt::Let(hir::HirLet {
+            id: var_id,
+            mutable: false,
+            name: var.to_owned(),
+            ty: var_ty,
+            init: var_init,
+        })];
+        let step = hir::Stmt::Assign { id: counter, name: "$i".to_string(), expr: step };
+        match &more {
+            // `if $i < $end { $i = $i + 1 } else { $more = false }`
+            Some((more, _)) => {
+                let done = hir::Expr::Bool { id: self.fresh(), value: false, ty: Type::Bool };
+                let done = hir::Block {
+                    id: self.fresh(),
+                    stmts: vec![hir::Stmt::Assign { id: *more, name: "$more".to_string(), expr: done }],
+                    spans: Vec::new(),
+                };
+                let guarded = hir::Expr::If {
+                    id: self.fresh(),
+                    cond: Box::new(compare(self, hir::BinOp::Lt)),
+                    then_blk: hir::Block { id: self.fresh(), stmts: vec![step], spans: Vec::new() },
+                    else_branch: Some(Box::new(hir::Expr::from_block(done))),
+                    ty: Type::Unit,
+                };
+                body_stmts.push(hir::Stmt::Semi(guarded));
+            }
+            None => body_stmts.push(step),
         }
+        body_stmts.push(hir::Stmt::Semi(hir::Expr::from_block(user_body)));
+        let while_body = hir::Block { id: self.fresh(), stmts: body_stmts, spans: Vec::new() };
+        let lets = [(counter, "$i", true, start), (limit, "$end", false, end)];
+        stmts.extend(lets.into_iter().map(|(id, name, mutable, init)| {
+            hir::Stmt::Let(hir::HirLet { id, mutable, name: name.to_string(), ty: idx_ty.clone(), init })
+        }));
+        if let Some((id, init)) = more {
+            stmts.push(hir::Stmt::Let(hir::HirLet { id, mutable: true, name: "$more".to_string(), ty: Type::Bool, init }));
+        }
+        stmts.push(hir::Stmt::Semi(hir::Expr::While {
+            id: self.fresh(),
+            cond: Box::new(cond),
+            body: while_body,
+            ty: Type::Unit,
+        }));
+        Ok(hir::Expr::Block {
+            id,
+            block: hir::Block { id: self.fresh(), stmts, spans: Vec::new() },
+            ty: Type::Unit,
+        })
     }
 
     /*──────── lower expr ────────*/
-    fn lower_expr(&mut self, e: &ast::Expr) -> Result<hir::Expr, ResolveError> {
-        use ast::Expr::*;
-        let id = self.fresh();
+    /// A name or path; functions and builtins stay callable here, see
+    /// `lower_expr` for their use as values.
+    fn lower_name(&mut self, id: hir::NodeId, e: &ast::Expr) -> Result<hir::Expr, ResolveError> {
         Ok(match e {
-            Ident(name) => {
-                if name == "print" {
+            ast::Expr::Ident(name) => {
+                // `print` je rezervisan, ostale ugrađene funkcije važe samo
+                // dok ime nije vezano za promenljivu ili funkciju
+                let builtin = match name.as_str() {
+                    "print" => Some(hir::Builtin::Print),
+                    _ if self.lookup(name).is_some() => None,
+                    "send" => Some(hir::Builtin::Send),
+                    "recv" => Some(hir::Builtin::Recv),
+                    "push" => Some(hir::Builtin::Push),
+                    "pop" => Some(hir::Builtin::Pop),
+                    "len" => Some(hir::Builtin::Len),
+                    _ => None,
+                };
+                if let Some(kind) = builtin {
                     hir::Expr::Builtin {
                         id,
-                        kind: hir::Builtin::Print,
+                        kind,
                         ty: Type::Unit,
                     }
                 } else {
-                    let sym = self.lookup(name).ok_or_else(|| ResolveError {
+                    let sym = self.lookup(name).cloned().ok_or_else(|| ResolveError {
                         span: Span::default(),
                         msg: format!("unknown name `{name}`"),
                     })?;
-                    hir::Expr::Ident {
-                        id: sym.id,
-                        name: name.clone(),
-                        ty: sym.ty.clone(),
-                    }
+                    self.used.insert(sym.id);
+                    self.capture(name);
+                    self.value_symbol(id, name, &sym)?
+                }
+            }
+            ast::Expr::Path(path) => {
+                let (method, prefix) = path.split_last().unwrap();
+                let trait_sym = match prefix {
+                    [name] => self.lookup(name).cloned(),
+                    _ => self.lookup_path(prefix).ok(),
+                };
+                if let Some(sym) = trait_sym.filter(|s| self.traits.contains_key(&s.id)) {
+                    self.used.insert(sym.id);
+                    return self.method_ref(id, sym.id, method);
+                }
+                // `Int::parse`, `Self::new` – funkcija iz `impl Type`
+                if let [ty_name] = prefix
+                    && self.find_module(prefix).is_none()
+                    && let Some(f) = self.assoc_fn(ty_name, method)
+                {
+                    return f;
                 }
+                let sym = self.lookup_path(path)?;
+                self.used.insert(sym.id);
+                self.value_symbol(id, &path.join("::"), &sym)?
             }
-            Int(v) => hir::Expr::Int {
+            _ => unreachable!("not a name: {e:?}"),
+        })
+    }
+
+    /// `symbol_expr`, unless the name is a trait.
+    fn value_symbol(&self, id: hir::NodeId, name: &str, sym: &Symbol) -> Result<hir::Expr, ResolveError> {
+        if self.traits.contains_key(&sym.id) {
+            return Err(ResolveError {
+                span: Span::default(),
+                msg: format!("expected a value, found trait `{name}`"),
+            });
+        }
+        Ok(self.symbol_expr(id, name, sym))
+    }
+
+    /// Callee of a call or `spawn`: a named function is called directly.
+    fn lower_callee(&mut self, callee: &ast::Expr) -> Result<hir::Expr, ResolveError> {
+        match callee {
+            ast::Expr::Ident(_) | ast::Expr::Path(_) => {
+                let id = self.fresh();
+                self.lower_name(id, callee)
+            }
+            _ => self.lower_expr(callee),
+        }
+    }
+
+    fn lower_expr(&mut self, e: &ast::Expr) -> Result<hir::Expr, ResolveError> {
+        use ast::Expr::*;
+        let id = self.fresh();
+        Ok(match e {
+            Ident(name) if name == "None" => hir::Expr::Variant {
                 id,
-                value: *v,
-                ty: Type::Int,
+                variant: hir::Variant::None,
+                value: None,
+                ty: Type::Option(Box::new(Type::Infer)),
+            },
+            Ident(name) if matches!(name.as_str(), "Some" | "Ok" | "Err") => {
+                return Err(ResolveError {
+                    span: Span::default(),
+                    msg: format!("variant `{name}` can only be called, not used as a value"),
+                });
+            }
+            Ident(_) | Path(_) => match self.lower_name(id, e)? {
+                hir::Expr::Builtin { kind, .. } => {
+                    return Err(ResolveError {
+                        span: Span::default(),
+                        msg: format!("builtin `{}` can only be called, not used as a value", kind.name()),
+                    });
+                }
+                hir::Expr::Ident { id: fn_id, name, ty } if self.fn_sigs.contains_key(&fn_id) => {
+                    let sig = &self.fn_sigs[&fn_id];
+                    if !sig.type_params.is_empty() {
+                        return Err(ResolveError {
+                            span: Span::default(),
+                            msg: format!("generic function `{name}` can only be called, not used as a value"),
+                        });
+                    }
+                    if let Some(mode @ (ast::SelfMode::Ref | ast::SelfMode::RefMut)) = sig.self_mode {
+                        return Err(ResolveError {
+                            span: Span::default(),
+                            msg: format!("method `{name}` takes `{}`, so it can only be called, not used as a value", mode.as_str()),
+                        });
+                    }
+                    hir::Expr::FnRef { id, fn_id, name, type_args: Vec::new(), ty }
+                }
+                hir::Expr::MethodRef { trait_name, method, .. } => {
+                    return Err(ResolveError {
+                        span: Span::default(),
+                        msg: format!("trait method `{trait_name}::{method}` can only be called, not used as a value"),
+                    });
+                }
+                h => h,
             },
+            Int(v) => {
+                if !NumTy::I32.fits(*v) {
+                    self.wide_literals.insert(id);
+                }
+                hir::Expr::Int { id, value: *v, ty: Type::Int }
+            }
             Float(v) => hir::Expr::Float {
                 id,
                 value: *v,
                 ty: Type::Float,
             },
+            TypedInt(v, n) => {
+                self.typed_literals.insert(id);
+                int_literal(id, *v, *n, false)?
+            }
+            TypedFloat(v, n) => {
+                self.typed_literals.insert(id);
+                hir::Expr::Float { id, value: float_value(*v, *n), ty: n.ty() }
+            }
             Bool(b) => hir::Expr::Bool {
                 id,
                 value: *b,
@@ -340,150 +2734,578 @@ impl Cx {
                 value: s.clone(),
                 ty: Type::Str,
             },
+            Char(c) => {
+                self.typed_literals.insert(id);
+                hir::Expr::Int { id, value: u32::from(*c).into(), ty: Type::Char }
+            }
+            // `b"ab"` je `[b'a', b'b']`
+            ByteStr(bytes) => {
+                let elems = bytes
+                    .iter()
+                    .map(|&b| {
+                        let id = self.fresh();
+                        self.typed_literals.insert(id);
+                        hir::Expr::Int { id, value: b.into(), ty: Type::Num(NumTy::U8) }
+                    })
+                    .collect();
+                hir::Expr::Array { id, elems, ty: Type::Array(Box::new(Type::Num(NumTy::U8)), bytes.len()) }
+            }
 
+            Call { callee, args } if matches!(&**callee, Ident(n) if matches!(n.as_str(), "Some" | "Ok" | "Err")) => {
+                let Ident(name) = &**callee else { unreachable!() };
+                self.lower_variant(id, name, args)?
+            }
             Call { callee, args } => {
-                let cal_h = self.lower_expr(callee)?;
+                let cal_h = self.lower_callee(callee)?;
+                let mut a = Vec::new();
+                for x in args {
+                    a.push(self.lower_expr(x)?);
+                }
+                self.lower_call(id, cal_h, a)?
+            }
+            // `x.m(a)` je poziv `m(x, a)` metode tipa od `x`
+            MethodCall { receiver, method, args } => {
+                let recv = self.lower_expr(receiver)?;
+                // metoda reference je metoda vrednosti na koju pokazuje
+                let recv = self.auto_deref(recv)?;
+                let cal_h = self.method_callee(&recv, method)?;
+                let mut a = vec![recv];
+                for x in args {
+                    a.push(self.lower_expr(x)?);
+                }
+                self.lower_call(id, cal_h, a)?
+            }
+
+            Unary { op, expr } => {
+                let operand = match (op, &**expr) {
+                    // `-128i8` staje u `i8`, iako `128i8` ne staje
+                    (ast::UnOp::Negate, TypedInt(v, n)) => {
+                        let lit = self.fresh();
+                        self.typed_literals.insert(lit);
+                        int_literal(lit, *v, *n, true)?
+                    }
+                    // `-2147483648` staje u Int, iako `2147483648` ne staje
+                    (ast::UnOp::Negate, Int(v)) if NumTy::I32.fits(-v) => {
+                        hir::Expr::Int { id: self.fresh(), value: *v, ty: Type::Int }
+                    }
+                    _ => self.lower_expr(expr)?,
+                };
+                let ty = match op {
+                    ast::UnOp::Negate => {
+                        if let Some(n) = operand.ty().num().filter(|n| n.is_unsigned()) {
+                            return Err(ResolveError {
+                                span: Span::default(),
+                                msg: format!("cannot negate unsigned type `{}`", n.name()),
+                            });
+                        } else if operand.ty().num().is_some() {
+                            operand.ty().clone()
+                        } else if !is_scalar(operand.ty()) {
+                            return self.overloaded_neg(id, operand);
+                        } else {
+                            return Err(ResolveError {
+                                span: Span::default(),
+                                msg: format!(
+                                    "cannot negate type `{:?}`, expected Int or Float",
+                                    operand.ty()
+                                ),
+                            });
+                        }
+                    }
+                    ast::UnOp::BitNot => {
+                        if operand.ty().num().is_some_and(|n| !n.is_float()) {
+                            operand.ty().clone()
+                        } else {
+                            return Err(ResolveError {
+                                span: Span::default(),
+                                msg: format!("cannot apply bitwise NOT to type `{:?}`, expected Int", operand.ty()),
+                            });
+                        }
+                    }
+                    ast::UnOp::Not => {
+                        if operand.ty() == &Type::Bool {
+                            Type::Bool
+                        } else {
+                            return Err(ResolveError {
+                                span: Span::default(),
+                                msg: format!(
+                                    "cannot apply logical NOT to type `{:?}`, expected Bool",
+                                    operand.ty()
+                                ),
+                            });
+                        }
+                    }
+                };
+                hir::Expr::Unary {
+                    id,
+                    op: hir::UnOp::from_ast(*op),
+                    rhs: Box::new(operand),
+                    ty,
+                }
+            }
+
+            Binary { op, lhs, rhs } => {
+                let l = self.lower_expr(lhs)?;
+                let r = self.lower_expr(rhs)?;
+                // literal bez sufiksa uzima tip druge strane
+                let (l, r) = match (l.ty(), r.ty()) {
+                    (t @ Type::Num(_), _) => {
+                        let t = t.clone();
+                        (l, self.adapt_literal(&t, r)?)
+                    }
+                    (_, t @ Type::Num(_)) => {
+                        let t = t.clone();
+                        (self.adapt_literal(&t, l)?, r)
+                    }
+                    _ => (l, r),
+                };
+                let builtin = match op {
+                    // арифметика - use unify with numeric promotion
+                    ast::BinOp::Plus
+                    | ast::BinOp::Minus
+                    | ast::BinOp::Star
+                    | ast::BinOp::Slash
+                    | ast::BinOp::Percent => match Type::unify(l.ty(), r.ty()) {
+                        Ok(t @ (Type::Int | Type::Float | Type::Num(_))) => Ok(t),
+                        _ => Err(format!("cannot apply {:?} to types `{:?}` and `{:?}`", op, l.ty(), r.ty())),
+                    },
+
+                    // битовске операције само над Int
+                    ast::BinOp::BitAnd
+                    | ast::BinOp::BitOr
+                    | ast::BinOp::BitXor
+                    | ast::BinOp::Shl
+                    | ast::BinOp::Shr => match Type::unify(l.ty(), r.ty()) {
+                        Ok(t) if t.num().is_some_and(|n| !n.is_float()) => Ok(t),
+                        _ => Err(format!("cannot apply {:?} to types `{:?}` and `{:?}`", op, l.ty(), r.ty())),
+                    },
+
+                    // логика
+                    ast::BinOp::AndAnd | ast::BinOp::OrOr => {
+                        if l.ty() == &Type::Bool && r.ty() == &Type::Bool {
+                            Ok(Type::Bool)
+                        } else {
+                            Err(format!(
+                                "logical operation requires Bool operands, got `{:?}` and `{:?}`",
+                                l.ty(),
+                                r.ty()
+                            ))
+                        }
+                    }
+                    // сравнение
+                    ast::BinOp::EqEq | ast::BinOp::NotEq => match Type::unify(l.ty(), r.ty()) {
+                        Ok(Type::Int | Type::Float | Type::Num(_) | Type::Bool | Type::Char | Type::Str) => Ok(Type::Bool),
+                        _ => Err(format!("cannot compare types `{:?}` and `{:?}`", l.ty(), r.ty())),
+                    },
+                    ast::BinOp::Lt | ast::BinOp::Le | ast::BinOp::Gt | ast::BinOp::Ge => {
+                        match Type::unify(l.ty(), r.ty()) {
+                            Ok(Type::Int | Type::Float | Type::Num(_) | Type::Char) => Ok(Type::Bool),
+                            _ => Err(format!("cannot order-compare types `{:?}` and `{:?}`", l.ty(), r.ty())),
+                        }
+                    }
+                };
+                let ty = match builtin {
+                    Ok(ty) => ty,
+                    // ostali tipovi dobijaju operator preko jezičke osobine
+                    Err(msg) if op_trait(*op).is_some() && !is_scalar(l.ty()) => {
+                        return self.overloaded_op(id, *op, l, r).map_err(|e| ResolveError {
+                            span: e.span,
+                            msg: format!("{msg}: {}", e.msg),
+                        });
+                    }
+                    Err(msg) => return Err(ResolveError { span: Span::default(), msg }),
+                };
+                hir::Expr::Binary {
+                    id,
+                    lhs: Box::new(l),
+                    op: hir::BinOp::from_ast(*op),
+                    rhs: Box::new(r),
+                    ty,
+                }
+            }
+
+            Block(b) => hir::Expr::from_block(self.lower_block(b)?),
+
+            If {
+                cond,
+                then_branch,
+                else_branch,
+            } => {
+                let c = self.lower_cond(cond, "if")?;
+                let mut then_blk = self.lower_block(then_branch)?;
+                let mut else_h = match else_branch {
+                    Some(e) => Some(Box::new(self.lower_expr(e)?)),
+                    None => None,
+                };
+                let ty = match &mut else_h {
+                    None => {
+                        if then_blk.ty() != Type::Unit {
+                            return Err(ResolveError {
+                                span: Span::default(),
+                                msg: format!(
+                                    "`if` without `else` must have type (), got {:?}",
+                                    then_blk.ty()
+                                ),
+                            });
+                        }
+                        Type::Unit
+                    }
+                    Some(e) => join_branches(&mut then_blk, e, "`if` and `else` have")?,
+                };
+                hir::Expr::If {
+                    id,
+                    cond: Box::new(c),
+                    then_blk,
+                    else_branch: else_h,
+                    ty,
+                }
+            }
+
+            While { cond, body } => {
+                let c = self.lower_cond(cond, "while")?;
+                self.loop_depth += 1;
+                let body = self.lower_block(body);
+                self.loop_depth -= 1;
+                let body = body?;
+                if body.ty() != Type::Unit {
+                    return Err(ResolveError {
+                        span: Span::default(),
+                        msg: format!("`while` body must have type (), got {:?}", body.ty()),
+                    });
+                }
+                hir::Expr::While {
+                    id,
+                    cond: Box::new(c),
+                    body,
+                    ty: Type::Unit,
+                }
+            }
+            For { var, iter, body, span } => self.lower_for(id, var, iter, body, *span)?,
+            Match { scrutinee, arms } => self.lower_match(id, scrutinee, arms)?,
+            Try(inner) => self.lower_try(id, inner)?,
+            Range { .. } => {
+                return Err(ResolveError {
+                    span: Span::default(),
+                    msg: "a range can only be used as the iterator of a `for` loop".to_string(),
+                });
+            }
+            Spawn { callee, args } => {
+                let cal_h = self.lower_callee(callee)?;
                 let mut a = Vec::new();
                 for x in args {
                     a.push(self.lower_expr(x)?);
                 }
+                let sig = match &cal_h {
+                    hir::Expr::Ident { id: fn_id, .. } => self.fn_sigs.get(fn_id).cloned(),
+                    _ => None,
+                };
+                let Some(sig) = sig else {
+                    return Err(ResolveError {
+                        span: Span::default(),
+                        msg: "`spawn` expects a call of a user-defined function".to_string(),
+                    });
+                };
+                if !sig.type_params.is_empty() {
+                    return Err(ResolveError {
+                        span: Span::default(),
+                        msg: format!("cannot spawn generic function `{}`", sig.name),
+                    });
+                }
+                if let Some(mode @ (ast::SelfMode::Ref | ast::SelfMode::RefMut)) = sig.self_mode {
+                    return Err(ResolveError {
+                        span: Span::default(),
+                        msg: format!("cannot spawn method `{}`: it takes `{}`", sig.name, mode.as_str()),
+                    });
+                }
+                self.check_args(&sig.name, &sig.params, &a)?;
+                // zatvorenje bi delilo uhvaćene promenljive sa novom niti
+                if let Some(x) = a.iter().find(|x| matches!(x.ty(), Type::Fn { .. })) {
+                    return Err(ResolveError {
+                        span: Span::default(),
+                        msg: format!("cannot pass a function value of type {:?} to a spawned function", x.ty()),
+                    });
+                }
+                if sig.ret != Type::Unit {
+                    return Err(ResolveError {
+                        span: Span::default(),
+                        msg: format!("spawned function `{}` must return (), got {:?}", sig.name, sig.ret),
+                    });
+                }
+                hir::Expr::Spawn {
+                    id,
+                    callee: Box::new(cal_h),
+                    args: a,
+                    ty: Type::Unit,
+                }
+            }
+            Array(elems) => {
+                let mut a = Vec::new();
+                for e in elems {
+                    a.push(self.lower_expr(e)?);
+                }
+                // literali bez sufiksa uzimaju tip elementa zadate širine
+                if let Some(t) = a.iter().map(|e| e.ty()).find(|t| matches!(t, Type::Num(_))).cloned() {
+                    a = a.into_iter().map(|e| self.adapt_literal(&t, e)).collect::<Result<_, _>>()?;
+                }
+                let Some(first) = a.first() else {
+                    return Err(ResolveError {
+                        span: Span::default(),
+                        msg: "cannot infer the element type of an empty array; write `[x; 0]`"
+                            .to_string(),
+                    });
+                };
+                // Int elementi se promovišu ako je bar jedan Float
+                let mut elem = first.ty().clone();
+                for e in &a[1..] {
+                    elem = Type::unify(&elem, e.ty()).map_err(|_| ResolveError {
+                        span: Span::default(),
+                        msg: format!("array elements must have the same type, got {:?} and {:?}", elem, e.ty()),
+                    })?;
+                }
+                let elem = self.array_elem(elem, Span::default())?;
+                let ty = Type::Array(Box::new(elem), a.len());
+                hir::Expr::Array { id, elems: a, ty }
+            }
+            Repeat { elem, len } => {
+                let elem = self.lower_expr(elem)?;
+                let len = self.array_len(len)?;
+                let ty = Type::Array(Box::new(self.array_elem(elem.ty().clone(), Span::default())?), len);
+                hir::Expr::Repeat { id, elem: Box::new(elem), len, ty }
+            }
+            Index { base, index, span } => {
+                let base = self.lower_expr(base)?;
+                let base = self.auto_deref(base)?;
+                let index = self.lower_expr(index)?;
+                // dužina `Vec`-a se zna tek u vreme izvršavanja
+                let (elem, len, what) = match base.ty().clone() {
+                    Type::Array(elem, len) => (elem, Some(len), "array"),
+                    Type::Vec(elem) => (elem, None, "Vec"),
+                    other => {
+                        return Err(ResolveError {
+                            span: *span,
+                            msg: format!("cannot index into a value of type {other:?}"),
+                        });
+                    }
+                };
+                if index.ty() != &Type::Int {
+                    return Err(ResolveError {
+                        span: *span,
+                        msg: format!("{what} index must be Int, got {:?}", index.ty()),
+                    });
+                }
+                // konstantan indeks van granica je greška već ovde
+                if let Ok(ConstValue::Int(i)) = self.consts.eval(&index)
+                    && let Some(len) = len
+                    && (i < 0 || i as usize >= len)
+                {
+                    return Err(ResolveError {
+                        span: *span,
+                        msg: ConstError::IndexOutOfBounds { index: i, len }.msg(),
+                    });
+                }
+                hir::Expr::Index {
+                    id,
+                    base: Box::new(base),
+                    index: Box::new(index),
+                    span: *span,
+                    ty: *elem,
+                }
+            }
+            Channel(elem) => {
+                let Some(elem) = elem else {
+                    return Err(ResolveError {
+                        span: Span::default(),
+                        msg: "cannot infer the element type of `channel()`; write `channel::<T>()`"
+                            .to_string(),
+                    });
+                };
+                let elem = Box::new(self.channel_elem(elem, Span::default())?);
+                hir::Expr::Channel { id, ty: Type::Tuple(vec![Type::Sender(elem.clone()), Type::Receiver(elem)]) }
+            }
+            Tuple(elems) => {
+                let mut a = Vec::new();
+                for e in elems {
+                    a.push(self.lower_expr(e)?);
+                }
+                let ty = Type::Tuple(a.iter().map(|e| e.ty().clone()).collect());
+                hir::Expr::Tuple { id, elems: a, ty }
+            }
+            Field { base, index } => {
+                let base = self.lower_expr(base)?;
+                let base = self.auto_deref(base)?;
+                let elem = match base.ty() {
+                    Type::Tuple(elems) => elems.get(*index).cloned(),
+                    _ => None,
+                };
+                let Some(ty) = elem else {
+                    return Err(ResolveError {
+                        span: Span::default(),
+                        msg: format!("no field `{index}` on type {:?}", base.ty()),
+                    });
+                };
+                hir::Expr::Field { id, base: Box::new(base), index: *index, ty }
+            }
+            VecNew(elem) => {
+                let elem = self.vec_elem(elem, Span::default())?;
+                hir::Expr::VecNew { id, ty: Type::Vec(Box::new(elem)) }
+            }
+            Closure { params, ret, body, by_move } => self.lower_closure(id, params, ret.as_ref(), body, *by_move)?,
+            Ref { mutable, expr } => {
+                let e = self.lower_expr(expr)?;
+                if *mutable && !self.check_mut_borrow(&e, "")? {
+                    return Err(ResolveError {
+                        span: Span::default(),
+                        msg: "cannot borrow a temporary value as mutable; bind it to a `mut` variable first".to_string(),
+                    });
+                }
+                let ty = Type::Ref { mutability: *mutable, inner: Box::new(e.ty().clone()), lifetime: None };
+                hir::Expr::Ref { id, mutable: *mutable, expr: Box::new(e), ty }
+            }
+            Deref(expr) => {
+                let e = self.lower_expr(expr)?;
+                self.deref(e)?
+            }
+        })
+    }
 
-                if let hir::Expr::Builtin { kind: hir::Builtin::Print, .. } = &cal_h {
-                    if a.len() != 1 || !(a[0].ty() == &Type::Int || a[0].ty() == &Type::Str) {
-                        return Err(ResolveError {
-                            span: Span::default(),
-                            msg: "print unsupported type".to_string(),
-                        });
-                    }
+    /// Check a call of `cal_h` with the lowered arguments `a`. A generic
+    /// function or trait method becomes a callee for its instance, the
+    /// receiver of a `&self` method is passed by reference, and references
+    /// passed for `&dyn Trait` parameters become trait objects.
+    fn lower_call(&mut self, id: hir::NodeId, mut cal_h: hir::Expr, mut a: Vec<hir::Expr>) -> Result<hir::Expr, ResolveError> {
+        let ty;
+        match &cal_h {
+            hir::Expr::Builtin { kind, .. } => {
+                if *kind == hir::Builtin::Push
+                    && let Some(Type::Vec(elem)) = a.first().map(|v| v.ty().clone())
+                {
+                    self.coerce_args(&[Type::Unit, *elem], &mut a)?;
                 }
-
-                hir::Expr::Call {
-                    id,
-                    callee: Box::new(cal_h),
-                    args: a,
-                    ty: Type::Unit,
+                ty = self.builtin_call(*kind, &a)?;
+                if matches!(kind, hir::Builtin::Push | hir::Builtin::Pop) {
+                    self.check_mutable_vec(*kind, &a[0])?;
                 }
             }
-
-            Unary { op, expr } => {
-                let operand = self.lower_expr(expr)?;
-                let ty = match op {
-                    ast::UnOp::Negate => {
-                        if operand.ty() == &Type::Int || operand.ty() == &Type::Float {
-                            operand.ty().clone()
-                        } else {
-                            return Err(ResolveError {
-                                span: Span::default(),
-                                msg: format!(
-                                    "cannot negate type `{:?}`, expected Int or Float",
-                                    operand.ty()
-                                ),
-                            });
-                        }
-                    }
-                    ast::UnOp::Not => {
-                        if operand.ty() == &Type::Bool {
-                            Type::Bool
-                        } else {
-                            return Err(ResolveError {
-                                span: Span::default(),
-                                msg: format!(
-                                    "cannot apply logical NOT to type `{:?}`, expected Bool",
-                                    operand.ty()
-                                ),
-                            });
-                        }
-                    }
-                };
-                hir::Expr::Unary {
-                    id,
-                    op: hir::UnOp::from_ast(*op),
-                    rhs: Box::new(operand),
-                    ty,
+            hir::Expr::MethodRef { id: ref_id, trait_id, method, .. } => {
+                let (trait_id, method) = (*trait_id, method.clone());
+                if let Some((_, sig)) = self.traits[&trait_id].methods.iter().find(|(n, _)| *n == method) {
+                    let params = sig.holed_params();
+                    self.coerce_args(&params, &mut a)?;
+                }
+                cal_h = self.call_method(*ref_id, trait_id, &method, &mut a)?;
+                let Type::Fn { ret, .. } = cal_h.ty() else { unreachable!("method of a trait") };
+                ty = (**ret).clone();
+                let tr = &self.traits[&trait_id];
+                let (name, sig) = tr.methods.iter().find(|(n, _)| *n == method).expect("checked by `method_ref`");
+                let name = format!("{}::{name}", tr.name);
+                self.auto_ref(sig.self_mode, &name, &mut a)?;
+            }
+            hir::Expr::Ident { id: fn_id, name, .. } if self.fn_sigs.contains_key(fn_id) => {
+                let sig = self.fn_sigs[fn_id].clone();
+                self.coerce_args(&sig.holed_params(), &mut a)?;
+                if sig.type_params.is_empty() {
+                    self.check_args(name, &sig.params, &a)?;
+                    ty = sig.ret.clone();
+                } else {
+                    cal_h = self.instantiate(*fn_id, &sig, &mut a)?;
+                    let Type::Fn { ret, .. } = cal_h.ty() else { unreachable!("instance of a fn") };
+                    ty = (**ret).clone();
                 }
+                self.auto_ref(sig.self_mode, &sig.name, &mut a)?;
+            }
+            // a function value: a variable, a closure, a call result
+            callee => {
+                let Type::Fn { params, ret } = callee.ty() else {
+                    return Err(ResolveError {
+                        span: Span::default(),
+                        msg: format!("cannot call a value of type {:?}", callee.ty()),
+                    });
+                };
+                let name = match callee {
+                    hir::Expr::Ident { name, .. } => name.clone(),
+                    hir::Expr::DynMethod { trait_name, method, .. } => format!("{trait_name}::{method}"),
+                    _ => "closure".to_string(),
+                };
+                let (params, ret) = (params.clone(), (**ret).clone());
+                self.coerce_args(&params, &mut a)?;
+                self.check_args(&name, &params, &a)?;
+                ty = ret;
             }
+        }
+        Ok(hir::Expr::Call { id, callee: Box::new(cal_h), args: a, ty })
+    }
 
-            Binary { op, lhs, rhs } => {
-                let l = self.lower_expr(lhs)?;
-                let r = self.lower_expr(rhs)?;
-                let ty = match op {
-                    // арифметика - use unify with numeric promotion
-                    ast::BinOp::Plus
-                    | ast::BinOp::Minus
-                    | ast::BinOp::Star
-                    | ast::BinOp::Slash
-                    | ast::BinOp::Percent => match Type::unify(l.ty(), r.ty()) {
-                        Ok(Type::Int) => Type::Int,
-                        Ok(Type::Float) => Type::Float,
-                        _ => {
-                            return Err(ResolveError {
-                                span: Span::default(),
-                                msg: format!(
-                                    "cannot apply {:?} to types `{:?}` and `{:?}`",
-                                    op,
-                                    l.ty(),
-                                    r.ty()
-                                ),
-                            });
-                        }
-                    }
+    /// `|a: Int| body` – the parameters live in a new scope and every use of
+    /// an outer local in the body becomes a capture. Without a return type
+    /// the closure returns the body's type.
+    fn lower_closure(
+        &mut self,
+        id: hir::NodeId,
+        params: &[ast::Param],
+        ret: Option<&ast::TypeExpr>,
+        body: &ast::Expr,
+        by_move: bool,
+    ) -> Result<hir::Expr, ResolveError> {
+        let ret = ret.map(|t| self.resolve_type(t, Span::default())).transpose()?;
+        let mut hparams = Vec::new();
+        for p in params {
+            let Some(t) = &p.ty else {
+                return Err(ResolveError {
+                    span: Span::default(),
+                    msg: format!("closure parameter `{}` needs a type: `|{}: T|`", p.name, p.name),
+                });
+            };
+            let ty = self.resolve_type(t, Span::default())?;
+            hparams.push(hir::Param { id: self.fresh(), name: p.name.clone(), ty });
+        }
 
-                    // логика
-                    ast::BinOp::AndAnd | ast::BinOp::OrOr => {
-                        if l.ty() == &Type::Bool && r.ty() == &Type::Bool {
-                            Type::Bool
-                        } else {
-                            return Err(ResolveError {
-                                span: Span::default(),
-                                msg: format!(
-                                    "logical operation requires Bool operands, got `{:?}` and `{:?}`",
-                                    l.ty(),
-                                    r.ty()
-                                ),
-                            });
-                        }
-                    }
-                    // сравнение
-                    ast::BinOp::EqEq | ast::BinOp::NotEq => {
-                        match Type::unify(l.ty(), r.ty()) {
-                            Ok(Type::Int) | Ok(Type::Float) | Ok(Type::Bool) | Ok(Type::Str) => Type::Bool,
-                            _ => {
-                                return Err(ResolveError {
-                                    span: Span::default(),
-                                    msg: format!(
-                                        "cannot compare types `{:?}` and `{:?}`",
-                                        l.ty(),
-                                        r.ty()
-                                    ),
-                                });
-                            }
-                        }
-                    }
-                    ast::BinOp::Lt | ast::BinOp::Le | ast::BinOp::Gt | ast::BinOp::Ge => {
-                        match Type::unify(l.ty(), r.ty()) {
-                            Ok(Type::Int) | Ok(Type::Float) => Type::Bool,
-                            _ => {
-                                return Err(ResolveError {
-                                    span: Span::default(),
-                                    msg: format!(
-                                        "cannot order-compare types `{:?}` and `{:?}`",
-                                        l.ty(),
-                                        r.ty()
-                                    ),
-                                });
-                            }
-                        }
-                    }
-                };
-                hir::Expr::Binary {
-                    id,
-                    lhs: Box::new(l),
-                    op: hir::BinOp::from_ast(*op),
-                    rhs: Box::new(r),
-                    ty,
+        self.push_scope();
+        let depth = self.scopes.len() - 1;
+        self.closures.push(ClosureFrame { depth, by_move, ret: ret.clone(), captures: Vec::new() });
+        let loop_depth = std::mem::take(&mut self.loop_depth);
+        let ret_ty = std::mem::replace(&mut self.current_ret_ty, ret.clone());
+        let mut bound = Ok(());
+        for p in &hparams {
+            let sym = Symbol { id: p.id, ty: p.ty.clone(), is_mut: false, value: None, is_pub: false };
+            bound = bound.and_then(|()| self.insert(&p.name, sym, Span::default()));
+        }
+        let body = bound.and_then(|()| self.lower_expr(body));
+        self.loop_depth = loop_depth;
+        self.current_ret_ty = ret_ty;
+        let frame = self.closures.pop().unwrap();
+        self.pop_scope();
+        // `self` metode sa `&self` pripada pozivaocu, a zatvorenje može
+        // nadživeti poziv
+        if !by_move && frame.captures.iter().any(|c| Some(c.id) == self.self_ref) {
+            return Err(ResolveError {
+                span: Span::default(),
+                msg: "closure may outlive the method, but it borrows `self`; use a `move` closure".to_string(),
+            });
+        }
+
+        let body = match body? {
+            hir::Expr::Block { block, .. } => block,
+            e => hir::Block { id: self.fresh(), stmts: vec![hir::Stmt::Expr(e)], spans: Vec::new() },
+        };
+        let mut body = body;
+        let ret = match ret {
+            Some(ret) => {
+                fill_block(&mut body, &ret);
+                let tail_returns = body.tail().is_some_and(|tail| !tail.diverges());
+                if tail_returns && !self.compatible(&ret, &body.ty()) {
+                    return Err(ResolveError {
+                        span: Span::default(),
+                        msg: format!("expected {:?}, got {:?}", ret, body.ty()),
+                    });
                 }
+                ret
             }
-        })
+            None => {
+                check_inferred("the closure's return value", &body.ty())?;
+                body.ty()
+            }
+        };
+        let ty = Type::Fn { params: hparams.iter().map(|p| p.ty.clone()).collect(), ret: Box::new(ret.clone()) };
+        Ok(hir::Expr::Closure { id, params: hparams, ret, body, captures: frame.captures, by_move, ty })
     }
 }
 
@@ -494,6 +3316,7 @@ impl hir::UnOp {
         match op {
             Negate => hir::UnOp::Negate,
             Not => hir::UnOp::Not,
+            BitNot => hir::UnOp::BitNot,
         }
     }
 }
@@ -516,11 +3339,516 @@ impl hir::BinOp {
             Ge => hir::BinOp::Ge,
             AndAnd => hir::BinOp::AndAnd,
             OrOr => hir::BinOp::OrOr,
+            BitAnd => hir::BinOp::BitAnd,
+            BitOr => hir::BinOp::BitOr,
+            BitXor => hir::BinOp::BitXor,
+            Shl => hir::BinOp::Shl,
+            Shr => hir::BinOp::Shr,
         }
     }
 }
 
 /*─────────── tests ──────────────*/
+/*──────────── module helpers ──────────*/
+fn module_name(path: &[String]) -> String {
+    if path.is_empty() { "crate".to_string() } else { path.join("::") }
+}
+
+/// Module named by `segs` as written in module `cur`: `crate::…` starts at
+/// the root, otherwise a submodule of `cur` is preferred over a module of
+/// the same name at the root.
+fn find_module(known: &[Vec<String>], cur: &[String], segs: &[String]) -> Option<Vec<String>> {
+    if segs.first().is_some_and(|s| s == "crate") {
+        let abs = segs[1..].to_vec();
+        return known.contains(&abs).then_some(abs);
+    }
+    let mut rel = cur.to_vec();
+    rel.extend_from_slice(segs);
+    if known.contains(&rel) {
+        return Some(rel);
+    }
+    known.iter().find(|m| m.as_slice() == segs).cloned()
+}
+
+/// Order in which modules are lowered: every module after the modules its
+/// `use` items and qualified paths refer to.
+fn module_order(mods: &[ModuleAst], known: &[Vec<String>]) -> Result<Vec<usize>, FileError> {
+    let deps: Vec<Vec<usize>> = mods
+        .iter()
+        .enumerate()
+        .map(|(i, m)| {
+            let mut paths = Vec::new();
+            for it in &m.ast.items {
+                item_paths(it, &mut paths);
+            }
+            let mut deps = Vec::new();
+            for p in paths {
+                // `a::b::item` refers to module `a::b`, `use a::b;` to `a::b`
+                // and `a::Trait::method` to `a`
+                let module = (0..p.len().min(3))
+                    .find_map(|drop| find_module(known, &m.path, &p[..p.len() - drop]));
+                if let Some(d) = module.and_then(|mp| known.iter().position(|k| *k == mp))
+                    && d != i
+                    && !deps.contains(&d)
+                {
+                    deps.push(d);
+                }
+            }
+            deps
+        })
+        .collect();
+
+    #[derive(Clone, Copy, PartialEq)]
+    enum Mark {
+        New,
+        Active,
+        Done,
+    }
+    fn visit(
+        i: usize,
+        deps: &[Vec<usize>],
+        marks: &mut [Mark],
+        stack: &mut Vec<usize>,
+        order: &mut Vec<usize>,
+    ) -> Result<(), Vec<usize>> {
+        marks[i] = Mark::Active;
+        stack.push(i);
+        for &d in &deps[i] {
+            match marks[d] {
+                Mark::Active => {
+                    let start = stack.iter().position(|&s| s == d).unwrap();
+                    let mut cycle = stack[start..].to_vec();
+                    cycle.push(d);
+                    return Err(cycle);
+                }
+                Mark::New => visit(d, deps, marks, stack, order)?,
+                Mark::Done => {}
+            }
+        }
+        stack.pop();
+        marks[i] = Mark::Done;
+        order.push(i);
+        Ok(())
+    }
+
+    let mut marks = vec![Mark::New; mods.len()];
+    let mut order = Vec::new();
+    for i in 0..mods.len() {
+        if marks[i] == Mark::New
+            && let Err(cycle) = visit(i, &deps, &mut marks, &mut Vec::new(), &mut order)
+        {
+            let names: Vec<String> =
+                cycle.iter().map(|&c| format!("`{}`", module_name(&mods[c].path))).collect();
+            return Err(FileError {
+                file: mods[cycle[0]].file,
+                err: ResolveError {
+                    span: Span::default(),
+                    msg: format!("import cycle: {}", names.join(" -> ")),
+                },
+            });
+        }
+    }
+    Ok(order)
+}
+
+/// Every `use` path and qualified path in an item.
+fn item_paths<'a>(it: &'a ast::Item, out: &mut Vec<&'a [String]>) {
+    match it {
+        ast::Item::Use(p) => out.push(p),
+        ast::Item::Function(f) => fn_paths(f, out),
+        ast::Item::Trait(t) => {
+            for m in &t.methods {
+                m.type_params.iter().flat_map(|tp| &tp.bounds).for_each(|b| out.push(b));
+                let tys = m.params.iter().filter_map(|p| p.ty.as_ref());
+                tys.chain(&m.return_ty).for_each(|t| type_paths(t, out));
+            }
+        }
+        ast::Item::Impl(imp) => {
+            out.extend(imp.trait_path.as_deref());
+            imp.type_params.iter().flat_map(|tp| &tp.bounds).for_each(|b| out.push(b));
+            type_paths(&imp.self_ty, out);
+            imp.methods.iter().for_each(|f| fn_paths(f, out));
+        }
+        ast::Item::Let(g) => expr_paths(&g.expr, out),
+        ast::Item::Const(c) => {
+            type_paths(&c.ty, out);
+            expr_paths(&c.expr, out);
+        }
+        ast::Item::Mod(_) => {}
+    }
+}
+
+fn fn_paths<'a>(f: &'a ast::Function, out: &mut Vec<&'a [String]>) {
+    f.type_params.iter().flat_map(|tp| &tp.bounds).for_each(|b| out.push(b));
+    let tys = f.params.iter().filter_map(|p| p.ty.as_ref());
+    tys.chain(&f.return_ty).for_each(|t| type_paths(t, out));
+    block_paths(&f.body, out);
+}
+
+fn block_paths<'a>(b: &'a ast::Block, out: &mut Vec<&'a [String]>) {
+    for s in &b.stmts {
+        match s {
+            ast::Stmt::Let { expr, .. }
+            | ast::Stmt::LetTuple { expr, .. }
+            | ast::Stmt::Assign { expr, .. }
+            | ast::Stmt::Expr(expr) => expr_paths(expr, out),
+            ast::Stmt::AssignPlace { target, expr } | ast::Stmt::AssignOp { target, expr, .. } => {
+                expr_paths(target, out);
+                expr_paths(expr, out);
+            }
+            ast::Stmt::Return(Some(e)) => expr_paths(e, out),
+            ast::Stmt::Decl { .. } | ast::Stmt::Return(None) | ast::Stmt::Break | ast::Stmt::Continue => {}
+        }
+    }
+    if let Some(t) = &b.tail {
+        expr_paths(t, out);
+    }
+}
+
+fn expr_paths<'a>(e: &'a ast::Expr, out: &mut Vec<&'a [String]>) {
+    use ast::Expr::*;
+    match e {
+        Path(p) => out.push(p),
+        Call { callee, args } | Spawn { callee, args } => {
+            expr_paths(callee, out);
+            args.iter().for_each(|a| expr_paths(a, out));
+        }
+        Binary { lhs, rhs, .. } => {
+            expr_paths(lhs, out);
+            expr_paths(rhs, out);
+        }
+        Unary { expr, .. } | Ref { expr, .. } | Deref(expr) | Try(expr) => expr_paths(expr, out),
+        Block(b) => block_paths(b, out),
+        Match { scrutinee, arms } => {
+            expr_paths(scrutinee, out);
+            arms.iter().for_each(|a| expr_paths(&a.body, out));
+        }
+        If { cond, then_branch, else_branch } => {
+            expr_paths(cond, out);
+            block_paths(then_branch, out);
+            if let Some(e) = else_branch {
+                expr_paths(e, out);
+            }
+        }
+        While { cond, body } => {
+            expr_paths(cond, out);
+            block_paths(body, out);
+        }
+        For { iter, body, .. } => {
+            expr_paths(iter, out);
+            block_paths(body, out);
+        }
+        Range { start, end, .. } => {
+            expr_paths(start, out);
+            expr_paths(end, out);
+        }
+        Array(elems) | Tuple(elems) => elems.iter().for_each(|e| expr_paths(e, out)),
+        Field { base, .. } => expr_paths(base, out),
+        MethodCall { receiver, args, .. } => {
+            expr_paths(receiver, out);
+            args.iter().for_each(|a| expr_paths(a, out));
+        }
+        Repeat { elem, len } => {
+            expr_paths(elem, out);
+            expr_paths(len, out);
+        }
+        Index { base, index, .. } => {
+            expr_paths(base, out);
+            expr_paths(index, out);
+        }
+        Channel(Some(t)) | VecNew(t) => type_paths(t, out),
+        Closure { params, ret, body, .. } => {
+            params.iter().filter_map(|p| p.ty.as_ref()).chain(ret).for_each(|t| type_paths(t, out));
+            expr_paths(body, out);
+        }
+        Ident(_) | Int(_) | Float(_) | TypedInt(..) | TypedFloat(..) | Bool(_) | Unit | Str(_) | Char(_) | ByteStr(_)
+        | Channel(None) => {}
+    }
+}
+
+/// Array lengths in a type annotation may name constants of other modules.
+fn type_paths<'a>(t: &'a ast::TypeExpr, out: &mut Vec<&'a [String]>) {
+    match t {
+        ast::TypeExpr::Named { args, .. } | ast::TypeExpr::Tuple(args) => {
+            args.iter().for_each(|a| type_paths(a, out))
+        }
+        ast::TypeExpr::Array { elem, len } => {
+            type_paths(elem, out);
+            expr_paths(len, out);
+        }
+        ast::TypeExpr::Fn { params, ret } => {
+            params.iter().chain(ret.as_deref()).for_each(|t| type_paths(t, out))
+        }
+        ast::TypeExpr::Ref { inner, .. } => type_paths(inner, out),
+        ast::TypeExpr::Dyn(path) => out.push(path),
+    }
+}
+
+/// Language trait and its method that an operator stands for on types
+/// without a built-in meaning of it, see `Cx::overloaded_op`.
+fn op_trait(op: ast::BinOp) -> Option<(&'static str, &'static str)> {
+    use ast::BinOp::*;
+    Some(match op {
+        Plus => ("Add", "add"),
+        Minus => ("Sub", "sub"),
+        Star => ("Mul", "mul"),
+        Slash => ("Div", "div"),
+        Percent => ("Rem", "rem"),
+        EqEq | NotEq => ("Eq", "eq"),
+        Lt | Le | Gt | Ge => ("Ord", "cmp"),
+        BitAnd => ("BitAnd", "bitand"),
+        BitOr => ("BitOr", "bitor"),
+        BitXor => ("BitXor", "bitxor"),
+        Shl => ("Shl", "shl"),
+        Shr => ("Shr", "shr"),
+        AndAnd | OrOr => return None,
+    })
+}
+
+/// `place` of a compound assignment with each index, and the reference
+/// under a `*`, that isn't a name or a literal replaced by a `$tmp`
+/// temporary `let`-bound in `temps`.
+fn hoist_place(place: &ast::Expr, temps: &mut Vec<ast::Stmt>) -> ast::Expr {
+    use ast::Expr::*;
+    fn temp(e: &ast::Expr, temps: &mut Vec<ast::Stmt>) -> ast::Expr {
+        if matches!(e, Ident(_) | Int(_)) {
+            return e.clone();
+        }
+        let name = format!("$tmp{}", temps.len());
+        temps.push(ast::Stmt::Let { name: name.clone(), ty: None, expr: e.clone(), mutable: false });
+        Ident(name)
+    }
+    match place {
+        Field { base, index } => Field { base: Box::new(hoist_place(base, temps)), index: *index },
+        Index { base, index, span } => {
+            let base = hoist_place(base, temps);
+            Index { base: Box::new(base), index: Box::new(temp(index, temps)), span: *span }
+        }
+        Deref(inner) if matches!(**inner, Field { .. } | Index { .. } | Deref(_)) => Deref(Box::new(hoist_place(inner, temps))),
+        Deref(inner) => Deref(Box::new(temp(inner, temps))),
+        _ => place.clone(),
+    }
+}
+
+/// Type of an `if` or `match` from its two branches. A branch that never
+/// completes doesn't constrain the type; otherwise each branch fills the
+/// unknown parts of the other's type. `what` starts the error message.
+fn join_branches(then_blk: &mut hir::Block, else_e: &mut hir::Expr, what: &str) -> Result<Type, ResolveError> {
+    if then_blk.diverges() {
+        return Ok(else_e.ty().clone());
+    }
+    if else_e.diverges() {
+        return Ok(then_blk.ty());
+    }
+    fill_block(then_blk, else_e.ty());
+    fill_holes(else_e, &then_blk.ty());
+    Type::unify(&then_blk.ty(), else_e.ty()).map_err(|()| ResolveError {
+        span: Span::default(),
+        msg: format!("{what} incompatible types `{:?}` and `{:?}`", then_blk.ty(), else_e.ty()),
+    })
+}
+
+/// Fill the unknown parts (`_`) of the type of `e` from `expected`: `None`
+/// used as an `Option<Int>` gets that type, and so do the blocks and
+/// branches that give `e` its value. What `expected` doesn't know stays
+/// unknown, and a mismatch is left for the caller to report.
+fn fill_holes(e: &mut hir::Expr, expected: &Type) {
+    if !e.ty().has_holes() {
+        return;
+    }
+    let filled = fill_type(e.ty(), expected);
+    match e {
+        hir::Expr::Variant { variant, value, ty, .. } => {
+            let inner = match (&filled, *variant) {
+                (Type::Option(t), _) | (Type::Result(t, _), hir::Variant::Ok) | (Type::Result(_, t), hir::Variant::Err) => t,
+                _ => return,
+            };
+            if let Some(v) = value {
+                fill_holes(v, inner);
+            }
+            *ty = filled;
+        }
+        hir::Expr::Block { block, ty, .. } => {
+            fill_block(block, &filled);
+            *ty = filled;
+        }
+        hir::Expr::If { then_blk, else_branch, ty, .. } => {
+            fill_block(then_blk, &filled);
+            if let Some(e) = else_branch {
+                fill_holes(e, &filled);
+            }
+            *ty = filled;
+        }
+        hir::Expr::Tuple { elems, ty, .. } => {
+            let Type::Tuple(tys) = &filled else { return };
+            for (e, t) in elems.iter_mut().zip(tys) {
+                fill_holes(e, t);
+            }
+            *ty = filled;
+        }
+        _ => {}
+    }
+}
+
+/// `fill_holes` for the tail of a block.
+fn fill_block(b: &mut hir::Block, expected: &Type) {
+    if let Some(hir::Stmt::Expr(tail)) = b.stmts.last_mut() {
+        fill_holes(tail, expected);
+    }
+}
+
+/// `ty` with its `_` parts taken from `expected`.
+fn fill_type(ty: &Type, expected: &Type) -> Type {
+    let fill = |t: &Type, u: &Type| Box::new(fill_type(t, u));
+    match (ty, expected) {
+        (Type::Infer, _) => expected.clone(),
+        (Type::Option(t), Type::Option(u)) => Type::Option(fill(t, u)),
+        (Type::Result(t, e), Type::Result(u, f)) => Type::Result(fill(t, u), fill(e, f)),
+        (Type::Tuple(ts), Type::Tuple(us)) if ts.len() == us.len() => {
+            Type::Tuple(ts.iter().zip(us).map(|(t, u)| fill_type(t, u)).collect())
+        }
+        _ => ty.clone(),
+    }
+}
+
+/// A variable needs a known type; `let x = None;` doesn't give one.
+fn check_inferred(name: &str, ty: &Type) -> Result<(), ResolveError> {
+    if ty.has_holes() {
+        return Err(ResolveError {
+            span: Span::default(),
+            msg: format!("cannot infer the type of `{name}`: its value has type {ty:?}"),
+        });
+    }
+    Ok(())
+}
+
+/// Suffixed literal, or an unsuffixed one given type `n`; `negated` when
+/// it is the operand of `-`, so `-128` fits in an `i8`.
+fn int_literal(id: hir::NodeId, value: i64, n: NumTy, negated: bool) -> Result<hir::Expr, ResolveError> {
+    if n.is_float() {
+        return Ok(hir::Expr::Float { id, value: float_value(value as f64, n), ty: n.ty() });
+    }
+    if !n.fits(if negated { -value } else { value }) {
+        return Err(ResolveError { span: Span::default(), msg: format!("literal out of range for `{}`", n.name()) });
+    }
+    Ok(hir::Expr::Int { id, value, ty: n.ty() })
+}
+
+/// A float literal rounded to the precision of `n`.
+fn float_value(v: f64, n: NumTy) -> f64 {
+    if n == NumTy::F32 { f64::from(v as f32) } else { v }
+}
+
+/// Operators on these types always have their built-in meaning.
+fn is_scalar(t: &Type) -> bool {
+    matches!(t, Type::Int | Type::Float | Type::Num(_) | Type::Bool | Type::Char | Type::Unit)
+}
+
+/// Name of the types `Type::function` can be called on, see `type_head`.
+fn type_head_name(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "Int" | "int" => "Int",
+        "Float" | "float" => "Float",
+        "Bool" | "bool" => "Bool",
+        "Char" | "char" => "Char",
+        "Str" | "String" => "Str",
+        "Vec" => "Vec",
+        "Sender" => "Sender",
+        "Receiver" => "Receiver",
+        "Option" => "Option",
+        "Result" => "Result",
+        name => return type_head(&NumTy::from_name(name)?.ty()),
+    })
+}
+
+/// Name of a type without its type arguments; tuples, arrays and function
+/// types have none.
+fn type_head(ty: &Type) -> Option<&'static str> {
+    Some(match ty {
+        Type::Int => "Int",
+        Type::Float => "Float",
+        Type::Num(n) => n.name(),
+        Type::Bool => "Bool",
+        Type::Char => "Char",
+        Type::Str => "Str",
+        Type::Vec(_) => "Vec",
+        Type::Sender(_) => "Sender",
+        Type::Receiver(_) => "Receiver",
+        Type::Option(_) => "Option",
+        Type::Result(..) => "Result",
+        _ => return None,
+    })
+}
+
+/// Lifetime elision: every reference in a parameter without a lifetime gets
+/// one named after the parameter, and those in the return type get the
+/// lifetime of `&self`, or else of the only lifetime among the parameters.
+fn elide(name: &str, self_mode: Option<ast::SelfMode>, params: &mut [Type], ret: &mut Type) -> Result<(), ResolveError> {
+    let mut inputs = Vec::new();
+    for (i, ty) in params.iter_mut().enumerate() {
+        ty.name_lifetimes(&i.to_string());
+        ty.lifetimes(&mut inputs);
+    }
+    let by_ref = matches!(self_mode, Some(ast::SelfMode::Ref | ast::SelfMode::RefMut));
+    let from = match inputs.as_slice() {
+        _ if by_ref => Ok("0"),
+        [only] => Ok(only.as_str()),
+        [] => Err("it has no reference parameter to borrow from"),
+        _ => Err("it could borrow from more than one parameter; name the lifetime, as in `&'a T`"),
+    };
+    match from {
+        Ok(lifetime) => {
+            ret.name_lifetimes(lifetime);
+            Ok(())
+        }
+        // `name_lifetimes` on a copy only tells if a reference needs one
+        Err(why) if ret.clone().name_lifetimes("_") => Err(ResolveError {
+            span: Span::default(),
+            msg: format!("missing lifetime specifier in the return type of `{name}`: {why}"),
+        }),
+        Err(_) => Ok(()),
+    }
+}
+
+/// A method takes `self` first and has no type parameters of its own.
+fn check_method(name: &str, type_params: &[ast::TypeParam], params: &[ast::Param]) -> Result<(), ResolveError> {
+    if !type_params.is_empty() {
+        return Err(ResolveError {
+            span: Span::default(),
+            msg: format!("method `{name}` cannot have type parameters of its own"),
+        });
+    }
+    if params.first().is_none_or(|p| p.name != "self") {
+        return Err(ResolveError {
+            span: Span::default(),
+            msg: format!("the first parameter of method `{name}` must be `self`"),
+        });
+    }
+    Ok(())
+}
+
+/// Literal standing in for a use of a `const`.
+fn const_expr(id: hir::NodeId, v: &ConstValue, ty: Type) -> hir::Expr {
+    match v {
+        ConstValue::Int(value) => hir::Expr::Int { id, value: *value, ty },
+        ConstValue::Float(value) => hir::Expr::Float { id, value: *value, ty },
+        ConstValue::Bool(value) => hir::Expr::Bool { id, value: *value, ty },
+        ConstValue::Str(value) => hir::Expr::Str { id, value: value.clone(), ty },
+        ConstValue::Unit => hir::Expr::Unit { id, ty },
+        // literal ids are never looked up, so the elements share the id
+        ConstValue::Array(vals) => {
+            let Type::Array(elem, _) = &ty else { unreachable!("array constant of type {ty:?}") };
+            let elems = vals.iter().map(|v| const_expr(id, v, (**elem).clone())).collect();
+            hir::Expr::Array { id, elems, ty }
+        }
+        ConstValue::Tuple(vals) => {
+            let Type::Tuple(tys) = &ty else { unreachable!("tuple constant of type {ty:?}") };
+            let elems = vals.iter().zip(tys).map(|(v, t)| const_expr(id, v, t.clone())).collect();
+            hir::Expr::Tuple { id, elems, ty }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -531,10 +3859,10 @@ mod tests {
         let src = "fn main(){ let b = 1 < 2; }";
         let (hir_mod, errs) = resolve(&Parser::new(src).parse_module());
         assert!(errs.is_empty());
-        if let hir::Item::Fn(f) = &hir_mod.items[0] {
-            if let hir::Stmt::Let(l) = &f.body.stmts[0] {
-                assert_eq!(l.ty, Type::Bool);
-            }
+        if let hir::Item::Fn(f) = &hir_mod.items[0]
+            && let hir::Stmt::Let(l) = &f.body.stmts[0]
+        {
+            assert_eq!(l.ty, Type::Bool);
         }
     }
 
@@ -543,10 +3871,10 @@ mod tests {
         let src = "fn main(){ let b = 1 == 2.0; }";
         let (hir_mod, errs) = resolve(&Parser::new(src).parse_module());
         assert!(errs.is_empty()); // Should now work
-        if let hir::Item::Fn(f) = &hir_mod.items[0] {
-            if let hir::Stmt::Let(l) = &f.body.stmts[0] {
-                assert_eq!(l.ty, Type::Bool);
-            }
+        if let hir::Item::Fn(f) = &hir_mod.items[0]
+            && let hir::Stmt::Let(l) = &f.body.stmts[0]
+        {
+            assert_eq!(l.ty, Type::Bool);
         }
     }
 
@@ -555,10 +3883,10 @@ mod tests {
         let src = "fn main(){ let f = 1 + 2.0; }";
         let (hir_mod, errs) = resolve(&Parser::new(src).parse_module());
         assert!(errs.is_empty());
-        if let hir::Item::Fn(f) = &hir_mod.items[0] {
-            if let hir::Stmt::Let(l) = &f.body.stmts[0] {
-                assert_eq!(l.ty, Type::Float);
-            }
+        if let hir::Item::Fn(f) = &hir_mod.items[0]
+            && let hir::Stmt::Let(l) = &f.body.stmts[0]
+        {
+            assert_eq!(l.ty, Type::Float);
         }
     }
 
@@ -567,10 +3895,10 @@ mod tests {
         let src = "fn main(){ let x = -42; }";
         let (hir_mod, errs) = resolve(&Parser::new(src).parse_module());
         assert!(errs.is_empty());
-        if let hir::Item::Fn(f) = &hir_mod.items[0] {
-            if let hir::Stmt::Let(l) = &f.body.stmts[0] {
-                assert_eq!(l.ty, Type::Int);
-            }
+        if let hir::Item::Fn(f) = &hir_mod.items[0]
+            && let hir::Stmt::Let(l) = &f.body.stmts[0]
+        {
+            assert_eq!(l.ty, Type::Int);
         }
     }
 
@@ -579,19 +3907,31 @@ mod tests {
         let src = "fn main(){ let b = !true; }";
         let (hir_mod, errs) = resolve(&Parser::new(src).parse_module());
         assert!(errs.is_empty());
-        if let hir::Item::Fn(f) = &hir_mod.items[0] {
-            if let hir::Stmt::Let(l) = &f.body.stmts[0] {
-                assert_eq!(l.ty, Type::Bool);
-            }
+        if let hir::Item::Fn(f) = &hir_mod.items[0]
+            && let hir::Stmt::Let(l) = &f.body.stmts[0]
+        {
+            assert_eq!(l.ty, Type::Bool);
         }
     }
 
     #[test]
-    fn duplicate_definition_error() {
+    fn let_shadows_previous_binding() {
         let src = "fn main(){ let x = 1; let x = 2; }";
+        let (hir_mod, errs) = resolve(&Parser::new(src).parse_module());
+        assert!(errs.is_empty());
+        if let hir::Item::Fn(f) = &hir_mod.items[0]
+            && let (hir::Stmt::Let(a), hir::Stmt::Let(b)) = (&f.body.stmts[0], &f.body.stmts[1])
+        {
+            assert_ne!(a.id, b.id);
+        }
+    }
+
+    #[test]
+    fn duplicate_definition_error() {
+        let src = "fn f(){ } fn f(){ }";
         let (_hir, errs) = resolve(&Parser::new(src).parse_module());
         assert_eq!(errs.len(), 1);
-        assert!(errs[0].msg.contains("cannot redeclare"));
+        assert!(errs[0].msg.contains("already defined"));
     }
 
     #[test]
@@ -13,7 +13,7 @@ pub fn compile_and_borrow(src: &str) -> BorrowOutput {
     let mut next_id: u32 = 0;
 
     // dummy block required by BorrowCtx but never used
-    let dummy = hir::Block { id: 0, stmts: vec![] };
+    let dummy = hir::Block { id: 0, stmts: vec![], spans: vec![] };
     let mut cx = BorrowCtx::new(&dummy);
 
     for line in src.lines() {
@@ -28,17 +28,16 @@ pub fn compile_and_borrow(src: &str) -> BorrowOutput {
             let mut_parts: Vec<&str> = rest.splitn(2, '=').collect();
             let left = mut_parts[0].trim();
             let expr = mut_parts.get(1).map(|s| s.trim());
-            let name = if left.starts_with("mut ") {
-                left[4..].trim()
-            } else {
-                left
+            let name = match left.strip_prefix("mut ") {
+                Some(n) => n.trim(),
+                None => left,
             };
             let id = next_id;
             next_id += 1;
             ids.insert(name.to_string(), id);
             if let Some(expr) = expr {
-                if expr.starts_with("&mut ") {
-                    if let Some(&target) = ids.get(expr[5..].trim()) {
+                if let Some(target) = expr.strip_prefix("&mut ") {
+                    if let Some(&target) = ids.get(target.trim()) {
                         cx.borrow_var(target);
                     }
                 } else if let Some(&src_id) = ids.get(expr) {
@@ -46,12 +45,12 @@ pub fn compile_and_borrow(src: &str) -> BorrowOutput {
                 }
             }
             cx.states.insert(id, BorrowState::Live);
-            if expr.map_or(true, |e| !e.starts_with("&mut ")) {
+            if expr.is_none_or(|e| !e.starts_with("&mut ")) {
                 cx.cleanup();
             }
-        } else if line.starts_with('*') {
+        } else if let Some(rest) = line.strip_prefix('*') {
             // use through deref: *y = ...
-            let name = line[1..].split('=').next().unwrap().trim();
+            let name = rest.split('=').next().unwrap().trim();
             if let Some(&id) = ids.get(name) {
                 cx.use_var(id);
             }
@@ -1,20 +1,37 @@
 // type.rs  (извезен као crate::type_)
 
+use std::collections::HashMap;
 use std::fmt;
 
-#[derive(Clone, PartialEq, Eq, Hash)]
+#[derive(Clone)]
 pub enum Type {
     Int,
     Float,
+    Num(NumTy),               // i8, u8, …, f32 – brojevi zadate širine osim `Int` (i32) i `Float` (f64)
     Bool,
+    Char,                     // Unicode skalar, u runtime-u i32
     Str,
     Unit,                     // ()
     Custom(String),           // struct / enum (за 0.1 само име)
+    Sender(Box<Type>),        // крај канала за слање
+    Receiver(Box<Type>),      // крај канала за пријем
+    Array(Box<Type>, usize),  // [T; N]
+    Vec(Box<Type>),           // Vec<T> – niz promenljive dužine u runtime-u
+    Tuple(Vec<Type>),         // (A, B); prazna torka je Unit
+    Fn {                      // fn(A, B) -> R – funkcija ili zatvorenje
+        params: Vec<Type>,
+        ret: Box<Type>,
+    },
+    Param(String),            // T – tip-parametar generičke funkcije
     Ref {                     // &T / &mut T
         mutability: bool,
         inner: Box<Type>,
-        lifetime: Option<String>,
+        lifetime: Option<String>, // `'a`; neimenovan vek `&T` parametra i dobija broj parametra
     },
+    Dyn(String),              // dyn Trait – samo iza reference; puno ime osobine
+    Option(Box<Type>),        // Option<T> – Some(T) ili None
+    Result(Box<Type>, Box<Type>), // Result<T, E> – Ok(T) ili Err(E)
+    Infer,                    // _ – još nepoznat deo tipa `None`, `Ok(x)` ili `Err(e)`
 }
 
 impl fmt::Debug for Type {
@@ -24,21 +41,186 @@ impl fmt::Debug for Type {
             Int   => write!(f, "Int"),
             Float => write!(f, "Float"),
             Bool  => write!(f, "Bool"),
+            Char  => write!(f, "Char"),
             Str   => write!(f, "String"),
             Unit  => write!(f, "()"),
-            Custom(s) => write!(f, "{s}"),
+            Num(n) => write!(f, "{}", n.name()),
+            Custom(s) | Param(s) => write!(f, "{s}"),
+            Dyn(s) => write!(f, "dyn {s}"),
+            Sender(t) => write!(f, "Sender<{t:?}>"),
+            Receiver(t) => write!(f, "Receiver<{t:?}>"),
+            Array(t, n) => write!(f, "[{t:?}; {n}]"),
+            Vec(t) => write!(f, "Vec<{t:?}>"),
+            Option(t) => write!(f, "Option<{t:?}>"),
+            Result(t, e) => write!(f, "Result<{t:?}, {e:?}>"),
+            Infer => write!(f, "_"),
+            Tuple(ts) => {
+                write!(f, "(")?;
+                for (i, t) in ts.iter().enumerate() {
+                    if i > 0 { write!(f, ", ")?; }
+                    write!(f, "{t:?}")?;
+                }
+                if ts.len() == 1 { write!(f, ",")?; }
+                write!(f, ")")
+            }
+            Fn { params, ret } => {
+                write!(f, "fn(")?;
+                for (i, t) in params.iter().enumerate() {
+                    if i > 0 { write!(f, ", ")?; }
+                    write!(f, "{t:?}")?;
+                }
+                write!(f, ")")?;
+                if **ret != Unit { write!(f, " -> {ret:?}")?; }
+                Ok(())
+            }
             Ref { mutability, inner, lifetime } => {
                 write!(f, "&")?;
                 if *mutability { write!(f, "mut ")?; }
-                if let Some(l) = lifetime { write!(f, "'{l} ")?; }
+                if let Some(l) = lifetime.as_ref().filter(|l| !is_elided(l)) { write!(f, "'{l} ")?; }
                 write!(f, "{inner:?}")
             }
         }
     }
 }
 
+/// Životne vekove proverava `region`; za proveru tipova `&'a T` je isto što
+/// i `&T`.
+impl PartialEq for Type {
+    fn eq(&self, other: &Type) -> bool {
+        use Type::*;
+        match (self, other) {
+            (Int, Int) | (Float, Float) | (Bool, Bool) | (Char, Char) | (Str, Str) | (Unit, Unit) | (Infer, Infer) => true,
+            (Num(a), Num(b)) => a == b,
+            (Custom(a), Custom(b)) | (Param(a), Param(b)) | (Dyn(a), Dyn(b)) => a == b,
+            (Sender(a), Sender(b)) | (Receiver(a), Receiver(b)) | (Vec(a), Vec(b)) | (Option(a), Option(b)) => a == b,
+            (Array(a, n), Array(b, m)) => n == m && a == b,
+            (Result(a, x), Result(b, y)) => a == b && x == y,
+            (Tuple(xs), Tuple(ys)) => xs == ys,
+            (Fn { params: xs, ret: a }, Fn { params: ys, ret: b }) => xs == ys && a == b,
+            (Ref { mutability: m, inner: a, .. }, Ref { mutability: n, inner: b, .. }) => m == n && a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Type {}
+
+/// Is `lifetime` one elision gave to an unnamed reference? Such a lifetime
+/// is named after the position of its parameter, `'0`, `'1`, ...
+pub fn is_elided(lifetime: &str) -> bool {
+    lifetime.starts_with(|c: char| c.is_ascii_digit())
+}
+
+/// Numeric type of a given width, named like its literal suffix: `10u8`,
+/// `2.5f32`. `i32` is `Int` and `f64` is `Float`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NumTy {
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+    F32,
+    F64,
+}
+
+impl NumTy {
+    pub const ALL: [NumTy; 10] = {
+        use NumTy::*;
+        [I8, I16, I32, I64, U8, U16, U32, U64, F32, F64]
+    };
+
+    pub fn name(self) -> &'static str {
+        use NumTy::*;
+        match self {
+            I8 => "i8",
+            I16 => "i16",
+            I32 => "i32",
+            I64 => "i64",
+            U8 => "u8",
+            U16 => "u16",
+            U32 => "u32",
+            U64 => "u64",
+            F32 => "f32",
+            F64 => "f64",
+        }
+    }
+
+    /// The type a suffix or a type name such as `u8` stands for.
+    pub fn from_name(name: &str) -> Option<NumTy> {
+        NumTy::ALL.into_iter().find(|n| n.name() == name)
+    }
+
+    pub fn bits(self) -> u32 {
+        use NumTy::*;
+        match self {
+            I8 | U8 => 8,
+            I16 | U16 => 16,
+            I32 | U32 | F32 => 32,
+            I64 | U64 | F64 => 64,
+        }
+    }
+
+    pub fn is_float(self) -> bool {
+        matches!(self, NumTy::F32 | NumTy::F64)
+    }
+
+    pub fn is_signed(self) -> bool {
+        matches!(self, NumTy::I8 | NumTy::I16 | NumTy::I32 | NumTy::I64)
+    }
+
+    pub fn is_unsigned(self) -> bool {
+        matches!(self, NumTy::U8 | NumTy::U16 | NumTy::U32 | NumTy::U64)
+    }
+
+    /// `v` cut to the width of this integer type, as the machine would.
+    pub fn wrap(self, v: i64) -> i64 {
+        let shift = 64 - self.bits();
+        match self {
+            _ if self.is_float() || shift == 0 => v,
+            _ if self.is_signed() => (v << shift) >> shift,
+            _ => ((v as u64) << shift >> shift) as i64,
+        }
+    }
+
+    /// Can an integer of this type hold `v`? Integers are kept in an
+    /// `i64`, a `u64` above `i64::MAX` as its bits, so every `v` fits those
+    /// two.
+    pub fn fits(self, v: i64) -> bool {
+        let bits = self.bits();
+        match self {
+            _ if self.is_float() => true,
+            NumTy::I64 | NumTy::U64 => true,
+            _ if self.is_signed() => (-(1i64 << (bits - 1))..1i64 << (bits - 1)).contains(&v),
+            _ => (0..1i64 << bits).contains(&v),
+        }
+    }
+
+    pub fn ty(self) -> Type {
+        match self {
+            NumTy::I32 => Type::Int,
+            NumTy::F64 => Type::Float,
+            n => Type::Num(n),
+        }
+    }
+}
+
 impl Type {
+    /// `Int`, `Float` or a `Num` as the numeric type it is.
+    pub fn num(&self) -> Option<NumTy> {
+        match self {
+            Type::Int => Some(NumTy::I32),
+            Type::Float => Some(NumTy::F64),
+            Type::Num(n) => Some(*n),
+            _ => None,
+        }
+    }
+
     /// Attempt to unify two types. Int and Float unify to Float.
+    #[allow(clippy::result_unit_err)]
     pub fn unify(a: &Type, b: &Type) -> Result<Type, ()> {
         use Type::*;
         match (a, b) {
@@ -46,14 +228,180 @@ impl Type {
             (Int, Int) => Ok(Int),
             (Float, Float) => Ok(Float),
             (Bool, Bool) => Ok(Bool),
+            (Char, Char) => Ok(Char),
             (Str, Str) => Ok(Str),
             (Unit, Unit) => Ok(Unit),
+            (Num(x), Num(y)) if x == y => Ok(a.clone()),
             (Custom(x), Custom(y)) if x == y => Ok(Custom(x.clone())),
+            (Sender(x), Sender(y)) | (Receiver(x), Receiver(y)) if x == y => Ok(a.clone()),
+            (Array(x, n), Array(y, m)) if x == y && n == m => Ok(a.clone()),
+            (Vec(x), Vec(y)) | (Option(x), Option(y)) if x == y => Ok(a.clone()),
+            (Result(..), Result(..)) if a == b => Ok(a.clone()),
+            (Tuple(x), Tuple(y)) if x == y => Ok(a.clone()),
+            (Fn { .. }, Fn { .. }) if a == b => Ok(a.clone()),
+            (Param(x), Param(y)) if x == y => Ok(a.clone()),
+            (Ref { .. }, Ref { .. }) | (Dyn(_), Dyn(_)) if a == b => Ok(a.clone()),
             _ => Err(()),
         }
     }
 
+    /// `Sender` is copied so that several threads can send to one channel;
+    /// a `Receiver` and a `Vec` have a single owner. Arrays and tuples are
+    /// copied element-wise. A function value is a pointer to code and to
+    /// the closure's environment, and copies share that environment. A type
+    /// parameter may stand for any type, so it is moved. A shared reference
+    /// is copied, a `&mut` one is unique. `Option` and `Result` are copied
+    /// when everything they may hold is.
     pub fn is_copy(&self) -> bool {
-        matches!(self, Type::Int | Type::Float | Type::Bool | Type::Unit)
+        match self {
+            Type::Array(elem, _) | Type::Option(elem) => elem.is_copy(),
+            Type::Result(ok, err) => ok.is_copy() && err.is_copy(),
+            Type::Tuple(elems) => elems.iter().all(Type::is_copy),
+            Type::Ref { mutability, .. } => !mutability,
+            t => matches!(t, Type::Int | Type::Float | Type::Num(_) | Type::Bool | Type::Char | Type::Unit | Type::Sender(_) | Type::Fn { .. }),
+        }
+    }
+
+    /// Replace type parameters by the types `map` gives them.
+    pub fn subst(&self, map: &HashMap<String, Type>) -> Type {
+        use Type::*;
+        let sub = |t: &Type| Box::new(t.subst(map));
+        match self {
+            Param(name) => map.get(name).cloned().unwrap_or_else(|| self.clone()),
+            Sender(t) => Sender(sub(t)),
+            Receiver(t) => Receiver(sub(t)),
+            Array(t, n) => Array(sub(t), *n),
+            Vec(t) => Vec(sub(t)),
+            Option(t) => Option(sub(t)),
+            Result(t, e) => Result(sub(t), sub(e)),
+            Tuple(ts) => Tuple(ts.iter().map(|t| t.subst(map)).collect()),
+            Fn { params, ret } => Fn { params: params.iter().map(|t| t.subst(map)).collect(), ret: sub(ret) },
+            Ref { mutability, inner, lifetime } => {
+                Ref { mutability: *mutability, inner: sub(inner), lifetime: lifetime.clone() }
+            }
+            Int | Float | Num(_) | Bool | Char | Str | Unit | Custom(_) | Dyn(_) | Infer => self.clone(),
+        }
+    }
+
+    /// Every lifetime named in the type, outside of `fn(..)` types, whose
+    /// references are not tied to anything around them.
+    pub fn lifetimes(&self, out: &mut Vec<String>) {
+        use Type::*;
+        match self {
+            Ref { inner, lifetime, .. } => {
+                if let Some(l) = lifetime && !out.contains(l) {
+                    out.push(l.clone());
+                }
+                inner.lifetimes(out)
+            }
+            Sender(t) | Receiver(t) | Array(t, _) | Vec(t) | Option(t) => t.lifetimes(out),
+            Result(t, e) => {
+                t.lifetimes(out);
+                e.lifetimes(out)
+            }
+            Tuple(ts) => ts.iter().for_each(|t| t.lifetimes(out)),
+            Int | Float | Num(_) | Bool | Char | Str | Unit | Custom(_) | Dyn(_) | Param(_) | Infer | Fn { .. } => {}
+        }
+    }
+
+    /// Give every reference without a lifetime, outside of `fn(..)` types,
+    /// the lifetime `name`. Returns whether there was such a reference.
+    pub fn name_lifetimes(&mut self, name: &str) -> bool {
+        use Type::*;
+        match self {
+            Ref { inner, lifetime, .. } => {
+                let unnamed = lifetime.is_none();
+                lifetime.get_or_insert_with(|| name.to_string());
+                inner.name_lifetimes(name) | unnamed
+            }
+            Sender(t) | Receiver(t) | Array(t, _) | Vec(t) | Option(t) => t.name_lifetimes(name),
+            Result(t, e) => t.name_lifetimes(name) | e.name_lifetimes(name),
+            Tuple(ts) => ts.iter_mut().fold(false, |any, t| t.name_lifetimes(name) | any),
+            Int | Float | Num(_) | Bool | Char | Str | Unit | Custom(_) | Dyn(_) | Param(_) | Infer | Fn { .. } => false,
+        }
+    }
+
+    /// Does a value of the type hold a reference, if only inside a tuple or
+    /// a `Vec`?
+    pub fn holds_ref(&self) -> bool {
+        use Type::*;
+        match self {
+            Ref { .. } | Dyn(_) => true,
+            Array(t, _) | Vec(t) | Option(t) => t.holds_ref(),
+            Result(t, e) => t.holds_ref() || e.holds_ref(),
+            Tuple(ts) => ts.iter().any(Type::holds_ref),
+            _ => false,
+        }
+    }
+
+    /// Does the type parameter `name` occur in the type?
+    pub fn mentions(&self, name: &str) -> bool {
+        use Type::*;
+        match self {
+            Param(p) => p == name,
+            Sender(t) | Receiver(t) | Array(t, _) | Vec(t) | Option(t) | Ref { inner: t, .. } => t.mentions(name),
+            Result(t, e) => t.mentions(name) || e.mentions(name),
+            Tuple(ts) => ts.iter().any(|t| t.mentions(name)),
+            Fn { params, ret } => params.iter().any(|t| t.mentions(name)) || ret.mentions(name),
+            Int | Float | Num(_) | Bool | Char | Str | Unit | Custom(_) | Dyn(_) | Infer => false,
+        }
+    }
+
+    /// Is some part of the type still unknown (`_`)?
+    pub fn has_holes(&self) -> bool {
+        use Type::*;
+        match self {
+            Infer => true,
+            Sender(t) | Receiver(t) | Array(t, _) | Vec(t) | Option(t) | Ref { inner: t, .. } => t.has_holes(),
+            Result(t, e) => t.has_holes() || e.has_holes(),
+            Tuple(ts) => ts.iter().any(Type::has_holes),
+            Fn { params, ret } => params.iter().any(Type::has_holes) || ret.has_holes(),
+            Int | Float | Num(_) | Bool | Char | Str | Unit | Custom(_) | Dyn(_) | Param(_) => false,
+        }
+    }
+
+    /// Match `self`, in which type parameters stand for any type, against
+    /// `ty`; `map` records the type each parameter stands for, and a
+    /// parameter used twice must stand for the same type both times.
+    pub fn matches(&self, ty: &Type, map: &mut HashMap<String, Type>) -> bool {
+        use Type::*;
+        match (self, ty) {
+            (Param(p), _) => match map.get(p) {
+                Some(t) => t == ty,
+                None => {
+                    map.insert(p.clone(), ty.clone());
+                    true
+                }
+            },
+            (Sender(a), Sender(b)) | (Receiver(a), Receiver(b)) | (Vec(a), Vec(b)) | (Option(a), Option(b)) => {
+                a.matches(b, map)
+            }
+            (Result(a, x), Result(b, y)) => a.matches(b, map) && x.matches(y, map),
+            (Ref { mutability: m, inner: a, .. }, Ref { mutability: n, inner: b, .. }) => m == n && a.matches(b, map),
+            (Array(a, n), Array(b, m)) => n == m && a.matches(b, map),
+            (Tuple(xs), Tuple(ys)) => xs.len() == ys.len() && xs.iter().zip(ys).all(|(x, y)| x.matches(y, map)),
+            (Fn { params: xs, ret: a }, Fn { params: ys, ret: b }) => {
+                xs.len() == ys.len() && xs.iter().zip(ys).all(|(x, y)| x.matches(y, map)) && a.matches(b, map)
+            }
+            _ => self == ty,
+        }
+    }
+
+    /// Could some type match both `self` and `other`? A type parameter is
+    /// taken to match anything, even when it is used twice.
+    pub fn overlaps(&self, other: &Type) -> bool {
+        use Type::*;
+        match (self, other) {
+            (Param(_), _) | (_, Param(_)) => true,
+            (Sender(a), Sender(b)) | (Receiver(a), Receiver(b)) | (Vec(a), Vec(b)) | (Option(a), Option(b)) => a.overlaps(b),
+            (Result(a, x), Result(b, y)) => a.overlaps(b) && x.overlaps(y),
+            (Ref { mutability: m, inner: a, .. }, Ref { mutability: n, inner: b, .. }) => m == n && a.overlaps(b),
+            (Array(a, n), Array(b, m)) => n == m && a.overlaps(b),
+            (Tuple(xs), Tuple(ys)) => xs.len() == ys.len() && xs.iter().zip(ys).all(|(x, y)| x.overlaps(y)),
+            (Fn { params: xs, ret: a }, Fn { params: ys, ret: b }) => {
+                xs.len() == ys.len() && xs.iter().zip(ys).all(|(x, y)| x.overlaps(y)) && a.overlaps(b)
+            }
+            _ => self == other,
+        }
     }
 }
@@ -26,6 +26,16 @@ fn gen_constraints(expr: &ast::Expr, cx: &mut InferCtx) -> TvOrTy {
             });
             tv
         }
+        TypedInt(_, n) | TypedFloat(_, n) => {
+            let tv = cx.fresh(Span::default());
+            cx.constraints.push_back(Constraint {
+                left: tv.clone(),
+                right: TvOrTy::Ty(Ty::from_type(&n.ty(), &Default::default())),
+                left_span: Span::default(),
+                right_span: Span::default(),
+            });
+            tv
+        }
         Bool(_) => {
             let tv = cx.fresh(Span::default());
             cx.constraints.push_back(Constraint {
@@ -46,7 +56,18 @@ fn gen_constraints(expr: &ast::Expr, cx: &mut InferCtx) -> TvOrTy {
             });
             tv
         }
-        Unit => {
+        Char(_) => {
+            let tv = cx.fresh(Span::default());
+            cx.constraints.push_back(Constraint {
+                left: tv.clone(),
+                right: TvOrTy::Ty(Ty::Char),
+                left_span: Span::default(),
+                right_span: Span::default(),
+            });
+            tv
+        }
+        Block(ast::Block { tail: Some(e), .. }) => gen_constraints(e, cx),
+        Unit | Block(_) => {
             let tv = cx.fresh(Span::default());
             cx.constraints.push_back(Constraint {
                 left: tv.clone(),
@@ -88,7 +109,11 @@ fn gen_constraints(expr: &ast::Expr, cx: &mut InferCtx) -> TvOrTy {
             res
         }
         Unary { expr, .. } => gen_constraints(expr, cx),
-        Ident(_) | Call { .. } => cx.fresh(Span::default()),
+        If { then_branch, .. } => gen_constraints(&Block(then_branch.clone()), cx),
+        While { .. } | For { .. } | Spawn { .. } => gen_constraints(&Unit, cx),
+        Ident(_) | Path(_) | Call { .. } | Channel(_) | VecNew(_) | Range { .. }
+        | Array(_) | ByteStr(_) | Repeat { .. } | Index { .. } | Tuple(_) | Field { .. } | MethodCall { .. } | Closure { .. }
+        | Ref { .. } | Deref(_) | Match { .. } | Try(_) => cx.fresh(Span::default()),
     }
 }
 
@@ -0,0 +1,151 @@
+use aethc_core::{
+    ast,
+    codegen::{LlvmCtx, codegen_module},
+    const_eval::ConstValue,
+    hir, mir,
+    parser::Parser,
+    type_::Type,
+};
+
+mod common;
+
+use common::{resolve_err, resolve_ok};
+
+fn let_types(m: &hir::HirModule) -> Vec<Type> {
+    let Some(hir::Item::Fn(f)) = m.items.last() else { panic!("expected fn") };
+    f.body
+        .stmts
+        .iter()
+        .filter_map(|s| match s {
+            hir::Stmt::Let(l) => Some(l.ty.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn arr(elem: Type, n: usize) -> Type {
+    Type::Array(Box::new(elem), n)
+}
+
+#[test]
+fn parses_array_forms() {
+    let m = Parser::new("fn f(a: [[Int; 2]; N]) { let b = [1, 2,]; let c = [0; 8]; a[0][1] = b[1]; }")
+        .parse_module();
+    let ast::Item::Function(f) = &m.items[0] else { panic!("expected fn") };
+    let Some(ast::TypeExpr::Array { elem, .. }) = &f.params[0].ty else { panic!("expected array type") };
+    assert!(matches!(**elem, ast::TypeExpr::Array { .. }));
+    assert!(matches!(&f.body.stmts[0], ast::Stmt::Let { expr: ast::Expr::Array(e), .. } if e.len() == 2));
+    assert!(matches!(&f.body.stmts[1], ast::Stmt::Let { expr: ast::Expr::Repeat { .. }, .. }));
+    let ast::Stmt::AssignPlace { target, .. } = &f.body.stmts[2] else { panic!("expected index assignment") };
+    let ast::Expr::Index { base, span, .. } = target else { panic!("expected index") };
+    assert!(matches!(**base, ast::Expr::Index { .. }));
+    assert_eq!((span.line, span.column), (1, 63));
+}
+
+#[test]
+fn array_types() {
+    let m = resolve_ok(
+        "const N: Int = 2 + 1;
+         fn main() { let a = [1, 2, 3]; let b = [[0.5; N]; 2]; let c = [1, 2.5]; let x = b[1][0]; }",
+    );
+    assert_eq!(
+        let_types(&m),
+        [arr(Type::Int, 3), arr(arr(Type::Float, 3), 2), arr(Type::Float, 2), Type::Float]
+    );
+    resolve_ok("const N: Int = 3; fn sum(xs: [Int; N]) -> Int { xs[0] } fn main() { print(sum([1, 2, 3])); }");
+}
+
+#[test]
+fn array_errors() {
+    resolve_err("fn main() { let a = []; }", "cannot infer the element type of an empty array");
+    resolve_err("fn main() { let a = [1, true]; }", "array elements must have the same type");
+    resolve_err("fn main() { let n = 3; let a = [0; n]; }", "array length must be a constant");
+    resolve_err("fn f(a: [Int; 0 - 1]) { }", "array length must not be negative, got -1");
+    resolve_err("fn main() { let x = 1; print(x[0]); }", "cannot index into a value of type Int");
+    resolve_err("fn main() { let a = [1]; print(a[true]); }", "array index must be Int, got Bool");
+    resolve_err("fn main() { let a = [1, 2]; print(a[2]); }", "the len is 2 but the index is 2");
+    resolve_err("fn main() { let a = [1, 2]; a[0] = 3; }", "cannot assign to an element of immutable binding `a`");
+    resolve_err("fn main() { let mut a = [1, 2]; a[0] = true; }", "expected Int, got Bool");
+    resolve_err("fn f(a: [Int; 2]) { } fn main() { f([1, 2, 3]); }", "expected [Int; 2], got [Int; 3]");
+    resolve_err("fn main() { let (tx, rx) = channel::<[Int; 2]>(); }", "a channel cannot carry an array");
+}
+
+#[test]
+fn arrays_in_constants() {
+    let m = resolve_ok(
+        "const PRIMES: [Int; 4] = [2, 3, 5, 7];
+         fn fill() -> Int { let mut a = [0; 4]; for i in 0..4 { a[i] = PRIMES[3 - i]; } a[0] * a[3] }
+         const P: Int = fill();
+         let GLOBAL = [PRIMES[1]; 2];
+         fn main() { print(P); }",
+    );
+    let consts: Vec<_> = m
+        .items
+        .iter()
+        .filter_map(|it| match it {
+            hir::Item::Const(c) => Some(c.value.clone()),
+            _ => None,
+        })
+        .collect();
+    let ints = |v: &[i64]| ConstValue::Array(v.iter().map(|&i| ConstValue::Int(i)).collect());
+    assert_eq!(consts, [ints(&[2, 3, 5, 7]), ConstValue::Int(14)]);
+    assert!(m.global_values.values().any(|v| *v == ints(&[3, 3])));
+
+    resolve_err("const A: [Int; 2] = [1, 2]; const B: Int = A[1 + 2];", "the len is 2 but the index is 3");
+    resolve_err(
+        "fn get(i: Int) -> Int { let a = [1, 2]; a[i] } const B: Int = get(5);",
+        "evaluation of constant `B` failed: index out of bounds",
+    );
+}
+
+#[test]
+fn indexing_is_bounds_checked() {
+    let m = resolve_ok("fn main() { let mut a = [1, 2, 3]; let i = 1; a[i] = a[i + 1]; }");
+    let mir_mod = mir::lower_module(&m);
+    let stmts = &mir_mod.fns[0].body.blocks[0].stmts;
+    let checks = stmts
+        .iter()
+        .filter(|s| matches!(s, mir::Statement::BoundsCheck { len: mir::Operand::Const(mir::Constant::Int(3)), .. }))
+        .count();
+    assert_eq!(checks, 2, "{stmts:?}");
+    // provera dolazi pre upisa
+    let store = stmts.iter().position(|s| matches!(s, mir::Statement::AssignPlace { .. }));
+    let last_check = stmts.iter().rposition(|s| matches!(s, mir::Statement::BoundsCheck { .. }));
+    assert!(last_check < store);
+}
+
+#[test]
+fn arrays_lower_to_llvm_arrays() {
+    let m = resolve_ok(
+        "let mut grid = [[0; 3]; 2];
+         fn sum(xs: [Int; 4]) -> Int { let mut s = 0; for i in 0..4 { s = s + xs[i]; } s }
+         fn main() { grid[1][2] = sum([1, 2, 3, 4]); print(grid[1][2]); }",
+    );
+    let mut llcx = LlvmCtx::new("test");
+    codegen_module(&mut llcx, &mir::lower_module(&m));
+    llcx.module.verify().unwrap_or_else(|e| panic!("{}", e.to_string()));
+    let ir = llcx.module.print_to_string().to_string();
+    assert!(ir.contains("@grid = global [2 x [3 x i32]] zeroinitializer"), "{ir}");
+    assert!(ir.contains("define i32 @sum([4 x i32]"), "{ir}");
+    assert!(ir.contains("call void @aethc_panic_bounds("), "{ir}");
+}
+
+#[test]
+fn repeat_arrays_are_filled_in_place() {
+    // veliki `[x; N]` se ne gradi element po element
+    let m = resolve_ok(
+        "fn main(n: Int) {
+             let z = [0; 100000]; let k = [7; 100000]; let d = [n; 100000]; let c = d;
+             print(z[1] + k[2] + c[3]);
+         }",
+    );
+    let mut llcx = LlvmCtx::new("test");
+    codegen_module(&mut llcx, &mir::lower_module(&m));
+    llcx.module.verify().unwrap_or_else(|e| panic!("{}", e.to_string()));
+    let ir = llcx.module.print_to_string().to_string();
+    assert!(!ir.contains("insertvalue") && !ir.contains("store [100000 x i32]"), "{ir}");
+    assert!(ir.contains("call void @llvm.memset"), "{ir}");
+    assert!(ir.contains("private constant [100000 x i32] [i32 7"), "{ir}");
+    assert!(ir.contains("fill:"), "{ir}");
+    assert!(ir.contains("call void @llvm.memcpy"), "{ir}");
+}
@@ -0,0 +1,61 @@
+use aethc_core::{borrowck::borrow_check, hir, parser::Parser, resolver::resolve, type_::Type};
+
+fn assert_ok(src: &str) -> hir::HirModule {
+    let (hir_mod, errs) = resolve(&Parser::new(src).parse_module());
+    assert!(errs.is_empty(), "expected ok, got {errs:?}");
+    assert!(borrow_check(&hir_mod).is_empty());
+    hir_mod
+}
+
+fn assert_err(src: &str, msg: &str) {
+    let (_hir, errs) = resolve(&Parser::new(src).parse_module());
+    assert!(errs.iter().any(|e| e.msg.contains(msg)), "expected `{msg}`, got {errs:?}");
+}
+
+#[test]
+fn inner_shadow_ends_with_block() {
+    let hir_mod = assert_ok(
+        r#"fn main() {
+            let x = 1;
+            { let x = "inner"; print(x); }
+            let y = x + 1;
+        }"#,
+    );
+    if let hir::Item::Fn(f) = &hir_mod.items[0]
+        && let hir::Stmt::Let(y) = &f.body.stmts[2]
+    {
+        assert_eq!(y.ty, Type::Int);
+    }
+}
+
+#[test]
+fn inner_binding_not_visible_outside() {
+    assert_err("fn main() { { let y = 1; } let z = y; }", "unknown name `y`");
+}
+
+#[test]
+fn let_initializer_sees_previous_binding() {
+    assert_ok("fn main() { let x = 1; let x = x + 1; { let x = x * 2; } }");
+}
+
+#[test]
+fn assign_outer_mut_from_inner_block() {
+    assert_ok("fn main() { let mut n = 1; { n = 2; }; { { n = 3; } } }");
+}
+
+#[test]
+fn shadow_keeps_its_own_mutability() {
+    assert_err("fn main() { let mut x = 1; { let x = 2; x = 3; } }", "cannot reassign");
+    assert_ok("fn main() { let x = 1; { let mut x = x; x = 3; } }");
+}
+
+#[test]
+fn block_as_expression_is_unit() {
+    let hir_mod = assert_ok("fn main() { let u = { let a = 1; }; }");
+    if let hir::Item::Fn(f) = &hir_mod.items[0]
+        && let hir::Stmt::Let(u) = &f.body.stmts[0]
+    {
+        assert_eq!(u.ty, Type::Unit);
+        assert!(matches!(u.init, hir::Expr::Block { .. }));
+    }
+}
@@ -0,0 +1,77 @@
+use aethc_core::{
+    ast, cfg, hir,
+    mir::{self, Operand, RET_TEMP, Rvalue, Statement},
+    parser::Parser,
+    type_::Type,
+};
+
+mod common;
+
+use common::{resolve_err, resolve_ok};
+
+#[test]
+fn parse_tail_expression() {
+    let module = Parser::new("fn sq(x: Int) -> Int { x * x }").parse_module();
+    if let ast::Item::Function(f) = &module.items[0] {
+        assert!(f.body.stmts.is_empty());
+        assert!(matches!(f.body.tail.as_deref(), Some(ast::Expr::Binary { .. })));
+    } else {
+        panic!("expected function");
+    }
+}
+
+#[test]
+fn semicolon_discards_value() {
+    let module = Parser::new("fn f() { 1; { 2 } 3; }").parse_module();
+    if let ast::Item::Function(f) = &module.items[0] {
+        assert_eq!(f.body.stmts.len(), 3);
+        assert!(f.body.tail.is_none());
+    }
+}
+
+#[test]
+fn tail_is_returned() {
+    resolve_ok("fn sq(x: Int) -> Int { x * x }");
+    resolve_ok("fn half(x: Int) -> Float { let h = x / 2; h }");
+    resolve_ok("fn one() -> Int { { 1 } }");
+}
+
+#[test]
+fn tail_type_mismatch() {
+    resolve_err("fn f() -> Int { true }", "expected Int, got Bool");
+    resolve_err("fn f() -> Int { 2.5 }", "expected Int, got Float");
+    resolve_err("fn f() { 1 }", "expected (), got Int");
+}
+
+#[test]
+fn missing_tail_is_unit() {
+    let errs = cfg::check_module(&resolve_ok("fn f() -> Int { 1; }")).errors;
+    assert!(errs[0].msg.contains("expected Int, got ()"), "{errs:?}");
+    resolve_ok("fn f() -> Int { return 1; }");
+    resolve_ok("fn f() -> Int { { return 1; } }");
+}
+
+#[test]
+fn block_expression_value() {
+    let hir_mod = resolve_ok("fn main() { let y = { let a = 2; a * 3 }; let z = y + 1; }");
+    if let hir::Item::Fn(f) = &hir_mod.items[0]
+        && let hir::Stmt::Let(y) = &f.body.stmts[0]
+    {
+        assert_eq!(y.ty, Type::Int);
+    } else {
+        panic!("expected let");
+    }
+}
+
+#[test]
+fn tail_lowered_to_return_value() {
+    let hir_mod = resolve_ok("fn sq(x: Int) -> Int { let y = x * x; y }");
+    if let hir::Item::Fn(f) = &hir_mod.items[0] {
+        let body = mir::lower_fn(f);
+        let ret = body.blocks[0].stmts.iter().find_map(|s| match s {
+            Statement::Assign { dst: RET_TEMP, rv: Rvalue::Use(op) } => Some(op.clone()),
+            _ => None,
+        });
+        assert!(matches!(ret, Some(Operand::Var(_))), "got {:?}", body.blocks[0].stmts);
+    }
+}
@@ -13,11 +13,11 @@ fn ok() {
 
 #[test]
 fn bad() {
-    let src = r#"fn main(){ let x = 1; let x = 2; }"#;
+    let src = r#"fn main(){ let x = 1; x = 2; }"#;
     let (hir_mod, res_errs) = resolve(&Parser::new(src).parse_module());
     assert_eq!(res_errs.len(), 1);
-    assert!(res_errs[0].msg.contains("cannot redeclare"));
-    if let Some(aethc_core::hir::Item::Fn(f)) = hir_mod.items.get(0) {
+    assert!(res_errs[0].msg.contains("cannot reassign"));
+    if let Some(aethc_core::hir::Item::Fn(f)) = hir_mod.items.first() {
         let errs = check_fn_body(&f.body);
         assert!(errs.is_empty());
     }
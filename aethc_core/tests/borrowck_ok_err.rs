@@ -5,19 +5,35 @@ fn borrowck_detects_reassignment() {
     let src = r#"
         fn main() {
             let x = 1;
-            let x = 2; // Error: first x was immutable
+            let x = 2; // OK: shadows the first x
             let mut y = 3;
-            let y = 4; // OK: first y was mutable
+            let y = 4; // OK: the new y is immutable
+            y = 5;     // Error: assigns to the immutable shadow
         }
     "#;
     let module = Parser::new(src).parse_module();
     let (hir_mod, res_errs) = resolve(&module);
 
-    // Resolver should report a duplicate-name error
+    // Resolver should report the assignment to the immutable shadow
     assert_eq!(res_errs.len(), 1);
-    assert!(res_errs[0].msg.contains("cannot redeclare"));
+    assert!(res_errs[0].msg.contains("cannot reassign"));
 
     // Borrow-checker sees no errors when resolve already failed
     let bc_errs = borrow_check(&hir_mod);
     assert!(bc_errs.is_empty());
 }
+
+#[test]
+fn borrowck_accepts_shadowing() {
+    let src = r#"
+        fn main() {
+            let x = 1;
+            let mut x = x;
+            x = 2;
+        }
+    "#;
+    let module = Parser::new(src).parse_module();
+    let (hir_mod, res_errs) = resolve(&module);
+    assert!(res_errs.is_empty(), "resolve errs: {res_errs:?}");
+    assert!(borrow_check(&hir_mod).is_empty());
+}
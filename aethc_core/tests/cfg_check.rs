@@ -0,0 +1,101 @@
+use aethc_core::{
+    cfg::{self, CfgDiagnostics},
+    hir,
+    mir::{self, Terminator},
+};
+
+mod common;
+
+use common::{resolve_err, resolve_ok};
+
+fn check(src: &str) -> CfgDiagnostics {
+    cfg::check_module(&resolve_ok(src))
+}
+
+#[test]
+fn if_else_chain_resolves() {
+    resolve_ok("fn sign(x: Int) -> Int { if x < 0 { -1 } else if x == 0 { 0 } else { 1 } }");
+    resolve_ok("fn f(x: Int) { if x > 1 { print(x); } }");
+    resolve_ok("fn f() -> Int { let mut i = 0; while i < 10 { i = i + 1; } i }");
+}
+
+#[test]
+fn if_and_while_type_errors() {
+    resolve_err("fn f() { if 1 { } }", "`if` condition must be Bool");
+    resolve_err("fn f() -> Int { if true { 1 } }", "`if` without `else`");
+    resolve_err("fn f() -> Int { if true { 1 } else { false } }", "incompatible types");
+    resolve_err("fn f() { while true { 1 } }", "`while` body must have type ()");
+    resolve_err("fn f() { break; }", "`break` outside of a loop");
+}
+
+#[test]
+fn while_lowers_to_loop() {
+    let hir_mod = resolve_ok("fn f() { let mut i = 0; while i < 3 { i = i + 1; } }");
+    if let hir::Item::Fn(f) = &hir_mod.items[0] {
+        let body = mir::lower_fn(f);
+        // bb0 → head → body → head, head → exit
+        assert!(matches!(body.blocks[0].term, Terminator::Goto(1)));
+        assert!(matches!(body.blocks[1].term, Terminator::CondBranch { then_bb: 2, else_bb: 3, .. }));
+        assert!(matches!(body.blocks[2].term, Terminator::Goto(1)));
+    } else {
+        panic!("expected function");
+    }
+}
+
+#[test]
+fn all_paths_return() {
+    let d = check("fn f(x: Int) -> Int { if x > 0 { return 1; } else { return 2; } }");
+    assert!(d.errors.is_empty() && d.warnings.is_empty(), "{:?} {:?}", d.errors, d.warnings);
+    let d = check("fn f(x: Int) -> Int { if x > 0 { return 1; } 2 }");
+    assert!(d.errors.is_empty(), "{:?}", d.errors);
+    let d = check("fn f() { let mut i = 0; while true { if i > 3 { break; } i = i + 1; } }");
+    assert!(d.errors.is_empty() && d.warnings.is_empty());
+}
+
+#[test]
+fn missing_return_on_some_path() {
+    let d = check("fn f(x: Int) -> Int { if x > 0 { return 1; } print(x); }");
+    assert_eq!(d.errors.len(), 1);
+    assert!(d.errors[0].msg.contains("`f` may reach its end"));
+    let d = check("fn g(x: Int) -> Int { while x > 0 { return 1; } print(x); }");
+    assert_eq!(d.errors.len(), 1);
+}
+
+#[test]
+fn unreachable_after_return_and_break() {
+    let d = check("fn f() -> Int { return 1; let x = 2; }");
+    assert!(d.errors.is_empty());
+    assert_eq!(d.warnings.len(), 1);
+    assert!(d.warnings[0].msg.contains("unreachable statement in function `f`"));
+
+    let d = check("fn g() { while true { break; print(1); } }");
+    assert_eq!(d.warnings.len(), 1);
+}
+
+#[test]
+fn unreachable_tails_after_return() {
+    // rep bez naredbi u MIR-u je i dalje nedostižan
+    let at = |d: &CfgDiagnostics| d.warnings.iter().map(|e| (e.span.line, e.span.column)).collect::<Vec<_>>();
+    let d = check("fn f() -> Int {\n    return 1;\n    2\n}");
+    assert!(d.errors.is_empty(), "{:?}", d.errors);
+    assert_eq!(at(&d), [(3, 5)]);
+    let d = check("fn f(x: Int, y: Int) -> Int {\n    return x;\n    y\n}");
+    assert_eq!(at(&d), [(3, 5)]);
+    let d = check("fn f(c: Bool) -> Int {\n    if c { return 1; } else { return 2; }\n    3\n}");
+    assert_eq!(at(&d), [(3, 5)]);
+    let d = check("fn f(x: Int) -> Int { let y = { return x; 1 }; y }");
+    assert_eq!(at(&d), [(1, 43)]);
+}
+
+#[test]
+fn diagnostics_point_at_the_function() {
+    let at = |e: &aethc_core::resolver::ResolveError| (e.span.line, e.span.column, e.span.end - e.span.start);
+    let d = check("fn f() {}\nfn long(x: Int) -> Int { if x > 0 { return 1; } print(x); }");
+    assert_eq!(d.errors.iter().map(at).collect::<Vec<_>>(), [(2, 4, 4)]);
+    // nedostižan kod se javlja na prvoj naredbi posle `return`
+    let d = check("fn f() -> Int {\n    return 1; let x = 2;\n}");
+    assert_eq!(d.warnings.iter().map(at).collect::<Vec<_>>(), [(2, 15, 3)]);
+    // zatvorenje nema ime, pa se greška vezuje za funkciju u kojoj je
+    let d = check("fn main() {\n    let g = || { let x: Int; print(x); };\n}");
+    assert_eq!(d.errors.iter().map(at).collect::<Vec<_>>(), [(1, 4, 4)]);
+}
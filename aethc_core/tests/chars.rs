@@ -0,0 +1,135 @@
+use aethc_core::{
+    LexError,
+    codegen::{LlvmCtx, codegen_module},
+    const_eval::ConstValue,
+    hir,
+    lexer::{Lexer, TokenKind},
+    mir,
+    parser::Parser,
+    type_::{NumTy, Type},
+};
+
+mod common;
+
+use common::{resolve_err, resolve_ok};
+
+fn kinds(src: &str) -> Vec<TokenKind> {
+    let mut lex = Lexer::new(src);
+    std::iter::from_fn(|| Some(lex.next_token().kind)).take_while(|k| *k != TokenKind::Eof).collect()
+}
+
+/// The first error the lexer reports in `src`.
+fn lex_err(src: &str) -> LexError {
+    let (_ast, errs) = Parser::new(src).parse();
+    errs.into_iter().next().expect("expected a lexer error")
+}
+
+/// Type of the value of `let <name>` in the first function.
+fn let_ty(m: &hir::HirModule, name: &str) -> Type {
+    let hir::Item::Fn(f) = &m.items[0] else { panic!("expected fn") };
+    f.body
+        .stmts
+        .iter()
+        .find_map(|s| match s {
+            hir::Stmt::Let(l) if l.name == name => Some(l.init.ty().clone()),
+            _ => None,
+        })
+        .unwrap_or_else(|| panic!("no `let {name}`"))
+}
+
+#[test]
+fn lexes_chars_bytes_and_escapes() {
+    use TokenKind::*;
+    assert_eq!(kinds(r"'a' '\n' '\'' '\\' '\0' '\x41' '\u{e9}' 'é'"), [
+        Char('a'),
+        Char('\n'),
+        Char('\''),
+        Char('\\'),
+        Char('\0'),
+        Char('A'),
+        Char('é'),
+        Char('é')
+    ]);
+    assert_eq!(kinds(r"b'a' b'\xff' b'\''"), [Byte(b'a'), Byte(0xFF), Byte(b'\'')]);
+    // strings share the escapes
+    assert_eq!(kinds(r#""\x41\'\u{e9}" b"a\x00\xff\n""#), [Str("A'é".into()), ByteStr(vec![b'a', 0, 0xFF, b'\n'])]);
+    // a lifetime is not closed by a quote, `b` alone stays an identifier
+    assert_eq!(kinds("&'a T"), [Amp, Lifetime("a".into()), Ident("T".into())]);
+    assert_eq!(kinds("<'a, 'b>")[1..4], [Lifetime("a".into()), Comma, Lifetime("b".into())]);
+    assert_eq!(kinds("b + 1"), [Ident("b".into()), Plus, Int(1)]);
+}
+
+#[test]
+fn invalid_char_literals_name_the_problem() {
+    let cases = [
+        ("''", "empty character literal"),
+        ("'ab'", "character literal may only contain one character"),
+        (r"'\na'", "unterminated character literal"),
+        ("b'é'", "non-ASCII character in a byte literal"),
+        (r#"b"né""#, "non-ASCII character in a byte string literal"),
+        (r"'\xff'", "out of range escape `\\xff`"),
+        (r"'\x4'", "invalid escape `\\x`: expected two hexadecimal digits"),
+        (r"b'\u{41}'", "unicode escape `\\u{..}` in a byte literal"),
+        (r"'\u{d800}'", "invalid unicode escape `\\u{d800}`"),
+    ];
+    for (lit, msg) in cases {
+        let err = lex_err(&format!("fn f() {{ let x = {lit}; }}")).msg;
+        assert!(err.starts_with(msg), "`{lit}`: expected `{msg}`, got `{err}`");
+    }
+}
+
+#[test]
+fn chars_and_bytes_have_their_own_types() {
+    let m = resolve_ok(
+        "fn f(c: char) -> Char {
+             let a = 'a'; let b = b'a'; let s = b\"hi\"; let e = b\"\";
+             let lt = a < c; let eq = a == 'b';
+             c
+         }",
+    );
+    let u8 = Type::Num(NumTy::U8);
+    assert_eq!(let_ty(&m, "a"), Type::Char);
+    assert_eq!(let_ty(&m, "b"), u8);
+    assert_eq!(let_ty(&m, "s"), Type::Array(Box::new(u8.clone()), 2));
+    assert_eq!(let_ty(&m, "e"), Type::Array(Box::new(u8), 0));
+    assert_eq!(let_ty(&m, "lt"), Type::Bool);
+
+    resolve_err("fn f() { let c: Char = 1; }", "expected Char, got Int");
+    resolve_err("fn f() { let c: u8 = 'a'; }", "expected u8, got Char");
+    resolve_err("fn f() { let c: Int = b'a'; }", "expected Int, got u8");
+    resolve_err("fn f() { let c = 'a' + 'b'; }", "cannot apply Plus to types `Char` and `Char`");
+    resolve_err("fn f() { let s: [u8; 3] = b\"hi\"; }", "expected [u8; 3], got [u8; 2]");
+    resolve_err("fn f() { print([1, 2]); }", "print unsupported type");
+}
+
+#[test]
+fn constants_hold_code_points() {
+    let m = resolve_ok("const A: Char = 'é'; const B: u8 = b'z' - b'a'; const C: [u8; 2] = b\"\\x01\\xff\";");
+    let value = |name: &str| {
+        m.items.iter().find_map(|it| match it {
+            hir::Item::Const(c) if c.name == name => Some(c.value.clone()),
+            _ => None,
+        })
+    };
+    assert_eq!(value("A"), Some(ConstValue::Int(0xE9)));
+    assert_eq!(value("B"), Some(ConstValue::Int(25)));
+    assert_eq!(value("C"), Some(ConstValue::Array(vec![ConstValue::Int(1), ConstValue::Int(0xFF)])));
+    resolve_err("const C: u8 = b'a' - b'b';", "integer overflow");
+}
+
+#[test]
+fn printing_chars_and_byte_strings_uses_the_runtime() {
+    let m = resolve_ok(
+        "fn main() {
+             print('\\u{1F600}'); print(b'A'); print(b\"hi\\n\"); print(b\"\");
+         }",
+    );
+    let main = mir::lower_module(&m);
+    let mut llcx = LlvmCtx::new("test");
+    codegen_module(&mut llcx, &main);
+    llcx.module.verify().unwrap_or_else(|e| panic!("{}", e.to_string()));
+    let ir = llcx.module.print_to_string().to_string();
+    for inst in ["@aethc_print_char(i32 128512)", "@aethc_print_u64", "store [3 x i8] c\"hi\\0A\"", "@aethc_print_bytes(i8*"] {
+        assert!(ir.contains(inst), "missing `{inst}` in {ir}");
+    }
+}
@@ -0,0 +1,150 @@
+use aethc_core::{
+    ast,
+    borrowck::borrow_check,
+    codegen::{LlvmCtx, codegen_module},
+    hir, mir,
+    parser::Parser,
+    type_::Type,
+};
+
+mod common;
+
+use common::{borrow_err, resolve_err, resolve_ok};
+
+/// Inicijalizator `let`-a sa imenom `name` u poslednjoj funkciji.
+fn init_of<'m>(m: &'m hir::HirModule, name: &str) -> &'m hir::Expr {
+    let Some(hir::Item::Fn(f)) = m.items.last() else { panic!("expected fn") };
+    f.body
+        .stmts
+        .iter()
+        .find_map(|s| match s {
+            hir::Stmt::Let(l) if l.name == name => Some(&l.init),
+            _ => None,
+        })
+        .unwrap_or_else(|| panic!("no `let {name}`"))
+}
+
+fn fn_ty(params: &[Type], ret: Type) -> Type {
+    Type::Fn { params: params.to_vec(), ret: Box::new(ret) }
+}
+
+#[test]
+fn parses_closure_forms() {
+    let m = Parser::new(
+        "fn f(g: fn(Int, Bool) -> Int, h: fn()) { let a = |x: Int, y| x; let b = || 1; let c = move || -> Int { 2 }; }",
+    )
+    .parse_module();
+    let ast::Item::Function(f) = &m.items[0] else { panic!("expected fn") };
+    let Some(ast::TypeExpr::Fn { params, ret: Some(_) }) = &f.params[0].ty else { panic!("expected fn type") };
+    assert_eq!(params.len(), 2);
+    assert!(matches!(&f.params[1].ty, Some(ast::TypeExpr::Fn { params, ret: None }) if params.is_empty()));
+
+    let ast::Stmt::Let { expr: ast::Expr::Closure { params, ret: None, by_move: false, .. }, .. } = &f.body.stmts[0] else {
+        panic!("expected closure, got {:?}", f.body.stmts[0]);
+    };
+    assert!(params[0].ty.is_some() && params[1].ty.is_none());
+    // `||` je zatvorenje bez parametara, a ne operator
+    assert!(matches!(&f.body.stmts[1], ast::Stmt::Let { expr: ast::Expr::Closure { params, .. }, .. } if params.is_empty()));
+    assert!(matches!(&f.body.stmts[2], ast::Stmt::Let { expr: ast::Expr::Closure { ret: Some(_), by_move: true, .. }, .. }));
+}
+
+#[test]
+fn closures_capture_outer_locals() {
+    let m = resolve_ok(
+        "let G = 1;
+         fn double(x: Int) -> Int { x * 2 }
+         fn main() { let k = 3; let s = \"s\"; let d = double;
+                     let f = |x: Int| { let y = x + k + G; print(s); y };
+                     let g = move || -> Bool { let k = true; k }; }",
+    );
+    let hir::Expr::FnRef { name, ty, .. } = init_of(&m, "d") else { panic!("expected fn ref") };
+    assert_eq!((name.as_str(), ty), ("double", &fn_ty(&[Type::Int], Type::Int)));
+
+    // globale i sopstvene promenljive tela se ne hvataju
+    let hir::Expr::Closure { captures, by_move: false, ty, .. } = init_of(&m, "f") else { panic!("expected closure") };
+    let names: Vec<_> = captures.iter().map(|c| (c.name.as_str(), c.ty.clone())).collect();
+    assert_eq!(names, [("k", Type::Int), ("s", Type::Str)]);
+    assert_eq!(ty, &fn_ty(&[Type::Int], Type::Int));
+
+    let hir::Expr::Closure { captures, by_move: true, ty, .. } = init_of(&m, "g") else { panic!("expected closure") };
+    assert!(captures.is_empty(), "{captures:?}");
+    assert_eq!(ty, &fn_ty(&[], Type::Bool));
+}
+
+#[test]
+fn closure_errors() {
+    resolve_err("fn main() { let f = |x| x; }", "closure parameter `x` needs a type: `|x: T|`");
+    resolve_err("fn main() { let f = |x: Int| { return x; }; }", "`return` in a closure needs an explicit return type");
+    resolve_err("fn main() { let f = |x: Int| -> Bool { x }; }", "expected Bool, got Int");
+    resolve_err("fn main() { let p = print; }", "builtin `print` can only be called, not used as a value");
+    resolve_err("fn main() { let x = 1; x(2); }", "cannot call a value of type Int");
+    resolve_err("fn main() { let f = |x: Int| x; print(f(true)); }", "expected Int, got Bool");
+    resolve_err("fn main() { let mut x = 1; let f = move || { x = 2; }; }", "it is captured by value in a `move` closure");
+    resolve_err("fn main() { let v = vec::<fn()>(); }", "a Vec cannot hold a function value");
+    resolve_err(
+        "fn work(f: fn()) { } fn main() { spawn work(|| print(1)); }",
+        "cannot pass a function value of type fn() to a spawned function",
+    );
+    // telo ne vidi `break` spoljašnje petlje
+    resolve_err("fn main() { while true { let f = || { break; }; } }", "`break` outside of a loop");
+}
+
+#[test]
+fn captured_variables_are_borrow_checked() {
+    borrow_err("fn main() { let s = \"a\"; let f = || print(s); let t = s; f(); }", "cannot move out of `s` because a closure borrows it");
+    borrow_err(
+        "fn take(v: Vec<Int>) { } fn main() { let v = vec::<Int>(); let f = || take(v); }",
+        "cannot move captured variable `v` out of a closure",
+    );
+    borrow_err(
+        "fn main() { let v = vec::<Int>(); let f = move || { let w = v; }; }",
+        "cannot move captured variable `v` out of a closure",
+    );
+    borrow_err("fn main() { let v = vec::<Int>(); let f = move || print(len(v)); let w = v; }", "value `v` is moved twice");
+    // Copy vrednosti ostaju upotrebljive i posle `move`
+    let m = resolve_ok("fn main() { let n = 1; let s = \"a\"; let f = move || { print(n); print(s); }; print(n); f(); }");
+    assert!(borrow_check(&m).is_empty(), "{:?}", borrow_check(&m));
+}
+
+#[test]
+fn closures_lower_to_functions_with_environment() {
+    let m = resolve_ok(
+        "fn apply(f: fn(Int) -> Int, x: Int) -> Int { f(x) }
+         fn double(x: Int) -> Int { x * 2 }
+         fn main() { let mut total = 0; let add = |n: Int| -> Int { total = total + n; total };
+                     let k = 5; let scale = move |x: Int| x * k;
+                     print(apply(add, 1) + apply(scale, 2) + apply(double, 3)); }",
+    );
+    let mir_mod = mir::lower_module(&m);
+    let names: Vec<_> = mir_mod.fns.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names, ["apply", "double", "main", "main.closure0", "main.closure1"]);
+
+    // samo promenljive uhvaćene po referenci žive u ćelijama
+    let main = &mir_mod.fns[2].body;
+    assert_eq!(main.boxed.len(), 1, "{:?}", main.boxed);
+    let stmts: Vec<_> = main.blocks.iter().flat_map(|b| &b.stmts).collect();
+    assert!(stmts.iter().any(|s| matches!(s, mir::Statement::NewCell(_))), "{stmts:?}");
+    let closures: Vec<_> = stmts
+        .iter()
+        .filter_map(|s| match s {
+            mir::Statement::Assign { rv: mir::Rvalue::Closure { by_ref, captures, .. }, .. } => Some((*by_ref, captures.len())),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(closures, [(true, 1), (false, 1)]);
+    assert!(mir_mod.fns[4].body.env.as_ref().is_some_and(|env| !env.by_ref));
+
+    let apply = &mir_mod.fns[0].body;
+    assert!(
+        apply.blocks[0].stmts.iter().any(|s| matches!(s, mir::Statement::Assign { rv: mir::Rvalue::CallValue { .. }, .. })),
+        "{:?}",
+        apply.blocks[0].stmts
+    );
+
+    let mut llcx = LlvmCtx::new("test");
+    codegen_module(&mut llcx, &mir_mod);
+    llcx.module.verify().unwrap_or_else(|e| panic!("{}", e.to_string()));
+    let ir = llcx.module.print_to_string().to_string();
+    assert!(ir.contains("define private i32 @double.fnval(i8*"), "{ir}");
+    assert!(ir.contains("@aethc_alloc"), "{ir}");
+}
@@ -0,0 +1,20 @@
+//! Pomoćne funkcije koje dele testovi; svaki test koristi samo neke.
+#![allow(dead_code)]
+
+use aethc_core::{borrowck::borrow_check, hir, parser::Parser, resolver::resolve};
+
+pub fn resolve_ok(src: &str) -> hir::HirModule {
+    let (hir_mod, errs) = resolve(&Parser::new(src).parse_module());
+    assert!(errs.is_empty(), "expected ok, got {errs:?}");
+    hir_mod
+}
+
+pub fn resolve_err(src: &str, msg: &str) {
+    let (_hir, errs) = resolve(&Parser::new(src).parse_module());
+    assert!(errs.iter().any(|e| e.msg.contains(msg)), "expected `{msg}`, got {errs:?}");
+}
+
+pub fn borrow_err(src: &str, msg: &str) {
+    let errs = borrow_check(&resolve_ok(src));
+    assert!(errs.iter().any(|e| e.msg.contains(msg)), "expected `{msg}`, got {errs:?}");
+}
@@ -5,10 +5,10 @@ fn int_less_than_int_is_bool() {
     let src = "fn main(){ let b = 1 < 2; }";
     let (hir_mod, errs) = resolve(&Parser::new(src).parse_module());
     assert!(errs.is_empty());
-    if let aethc_core::hir::Item::Fn(f) = &hir_mod.items[0] {
-        if let aethc_core::hir::Stmt::Let(l) = &f.body.stmts[0] {
-            assert_eq!(l.ty, Type::Bool);
-        }
+    if let aethc_core::hir::Item::Fn(f) = &hir_mod.items[0]
+        && let aethc_core::hir::Stmt::Let(l) = &f.body.stmts[0]
+    {
+        assert_eq!(l.ty, Type::Bool);
     }
 }
 
@@ -17,9 +17,9 @@ fn int_eq_float_is_error() {
     let src = "fn main(){ let b = 1 == 2.0; }";
     let (hir_mod, errs) = resolve(&Parser::new(src).parse_module());
     assert!(errs.is_empty());
-    if let aethc_core::hir::Item::Fn(f) = &hir_mod.items[0] {
-        if let aethc_core::hir::Stmt::Let(l) = &f.body.stmts[0] {
-            assert_eq!(l.ty, Type::Bool);
-        }
+    if let aethc_core::hir::Item::Fn(f) = &hir_mod.items[0]
+        && let aethc_core::hir::Stmt::Let(l) = &f.body.stmts[0]
+    {
+        assert_eq!(l.ty, Type::Bool);
     }
 }
@@ -0,0 +1,98 @@
+use aethc_core::{
+    borrowck::borrow_check,
+    codegen::{LlvmCtx, codegen_module},
+    hir, mir,
+    type_::Type,
+};
+
+mod common;
+
+use common::{borrow_err, resolve_err, resolve_ok};
+
+const PINGPONG: &str = "
+    fn worker(rx: Receiver<Int>, tx: Sender<Int>) { send(tx, recv(rx) * 2); }
+    fn main() {
+        let (tx, rx) = channel::<Int>();
+        let (back, results) = channel::<Int>();
+        spawn worker(rx, back);
+        send(tx, 21);
+        print(recv(results));
+    }
+";
+
+#[test]
+fn channel_endpoints_are_typed() {
+    let m = resolve_ok(PINGPONG);
+    let hir::Item::Fn(main) = &m.items[1] else { panic!("expected main") };
+    let let_named = |name: &str| {
+        main.body
+            .stmts
+            .iter()
+            .find_map(|s| match s {
+                hir::Stmt::Let(l) if l.name == name => Some(l),
+                _ => None,
+            })
+            .unwrap_or_else(|| panic!("no `let {name}`"))
+    };
+    let (tx, rx) = (let_named("tx"), let_named("rx"));
+    assert_eq!(tx.ty, Type::Sender(Box::new(Type::Int)));
+    assert_eq!(rx.ty, Type::Receiver(Box::new(Type::Int)));
+    assert!(borrow_check(&m).is_empty());
+}
+
+#[test]
+fn channel_and_spawn_type_errors() {
+    resolve_err("fn main() { let (tx, rx) = channel(); }", "write `channel::<T>()`");
+    resolve_err("fn main() { let (tx, rx) = channel::<Int>(); send(tx, \"s\"); }", "expected Int");
+    resolve_err("fn main() { let (tx, rx) = channel::<Int>(); recv(tx); }", "expects a Receiver");
+    resolve_err("fn f() -> Int { 1 } fn main() { spawn f(); }", "must return ()");
+    resolve_err("fn f(x: Int) { } fn main() { spawn f(); }", "takes 1 argument(s), got 0");
+    resolve_err("fn main() { spawn print(1); }", "user-defined function");
+    resolve_err("fn f(tx: Sender) { }", "`Sender` takes 1 type argument(s)");
+}
+
+#[test]
+fn spawned_fn_must_not_touch_mutable_globals() {
+    borrow_err(
+        "let mut hits = 0; fn count() { hits = hits + 1; } fn work() { count(); }
+         fn main() { spawn work(); }",
+        "cannot spawn `work`: it accesses mutable global `hits`",
+    );
+    // nepromenljive globalne se smeju čitati
+    let m = resolve_ok("let limit = 3; fn work() { print(limit); } fn main() { spawn work(); }");
+    assert!(borrow_check(&m).is_empty());
+}
+
+#[test]
+fn receiver_moves_into_thread() {
+    let take = "fn take(rx: Receiver<Int>) { print(recv(rx)); }";
+    borrow_err(
+        &format!("{take} fn main() {{ let (tx, rx) = channel::<Int>(); spawn take(rx); recv(rx); }}"),
+        "use of receiver `rx` after it was moved",
+    );
+    borrow_err(
+        &format!(
+            "{take} fn main() {{ let (tx, rx) = channel::<Int>(); let mut i = 0;
+             while i < 2 {{ spawn take(rx); i = i + 1; }} }}"
+        ),
+        "moved into a spawned thread inside a loop",
+    );
+    // Sender se kopira – više niti može da šalje u isti kanal
+    let m = resolve_ok(
+        "fn put(tx: Sender<Int>) { send(tx, 1); }
+         fn main() { let (tx, rx) = channel::<Int>(); spawn put(tx); spawn put(tx); print(recv(rx)); }",
+    );
+    assert!(borrow_check(&m).is_empty());
+}
+
+#[test]
+fn spawn_lowers_to_runtime_calls() {
+    let mut llcx = LlvmCtx::new("test");
+    codegen_module(&mut llcx, &mir::lower_module(&resolve_ok(PINGPONG)));
+    llcx.module.verify().unwrap_or_else(|e| panic!("{}", e.to_string()));
+    let ir = llcx.module.print_to_string().to_string();
+    assert!(ir.contains("define private void @worker.spawn(i8*"), "{ir}");
+    assert!(ir.contains("call void @aethc_spawn("), "{ir}");
+    assert!(ir.contains("call i64 @aethc_chan_recv("), "{ir}");
+    assert!(ir.contains("call void @aethc_join_all()"), "{ir}");
+}
@@ -0,0 +1,91 @@
+use aethc_core::{
+    const_eval::ConstValue,
+    hir, mir,
+};
+
+mod common;
+
+use common::{resolve_err, resolve_ok};
+
+fn const_value(m: &hir::HirModule, name: &str) -> ConstValue {
+    m.items
+        .iter()
+        .find_map(|it| match it {
+            hir::Item::Const(c) if c.name == name => Some(c.value.clone()),
+            _ => None,
+        })
+        .unwrap_or_else(|| panic!("no const `{name}`"))
+}
+
+#[test]
+fn folds_arithmetic_and_comparisons() {
+    let m = resolve_ok("const A: Int = 6 * 7; const B: Bool = (A > 40) && !false; const C: Float = A / 4;");
+    assert_eq!(const_value(&m, "A"), ConstValue::Int(42));
+    assert_eq!(const_value(&m, "B"), ConstValue::Bool(true));
+    assert_eq!(const_value(&m, "C"), ConstValue::Float(10.0));
+}
+
+#[test]
+fn calls_pure_functions() {
+    let m = resolve_ok(
+        "fn fact(n: Int) -> Int { if n <= 1 { 1 } else { n * fact(n - 1) } }
+         fn sum_to(n: Int) -> Int { let mut s = 0; let mut i = 0; while i < n { i = i + 1; s = s + i; } s }
+         const F: Int = fact(5);
+         const S: Int = sum_to(10);",
+    );
+    assert_eq!(const_value(&m, "F"), ConstValue::Int(120));
+    assert_eq!(const_value(&m, "S"), ConstValue::Int(55));
+}
+
+#[test]
+fn uses_are_replaced_by_the_value() {
+    let m = resolve_ok("const N: Int = 3; fn main() { let x = N + 1; }");
+    if let hir::Item::Fn(f) = &m.items[1]
+        && let hir::Stmt::Let(l) = &f.body.stmts[0]
+        && let hir::Expr::Binary { lhs, .. } = &l.init
+    {
+        assert!(matches!(**lhs, hir::Expr::Int { value: 3, .. }));
+    } else {
+        panic!("expected `let x = 3 + 1`");
+    }
+}
+
+#[test]
+fn overflow_and_division_by_zero_are_errors() {
+    // `Int` is 32-bit, as when the program runs
+    resolve_err("const BIG: Int = 2147483647 + 1;", "evaluation of constant `BIG` failed: integer overflow");
+    resolve_err("const SMALL: Int = -2147483648 - 1;", "evaluation of constant `SMALL` failed: integer overflow");
+    resolve_err("const SQ: Int = 65536 * 65536;", "integer overflow");
+    let m = resolve_ok("const MAX: Int = 2147483646 + 1; const MIN: Int = -2147483647 - 1;");
+    assert_eq!(const_value(&m, "MAX"), ConstValue::Int(i32::MAX.into()));
+    assert_eq!(const_value(&m, "MIN"), ConstValue::Int(i32::MIN.into()));
+    resolve_err("const Z: Int = 1 / (2 - 2);", "attempt to divide by zero");
+    resolve_err("fn f(x: Int) -> Int { 10 % x } const R: Int = f(0);", "attempt to divide by zero");
+    resolve_err("let g = 5 / 0;", "initializer of `g` always fails");
+}
+
+#[test]
+fn non_constant_expressions_are_rejected() {
+    resolve_err("let mut g = 1; const C: Int = g;", "`g` is not a constant");
+    resolve_err("fn f() -> Int { print(1); 1 } const C: Int = f();", "`print` cannot be called");
+    resolve_err("fn spin() -> Int { while true { } 0 } const C: Int = spin();", "step limit");
+    resolve_err("const C: Int = true;", "expected Int, got Bool");
+    resolve_err("const C: Int = 1; fn main() { C = 2; }", "cannot reassign immutable binding `C`");
+}
+
+#[test]
+fn global_initializers_use_the_evaluator() {
+    let m = resolve_ok(
+        "fn double(x: Int) -> Int { x * 2 }
+         let a = double(4);
+         let b = a + 1;
+         let mut c = 0;
+         let d = c + 1;",
+    );
+    let mir = mir::lower_module(&m);
+    assert!(matches!(mir.globals[0].init, Some(mir::Constant::Int(8))));
+    assert!(matches!(mir.globals[1].init, Some(mir::Constant::Int(9))));
+    // `c` is mutable, so `d` is computed at startup
+    assert!(mir.globals[3].init.is_none());
+    assert!(mir.init.is_some());
+}
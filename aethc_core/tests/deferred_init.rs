@@ -0,0 +1,101 @@
+use aethc_core::{
+    ast,
+    borrowck::borrow_check,
+    cfg, hir,
+    mir::{self, Statement},
+    parser::Parser,
+    type_::Type,
+};
+
+mod common;
+
+use common::{resolve_err, resolve_ok};
+
+fn init_ok(src: &str) {
+    let m = resolve_ok(src);
+    let errs = borrow_check(&m);
+    assert!(errs.is_empty(), "expected ok, got {errs:?}");
+    let diags = cfg::check_module(&m);
+    assert!(diags.errors.is_empty(), "expected ok, got {:?}", diags.errors);
+}
+
+fn init_err(src: &str, msg: &str) {
+    let diags = cfg::check_module(&resolve_ok(src));
+    assert!(diags.errors.iter().any(|e| e.msg == msg), "expected `{msg}`, got {:?}", diags.errors);
+}
+
+#[test]
+fn parses_declarations_without_a_value() {
+    let m = Parser::new("fn f() { let x: Int; let mut v: Vec<Int>; let y: Float = 1; }").parse_module();
+    let ast::Item::Function(f) = &m.items[0] else { panic!("expected fn") };
+    assert!(matches!(&f.body.stmts[0], ast::Stmt::Decl { name, mutable: false, ty: ast::TypeExpr::Named { .. } } if name == "x"));
+    assert!(matches!(&f.body.stmts[1], ast::Stmt::Decl { mutable: true, .. }));
+    assert!(matches!(&f.body.stmts[2], ast::Stmt::Let { ty: Some(_), expr: ast::Expr::Int(1), .. }));
+
+    let m = resolve_ok("fn f() { let x: Int; x = 2; let y: Float = 1; print(x); }");
+    let hir::Item::Fn(f) = &m.items[0] else { panic!("expected fn") };
+    assert!(matches!(&f.body.stmts[0], hir::Stmt::Decl { ty: Type::Int, mutable: false, .. }));
+    assert!(matches!(&f.body.stmts[2], hir::Stmt::Let(l) if l.ty == Type::Float));
+    let body = mir::lower_fn(f);
+    assert!(matches!(body.blocks[0].stmts[0], Statement::Uninit(_)));
+    assert_eq!(body.deferred.values().collect::<Vec<_>>(), [&("x".to_string(), false)]);
+}
+
+#[test]
+fn immutable_deferred_bindings_take_one_assignment() {
+    init_ok(
+        "fn pick(c: Bool) -> Int { let x: Int; if c { x = 1; } else { x = 2; } x }
+         fn main() { let s: Str; s = \"hi\"; print(s); let mut n: Int; n = 1; n = n + 1; print(n); }",
+    );
+    init_ok("fn main() { let mut i = 0; while i < 3 { let y: Int; y = i; print(y); i = i + 1; } }");
+    init_err(
+        "fn f() { let x: Int; x = 1; x = 2; }",
+        "cannot assign twice to immutable variable `x` in function `f`",
+    );
+    init_err(
+        "fn f(k: Int) { let mut i = 0; let y: Int; while i < k { y = i; i = i + 1; } }",
+        "cannot assign twice to immutable variable `y` in function `f`",
+    );
+    let errs = borrow_check(&resolve_ok("fn f() { let mut x: Int; let y: Int; x = 1; y = x; }"));
+    assert!(errs.is_empty(), "expected ok, got {errs:?}");
+}
+
+#[test]
+fn uses_before_assignment_name_the_path() {
+    init_err("fn f() { let x: Int; print(x); }", "use of uninitialized variable `x` in function `f`");
+    init_err(
+        "fn f(c: Bool) -> Int { let x: Int; if c { x = 1; } x }",
+        "use of possibly uninitialized variable `x` in function `f`: it is not assigned when the `if` condition is false",
+    );
+    init_err(
+        "fn f(c: Bool) -> Int { let x: Int; if c { } else { x = 1; } let mut i = 0; while i < 2 { i = i + 1; } x }",
+        "use of possibly uninitialized variable `x` in function `f`: it is not assigned when the `if` condition is true",
+    );
+    init_err(
+        "fn f(k: Int) -> Int { let mut i = 0; let mut y: Int; while i < k { y = i; i = i + 1; } y }",
+        "use of possibly uninitialized variable `y` in function `f`: it is not assigned when the `while` condition is false",
+    );
+    init_err(
+        "fn f(c: Bool, d: Bool) -> Int { let x: Int; if c { if d { x = 1; } } else { x = 2; } x }",
+        "use of possibly uninitialized variable `x` in function `f`: it is not assigned when the `if` condition is true and then the `if` condition is false",
+    );
+}
+
+#[test]
+fn element_writes_and_captures_read_the_variable() {
+    init_err("fn f() { let mut a: [Int; 2]; a[0] = 1; }", "use of uninitialized variable `a` in function `f`");
+    init_err("fn f() { let x: Int; let g = || x + 1; x = 1; }", "use of uninitialized variable `x` in function `f`");
+    init_ok("fn f() { let x: Int; x = 1; let g = || x + 1; print(g()); let mut a: [Int; 2]; a = [1, 2]; a[0] = 3; }");
+    resolve_err(
+        "fn f() { let x: Int; let g = || { x = 1; }; }",
+        "cannot assign to immutable `x` inside a closure; declare it `let mut x`",
+    );
+}
+
+#[test]
+fn annotations_are_checked_against_values() {
+    resolve_err("fn f() { let x: Int = true; }", "expected Int, got Bool");
+    resolve_err("fn f() { let x: Int; x = \"s\"; }", "expected Int, got String");
+    resolve_err("fn f() { let x: Int; let y: Int = 1; y = 2; }", "cannot reassign immutable binding `y`");
+    resolve_ok("fn f() { let v: Vec<Int> = vec::<Int>(); let o: Option<Int> = None; let x: Int; { x = 1; } print(x); }");
+}
@@ -0,0 +1,113 @@
+use aethc_core::{
+    ast,
+    borrowck::borrow_check,
+    codegen::{LlvmCtx, codegen_module},
+    hir,
+    mir,
+    parser::Parser,
+    type_::Type,
+};
+
+mod common;
+
+use common::{borrow_err, resolve_err, resolve_ok};
+
+const SHAPE: &str = "trait Shape { fn area(&self) -> Int; fn grow(&mut self, by: Int); }
+                     impl Shape for Int { fn area(&self) -> Int { self * self } fn grow(&mut self, by: Int) { self = self + by; } }
+                     impl<T: Shape> Shape for (T, T) {
+                         fn area(&self) -> Int { self.0.area() + self.1.area() }
+                         fn grow(&mut self, by: Int) { } }";
+
+#[test]
+fn parses_references_and_trait_objects() {
+    let m = Parser::new("fn f(x: &dyn geo::Shape, y: &mut Int) { let r = &mut y; let v = *x; }").parse_module();
+    let ast::Item::Function(f) = &m.items[0] else { panic!("expected fn") };
+    let Some(ast::TypeExpr::Ref { mutable: false, inner, .. }) = &f.params[0].ty else { panic!("expected a reference") };
+    assert!(matches!(&**inner, ast::TypeExpr::Dyn(path) if path == &["geo", "Shape"]));
+    assert!(matches!(&f.params[1].ty, Some(ast::TypeExpr::Ref { mutable: true, .. })));
+    assert!(matches!(&f.body.stmts[0], ast::Stmt::Let { expr: ast::Expr::Ref { mutable: true, .. }, .. }));
+    assert!(matches!(&f.body.stmts[1], ast::Stmt::Let { expr: ast::Expr::Deref(_), .. }));
+}
+
+#[test]
+fn references_coerce_to_trait_objects() {
+    let m = resolve_ok(&format!(
+        "{SHAPE} fn total(v: Vec<&dyn Shape>) -> Int {{ let mut n = 0; for s in v {{ n = n + s.area(); }} n }}
+         fn main() {{ let x = 2; let p = (1, 3); let mut v = vec::<&dyn Shape>(); push(v, &x); push(v, &p); let t = total(v); }}"
+    ));
+    let Some(hir::Item::Fn(main)) = m.items.last() else { panic!("expected fn") };
+    let hir::Stmt::Semi(hir::Expr::Call { args, .. }) = &main.body.stmts[4] else { panic!("expected push") };
+    let hir::Expr::ToDyn { expr, trait_name, methods, ty, .. } = &args[1] else { panic!("expected a trait object") };
+    assert_eq!((trait_name.as_str(), methods.as_slice()), ("Shape", &["area".to_string(), "grow".into()][..]));
+    assert_eq!(expr.ty(), &Type::Ref { mutability: false, inner: Box::new(Type::Tuple(vec![Type::Int, Type::Int])), lifetime: None });
+    assert_eq!(ty, &Type::Ref { mutability: false, inner: Box::new(Type::Dyn("Shape".into())), lifetime: None });
+
+    resolve_err(&format!("{SHAPE} fn f(s: &dyn Shape) {{ }} fn main() {{ f(&true); }}"), "the trait `Shape` is not implemented for `Bool`");
+    resolve_err(&format!("{SHAPE} fn f(s: &mut dyn Shape) {{ }} fn main() {{ f(&1); }}"), "expected &mut dyn Shape, got &Int");
+}
+
+#[test]
+fn trait_object_errors() {
+    resolve_err("trait S { fn a(self); } fn f(x: &dyn S) { }", "the trait `S` cannot be made into an object: method `a` takes `self` by value");
+    resolve_err(
+        "trait S { fn a(&self, o: Self); } fn f(x: &dyn S) { }",
+        "the trait `S` cannot be made into an object: method `a` uses `Self` outside of its receiver",
+    );
+    resolve_err("trait S { fn a(&self); } fn f(x: dyn S) { }", "a trait object must be behind a reference: `&dyn S`");
+    resolve_err(&format!("{SHAPE} fn f(s: &dyn Shape) {{ s.grow(1); }}"), "`Shape::grow` takes `&mut self`, so it cannot be called through `&dyn Shape`");
+    resolve_err(&format!("{SHAPE} fn f(s: &dyn Shape) {{ s.size(); }}"), "no method named `size` for type `&dyn Shape`");
+    resolve_err(&format!("{SHAPE} fn f(s: &dyn Shape) {{ let x = *s; }}"), "cannot dereference `&dyn Shape`; call its methods instead");
+    resolve_err("fn f(x: Int) { let y = *x; }", "cannot dereference a value of type Int");
+    resolve_err("fn f(x: &Int) { let m = &mut *x; }", "cannot borrow data behind a `&` reference as mutable");
+    resolve_err("fn main() { let x = 1; let r = &mut x; }", "cannot borrow `x` as mutable, as it is not declared `mut`");
+    resolve_err("fn main() { let r = &mut 5; }", "cannot borrow a temporary value as mutable");
+}
+
+#[test]
+fn references_are_reborrowed_not_moved() {
+    let ok = "impl Int { fn inc(&mut self) { self = self + 1; } }
+              fn bump(r: &mut Int) { r.inc(); }
+              fn twice(r: &mut Int) { bump(r); bump(r); }
+              fn size(v: &Vec<Int>) -> Int { len(*v) }";
+    assert!(borrow_check(&resolve_ok(ok)).is_empty());
+    borrow_err("fn f(v: &Vec<Int>) -> Vec<Int> { *v }", "cannot move out of `*v`, which is behind a reference");
+    borrow_err("fn f(v: &Vec<Int>) { let w = *v; }", "cannot move out of `*v`, which is behind a reference");
+    borrow_err(
+        "fn w(v: Vec<&Int>) { } fn main() { let x = 1; let mut v = vec::<&Int>(); push(v, &x); spawn w(v); }",
+        "borrowed value cannot be sent to a spawned thread",
+    );
+}
+
+#[test]
+fn method_calls_go_through_vtables() {
+    let m = resolve_ok(&format!(
+        "{SHAPE} fn show(s: &dyn Shape) {{ print(s.area()); }} fn enlarge(s: &mut dyn Shape) {{ s.grow(1); }}
+         fn main() {{ let mut x = 2; enlarge(&mut x); show(&x); show(&(x, 3)); show(&x); }}"
+    ));
+    let mir_mod = mir::lower_module(&m);
+    let vtables: Vec<_> = mir_mod.vtables.iter().map(|v| (v.name.as_str(), v.fns.clone())).collect();
+    assert_eq!(
+        vtables,
+        [
+            ("<Int as Shape>::vtable", vec!["<Int as Shape>::area".to_string(), "<Int as Shape>::grow".into()]),
+            (
+                "<(Int, Int) as Shape>::vtable",
+                vec!["<(T, T) as Shape>::area<Int>".to_string(), "<(T, T) as Shape>::grow<Int>".into()]
+            ),
+        ]
+    );
+
+    let mut llcx = LlvmCtx::new("test");
+    codegen_module(&mut llcx, &mir_mod);
+    llcx.module.verify().unwrap_or_else(|e| panic!("{}", e.to_string()));
+    let ir = llcx.module.print_to_string().to_string();
+    assert!(ir.contains("@\"<Int as Shape>::vtable\" = private constant { i8*, i8* }"), "{ir}");
+    // `show` ne zna tip vrednosti, poziva preko vtable-a
+    let show = &ir[ir.find("define void @show({ i8*, i8* }").expect("show")..];
+    let show = &show[..show.find("\n}").unwrap()];
+    assert!(show.contains("call i32 %fn(i8*") && !show.contains("call i32 @"), "{show}");
+    // objekat je par po vrednosti, ne alocira se
+    let main = &ir[ir.find("define void @main(").expect("main")..];
+    let main = &main[..main.find("\n}").unwrap()];
+    assert!(main.contains("call void @show({ i8*, i8* }") && !main.contains("@aethc_alloc"), "{main}");
+}
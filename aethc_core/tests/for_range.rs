@@ -0,0 +1,146 @@
+use aethc_core::{
+    ast,
+    codegen::{LlvmCtx, codegen_module},
+    hir, mir,
+    parser::Parser,
+    resolver::resolve,
+    type_::{NumTy, Type},
+};
+
+mod common;
+
+use common::{resolve_err, resolve_ok};
+
+fn main_body(m: &hir::HirModule) -> &hir::Block {
+    match m.items.last() {
+        Some(hir::Item::Fn(f)) => &f.body,
+        _ => panic!("expected main"),
+    }
+}
+
+#[test]
+fn parses_exclusive_and_inclusive_ranges() {
+    let m = Parser::new("fn main() { for i in 0..n + 1 { } for j in 1..=3 { } }").parse_module();
+    let ast::Item::Function(f) = &m.items[0] else { panic!("expected fn") };
+    let exprs = f.body.stmts.iter().filter_map(|s| match s {
+        ast::Stmt::Expr(e) => Some(e),
+        _ => None,
+    });
+    let ranges: Vec<bool> = exprs
+        .chain(f.body.tail.as_deref())
+        .map(|e| match e {
+            ast::Expr::For { iter, .. } => match &**iter {
+                ast::Expr::Range { end, inclusive, .. } => {
+                    // `..` vezuje slabije od aritmetike
+                    assert!(*inclusive || matches!(**end, ast::Expr::Binary { .. }));
+                    *inclusive
+                }
+                e => panic!("expected range, got {e:?}"),
+            },
+            e => panic!("expected for, got {e:?}"),
+        })
+        .collect();
+    assert_eq!(ranges, [false, true]);
+}
+
+#[test]
+fn for_desugars_to_counted_while() {
+    let m = resolve_ok("fn main() { for i in 0..3 { print(i); } }");
+    let stmts = &main_body(&m).stmts;
+    // `for` na kraju tela je rep bloka
+    let hir::Stmt::Expr(hir::Expr::Block { block, .. }) = &stmts[0] else {
+        panic!("expected block, got {stmts:?}")
+    };
+    assert!(matches!(&block.stmts[0], hir::Stmt::Let(l) if l.mutable));
+    assert!(matches!(&block.stmts[1], hir::Stmt::Let(l) if !l.mutable));
+    let hir::Stmt::Semi(hir::Expr::While { cond, body, .. }) = &block.stmts[2] else {
+        panic!("expected while, got {:?}", block.stmts[2])
+    };
+    assert!(matches!(&**cond, hir::Expr::Binary { op: hir::BinOp::Lt, .. }));
+    // brojač se pomera pre tela, pa `continue` ne preskače korak
+    assert!(matches!(&body.stmts[0], hir::Stmt::Let(l) if l.name == "i"));
+    assert!(matches!(&body.stmts[1], hir::Stmt::Assign { .. }));
+
+    let m = resolve_ok("fn main() { for i in 1..=3 { continue; } }");
+    let hir::Stmt::Expr(hir::Expr::Block { block, .. }) = &main_body(&m).stmts[0] else {
+        panic!("expected block")
+    };
+    // `a..=b` staje preko `$more`, pa `$i` nikad ne prelazi `b`
+    assert!(matches!(&block.stmts[2], hir::Stmt::Let(l) if l.name == "$more"
+        && matches!(l.init, hir::Expr::Binary { op: hir::BinOp::Le, .. })));
+    let hir::Stmt::Semi(hir::Expr::While { cond, body, .. }) = &block.stmts[3] else { panic!() };
+    assert!(matches!(&**cond, hir::Expr::Ident { name, .. } if name == "$more"));
+    assert!(matches!(&body.stmts[1], hir::Stmt::Semi(hir::Expr::If { .. })));
+}
+
+#[test]
+fn inclusive_range_ends_at_the_int_maximum() {
+    let m = resolve_ok(
+        "fn count() -> Int { let mut n = 0; for i in 2147483645..=2147483647 { n = n + 1; } n }
+         const N: Int = count();
+         fn main() { for i in 2147483646..=2147483647 { print(i); } }",
+    );
+    let n = m.items.iter().find_map(|it| match it {
+        hir::Item::Const(c) if c.name == "N" => Some(c.value.clone()),
+        _ => None,
+    });
+    assert_eq!(n, Some(aethc_core::const_eval::ConstValue::Int(3)));
+    let mut llcx = LlvmCtx::new("test");
+    codegen_module(&mut llcx, &mir::lower_module(&m));
+    llcx.module.verify().unwrap_or_else(|e| panic!("{}", e.to_string()));
+}
+
+#[test]
+fn sized_integer_bounds_type_the_loop_variable() {
+    let var_ty = |src: &str| {
+        let m = resolve_ok(src);
+        let hir::Stmt::Expr(hir::Expr::Block { block, .. }) = &main_body(&m).stmts.last().unwrap() else {
+            panic!("expected block")
+        };
+        let hir::Stmt::Semi(hir::Expr::While { body, .. }) = block.stmts.last().unwrap() else {
+            panic!("expected while")
+        };
+        match &body.stmts[0] {
+            hir::Stmt::Let(l) if l.name == "i" => l.ty.clone(),
+            s => panic!("expected `i`, got {s:?}"),
+        }
+    };
+    assert_eq!(var_ty("fn main() { for i in 0u8..3u8 { print(i); } }"), Type::Num(NumTy::U8));
+    // literal bez sufiksa uzima tip druge granice
+    assert_eq!(var_ty("fn main() { let n: i64 = 5; for i in 0..n { print(i); } }"), Type::Num(NumTy::I64));
+    assert_eq!(var_ty("fn main() { for i in 250u8..=255 { print(i); } }"), Type::Num(NumTy::U8));
+
+    resolve_err("fn main() { for i in 0u8..3i64 { } }", "range bounds have different types");
+    resolve_err("fn main() { for i in 0.0..3.0 { } }", "range bounds must be integers");
+    resolve_err("fn main() { for i in 0u8..300 { } }", "out of range");
+}
+
+#[test]
+fn for_errors() {
+    resolve_err("fn main() { for i in 0..true { } }", "range bounds must be integers, got Bool");
+    resolve_err("fn main() { for i in 0..3 { 1 } }", "`for` body must have type ()");
+    resolve_err("fn main() { let r = 0..3; }", "only be used as the iterator of a `for` loop");
+    resolve_err("fn main() { for i in 3 { } }", "`for` can only iterate over a range");
+    // promenljiva petlje je nepromenljiva i ne vidi se posle petlje
+    resolve_err("fn main() { for i in 0..3 { } print(i); }", "i");
+    let (_m, errs) = resolve(&Parser::new("fn main() { for i in 0..3 { i = 1; } }").parse_module());
+    assert!(!errs.is_empty());
+}
+
+#[test]
+fn for_lowers_and_verifies() {
+    let m = resolve_ok(
+        "fn main() { let mut s = 0; for i in 0..10 { for j in i..10 { if j == 5 { break; } s = s + j; } } print(s); }",
+    );
+    let mir_mod = mir::lower_module(&m);
+    let loops = mir_mod.fns[0]
+        .body
+        .blocks
+        .iter()
+        .filter(|b| matches!(b.term, mir::Terminator::CondBranch { .. }))
+        .count();
+    assert!(loops >= 3, "two loop headers and the `if`");
+    let mut llcx = LlvmCtx::new("test");
+    codegen_module(&mut llcx, &mir_mod);
+    llcx.module.verify().unwrap_or_else(|e| panic!("{}", e.to_string()));
+}
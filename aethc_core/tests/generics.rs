@@ -0,0 +1,140 @@
+use aethc_core::{
+    ast,
+    borrowck::borrow_check,
+    codegen::{LlvmCtx, codegen_module},
+    hir,
+    infer_ctx::{InferCtx, TvOrTy, Ty},
+    mir,
+    parser::Parser,
+    type_::Type,
+};
+use std::collections::HashMap;
+
+mod common;
+
+use common::{resolve_err, resolve_ok};
+
+/// Pozvane funkcije u poslednjoj funkciji, sa tipskim argumentima.
+fn calls(m: &hir::HirModule) -> Vec<(String, Vec<Type>)> {
+    let Some(hir::Item::Fn(f)) = m.items.last() else { panic!("expected fn") };
+    f.body
+        .stmts
+        .iter()
+        .filter_map(|s| match s {
+            hir::Stmt::Let(hir::HirLet { init: hir::Expr::Call { callee, .. }, .. }) => match &**callee {
+                hir::Expr::FnRef { name, type_args, .. } => Some((name.clone(), type_args.clone())),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn parses_type_parameters() {
+    let m = Parser::new("fn max<T>(a: T, b: T) -> T { a } fn pair<A, B>(a: A, b: B) -> (A, B) { (a, b) }").parse_module();
+    let names = |f: &ast::Function| f.type_params.iter().map(|p| p.name.clone()).collect::<Vec<_>>();
+    let ast::Item::Function(max) = &m.items[0] else { panic!("expected fn") };
+    assert_eq!(names(max), ["T"]);
+    let ast::Item::Function(pair) = &m.items[1] else { panic!("expected fn") };
+    assert_eq!(names(pair), ["A", "B"]);
+}
+
+#[test]
+fn infer_ctx_unifies_structural_types() {
+    let mut cx = InferCtx::new();
+    let TvOrTy::Var(t) = cx.fresh(Default::default()) else { panic!("expected a variable") };
+    let vars = HashMap::from([("T".to_string(), t)]);
+    let param = Ty::from_type(&Type::Vec(Box::new(Type::Tuple(vec![Type::Param("T".into()), Type::Bool]))), &vars);
+    let arg = Ty::from_type(&Type::Vec(Box::new(Type::Tuple(vec![Type::Str, Type::Bool]))), &HashMap::new());
+    cx.constrain(arg, param);
+    cx.solve();
+    assert!(cx.errors.is_empty(), "{:?}", cx.errors);
+    assert_eq!(cx.apply(TvOrTy::Var(t)), TvOrTy::Ty(Ty::Str));
+
+    // Int se penje u Float samo na vrhu, a ne unutar Vec-a
+    let mut cx = InferCtx::new();
+    let TvOrTy::Var(t) = cx.fresh(Default::default()) else { panic!("expected a variable") };
+    let (u, float) = (cx.fresh(Default::default()), TvOrTy::Ty(Ty::Float));
+    cx.constrain(Ty::Int, Ty::Var(t));
+    cx.constrain(Ty::Int, match u { TvOrTy::Var(u) => Ty::Var(u), _ => unreachable!() });
+    cx.constrain(Ty::Float, Ty::Var(t));
+    cx.solve();
+    assert_eq!(cx.apply(TvOrTy::Var(t)), float);
+    assert_eq!(cx.apply(u), TvOrTy::Ty(Ty::Int));
+    cx.constrain(Ty::Vec(Box::new(Ty::Int)), Ty::Vec(Box::new(Ty::Float)));
+    cx.solve();
+    assert_eq!(cx.errors.len(), 1);
+}
+
+#[test]
+fn type_arguments_are_inferred_at_call_sites() {
+    let m = resolve_ok(
+        "fn pick<T>(first: Bool, a: T, b: T) -> T { if first { a } else { b } }
+         fn swap<A, B>(p: (A, B)) -> (B, A) { (p.1, p.0) }
+         fn apply<T, R>(f: fn(T) -> R, x: T) -> R { f(x) }
+         fn main() { let a = pick(true, 1, 2); let b = pick(false, 1, 2.5); let c = swap((\"s\", [1, 2]));
+                     let d = apply(|x: Int| x > 0, 3); }",
+    );
+    let arr = Type::Array(Box::new(Type::Int), 2);
+    assert_eq!(
+        calls(&m),
+        [
+            ("pick".into(), vec![Type::Int]),
+            ("pick".into(), vec![Type::Float]),
+            ("swap".into(), vec![Type::Str, arr.clone()]),
+            ("apply".into(), vec![Type::Int, Type::Bool]),
+        ]
+    );
+    let Some(hir::Item::Fn(main)) = m.items.last() else { panic!("expected fn") };
+    let hir::Stmt::Let(c) = &main.body.stmts[2] else { panic!("expected let") };
+    assert_eq!(c.ty, Type::Tuple(vec![arr, Type::Str]));
+}
+
+#[test]
+fn generic_errors() {
+    resolve_err("fn add<T>(a: T, b: T) -> T { a + b }", "cannot apply Plus to types `T` and `T`");
+    resolve_err("fn show<T>(x: T) { print(x); }", "print unsupported type");
+    resolve_err("fn f<T, T>(x: T) { }", "type parameter `T` is declared twice");
+    resolve_err("fn same<T>(a: T, b: T) { } fn main() { same(1, true); }", "expected Int, got Bool");
+    resolve_err("fn first<T>(v: Vec<T>) { } fn main() { first(5); }", "expected Vec<T>, got Int");
+    resolve_err("fn make<T>() -> Int { 1 } fn main() { make(); }", "cannot infer type parameter `T` of `make`");
+    resolve_err("fn id<T>(x: T) -> T { x } fn main() { let f = id; }", "generic function `id` can only be called");
+    resolve_err("fn w<T>(x: T) { } fn main() { spawn w(1); }", "cannot spawn generic function `w`");
+    resolve_err("fn grow<T>(x: T) { grow((x, x)); }", "recursive call of `grow` must use its own type parameters");
+    // zahtev da T stane u reč prelazi i kroz druge generičke funkcije
+    let keep = "fn keep<T>(x: T) { let mut v = vec::<T>(); push(v, x); }";
+    resolve_err(&format!("{keep} fn main() {{ keep((1, 2)); }}"), "`keep` puts `T` in a Vec, so it cannot be a tuple");
+    resolve_err(
+        &format!("{keep} fn wrap<U>(u: U) {{ keep(u); }} fn main() {{ wrap([1, 2]); }}"),
+        "`wrap` puts `U` in a Vec, so it cannot be an array",
+    );
+    resolve_err(&format!("{keep} fn main() {{ keep(vec::<Int>()); }}"), "so it cannot be another Vec, got Vec<Int>");
+    resolve_ok(&format!("{keep} fn main() {{ keep(\"s\"); keep(1.5); }}"));
+
+    // vrednost tipa T se premešta, jer T može biti i Vec
+    let errs = borrow_check(&resolve_ok("fn dup<T>(x: T) -> (T, T) { (x, x) }"));
+    assert!(errs.iter().any(|e| e.msg.contains("value `x` is moved twice")), "{errs:?}");
+}
+
+#[test]
+fn instances_are_lowered_under_mangled_names() {
+    let m = resolve_ok(
+        "fn id<T>(x: T) -> T { x }
+         fn twice<T>(f: fn(T) -> T, x: T) -> T { f(id(f(x))) }
+         fn main() { print(twice(|n: Int| n + 1, id(1))); print(id(\"s\")); let p = id((1, true)); }",
+    );
+    let mir_mod = mir::lower_module(&m);
+    let mut names: Vec<_> = mir_mod.fns.iter().map(|f| f.name.as_str()).collect();
+    names.sort();
+    assert_eq!(names, ["id<(Int,Bool)>", "id<Int>", "id<String>", "main", "main.closure0", "twice<Int>"]);
+    let id_str = mir_mod.fns.iter().find(|f| f.name == "id<String>").unwrap();
+    assert_eq!(id_str.body.ret_ty, mir::MirType::Str);
+
+    let mut llcx = LlvmCtx::new("test");
+    codegen_module(&mut llcx, &mir_mod);
+    llcx.module.verify().unwrap_or_else(|e| panic!("{}", e.to_string()));
+    let ir = llcx.module.print_to_string().to_string();
+    assert!(ir.contains("define i32 @\"twice<Int>\"({ i8*, i8* }"), "{ir}");
+    assert!(ir.contains("call i32 @\"id<Int>\""), "{ir}");
+}
@@ -0,0 +1,65 @@
+use aethc_core::{
+    codegen::{LlvmCtx, codegen_module},
+    mir::{self, Constant, MirModule},
+    parser::Parser,
+    resolver::resolve,
+};
+
+fn lower(src: &str) -> MirModule {
+    let (hir_mod, errs) = resolve(&Parser::new(src).parse_module());
+    assert!(errs.is_empty(), "resolve errs: {errs:?}");
+    mir::lower_module(&hir_mod)
+}
+
+fn llvm_ir(src: &str) -> String {
+    let mut llcx = LlvmCtx::new("test");
+    codegen_module(&mut llcx, &lower(src));
+    llcx.module.verify().unwrap_or_else(|e| panic!("{}", e.to_string()));
+    llcx.module.print_to_string().to_string()
+}
+
+#[test]
+fn constant_initializer_is_folded() {
+    let m = lower("let two = 1 + 1; let neg = -2.5; fn main() { }");
+    assert!(matches!(m.globals[0].init, Some(Constant::Int(2))));
+    assert!(matches!(m.globals[1].init, Some(Constant::Float(v)) if v == -2.5));
+    assert!(m.init.is_none());
+}
+
+#[test]
+fn non_constant_initializer_uses_init_fn() {
+    let m = lower("let mut a = 2; let b = a * 3; fn main() { }");
+    assert!(m.globals[0].init.is_some());
+    assert!(m.globals[1].init.is_none());
+    assert!(m.init.is_some());
+}
+
+#[test]
+fn globals_become_llvm_globals() {
+    let ir = llvm_ir("let two = 2; let mut count = 0; fn main() { count = count + two; print(count); }");
+    assert!(ir.contains("@two = constant i32 2"), "{ir}");
+    assert!(ir.contains("@count = global i32 0"), "{ir}");
+    assert!(ir.contains("load i32, i32* @count"), "{ir}");
+    assert!(ir.contains("store i32 %"), "{ir}");
+}
+
+#[test]
+fn main_runs_global_initializer() {
+    let ir = llvm_ir("fn three() -> Int { print(3); 3 } let x = three(); fn main() { print(x); }");
+    assert!(ir.contains("define void @__aethc_init_globals()"), "{ir}");
+    assert!(ir.contains("call void @__aethc_init_globals()"), "{ir}");
+}
+
+#[test]
+fn immutable_global_cannot_be_assigned() {
+    let src = "let limit = 10; fn main() { limit = 11; }";
+    let (_hir, errs) = resolve(&Parser::new(src).parse_module());
+    assert!(errs.iter().any(|e| e.msg.contains("cannot reassign immutable binding `limit`")));
+}
+
+#[test]
+fn strings_compare_by_content() {
+    let ir = llvm_ir("let NAME = \"a\"; fn main() { if NAME == \"a\" { print(1); } if \"a\" != \"b\" { print(2); } }");
+    assert!(ir.contains("call i32 @aethc_str_cmp("), "{ir}");
+    assert!(ir.contains("icmp eq i32") && ir.contains("icmp ne i32"), "{ir}");
+}
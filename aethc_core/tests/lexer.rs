@@ -50,3 +50,12 @@ fn unit_tokens() {
     assert_tokens("()", &[LParen, RParen]);
 }
  
+
+#[test]
+fn string_spans_cover_prefix_and_quotes() {
+    let mut lex = Lexer::new(r#""hi" b"hi""#);
+    let s = lex.next_token().span;
+    assert_eq!((s.start, s.end), (0, 4));
+    let b = lex.next_token().span;
+    assert_eq!((b.start, b.end), (5, 10));
+}
@@ -0,0 +1,167 @@
+use aethc_core::{
+    ast,
+    borrowck::borrow_check,
+    hir,
+    lexer::{Lexer, TokenKind},
+    parser::Parser,
+    type_::Type,
+};
+
+mod common;
+
+use common::{resolve_err, resolve_ok};
+
+fn region_ok(src: &str) {
+    let errs = borrow_check(&resolve_ok(src));
+    assert!(errs.is_empty(), "expected ok, got {errs:?}");
+}
+
+fn region_err(src: &str, msg: &str) {
+    let errs = borrow_check(&resolve_ok(src));
+    assert!(errs.iter().any(|e| e.msg.contains(msg)), "expected `{msg}`, got {errs:?}");
+}
+
+fn fn_named<'m>(m: &'m hir::HirModule, name: &str) -> &'m hir::HirFn {
+    m.items
+        .iter()
+        .find_map(|it| match it {
+            hir::Item::Fn(f) if f.name == name => Some(f),
+            _ => None,
+        })
+        .unwrap_or_else(|| panic!("no fn `{name}`"))
+}
+
+fn int_ref(lifetime: &str) -> Type {
+    Type::Ref { mutability: false, inner: Box::new(Type::Int), lifetime: Some(lifetime.to_string()) }
+}
+
+#[test]
+fn parses_lifetime_params_and_refs() {
+    let mut lex = Lexer::new("&'a mut x 'static");
+    let kinds: Vec<TokenKind> = std::iter::from_fn(|| Some(lex.next_token().kind)).take_while(|k| *k != TokenKind::Eof).collect();
+    assert_eq!(
+        kinds,
+        [TokenKind::Amp, TokenKind::Lifetime("a".into()), TokenKind::Mut, TokenKind::Ident("x".into()), TokenKind::Lifetime("static".into())]
+    );
+
+    let m = Parser::new("fn f<'a, 'b, T: Show>(x: &'a mut T, y: &Int) -> &'b T { x } impl<'a> Int { }").parse_module();
+    let ast::Item::Function(f) = &m.items[0] else { panic!("expected fn") };
+    assert_eq!(f.lifetimes, ["a", "b"]);
+    assert_eq!(f.type_params.len(), 1);
+    assert!(matches!(&f.params[0].ty, Some(ast::TypeExpr::Ref { mutable: true, lifetime: Some(l), .. }) if l == "a"));
+    assert!(matches!(&f.params[1].ty, Some(ast::TypeExpr::Ref { mutable: false, lifetime: None, .. })));
+    let ast::Item::Impl(imp) = &m.items[1] else { panic!("expected impl") };
+    assert_eq!(imp.lifetimes, ["a"]);
+}
+
+#[test]
+fn elision_names_the_lifetimes_of_a_signature() {
+    let m = resolve_ok(
+        "fn first(t: &(Int, Int)) -> &Int { &t.0 }
+         fn pick<'a>(x: &'a Int, y: &Int) -> &'a Int { x }
+         impl Int { fn me(&self, o: &Int) -> &Int { &self } }",
+    );
+    let first = fn_named(&m, "first");
+    assert_eq!(first.return_ty, Type::Ref { mutability: false, inner: Box::new(Type::Int), lifetime: None });
+    let Type::Ref { lifetime: Some(l), .. } = &first.return_ty else { panic!("expected a named lifetime") };
+    assert_eq!(l, "0");
+    // lifetimes don't make types different, and elided ones aren't printed
+    assert_eq!(format!("{:?}", first.return_ty), "&Int");
+    let pick = fn_named(&m, "pick");
+    assert_eq!(format!("{:?} {:?}", pick.params[1].ty, pick.return_ty), "&Int &'a Int");
+    let Type::Ref { lifetime: Some(l), .. } = &pick.params[1].ty else { panic!("expected a named lifetime") };
+    assert_eq!(l, "1");
+    let Type::Ref { lifetime, .. } = &fn_named(&m, "Int::me").return_ty else { panic!("expected a reference") };
+    assert_eq!(lifetime.as_deref(), Some("0"));
+    assert_eq!(int_ref("a"), int_ref("b"));
+
+    resolve_err("fn f() -> &Int { &1 }", "missing lifetime specifier in the return type of `f`: it has no reference parameter to borrow from");
+    resolve_err("fn f(a: &Int, b: &Int) -> &Int { a }", "it could borrow from more than one parameter");
+    resolve_err("fn f(a: &'b Int) { }", "use of undeclared lifetime name `'b`");
+    resolve_err("fn f<'a, 'a>(a: &'a Int) { }", "lifetime `'a` is declared twice");
+    resolve_err("fn f<'static>(a: &'static Int) { }", "`'static` is a reserved lifetime name");
+    resolve_err("trait T { fn get(self, a: &Int, b: &Int) -> &Int; }", "missing lifetime specifier in the return type of `get`");
+    resolve_ok("fn f(a: &Int, b: &Int) -> &'static Int { a } fn g(a: &Int) -> Int { *a }");
+}
+
+#[test]
+fn returned_references_borrow_from_the_signature() {
+    region_ok(
+        "fn longest<'a>(x: &'a Int, y: &'a Int) -> &'a Int { if *x > *y { x } else { y } }
+         fn first(t: &(Int, Int)) -> &Int { &t.0 }
+         fn chain<'a>(x: &'a Int, y: &Int) -> &'a Int { let r = longest(x, x); print(*y); first(&(1, 2)); r }
+         impl Int { fn me(&self) -> &Int { &self } }",
+    );
+    region_err("fn f<'a>(a: &'a Int) -> &'a Int { let x = 1; &x }", "cannot return a reference to local variable `x`");
+    region_err("fn f(x: Int) -> &'static Int { &x }", "cannot return a reference to function parameter `x`");
+    region_err("fn f(a: &Int) -> &Int { &(*a + 1) }", "cannot return a reference to a temporary value");
+    region_err(
+        "fn f<'a, 'b>(a: &'a Int, b: &'b Int) -> &'a Int { if *a > 0 { return b; } a }",
+        "lifetime mismatch: `f` returns a value borrowed from `'b`, but its return type borrows from `'a`",
+    );
+    region_err(
+        "impl Int { fn get(&self, o: &Int) -> &Int { o } }",
+        "returns a value borrowed from parameter `o`, but its return type borrows from parameter `self`",
+    );
+}
+
+#[test]
+fn calls_return_what_their_arguments_borrow() {
+    let src = |body: &str| {
+        format!(
+            "fn longest<'a>(x: &'a Int, y: &'a Int) -> &'a Int {{ if *x > *y {{ x }} else {{ y }} }}
+             fn pick<'a>(x: &'a Int, y: &Int) -> &'a Int {{ x }}
+             fn id<T>(x: T) -> T {{ x }}
+             {body}"
+        )
+    };
+    region_ok(&src("fn f<'a>(a: &'a Int) -> &'a Int { let x = 1; pick(a, &x) }"));
+    region_err(&src("fn f<'a>(a: &'a Int) -> &'a Int { let x = 1; longest(a, &x) }"), "cannot return a reference to local variable `x`");
+    region_err(&src("fn f(a: &Int) -> &Int { let x = 2; id(&x) }"), "cannot return a reference to local variable `x`");
+    region_err(&src("fn f(a: &Int) -> (&Int, Int) { let x = 2; (id(&x), 1) }"), "cannot return a reference to local variable `x`");
+    region_err(&src("fn f(a: &Int) -> Option<&Int> { let x = 2; Some(&x) }"), "cannot return a reference to local variable `x`");
+}
+
+#[test]
+fn references_cannot_outlive_their_block() {
+    region_ok(
+        "let G = 5;
+         fn g() -> &'static Int { &G }
+         fn main() { let a = 1; let mut r = &a; { let x = 9; let s = &x; print(*s); } r = &a; print(*r); }",
+    );
+    region_err("fn main() { let r = { let x = 5; &x }; print(*r); }", "`x` does not live long enough: the block's value borrows it");
+    region_err(
+        "fn main() { let a = 1; let mut r = &a; { let x = 5; r = &x; } print(*r); }",
+        "`x` does not live long enough: `r` still holds a reference to it",
+    );
+    region_err(
+        "fn main() { let a = 1; let mut v = vec::<&Int>(); push(v, &a); if a > 0 { let x = 2; push(v, &x); } }",
+        "`x` does not live long enough: `v` still holds a reference to it",
+    );
+    region_err("fn main() { let a = 1; let r = if a > 0 { &a } else { let y = 3; &y }; }", "`y` does not live long enough");
+}
+
+#[test]
+fn closures_carry_the_references_they_capture() {
+    region_ok(
+        "fn counter() -> fn() -> Int { let mut n = 0; || { n = n + 1; n } }
+         fn copy() -> fn() -> Int { let x = 5; move || x }
+         fn main() { let x = 1; let r = &x; let f = move || *r; print(f()); }",
+    );
+    region_err(
+        "fn make() -> fn() -> Int { let x = 5; let r = &x; move || *r }",
+        "cannot return a reference to local variable `x`",
+    );
+    region_err(
+        "fn make() -> fn() -> Int { let x = 5; let r = &x; let f = || *r; return f; }",
+        "cannot return a reference to local variable `x`",
+    );
+    region_err(
+        "fn make(p: &Int) -> fn() -> Int { move || *p }",
+        "`make` returns a value borrowed from parameter `p`, but its return type borrows from nothing",
+    );
+    region_err(
+        "fn main() { let mut f = || 0; { let y = 3; let r = &y; f = move || *r; } print(f()); }",
+        "`y` does not live long enough: `f` still holds a reference to it",
+    );
+}
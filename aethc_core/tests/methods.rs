@@ -0,0 +1,129 @@
+use aethc_core::{
+    ast,
+    borrowck::borrow_check,
+    codegen::{LlvmCtx, codegen_module},
+    hir,
+    mir,
+    parser::Parser,
+    type_::Type,
+};
+
+mod common;
+
+use common::{borrow_err, resolve_err, resolve_ok};
+
+const INT: &str = "impl Int { fn double(&self) -> Int { self * 2 } fn inc(&mut self) { self = self + 1; }
+                              fn zero() -> Int { 0 } fn take(self) -> Int { self } }";
+const VEC: &str = "impl<T> Vec<T> { fn add(&mut self, x: T) { push(self, x); } fn size(&self) -> Int { len(self) }
+                                   fn into_first(self) -> T { self[0] } }";
+
+#[test]
+fn parses_inherent_impls_and_method_calls() {
+    let m = Parser::new("impl<T> Vec<T> { fn a(&self) { } fn b(&mut self, x: T) { } fn c(self) { } fn d() { } }")
+        .parse_module();
+    let ast::Item::Impl(imp) = &m.items[0] else { panic!("expected impl") };
+    assert!(imp.trait_path.is_none());
+    let ast::TypeExpr::Named { name, args } = &imp.self_ty else { panic!("expected a named type") };
+    assert_eq!((name.as_str(), args.len()), ("Vec", 1));
+    let modes: Vec<_> = imp.methods.iter().map(|f| f.self_mode).collect();
+    use ast::SelfMode::*;
+    assert_eq!(modes, [Some(Ref), Some(RefMut), Some(Value), None]);
+    assert_eq!(imp.methods[1].params.len(), 2);
+
+    // `.0` se odnosi na rezultat poziva
+    let m = Parser::new("fn main() { let x = v.pair(1, 2).0; }").parse_module();
+    let ast::Item::Function(main) = &m.items[0] else { panic!("expected fn") };
+    let ast::Stmt::Let { expr: ast::Expr::Field { base, index: 0 }, .. } = &main.body.stmts[0] else {
+        panic!("expected a field access, got {:?}", main.body.stmts[0])
+    };
+    let ast::Expr::MethodCall { receiver, method, args } = &**base else { panic!("expected a method call") };
+    assert!(matches!(&**receiver, ast::Expr::Ident(v) if v == "v"));
+    assert_eq!((method.as_str(), args.len()), ("pair", 2));
+}
+
+#[test]
+fn receivers_are_passed_by_reference() {
+    let m = resolve_ok(&format!("{INT} fn main() {{ let mut n = Int::zero(); n.inc(); let d = n.double(); let t = 5.take(); }}"));
+    let Some(hir::Item::Fn(main)) = m.items.last() else { panic!("expected fn") };
+    let args = |i: usize| match &main.body.stmts[i] {
+        hir::Stmt::Semi(hir::Expr::Call { callee, args, .. })
+        | hir::Stmt::Let(hir::HirLet { init: hir::Expr::Call { callee, args, .. }, .. }) => {
+            let hir::Expr::Ident { name, .. } = &**callee else { panic!("expected a named callee") };
+            (name.clone(), args[0].clone())
+        }
+        s => panic!("expected a call, got {s:?}"),
+    };
+    let (name, recv) = args(1);
+    assert_eq!(name, "Int::inc");
+    assert!(matches!(recv, hir::Expr::Ref { mutable: true, ty: Type::Ref { mutability: true, .. }, .. }));
+    let (name, recv) = args(2);
+    assert_eq!(name, "Int::double");
+    assert!(matches!(recv, hir::Expr::Ref { mutable: false, .. }));
+    let (name, recv) = args(3);
+    assert_eq!(name, "Int::take");
+    assert!(matches!(recv, hir::Expr::Int { value: 5, .. }));
+}
+
+#[test]
+fn method_errors() {
+    resolve_err(&format!("{INT} fn main() {{ let n = 1; n.inc(); }}"), "cannot borrow `n` as mutable for `Int::inc`");
+    resolve_err(&format!("{INT} fn main() {{ 5.inc(); }}"), "`Int::inc` takes `&mut self`, so its receiver must be a variable");
+    resolve_err(&format!("{INT} fn main() {{ let f = Int::double; }}"), "method `Int::double` takes `&self`, so it can only be called");
+    resolve_err(&format!("{INT} fn main() {{ true.double(); }}"), "no method named `double` for type `Bool`");
+    resolve_err(&format!("{INT} fn main() {{ Int::triple(1); }}"), "no function or method named `triple` for type `Int`");
+    resolve_err(&format!("{INT} impl Int {{ fn double(self) -> Int {{ 2 }} }}"), "duplicate definitions of method `double` for `Int`");
+    resolve_err("impl Int { fn f(&self) { self = 2; } }", "cannot reassign immutable binding `self`");
+    resolve_err("impl Int { fn f(&self) -> fn() -> Int { || self } }", "closure may outlive the method, but it borrows `self`");
+    resolve_ok("impl Int { fn f(&self) -> fn() -> Int { move || self } }");
+    resolve_err("impl<T> T { fn f(&self) { } }", "cannot define methods for type parameter `T`");
+    resolve_err("impl<U> Int { fn f(&self) { } }", "type parameter `U` of the impl for `Int` is not used by it");
+    resolve_err("impl Int { fn f(x: Int, self) { } }", "`self` must be the first parameter of `f`");
+    resolve_err(
+        "impl Vec<Int> { fn s(&self) -> Int { 1 } } impl Vec<Bool> { fn s(&self) -> Int { 2 } } fn main() { Vec::s(vec::<Int>()); }",
+        "`Vec::s` is ambiguous",
+    );
+    resolve_err(
+        "trait A { fn m(&self); } trait B { fn m(&self); } impl A for Int { fn m(&self) { } } impl B for Int { fn m(&self) { } }
+         fn main() { 1.m(); }",
+        "multiple traits provide method `m` for `Int`: `A`, `B`",
+    );
+    resolve_err(
+        "trait A { fn m(&self); } impl A for Int { fn m(self) { } }",
+        "method `m` of the impl of `A` for `Int` takes `self`, but the trait declares `&self`",
+    );
+}
+
+#[test]
+fn receiver_modes_are_borrow_checked() {
+    // `&self` i `&mut self` ne premeštaju primaoca, `self` ga premešta
+    let ok = format!("{VEC} fn main() {{ let mut v = vec::<Int>(); v.add(1); v.add(v.size()); let n = Vec::size(v); print(v.into_first()); }}");
+    assert!(borrow_check(&resolve_ok(&ok)).is_empty());
+    borrow_err(&format!("{VEC} fn main() {{ let v = vec::<Int>(); let x = v.into_first(); let n = v.size(); }}"), "use of moved value `v`");
+    borrow_err(
+        &format!("{VEC} impl Vec<Int> {{ fn steal(&self) -> Vec<Int> {{ self }} }}"),
+        "cannot move out of `self`, which is behind a reference",
+    );
+}
+
+#[test]
+fn methods_lower_to_functions_taking_addresses() {
+    let m = resolve_ok(&format!(
+        "{INT} {VEC} fn main() {{ let mut n = 1; n.inc(); print(n.double()); let mut v = vec::<Str>(); v.add(\"a\"); print(v.size()); }}"
+    ));
+    let mir_mod = mir::lower_module(&m);
+    let mut names: Vec<_> = mir_mod.fns.iter().map(|f| f.name.as_str()).collect();
+    names.sort();
+    assert_eq!(
+        names,
+        ["Int::double", "Int::inc", "Int::take", "Int::zero", "Vec<T>::add<String>", "Vec<T>::size<String>", "main"]
+    );
+    let inc = mir_mod.fns.iter().find(|f| f.name == "Int::inc").unwrap();
+    assert!(inc.body.self_ref && inc.body.boxed.contains(&inc.body.params[0]));
+
+    let mut llcx = LlvmCtx::new("test");
+    codegen_module(&mut llcx, &mir_mod);
+    llcx.module.verify().unwrap_or_else(|e| panic!("{}", e.to_string()));
+    let ir = llcx.module.print_to_string().to_string();
+    assert!(ir.contains("define void @\"Int::inc\"(i32*"), "{ir}");
+    assert!(ir.contains("define i32 @\"Int::take\"(i32"), "{ir}");
+}
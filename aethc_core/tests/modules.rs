@@ -0,0 +1,209 @@
+use aethc_core::{
+    ast, hir, loader,
+    parser::Parser,
+    resolver::{ModuleAst, ResolveDiagnostics, resolve_modules},
+};
+
+/// Parsira `(put, izvor)` parove i razrešava ih kao jedno stablo modula.
+fn resolve_tree(srcs: &[(&str, &str)]) -> (hir::HirModule, ResolveDiagnostics) {
+    let asts: Vec<ast::Module> = srcs.iter().map(|(_, s)| Parser::new(s).parse_module()).collect();
+    let mods: Vec<ModuleAst> = srcs
+        .iter()
+        .zip(&asts)
+        .enumerate()
+        .map(|(file, ((path, _), ast))| ModuleAst {
+            path: path.split("::").filter(|s| !s.is_empty()).map(String::from).collect(),
+            file,
+            ast,
+        })
+        .collect();
+    resolve_modules(&mods)
+}
+
+fn fn_names(m: &hir::HirModule) -> Vec<String> {
+    m.items
+        .iter()
+        .filter_map(|it| match it {
+            hir::Item::Fn(f) => Some(f.name.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn tree_ok(srcs: &[(&str, &str)]) -> hir::HirModule {
+    let (m, diags) = resolve_tree(srcs);
+    assert!(diags.errors.is_empty(), "expected ok, got {:?}", diags.errors);
+    m
+}
+
+fn tree_err(srcs: &[(&str, &str)], msg: &str) -> usize {
+    let errs = resolve_tree(srcs).1.errors;
+    let e = errs.iter().find(|e| e.err.msg.contains(msg));
+    e.unwrap_or_else(|| panic!("expected `{msg}`, got {errs:?}")).file
+}
+
+#[test]
+fn qualified_paths_and_names() {
+    let m = tree_ok(&[
+        ("", "mod geom; fn main() { print(geom::area(2, 3)); }"),
+        ("geom", "mod shapes; pub fn area(w: Int, h: Int) -> Int { shapes::rect(w, h) }"),
+        ("geom::shapes", "pub fn rect(w: Int, h: Int) -> Int { w * h }"),
+    ]);
+    let names = fn_names(&m);
+    // zavisnosti se spuštaju pre modula koji ih koristi
+    assert_eq!(names, ["geom::shapes::rect", "geom::area", "main"]);
+}
+
+#[test]
+fn use_imports_items_and_modules() {
+    tree_ok(&[
+        ("", "mod a; mod b; use a::twice; fn main() { print(twice(b::one())); }"),
+        ("a", "pub fn twice(x: Int) -> Int { x * 2 }"),
+        ("b", "use crate::a; const ONE: Int = 1; pub fn one() -> Int { a::twice(ONE) - ONE }"),
+    ]);
+}
+
+#[test]
+fn unresolved_names() {
+    tree_err(&[("", "use nope::f; fn main() { }")], "unresolved import `nope::f`");
+    tree_err(&[("", "fn main() { print(m::f()); }")], "unresolved module `m`");
+    let file = tree_err(
+        &[("", "mod m; fn main() { print(m::g()); }"), ("m", "fn f() -> Int { 1 }")],
+        "cannot find `g` in module `m`",
+    );
+    assert_eq!(file, 0);
+}
+
+#[test]
+fn errors_name_their_file() {
+    let file = tree_err(
+        &[("", "mod m; fn main() { m::f(); }"), ("m", "pub fn f() { print(x); }")],
+        "x",
+    );
+    assert_eq!(file, 1);
+}
+
+#[test]
+fn import_cycle_is_reported() {
+    tree_err(
+        &[
+            ("", "mod a; mod b; fn main() { a::f(); }"),
+            ("a", "use crate::b; pub fn f() { b::g(); }"),
+            ("b", "use crate::a; pub fn g() { a::f(); }"),
+        ],
+        "import cycle: `a` -> `b` -> `a`",
+    );
+}
+
+#[test]
+fn loader_reads_module_files() {
+    let dir = std::env::temp_dir().join(format!("aethc_modules_{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("geom")).unwrap();
+    std::fs::write(dir.join("main.ae"), "mod geom; mod util; fn main() { }").unwrap();
+    std::fs::write(dir.join("geom.ae"), "mod shapes;").unwrap();
+    std::fs::write(dir.join("geom/shapes.ae"), "fn area() -> Int { 1 }").unwrap();
+    std::fs::create_dir_all(dir.join("util")).unwrap();
+    std::fs::write(dir.join("util/mod.ae"), "fn id(x: Int) -> Int { x }").unwrap();
+
+    let tree = loader::load(&dir.join("main.ae")).unwrap_or_else(|(_, e)| panic!("{e:?}"));
+    let paths: Vec<_> = tree.modules.iter().map(|m| m.path.join("::")).collect();
+    assert_eq!(paths, ["", "geom", "geom::shapes", "util"]);
+    let (_hir, diags) = resolve_modules(&tree.module_asts());
+    assert!(diags.errors.is_empty(), "{:?}", diags.errors);
+
+    std::fs::write(dir.join("main.ae"), "mod missing; fn main() { }").unwrap();
+    let Err((tree, errs)) = loader::load(&dir.join("main.ae")) else {
+        panic!("expected a missing-file error");
+    };
+    assert!(errs[0].err.msg.contains("file not found for module `missing`"));
+    assert!(tree.files[errs[0].file].path.ends_with("main.ae"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn private_items_stay_in_their_module() {
+    let file = tree_err(
+        &[("", "mod m; fn main() { print(m::f()); }"), ("m", "fn f() -> Int { 1 }")],
+        "`f` is private to module `m`",
+    );
+    assert_eq!(file, 0);
+    tree_err(
+        &[("", "mod m; use m::LIMIT; fn main() { print(LIMIT); }"), ("m", "const LIMIT: Int = 3;")],
+        "`LIMIT` is private to module `m`",
+    );
+    // sibling modules only see each other's `pub` items
+    tree_err(
+        &[
+            ("", "mod a; mod b; fn main() { b::g(); }"),
+            ("a", "let X = 1;"),
+            ("b", "pub fn g() { print(crate::a::X); }"),
+        ],
+        "`X` is private to module `a`",
+    );
+}
+
+#[test]
+fn submodules_see_private_parent_items() {
+    tree_ok(&[
+        ("", "mod m; fn main() { print(m::inner::f()); }"),
+        ("m", "mod inner; const BASE: Int = 10;"),
+        ("m::inner", "pub fn f() -> Int { crate::m::BASE + 1 }"),
+    ]);
+}
+
+#[test]
+fn imports_are_not_reexported() {
+    tree_err(
+        &[
+            ("", "mod a; mod b; fn main() { print(b::f()); }"),
+            ("a", "pub fn f() -> Int { 1 }"),
+            ("b", "use crate::a::f; pub fn g() -> Int { f() }"),
+        ],
+        "`f` is private to module `b`",
+    );
+}
+
+#[test]
+fn unused_private_items_warn() {
+    let (_m, diags) = resolve_tree(&[
+        ("", "mod m; fn helper() { } fn main() { print(m::used()); }"),
+        ("m", "const UNUSED: Int = 1; let mut N = 0; pub fn used() -> Int { 2 } pub fn api() { }"),
+    ]);
+    assert!(diags.errors.is_empty(), "{:?}", diags.errors);
+    let mut msgs: Vec<_> = diags.warnings.iter().map(|w| (w.file, w.err.msg.as_str())).collect();
+    msgs.sort();
+    assert_eq!(
+        msgs,
+        [
+            (0, "function `helper` is never used"),
+            (1, "constant `UNUSED` is never used"),
+            (1, "global `N` is never used"),
+        ]
+    );
+}
+
+#[test]
+fn globals_are_assigned_through_paths() {
+    let m = tree_ok(&[
+        ("", "mod m; fn main() { m::G = 5; m::G += 1; m::T.1 = m::G; crate::m::T.0 = 2; }"),
+        ("m", "pub let mut G = 1; pub let mut T = (0, 0);"),
+    ]);
+    let hir::Item::Fn(main) = m.items.iter().find(|it| matches!(it, hir::Item::Fn(f) if f.name == "main")).unwrap()
+    else {
+        unreachable!()
+    };
+    let hir::Stmt::AssignPlace { target: hir::Expr::Ident { name, .. }, .. } = &main.body.stmts[0] else {
+        panic!("expected an assignment to `m::G`, got {:?}", main.body.stmts[0]);
+    };
+    assert_eq!(name, "m::G");
+    tree_err(
+        &[("", "mod m; fn main() { m::K = 2; }"), ("m", "pub let K = 1;")],
+        "cannot reassign immutable binding `m::K`",
+    );
+    tree_err(
+        &[("", "mod m; fn main() { m::T.0 += 1; }"), ("m", "pub let T = (0, 0);")],
+        "cannot assign to a field of immutable binding `m::T`",
+    );
+    tree_err(&[("", "mod m; fn main() { m::P = 2; }"), ("m", "let mut P = 1;")], "`P` is private to module `m`");
+}
@@ -0,0 +1,215 @@
+use aethc_core::{
+    LexError,
+    codegen::{LlvmCtx, codegen_module},
+    const_eval::ConstValue,
+    hir,
+    lexer::{Lexer, TokenKind},
+    mir,
+    parser::Parser,
+    type_::{NumTy, Type},
+};
+
+mod common;
+
+use common::{resolve_err, resolve_ok};
+
+fn kinds(src: &str) -> Vec<TokenKind> {
+    let mut lex = Lexer::new(src);
+    std::iter::from_fn(|| Some(lex.next_token().kind)).take_while(|k| *k != TokenKind::Eof).collect()
+}
+
+/// The first error the lexer reports in `src`.
+fn lex_err(src: &str) -> LexError {
+    let (_ast, errs) = Parser::new(src).parse();
+    errs.into_iter().next().expect("expected a lexer error")
+}
+
+fn const_value(m: &hir::HirModule, name: &str) -> Option<ConstValue> {
+    m.items.iter().find_map(|it| match it {
+        hir::Item::Const(c) if c.name == name => Some(c.value.clone()),
+        _ => None,
+    })
+}
+
+/// Type of the value of `let <name>` in the first function.
+fn let_ty(m: &hir::HirModule, name: &str) -> Type {
+    let hir::Item::Fn(f) = &m.items[0] else { panic!("expected fn") };
+    f.body
+        .stmts
+        .iter()
+        .find_map(|s| match s {
+            hir::Stmt::Let(l) if l.name == name => Some(l.init.ty().clone()),
+            _ => None,
+        })
+        .unwrap_or_else(|| panic!("no `let {name}`"))
+}
+
+#[test]
+fn lexes_prefixes_separators_and_suffixes() {
+    use TokenKind::*;
+    assert_eq!(kinds("0xFF 0o17 0b1010 1_000_000 0b1111_0000 0x_dead_BEEF"), [
+        Int(255),
+        Int(15),
+        Int(10),
+        Int(1_000_000),
+        Int(0xF0),
+        Int(0xdead_beef)
+    ]);
+    assert_eq!(kinds("2.5 1e3 6.02e-23 1_0.2_5"), [Float(2.5), Float(1e3), Float(6.02e-23), Float(10.25)]);
+    assert_eq!(kinds("10u8 7i64 0xFFu16 2.5f32 2f32 1e2f64"), [
+        TypedInt(10, NumTy::U8),
+        TypedInt(7, NumTy::I64),
+        TypedInt(255, NumTy::U16),
+        TypedFloat(2.5, NumTy::F32),
+        TypedFloat(2.0, NumTy::F32),
+        TypedFloat(100.0, NumTy::F64)
+    ]);
+    // `0x1f32` is a hexadecimal integer, `t.0.1` two tuple fields, `0..2` a range
+    assert_eq!(kinds("0x1f32"), [Int(0x1f32)]);
+    assert_eq!(kinds("t.0.1")[1..], [Dot, Int(0), Dot, Int(1)]);
+    assert_eq!(kinds("0..2"), [Int(0), DotDot, Int(2)]);
+}
+
+#[test]
+fn invalid_literals_name_the_problem() {
+    let cases = [
+        ("0b102", "invalid digit `2` in a binary literal"),
+        ("0o8", "invalid digit `8` in an octal literal"),
+        ("0xG1", "invalid digit `G` in a hexadecimal literal"),
+        ("0b", "missing digits after the `0b` prefix"),
+        ("1.5u8", "invalid suffix `u8` for a float literal"),
+        ("0b1f32", "invalid suffix `f32` for a binary literal"),
+        ("10xyz", "invalid suffix `xyz` for a number literal"),
+        ("1e", "invalid suffix `e` for a number literal"),
+        ("99999999999999999999", "integer literal is too large"),
+    ];
+    for (lit, msg) in cases {
+        let err = lex_err(&format!("fn f() {{ let x = {lit}; }}")).msg;
+        assert!(err.starts_with(msg), "`{lit}`: expected `{msg}`, got `{err}`");
+    }
+    // greška se prijavljuje, a parsiranje ide dalje
+    let (ast, errs) = Parser::new("fn f() {\n  let x = 0b2; let y = 1u7; }").parse();
+    let at: Vec<_> = errs.iter().map(|e| (e.span.line, e.span.column)).collect();
+    assert_eq!(at, [(2, 11), (2, 24)], "{errs:?}");
+    assert_eq!(ast.items.len(), 1);
+}
+
+#[test]
+fn literals_take_the_expected_type() {
+    let m = resolve_ok(
+        "fn f(b: u8) -> u32 {
+             let a = 10u8; let c: u8 = 200; let d = b + 1; let e = 2.5f32 * 2; let g: i8 = -128;
+             let h: [u16; 2] = [1, 2]; let t: (i64, f32) = (1, 2.5); let k = 7i32; let n = [1u8, 2, 3];
+             4_000_000_000
+         }",
+    );
+    let u8 = Type::Num(NumTy::U8);
+    for (name, ty) in [("a", u8.clone()), ("c", u8.clone()), ("d", u8.clone()), ("g", Type::Num(NumTy::I8)), ("k", Type::Int)] {
+        assert_eq!(let_ty(&m, name), ty, "`{name}`");
+    }
+    assert_eq!(let_ty(&m, "e"), Type::Num(NumTy::F32));
+    assert_eq!(let_ty(&m, "h"), Type::Array(Box::new(Type::Num(NumTy::U16)), 2));
+    assert_eq!(let_ty(&m, "t"), Type::Tuple(vec![Type::Num(NumTy::I64), Type::Num(NumTy::F32)]));
+    assert_eq!(let_ty(&m, "n"), Type::Array(Box::new(u8), 3));
+
+    resolve_err("fn f() { let x = 256u8; }", "literal out of range for `u8`");
+    resolve_err("fn f() { let x: i8 = -129; }", "literal out of range for `i8`");
+    resolve_err("fn f(b: u16) -> u16 { b * 70000 }", "literal out of range for `u16`");
+    resolve_err("fn f() { let x: u8 = -1; }", "cannot negate unsigned type `u8`");
+    resolve_err("fn f(x: u32) { let y = -x; }", "cannot negate unsigned type `u32`");
+    resolve_err("fn f() { let x = 5u8; let y: Int = x; }", "expected Int, got u8");
+    resolve_err("fn f() { let x = 5u8 + 1i32; }", "cannot apply Plus to types `u8` and `Int`");
+    resolve_err("fn f() { let x = 1u8; let y = 1.5f32 & 1; }", "cannot apply BitAnd to types `f32` and `f32`");
+    // a suffix fixes the type, even where another is expected
+    resolve_err("fn f() { let x: u8 = 1i64; }", "expected u8, got i64");
+}
+
+#[test]
+fn unsuffixed_literals_must_fit_in_int() {
+    resolve_ok(
+        "const MIN: Int = -2147483648;
+         fn f() -> i64 { let a = 2147483647; let b = -2147483648; let c: i64 = -3000000000; c + 5000000000 }",
+    );
+    resolve_err("fn f() { let y = 2147483648; }", "literal out of range for `Int`");
+    resolve_err("fn f() { let y = -2147483649; }", "literal out of range for `Int`");
+    resolve_err("const X: Int = 3000000000;", "literal out of range for `Int`");
+}
+
+#[test]
+fn constants_are_checked_against_the_width() {
+    let m = resolve_ok(
+        "const A: u8 = ~0 ^ 1 << 7;
+         const B: i8 = -(1 << 6) * 2;
+         const C: u16 = 0xFFFF;
+         const D: i64 = 1 << 40;
+         const E: f32 = 0.1;
+         const F: u8 = 200u8 << 1;",
+    );
+    assert_eq!(const_value(&m, "A"), Some(ConstValue::Int(127)));
+    assert_eq!(const_value(&m, "B"), Some(ConstValue::Int(-128)));
+    assert_eq!(const_value(&m, "C"), Some(ConstValue::Int(65535)));
+    assert_eq!(const_value(&m, "D"), Some(ConstValue::Int(1 << 40)));
+    assert_eq!(const_value(&m, "E"), Some(ConstValue::Float(f64::from(0.1f32))));
+    assert_eq!(const_value(&m, "F"), Some(ConstValue::Int(144)));
+    resolve_err("const C: u8 = 200 + 100;", "evaluation of constant `C` failed: integer overflow");
+    resolve_err("const C: i16 = -32768 - 1;", "integer overflow");
+    resolve_err("const C: u8 = 1 << 8;", "attempt to shift a `u8` by 8 bits");
+    resolve_err("const C: Int = 1 << 32;", "attempt to shift an Int by 32 bits");
+}
+
+#[test]
+fn u64_literals_use_the_whole_range() {
+    use TokenKind::*;
+    // iznad `i64::MAX` vrednost se čuva kao svoji bitovi
+    assert_eq!(kinds("18446744073709551615u64 0xFFFF_FFFF_FFFF_FFFFu64 9223372036854775808u64"), [
+        TypedInt(-1, NumTy::U64),
+        TypedInt(-1, NumTy::U64),
+        TypedInt(i64::MIN, NumTy::U64)
+    ]);
+    for lit in ["18446744073709551616u64", "9223372036854775808i64", "9223372036854775808"] {
+        let err = lex_err(&format!("fn f() {{ let x = {lit}; }}")).msg;
+        assert!(err.starts_with("integer literal is too large"), "`{lit}`: got `{err}`");
+    }
+    let m = resolve_ok(
+        "const MAX: u64 = 18446744073709551615u64;
+         const HALF: u64 = MAX / 2;
+         const TOP: u64 = MAX >> 63;
+         const ALL: u64 = ~0;
+         const GT: Bool = MAX > 1u64;",
+    );
+    assert_eq!(const_value(&m, "MAX"), Some(ConstValue::Int(-1)));
+    assert_eq!(const_value(&m, "HALF"), Some(ConstValue::Int(i64::MAX)));
+    assert_eq!(const_value(&m, "TOP"), Some(ConstValue::Int(1)));
+    assert_eq!(const_value(&m, "ALL"), Some(ConstValue::Int(-1)));
+    assert_eq!(const_value(&m, "GT"), Some(ConstValue::Bool(true)));
+    resolve_err("const C: u64 = 18446744073709551615u64 + 1;", "integer overflow");
+    resolve_err("const C: u64 = 1u64 - 2;", "integer overflow");
+}
+
+#[test]
+fn codegen_follows_width_and_signedness() {
+    let m = resolve_ok(
+        "let G = 3_000_000_000u32;
+         fn f(a: u8, b: u8) -> u8 { if a < b { a / b } else { a >> 1 } }
+         fn g(a: i16, b: i16) -> i16 { if a < b { a % b } else { a >> 1 } }
+         fn k(x: f32) -> f32 { x + 2.0 }
+         fn main() {
+             print(f(200, 3)); print(g(-7, 2)); print(G); print(9_000_000_000i64);
+             let h = k(1.5);
+             let mut v = vec::<f32>(); push(v, h);
+             let mut w = vec::<u64>(); push(w, 7);
+             print(w[0]);
+         }",
+    );
+    let main = mir::lower_module(&m);
+    let mut llcx = LlvmCtx::new("test");
+    codegen_module(&mut llcx, &main);
+    llcx.module.verify().unwrap_or_else(|e| panic!("{}", e.to_string()));
+    let ir = llcx.module.print_to_string().to_string();
+    for inst in [
+        "icmp ult i8", "udiv i8", "lshr i8", "icmp slt i16", "srem i16", "ashr i16", "fadd float", "@aethc_print_u64",
+        "@aethc_print_i64", "i32 -1294967296",
+    ] {
+        assert!(ir.contains(inst), "missing `{inst}` in {ir}");
+    }
+}
@@ -0,0 +1,105 @@
+use aethc_core::{
+    codegen::{LlvmCtx, codegen_module},
+    hir,
+    mir,
+    type_::Type,
+};
+
+mod common;
+
+use common::{resolve_err, resolve_ok};
+
+const PAIR: &str = "impl Add for (Int, Int) { fn add(self, rhs: Self) -> Self { (self.0 + rhs.0, self.1 + rhs.1) } }
+                    impl Neg for (Int, Int) { fn neg(self) -> Self { (-self.0, -self.1) } }
+                    impl Eq for (Int, Int) { fn eq(&self, other: &Self) -> Bool { (self.0 == other.0) && (self.1 == other.1) } }
+                    impl Ord for (Int, Int) { fn cmp(&self, other: &Self) -> Int { self.0 - other.0 } }";
+
+/// Inicijalizator `let`-a broj `i` u poslednjoj funkciji.
+fn init(m: &hir::HirModule, i: usize) -> &hir::Expr {
+    let Some(hir::Item::Fn(f)) = m.items.last() else { panic!("expected fn") };
+    let hir::Stmt::Let(l) = &f.body.stmts[i] else { panic!("expected let, got {:?}", f.body.stmts[i]) };
+    &l.init
+}
+
+fn method_call(e: &hir::Expr) -> (&str, &str, &Type, &[hir::Expr]) {
+    let hir::Expr::Call { callee, args, .. } = e else { panic!("expected a call, got {e:?}") };
+    let hir::Expr::MethodRef { trait_name, method, self_ty, .. } = &**callee else { panic!("expected a method") };
+    (trait_name, method, self_ty, args)
+}
+
+#[test]
+fn operators_on_user_types_become_trait_calls() {
+    let m = resolve_ok(&format!("{PAIR} fn main() {{ let p = (1, 2) + (3, 4); let n = -p; let s = 1 + 2; }}"));
+    let pair = Type::Tuple(vec![Type::Int, Type::Int]);
+    let (trait_name, method, self_ty, args) = method_call(init(&m, 0));
+    assert_eq!((trait_name, method, self_ty, args.len()), ("Add", "add", &pair, 2));
+    let (trait_name, method, self_ty, _) = method_call(init(&m, 1));
+    assert_eq!((trait_name, method, self_ty), ("Neg", "neg", &pair));
+    // brojevi zadržavaju ugrađeni operator
+    assert!(matches!(init(&m, 2), hir::Expr::Binary { op: hir::BinOp::Plus, ty: Type::Int, .. }));
+}
+
+#[test]
+fn comparisons_take_references() {
+    let m = resolve_ok(&format!("{PAIR} fn main() {{ let p = (1, 2); let a = p == (1, 2); let b = p != p; let c = p < (0, 5); }}"));
+    let (trait_name, method, _, args) = method_call(init(&m, 1));
+    assert_eq!((trait_name, method), ("Eq", "eq"));
+    assert!(matches!(&args[1], hir::Expr::Ref { mutable: false, .. }));
+    let hir::Expr::Unary { op: hir::UnOp::Not, rhs, .. } = init(&m, 2) else { panic!("expected `!`") };
+    assert_eq!(method_call(rhs).1, "eq");
+    let hir::Expr::Binary { lhs, op: hir::BinOp::Lt, rhs, ty: Type::Bool, .. } = init(&m, 3) else { panic!("expected `<`") };
+    assert_eq!(method_call(lhs).0, "Ord");
+    assert!(matches!(&**rhs, hir::Expr::Int { value: 0, .. }));
+}
+
+#[test]
+fn operator_errors() {
+    resolve_err("fn main() { let x = (1, 2) + (3, 4); }", "cannot apply Plus to types `(Int, Int)` and `(Int, Int)`: the trait `Add` is not implemented for `(Int, Int)`");
+    resolve_err(&format!("{PAIR} fn main() {{ let x = (1, 2) - (3, 4); }}"), "the trait `Sub` is not implemented for `(Int, Int)`");
+    resolve_err(&format!("{PAIR} fn main() {{ let x = (1, 2) + 3; }}"), "expected (Int, Int), got Int");
+    resolve_err("fn main() { let x = -\"s\"; }", "cannot negate type `String`: the trait `Neg` is not implemented for `String`");
+    resolve_err("impl Add for Int { fn add(self, rhs: Self) -> Self { 0 } }", "conflicting implementations of `Add` for `Int`");
+    resolve_err("fn add<T>(a: T, b: T) -> T { a + b }", "add the bound `T: Add`");
+}
+
+#[test]
+fn built_in_types_satisfy_operator_bounds() {
+    let m = resolve_ok(&format!(
+        "{PAIR} fn sum<T: Add>(a: T, b: T, c: T) -> T {{ a + b + c }} fn max<T: Ord>(a: T, b: T) -> T {{ if a > b {{ a }} else {{ b }} }}
+         fn main() {{ print(sum(1, 2, 3)); let p = sum((1, 1), (2, 2), (3, 3)); let b = max(1.5, 2.5) > 2.0; }}"
+    ));
+    let mir_mod = mir::lower_module(&m);
+    let mut names: Vec<_> = mir_mod.fns.iter().map(|f| f.name.as_str()).collect();
+    names.sort();
+    // od ugrađenih implementacija prevode se samo pozvane
+    assert_eq!(
+        names,
+        [
+            "<(Int, Int) as Add>::add",
+            "<(Int, Int) as Eq>::eq",
+            "<(Int, Int) as Neg>::neg",
+            "<(Int, Int) as Ord>::cmp",
+            "<Float as Ord>::cmp",
+            "<Int as Add>::add",
+            "main",
+            "max<Float>",
+            "sum<(Int,Int)>",
+            "sum<Int>",
+        ]
+    );
+}
+
+#[test]
+fn overloaded_operators_call_the_impls() {
+    let m = resolve_ok(&format!("{PAIR} fn main() {{ let p = (1, 2) + (3, 4); if p == (4, 6) {{ print(p.1); }} print(p.0 + p.1); }}"));
+    let mir_mod = mir::lower_module(&m);
+    let mut llcx = LlvmCtx::new("test");
+    codegen_module(&mut llcx, &mir_mod);
+    llcx.module.verify().unwrap_or_else(|e| panic!("{}", e.to_string()));
+    let ir = llcx.module.print_to_string().to_string();
+    let main = &ir[ir.find("define void @main(").expect("main")..];
+    let main = &main[..main.find("\n}").unwrap()];
+    assert!(main.contains("@\"<(Int, Int) as Add>::add\""), "{main}");
+    assert!(main.contains("@\"<(Int, Int) as Eq>::eq\""), "{main}");
+    assert!(main.contains("add i32"), "{main}");
+}
@@ -0,0 +1,159 @@
+use aethc_core::{
+    ast,
+    codegen::{LlvmCtx, codegen_module},
+    const_eval::ConstValue,
+    hir,
+    lexer::{Lexer, TokenKind},
+    mir,
+    parser::Parser,
+};
+
+mod common;
+
+use common::{resolve_err, resolve_ok};
+
+/// The expression of `fn f() { <src>; }` with every operator parenthesized.
+fn grouped(src: &str) -> String {
+    fn show(e: &ast::Expr) -> String {
+        match e {
+            ast::Expr::Binary { op, lhs, rhs } => format!("({} {} {})", show(lhs), op.symbol(), show(rhs)),
+            ast::Expr::Unary { op, expr } => format!("({op:?} {})", show(expr)),
+            ast::Expr::Ref { expr, .. } => format!("(& {})", show(expr)),
+            ast::Expr::Ident(n) => n.clone(),
+            ast::Expr::Int(i) => i.to_string(),
+            ast::Expr::Bool(b) => b.to_string(),
+            other => format!("{other:?}"),
+        }
+    }
+    let m = Parser::new(&format!("fn f() {{ {src}; }}")).parse_module();
+    let ast::Item::Function(f) = &m.items[0] else { panic!("expected fn") };
+    let ast::Stmt::Expr(e) = &f.body.stmts[0] else { panic!("expected an expression") };
+    show(e)
+}
+
+/// Message of the panic parsing `src` ends with.
+fn parse_panic(src: &str) -> String {
+    let src = src.to_string();
+    let err = std::panic::catch_unwind(move || Parser::new(&src).parse_module()).expect_err("expected a parse error");
+    err.downcast_ref::<String>().cloned().unwrap_or_default()
+}
+
+fn const_value(m: &hir::HirModule, name: &str) -> Option<ConstValue> {
+    m.items.iter().find_map(|it| match it {
+        hir::Item::Const(c) if c.name == name => Some(c.value.clone()),
+        _ => None,
+    })
+}
+
+#[test]
+fn lexes_bitwise_and_compound_assignment_operators() {
+    let mut lex = Lexer::new("a ^ ~b << 2 >> c += -= *= /= %= &= |= ^= <<= >>= <= >= & |");
+    let kinds: Vec<TokenKind> = std::iter::from_fn(|| Some(lex.next_token().kind)).take_while(|k| *k != TokenKind::Eof).collect();
+    use ast::BinOp::*;
+    let assign = [Plus, Minus, Star, Slash, Percent, BitAnd, BitOr, BitXor, Shl, Shr].map(TokenKind::OpAssign);
+    assert_eq!(&kinds[1..8], [TokenKind::Caret, TokenKind::Tilde, TokenKind::Ident("b".into()), TokenKind::Shl, TokenKind::Int(2), TokenKind::Shr, TokenKind::Ident("c".into())]);
+    assert_eq!(&kinds[8..18], assign);
+    assert_eq!(&kinds[18..], [TokenKind::Le, TokenKind::Ge, TokenKind::Amp, TokenKind::Pipe]);
+}
+
+#[test]
+fn precedence_follows_the_conventional_table() {
+    assert_eq!(grouped("a < b && c < d"), "((a < b) && (c < d))");
+    assert_eq!(grouped("a || b && c"), "(a || (b && c))");
+    assert_eq!(grouped("a == b || a != c && d >= 1"), "((a == b) || ((a != c) && (d >= 1)))");
+    assert_eq!(grouped("a | b ^ c & d"), "(a | (b ^ (c & d)))");
+    assert_eq!(grouped("a & 1 == 0"), "((a & 1) == 0)");
+    assert_eq!(grouped("1 << 2 + 3 * 4"), "(1 << (2 + (3 * 4)))");
+    assert_eq!(grouped("a - b - c >> 1 >> 2"), "((((a - b) - c) >> 1) >> 2)");
+    assert_eq!(grouped("-a * ~b + !c"), "(((Negate a) * (BitNot b)) + (Not c))");
+    assert_eq!(grouped("&a + *b"), "((& a) + Deref(Ident(\"b\")))");
+    // a type argument list may close with `>>`
+    let m = Parser::new("fn f(o: Option<Option<Int>>) { let p: Option<Option<Int>>= None; }").parse_module();
+    let ast::Item::Function(f) = &m.items[0] else { panic!("expected fn") };
+    assert!(matches!(&f.body.stmts[0], ast::Stmt::Let { ty: Some(ast::TypeExpr::Named { args, .. }), .. } if args.len() == 1));
+}
+
+#[test]
+fn comparisons_cannot_be_chained() {
+    assert!(parse_panic("fn f() { a == b == c; }").contains("comparison operators cannot be chained: `a == b == c`"));
+    assert!(parse_panic("fn f() { let x = 1 < 2 > 3; }").contains("`a < b > c` needs parentheses or `&&`"));
+    assert_eq!(grouped("(a == b) == c"), "((a == b) == c)");
+    assert!(parse_panic("fn f() { a < b == (c < d); }").contains("`a < b == c`"));
+    assert_eq!(grouped("(a < b) == (c < d)"), "((a < b) == (c < d))");
+}
+
+#[test]
+fn bitwise_operators_take_ints() {
+    let m = resolve_ok(
+        "fn f(a: Int, b: Int) -> Int { (a & b | a ^ b) << 1 >> ~a }
+         const MASK: Int = 0 - 1 << 30 >> 28;
+         const BITS: Int = (12 & 10) + (12 | 3) + (12 ^ 10) + ~0;",
+    );
+    assert_eq!(const_value(&m, "MASK"), Some(ConstValue::Int(-4)));
+    assert_eq!(const_value(&m, "BITS"), Some(ConstValue::Int(8 + 15 + 6 - 1)));
+    resolve_err("fn f() { let x = 1.5 & 2; }", "cannot apply BitAnd to types `Float` and `Int`");
+    resolve_err("fn f() { let x = true | false; }", "cannot apply BitOr to types `Bool` and `Bool`");
+    resolve_err("fn f() { let x = ~1.0; }", "cannot apply bitwise NOT to type `Float`, expected Int");
+    resolve_err("fn f() { let x = (1, 2) ^ (3, 4); }", "the trait `BitXor` is not implemented for `(Int, Int)`");
+    resolve_err("const C: Int = 1 << 32;", "attempt to shift an Int by 32 bits");
+    resolve_ok(
+        "impl BitOr for (Int, Int) { fn bitor(self, rhs: Self) -> Self { (self.0 | rhs.0, self.1 | rhs.1) } }
+         fn main() { let p = (1, 2) | (4, 8); print(p.1); }",
+    );
+
+    let mut llcx = LlvmCtx::new("test");
+    codegen_module(&mut llcx, &mir::lower_module(&m));
+    llcx.module.verify().unwrap_or_else(|e| panic!("{}", e.to_string()));
+    let ir = llcx.module.print_to_string().to_string();
+    for inst in ["and i", "or i", "xor i", "shl i", "ashr i"] {
+        assert!(ir.contains(inst), "missing `{inst}` in {ir}");
+    }
+}
+
+#[test]
+fn compound_assignment_reads_and_writes_the_place_once() {
+    let m = Parser::new("fn f() { x += 1; a[i] <<= 2; p.0 -= g(); *r |= 1; }").parse_module();
+    let ast::Item::Function(f) = &m.items[0] else { panic!("expected fn") };
+    assert!(matches!(&f.body.stmts[0], ast::Stmt::AssignOp { target: ast::Expr::Ident(_), op: ast::BinOp::Plus, .. }));
+    assert!(matches!(&f.body.stmts[1], ast::Stmt::AssignOp { target: ast::Expr::Index { .. }, op: ast::BinOp::Shl, .. }));
+    assert!(matches!(&f.body.stmts[3], ast::Stmt::AssignOp { target: ast::Expr::Deref(_), op: ast::BinOp::BitOr, .. }));
+
+    let m = resolve_ok(
+        "fn next(c: &mut Int) -> Int { *c = *c + 1; *c }
+         fn main() { let mut x = 1; x *= 3; let mut a = [1, 2]; let mut k = 0; a[next(&mut k)] += 5; let mut p = (1.5, 2); p.0 /= 2; }",
+    );
+    let Some(hir::Item::Fn(main)) = m.items.last() else { panic!("expected fn") };
+    assert!(matches!(&main.body.stmts[1], hir::Stmt::Assign { expr: hir::Expr::Binary { op: ast::BinOp::Star, .. }, .. }));
+    // the index is evaluated once, into a temporary
+    let hir::Stmt::Semi(hir::Expr::Block { block, .. }) = &main.body.stmts[4] else { panic!("expected a block, got {:?}", main.body.stmts[4]) };
+    assert!(matches!(&block.stmts[..], [hir::Stmt::Let(l), hir::Stmt::AssignPlace { .. }] if l.name == "$tmp0"));
+    assert!(matches!(&main.body.stmts[6], hir::Stmt::AssignPlace { .. }));
+    let mut llcx = LlvmCtx::new("test");
+    codegen_module(&mut llcx, &mir::lower_module(&m));
+    llcx.module.verify().unwrap_or_else(|e| panic!("{}", e.to_string()));
+
+    resolve_err("fn f() { let x = 1; x += 1; }", "cannot reassign immutable binding `x`");
+    resolve_err("fn f() { let p = (1, 2); p.1 -= 1; }", "cannot assign to a field of immutable binding `p`");
+    resolve_err("fn f() { let mut s = \"a\"; s -= \"b\"; }", "cannot apply Minus to types `String` and `String`");
+    resolve_err("fn f(r: &Int) { *r += 1; }", "cannot assign to data behind a `&` reference");
+}
+
+#[test]
+fn integer_division_panics_on_zero_and_overflow() {
+    let m = resolve_ok("fn f(a: Int, b: Int) -> Int { a / b } fn g(a: u8, b: u8) -> u8 { a % b }");
+    let mut llcx = LlvmCtx::new("test");
+    codegen_module(&mut llcx, &mir::lower_module(&m));
+    llcx.module.verify().unwrap_or_else(|e| panic!("{}", e.to_string()));
+    let ir = llcx.module.print_to_string().to_string();
+    let body = |name: &str| {
+        let start = ir.find(&format!("@{name}(")).unwrap();
+        ir[start..].split("\n}\n").next().unwrap().to_string()
+    };
+    // `i32::MIN / -1` ne staje u `Int`, kao ni deljenje nulom
+    let f = body("f");
+    assert!(f.contains("icmp eq i32 %") && f.contains("-2147483648"), "{f}");
+    assert!(f.contains("call void @aethc_panic_div("), "{f}");
+    // neoznačeno deljenje ne može da se prelije, proverava se samo nula
+    let g = body("g");
+    assert!(g.contains("call void @aethc_panic_div(") && !g.contains("-128"), "{g}");
+}
@@ -0,0 +1,136 @@
+use aethc_core::{
+    ast,
+    borrowck::borrow_check,
+    codegen::{LlvmCtx, codegen_module},
+    hir,
+    mir,
+    parser::Parser,
+    type_::Type,
+};
+
+mod common;
+
+use common::{borrow_err, resolve_err, resolve_ok};
+
+const HALF: &str = "fn half(n: Int) -> Option<Int> { if n % 2 == 0 { Some(n / 2) } else { None } }
+                    fn parse(n: Int) -> Result<Int, Str> { if n < 0 { Err(\"negative\") } else { Ok(n) } }";
+
+#[test]
+fn parses_match_and_try() {
+    let m = Parser::new("fn f(x: Option<(Int, Int)>) -> Int { let y = g()?.0; match x { Some((a, _)) => a, None => { 0 } _ => 1 } }")
+        .parse_module();
+    let ast::Item::Function(f) = &m.items[0] else { panic!("expected fn") };
+    let ast::Stmt::Let { expr: ast::Expr::Field { base, index: 0 }, .. } = &f.body.stmts[0] else {
+        panic!("expected a field access, got {:?}", f.body.stmts[0])
+    };
+    assert!(matches!(&**base, ast::Expr::Try(call) if matches!(**call, ast::Expr::Call { .. })));
+    let Some(ast::Expr::Match { scrutinee, arms }) = f.body.tail.as_deref() else { panic!("expected match") };
+    assert!(matches!(&**scrutinee, ast::Expr::Ident(x) if x == "x"));
+    assert_eq!(arms.len(), 3);
+    let ast::MatchPattern::Variant { name, inner: Some(ast::Pattern::Tuple(pats)) } = &arms[0].pattern else {
+        panic!("expected `Some((a, _))`")
+    };
+    assert_eq!((name.as_str(), pats.len()), ("Some", 2));
+    assert!(matches!(&arms[1].pattern, ast::MatchPattern::Variant { name, inner: None } if name == "None"));
+    assert!(matches!(&arms[1].body, ast::Expr::Block(_)));
+    assert!(matches!(arms[2].pattern, ast::MatchPattern::Wildcard));
+}
+
+#[test]
+fn variants_take_their_type_from_use() {
+    let m = resolve_ok(
+        "fn f(c: Bool) -> Result<Int, Str> { if c { Ok(1) } else { Err(\"no\") } }
+         fn g() -> Option<Option<Int>> { Some(None) }",
+    );
+    let result = Type::Result(Box::new(Type::Int), Box::new(Type::Str));
+    let hir::Item::Fn(f) = &m.items[0] else { panic!("expected fn") };
+    let Some(hir::Expr::If { then_blk, else_branch: Some(else_e), ty, .. }) = f.body.tail() else { panic!("expected if") };
+    assert_eq!((ty, &then_blk.ty(), else_e.ty()), (&result, &result, &result));
+    let hir::Item::Fn(g) = &m.items[1] else { panic!("expected fn") };
+    let Some(hir::Expr::Variant { variant: hir::Variant::Some, value: Some(inner), .. }) = g.body.tail() else {
+        panic!("expected `Some`")
+    };
+    assert_eq!(inner.ty(), &Type::Option(Box::new(Type::Int)));
+
+    resolve_err("fn main() { let x = None; }", "cannot infer the type of `x`: its value has type Option<_>");
+    resolve_err("fn f() -> Option<Int> { Some(\"s\") }", "expected Option<Int>, got Option<String>");
+    resolve_err("fn f() -> Result<Int, Str> { Ok(2.5) }", "expected Result<Int, String>, got Result<Float, String>");
+    resolve_err("fn main() { let x = Some(1, 2); }", "`Some` takes 1 argument(s), got 2");
+    resolve_err("fn main() { let f = Ok; }", "variant `Ok` can only be called, not used as a value");
+    resolve_err("fn f(x: Option<Int, Int>) { }", "`Option` takes 1 type argument(s), got 2");
+    resolve_err("fn main() { let v = vec::<Option<Int>>(); }", "a Vec cannot hold an Option, got Option<Int>");
+    resolve_err("fn main() { let x = if true { Some(1) } else { Some(true) }; }", "`if` and `else` have incompatible types");
+}
+
+#[test]
+fn match_becomes_an_if_on_the_tag() {
+    let m = resolve_ok(&format!("{HALF} fn main() {{ let h = half(4); let n = match h {{ Some(x) => x, None => 0 }}; }}"));
+    let Some(hir::Item::Fn(main)) = m.items.last() else { panic!("expected fn") };
+    let hir::Stmt::Let(l) = &main.body.stmts[1] else { panic!("expected let") };
+    // imenovana vrednost se čita na mestu
+    let hir::Expr::If { cond, then_blk, ty: Type::Int, .. } = &l.init else { panic!("expected if, got {:?}", l.init) };
+    assert!(matches!(&**cond, hir::Expr::Field { index: 0, ty: Type::Bool, .. }));
+    let hir::Stmt::Let(x) = &then_blk.stmts[0] else { panic!("expected the binding of `x`") };
+    assert!(matches!(&x.init, hir::Expr::Field { index: 1, ty: Type::Int, .. }));
+
+    let m = format!("{HALF} fn main() {{ let x = half(2);");
+    resolve_err(&format!("{m} match x {{ Some(n) => print(n), }} }}"), "non-exhaustive patterns in `match`: `None` not covered");
+    resolve_err(&format!("{m} match parse(1) {{ Err(e) => print(e) }} }}"), "`Ok(_)` not covered");
+    resolve_err(&format!("{m} match x {{ _ => print(1), None => print(2) }} }}"), "unreachable pattern `None`");
+    resolve_err(&format!("{m} match x {{ Ok(n) => print(n), _ => print(2) }} }}"), "`Ok` is not a variant of Option<Int>");
+    resolve_err(&format!("{m} match x {{ Some => print(1), _ => print(2) }} }}"), "`Some` holds a value; match it with `Some(_)`");
+    resolve_err(&format!("{m} match x {{ None(y) => print(1), _ => print(2) }} }}"), "`None` has no value to bind");
+    resolve_err(&format!("{m} match 5 {{ _ => print(2) }} }}"), "`match` needs an `Option` or a `Result`, got Int");
+    resolve_err(&format!("{m} let y = match x {{ Some(n) => n, None => \"zero\" }}; }}"), "`match` arms have incompatible types `Int` and `String`");
+}
+
+#[test]
+fn try_checks_the_return_type() {
+    resolve_ok(&format!("{HALF} fn q(n: Int) -> Option<Int> {{ half(half(n)?) }} fn t() -> Result<Int, Str> {{ Ok(parse(1)? + parse(2)?) }}"));
+    resolve_err(&format!("{HALF} fn f() -> Int {{ half(2)? }}"), "`?` on an `Option` can only be used in a function that returns an `Option`, not Int");
+    resolve_err(&format!("{HALF} fn f() -> Option<Int> {{ Some(parse(2)?) }}"), "`?` on a `Result` can only be used in a function that returns a `Result`");
+    resolve_err(&format!("{HALF} fn f() -> Result<Int, Int> {{ Ok(parse(2)?) }}"), "`?` cannot return an error of type String from a function returning Result<Int, Int>");
+    resolve_err("fn f(x: Int) -> Option<Int> { Some(x?) }", "`?` can only be applied to an `Option` or a `Result`, got Int");
+    resolve_err(&format!("{HALF} fn main() {{ let f = || half(2)?; }}"), "`?` in a closure needs an explicit return type");
+    resolve_ok(&format!("{HALF} fn main() {{ let f = |n: Int| -> Option<Int> {{ Some(half(n)? + 1) }}; }}"));
+
+    // vrednost se premešta iz `Option`-a samo kad je to dozvoljeno
+    let ok = "fn f(x: &Option<Str>) -> Bool { x.is_some() } fn g(x: &Option<Int>) -> Int { match x { Some(n) => n, None => 0 } }";
+    assert!(borrow_check(&resolve_ok(ok)).is_empty());
+    borrow_err(
+        "fn f(x: &Option<Str>) -> Str { match x { Some(s) => s, None => \"\" } }",
+        "cannot move out of `*x`, which is behind a reference",
+    );
+    borrow_err(
+        "fn f(x: Option<Str>) -> Option<Str> { let s = x?; print(s); x }",
+        "value `x` is moved twice",
+    );
+}
+
+#[test]
+fn try_returns_early_in_mir() {
+    let m = resolve_ok(&format!(
+        "{HALF} fn quarter(n: Int) -> Option<Int> {{ let h = half(n)?; half(h) }}
+         fn total(a: Int, b: Int) -> Result<Int, Str> {{ Ok(parse(a)? + parse(b)?) }}
+         fn main() {{ print(quarter(12).unwrap_or(-1)); print(total(1, 2).unwrap_or(0)); }}"
+    ));
+    let mir_mod = mir::lower_module(&m);
+    let mut names: Vec<_> = mir_mod.fns.iter().map(|f| f.name.as_str()).collect();
+    names.sort();
+    // od metoda iz preluda prevode se samo pozvane
+    assert_eq!(
+        names,
+        ["Option<T>::unwrap_or<Int>", "Result<T, E>::unwrap_or<Int,String>", "half", "main", "parse", "quarter", "total"]
+    );
+    let quarter = mir_mod.fns.iter().find(|f| f.name == "quarter").unwrap();
+    assert_eq!(quarter.body.ret_ty, mir::MirType::Tuple(vec![mir::MirType::Bool, mir::MirType::Int]));
+    let returns = quarter.body.blocks.iter().filter(|b| matches!(b.term, mir::Terminator::Return)).count();
+    assert!(returns >= 2, "{:?}", quarter.body.blocks);
+
+    let mut llcx = LlvmCtx::new("test");
+    codegen_module(&mut llcx, &mir_mod);
+    llcx.module.verify().unwrap_or_else(|e| panic!("{}", e.to_string()));
+    let ir = llcx.module.print_to_string().to_string();
+    assert!(ir.contains("define { i1, i32 } @quarter(i32"), "{ir}");
+    assert!(ir.contains("define { i1, i32, i8* } @total(i32"), "{ir}");
+}
@@ -5,10 +5,10 @@ fn assert_ast(src: &str) -> ast::Expr {
     // Wrap expression into a function body so we can parse it
     let wrapped = format!("fn main() {{ {src}; }}");
     let module = Parser::new(&wrapped).parse_module();
-    if let ast::Item::Function(f) = &module.items[0] {
-        if let ast::Stmt::Expr(e) = &f.body[0] {
-            return e.clone();
-        }
+    if let ast::Item::Function(f) = &module.items[0]
+        && let ast::Stmt::Expr(e) = &f.body.stmts[0]
+    {
+        return e.clone();
     }
     panic!("unexpected AST shape");
 }
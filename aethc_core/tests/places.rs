@@ -0,0 +1,114 @@
+use aethc_core::{
+    ast,
+    borrowck::borrow_check,
+    codegen::{LlvmCtx, codegen_module},
+    const_eval::ConstValue,
+    hir,
+    mir::{self, Operand, Projection, Statement},
+    parser::Parser,
+};
+
+mod common;
+
+use common::{resolve_err, resolve_ok};
+
+fn fn_named<'m>(m: &'m hir::HirModule, name: &str) -> &'m hir::HirFn {
+    m.items
+        .iter()
+        .find_map(|it| match it {
+            hir::Item::Fn(f) if f.name == name => Some(f),
+            _ => None,
+        })
+        .unwrap_or_else(|| panic!("no fn `{name}`"))
+}
+
+/// Projections of every `AssignPlace` in the body, in order.
+fn stores(body: &mir::MirBody) -> Vec<&[Projection]> {
+    body.blocks
+        .iter()
+        .flat_map(|b| &b.stmts)
+        .filter_map(|s| match s {
+            Statement::AssignPlace { place, .. } => Some(&place.proj[..]),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn parses_assignments_to_places() {
+    let m = Parser::new("fn f() { p.0 = 1; a[i].1 = 2; *r = 3; (*r).0 = 4; g().0 = 5; x = 6; }").parse_module();
+    let ast::Item::Function(f) = &m.items[0] else { panic!("expected fn") };
+    assert!(matches!(&f.body.stmts[0], ast::Stmt::AssignPlace { target: ast::Expr::Field { index: 0, .. }, .. }));
+    assert!(matches!(&f.body.stmts[1], ast::Stmt::AssignPlace { target: ast::Expr::Field { base, index: 1 }, .. }
+        if matches!(**base, ast::Expr::Index { .. })));
+    assert!(matches!(&f.body.stmts[2], ast::Stmt::AssignPlace { target: ast::Expr::Deref(_), expr: ast::Expr::Int(3) }));
+    assert!(matches!(&f.body.stmts[3], ast::Stmt::AssignPlace { target: ast::Expr::Field { .. }, .. }));
+    assert!(matches!(&f.body.stmts[4], ast::Stmt::AssignPlace { target: ast::Expr::Field { .. }, .. }));
+    assert!(matches!(&f.body.stmts[5], ast::Stmt::Assign { name, .. } if name == "x"));
+}
+
+#[test]
+fn mutability_is_checked_along_the_whole_path() {
+    resolve_ok(
+        "fn f(r: &mut (Int, [Int; 2])) { r.0 = 1; r.1[0] = 2; (*r).0 = 3; }
+         fn g(r: &mut Int) { *r = 1; }
+         fn main() { let mut p = (1, (2, 3)); p.1.0 = 4; let q = &mut p; q.1.1 = 5; *&mut p = (6, (7, 8)); }",
+    );
+    resolve_err("fn main() { let p = (1, 2); p.0 = 3; }", "cannot assign to a field of immutable binding `p`");
+    resolve_err("fn main() { let t = ([1, 2], 3); t.0[1] = 3; }", "cannot assign to an element of immutable binding `t`");
+    resolve_err("fn f(r: &Int) { *r = 1; }", "cannot assign to data behind a `&` reference");
+    resolve_err("fn f(r: &(Int, Int)) { r.1 = 1; }", "cannot assign to data behind a `&` reference");
+    resolve_err("fn f(r: &mut &Int) { **r = 1; }", "cannot assign to data behind a `&` reference");
+    resolve_err("fn g() -> (Int, Int) { (1, 2) } fn main() { g().0 = 1; }", "invalid left-hand side of assignment");
+    resolve_err("fn main() { let mut a = [(1, 2)]; a[0].1 = true; }", "expected Int, got Bool");
+    resolve_err("fn main() { let mut p = (1, 2); let f = move || { p.0 = 3; }; }", "captured by value in a `move` closure");
+}
+
+#[test]
+fn places_lower_to_projections() {
+    let m = resolve_ok(
+        "fn f(r: &mut (Int, [Int; 2])) { r.1[1] = 2; *r = (0, [0; 2]); }
+         fn main() { let mut p = (1, (2, 3)); p.1.0 = 4; let mut n = 1; *&mut n = 2; let mut v = vec::<Int>(); push(v, 1); v[0] = 5; }",
+    );
+    let f = mir::lower_fn(fn_named(&m, "f"));
+    let [store, whole] = stores(&f)[..] else { panic!("expected two stores") };
+    assert!(matches!(store, [Projection::Deref(mir::MirType::Tuple(_)), Projection::Field(1), Projection::Index(Operand::Const(_))]));
+    assert!(matches!(whole, [Projection::Deref(_)]));
+
+    let main = mir::lower_fn(fn_named(&m, "main"));
+    let [field, var] = stores(&main)[..] else { panic!("expected two stores") };
+    assert!(matches!(field, [Projection::Field(1), Projection::Field(0)]));
+    // `*&mut n` is `n` itself; `v[0] = 5` is `vec.set`
+    assert!(var.is_empty());
+    let calls_set = main.blocks.iter().flat_map(|b| &b.stmts).any(
+        |s| matches!(s, Statement::Assign { rv: mir::Rvalue::Call { fn_name, .. }, .. } if fn_name == "vec.set"),
+    );
+    assert!(calls_set);
+
+    let mut llcx = LlvmCtx::new("test");
+    codegen_module(&mut llcx, &mir::lower_module(&m));
+    llcx.module.verify().unwrap_or_else(|e| panic!("{}", e.to_string()));
+}
+
+#[test]
+fn constants_evaluate_place_assignments() {
+    let m = resolve_ok("fn f() -> Int { let mut t = (1, [2, 3]); t.1[0] = 5; t.0 = 4; t.0 + t.1[0] } const C: Int = f();");
+    let value = m.items.iter().find_map(|it| match it {
+        hir::Item::Const(c) if c.name == "C" => Some(c.value.clone()),
+        _ => None,
+    });
+    assert_eq!(value, Some(ConstValue::Int(9)));
+}
+
+#[test]
+fn borrow_checks_see_the_root_of_the_place() {
+    let errs = borrow_check(&resolve_ok("fn main() { let mut p = (1, 2); p.0 = 3; let mut a = [[0; 2]; 2]; a[1][0] = 4; }"));
+    assert!(errs.is_empty(), "expected ok, got {errs:?}");
+    let errs = borrow_check(&resolve_ok(
+        "fn main() { let a = 1; let mut p = (&a, 2); { let x = 5; p.0 = &x; } print(*p.0); }",
+    ));
+    assert!(
+        errs.iter().any(|e| e.msg == "`x` does not live long enough: `p` still holds a reference to it"),
+        "got {errs:?}"
+    );
+}
@@ -7,9 +7,9 @@ fn infer_binary_types() {
     let (hir_mod, errs) = resolve(&module);
     assert!(errs.is_empty());
 
-    if let hir::Item::Fn(f) = &hir_mod.items[0] {
-        if let hir::Stmt::Let(l) = &f.body.stmts[0] {
-            assert_eq!(l.ty, Type::Int);
-        }
+    if let hir::Item::Fn(f) = &hir_mod.items[0]
+        && let hir::Stmt::Let(l) = &f.body.stmts[0]
+    {
+        assert_eq!(l.ty, Type::Int);
     }
 }
@@ -4,17 +4,27 @@ fn duplicate_name_error() {
     let src = r#"
         fn main() {
             let x = 1;
+        }
+        fn main() {
             let x = 2;
         }
     "#;
-    let module = Parser::new(&src).parse_module();
+    let module = Parser::new(src).parse_module();
     let (hir_mod, res_errs) = resolve(&module);
 
-    // Resolver should report an error for the duplicate name
+    // Resolver should report an error for the duplicate function name
     assert_eq!(res_errs.len(), 1);
-    assert!(res_errs[0].msg.contains("cannot redeclare"));
+    assert!(res_errs[0].msg.contains("already defined"));
 
     // Borrow checker sees no errors when resolve already failed
     let bc_errs = borrow_check(&hir_mod);
     assert!(bc_errs.is_empty());
 }
+
+#[test]
+fn duplicate_param_error() {
+    let src = "fn add(a: Int, a: Int) { }";
+    let (_hir, res_errs) = resolve(&Parser::new(src).parse_module());
+    assert_eq!(res_errs.len(), 1);
+    assert!(res_errs[0].msg.contains("already defined"));
+}
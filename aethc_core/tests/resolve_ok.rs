@@ -20,9 +20,9 @@ fn unary_not_true() {
     let (hir_mod, errs) = resolve(&module);
     assert!(errs.is_empty(), "resolve errs: {errs:?}");
 
-    if let aethc_core::hir::Item::Fn(f) = &hir_mod.items[0] {
-        if let aethc_core::hir::Stmt::Let(l) = &f.body.stmts[0] {
-            assert_eq!(l.ty, aethc_core::type_::Type::Bool);
-        }
+    if let aethc_core::hir::Item::Fn(f) = &hir_mod.items[0]
+        && let aethc_core::hir::Stmt::Let(l) = &f.body.stmts[0]
+    {
+        assert_eq!(l.ty, aethc_core::type_::Type::Bool);
     }
 }
@@ -1,4 +1,4 @@
-use aethc_core::{parser::Parser, resolver::resolve, hir, type_::Type};
+use aethc_core::{cfg, parser::Parser, resolver::resolve, hir, type_::Type};
 
 #[test]
 fn annotated_return_type_ok() {
@@ -13,7 +13,9 @@ fn annotated_return_type_ok() {
 #[test]
 fn missing_return_value_error() {
     let src = "fn foo() -> Int { }";
-    let (_hir, errs) = resolve(&Parser::new(src).parse_module());
+    let (hir_mod, errs) = resolve(&Parser::new(src).parse_module());
+    assert!(errs.is_empty());
+    let errs = cfg::check_module(&hir_mod).errors;
     assert_eq!(errs.len(), 1);
     assert!(errs[0].msg.contains("expected Int"));
 }
@@ -0,0 +1,115 @@
+use aethc_core::{
+    ast,
+    codegen::{LlvmCtx, codegen_module},
+    hir,
+    mir,
+    parser::Parser,
+    type_::Type,
+};
+
+mod common;
+
+use common::{resolve_err, resolve_ok};
+
+const SIZE: &str = "trait Size { fn size(self) -> Int; }
+                    impl Size for Int { fn size(self) -> Int { 1 } }
+                    impl<T: Size> Size for (T, T) { fn size(self) -> Int { Size::size(self.0) + Size::size(self.1) } }";
+
+#[test]
+fn parses_traits_and_impls() {
+    let m = Parser::new(
+        "pub trait Show { fn show(self) -> Str; fn twice(self, n: Int); }
+         impl<T: Show + fmt::Debug> Show for Vec<T> { fn show(self) -> Str { \"v\" } fn twice(self, n: Int) { } }",
+    )
+    .parse_module();
+    let ast::Item::Trait(t) = &m.items[0] else { panic!("expected trait") };
+    assert!(t.is_pub);
+    assert_eq!(t.methods.iter().map(|f| f.name.as_str()).collect::<Vec<_>>(), ["show", "twice"]);
+    assert_eq!(t.methods[1].params.len(), 2);
+    let ast::Item::Impl(imp) = &m.items[1] else { panic!("expected impl") };
+    assert_eq!(imp.trait_path.as_deref(), Some(&["Show".to_string()][..]));
+    assert_eq!(imp.type_params[0].name, "T");
+    assert_eq!(imp.type_params[0].bounds, [vec!["Show".to_string()], vec!["fmt".into(), "Debug".into()]]);
+    assert_eq!(imp.methods.len(), 2);
+}
+
+#[test]
+fn trait_method_calls_resolve_to_the_self_type() {
+    let m = resolve_ok(&format!("{SIZE} fn total<T: Size>(x: T) -> Int {{ Size::size(x) }} fn main() {{ let n = Size::size((1, 2)); }}"));
+    assert_eq!(m.impls.len(), 2);
+    assert_eq!(m.impls[1].type_params, ["T"]);
+    let pair = Type::Tuple(vec![Type::Int, Type::Int]);
+    assert!(m.impls[1].matches(&pair).is_some_and(|map| map["T"] == Type::Int));
+    assert!(m.impls[1].matches(&Type::Tuple(vec![Type::Int, Type::Bool])).is_none());
+    let Some(hir::Item::Fn(main)) = m.items.last() else { panic!("expected fn") };
+    let hir::Stmt::Let(hir::HirLet { init: hir::Expr::Call { callee, .. }, .. }) = &main.body.stmts[0] else {
+        panic!("expected call")
+    };
+    let hir::Expr::MethodRef { trait_name, method, self_ty, .. } = &**callee else { panic!("expected method") };
+    assert_eq!((trait_name.as_str(), method.as_str(), self_ty), ("Size", "size", &pair));
+}
+
+#[test]
+fn bounds_are_checked() {
+    resolve_err(&format!("{SIZE} fn main() {{ Size::size(true); }}"), "the trait `Size` is not implemented for `Bool`");
+    resolve_err(
+        &format!("{SIZE} fn main() {{ Size::size((1, true)); }}"),
+        "the trait `Size` is not implemented for `(Int, Bool)`",
+    );
+    resolve_err(&format!("{SIZE} fn f<T>(x: T) -> Int {{ Size::size(x) }}"), "add the bound `T: Size`");
+    resolve_err(
+        &format!("{SIZE} fn f<T: Size>(x: T) -> Int {{ Size::size(x) }} fn main() {{ f(2.5); }}"),
+        "required by `T` of `f`",
+    );
+    resolve_err("fn f<T: Nope>(x: T) { }", "unknown trait `Nope`");
+    resolve_err("fn g() { } fn f<T: g>(x: T) { }", "`g` is not a trait");
+    resolve_err(&format!("{SIZE} fn main() {{ Size::len(1); }}"), "trait `Size` has no method `len`");
+    resolve_err(&format!("{SIZE} fn main() {{ let f = Size::size; }}"), "can only be called, not used as a value");
+    resolve_ok(&format!("{SIZE} fn main() {{ let n = Size::size(((1, 2), (3, 4))); }}"));
+}
+
+#[test]
+fn impl_errors() {
+    let tr = "trait Size { fn size(self) -> Int; }";
+    resolve_err(&format!("{tr} impl Size for Int {{ }}"), "missing method `size` in the impl of `Size` for `Int`");
+    resolve_err(
+        &format!("{tr} impl Size for Int {{ fn size(self) -> Bool {{ true }} }}"),
+        "method `size` of the impl of `Size` for `Int` has type",
+    );
+    resolve_err(
+        &format!("{tr} impl Size for Int {{ fn size(self) -> Int {{ 1 }} fn len(self) -> Int {{ 1 }} }}"),
+        "method `len` is not a member of trait `Size`",
+    );
+    resolve_err(
+        &format!("{tr} impl Size for Int {{ fn size(self) -> Int {{ 1 }} }} impl<T> Size for T {{ fn size(self) -> Int {{ 2 }} }}"),
+        "conflicting implementations of `Size`",
+    );
+    resolve_err(&format!("{tr} impl<U> Size for Int {{ fn size(self) -> Int {{ 1 }} }}"), "type parameter `U` of the impl of `Size` is not used");
+    resolve_err("trait T { fn a(self); fn a(self); }", "method `a` is declared twice in trait `T`");
+    resolve_err("trait T { fn a(x: Int); }", "the first parameter of method `a` must be `self`");
+    resolve_err("trait T { fn a<U>(self, u: U); }", "method `a` cannot have type parameters of its own");
+    resolve_err("fn f(self) { }", "`self` parameter is only allowed in methods");
+    resolve_err(
+        "trait Grow { fn grow(self) -> Int; } impl<T: Grow> Grow for (T, T) { fn grow(self) -> Int { Grow::grow((self, self)) } }",
+        "must use the impl's own type parameters",
+    );
+}
+
+#[test]
+fn impls_are_picked_during_monomorphization() {
+    let m = resolve_ok(&format!(
+        "{SIZE} fn total<T: Size>(x: T) -> Int {{ Size::size(x) * 10 }}
+         fn main() {{ print(total((4, 5))); print(Size::size(7)); }}"
+    ));
+    let mir_mod = mir::lower_module(&m);
+    let mut names: Vec<_> = mir_mod.fns.iter().map(|f| f.name.as_str()).collect();
+    names.sort();
+    assert_eq!(names, ["<(T, T) as Size>::size<Int>", "<Int as Size>::size", "main", "total<(Int,Int)>"]);
+
+    let mut llcx = LlvmCtx::new("test");
+    codegen_module(&mut llcx, &mir_mod);
+    llcx.module.verify().unwrap_or_else(|e| panic!("{}", e.to_string()));
+    let ir = llcx.module.print_to_string().to_string();
+    assert!(ir.contains("call i32 @\"<Int as Size>::size\""), "{ir}");
+    assert!(ir.contains("call i32 @\"<(T, T) as Size>::size<Int>\""), "{ir}");
+}
@@ -0,0 +1,148 @@
+use aethc_core::{
+    ast,
+    borrowck::borrow_check,
+    codegen::{LlvmCtx, codegen_module},
+    const_eval::ConstValue,
+    hir, mir,
+    parser::Parser,
+    type_::Type,
+};
+
+mod common;
+
+use common::{borrow_err, resolve_err, resolve_ok};
+
+/// Imena i tipovi svih `let`-ova u poslednjoj funkciji, bez skrivenih.
+fn lets(m: &hir::HirModule) -> Vec<(String, Type, bool)> {
+    let Some(hir::Item::Fn(f)) = m.items.last() else { panic!("expected fn") };
+    f.body
+        .stmts
+        .iter()
+        .filter_map(|s| match s {
+            hir::Stmt::Let(l) if !l.name.starts_with('$') => Some((l.name.clone(), l.ty.clone(), l.mutable)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn tuple(elems: &[Type]) -> Type {
+    Type::Tuple(elems.to_vec())
+}
+
+#[test]
+fn parses_tuple_forms() {
+    let m = Parser::new("fn f(p: (Int, (Bool,)), u: ()) { let (a, (mut b, _)) = p; let x = (1,); let y = (2); let z = p.1.0; }")
+        .parse_module();
+    let ast::Item::Function(f) = &m.items[0] else { panic!("expected fn") };
+    let Some(ast::TypeExpr::Tuple(elems)) = &f.params[0].ty else { panic!("expected tuple type") };
+    assert!(matches!(&elems[1], ast::TypeExpr::Tuple(inner) if inner.len() == 1));
+    assert!(matches!(&f.params[1].ty, Some(ast::TypeExpr::Tuple(e)) if e.is_empty()));
+
+    let ast::Stmt::LetTuple { pats, .. } = &f.body.stmts[0] else { panic!("expected destructuring let") };
+    assert!(matches!(&pats[0], ast::Pattern::Ident { name, mutable: false } if name == "a"));
+    let ast::Pattern::Tuple(inner) = &pats[1] else { panic!("expected nested pattern") };
+    assert!(matches!(&inner[0], ast::Pattern::Ident { mutable: true, .. }));
+    assert!(matches!(inner[1], ast::Pattern::Wildcard));
+
+    // `(1,)` je torka od jednog elementa, `(2)` samo zagrada
+    assert!(matches!(&f.body.stmts[1], ast::Stmt::Let { expr: ast::Expr::Tuple(e), .. } if e.len() == 1));
+    assert!(matches!(&f.body.stmts[2], ast::Stmt::Let { expr: ast::Expr::Int(2), .. }));
+    let ast::Stmt::Let { expr: ast::Expr::Field { base, index: 0 }, .. } = &f.body.stmts[3] else {
+        panic!("expected field access, got {:?}", f.body.stmts[3]);
+    };
+    assert!(matches!(**base, ast::Expr::Field { index: 1, .. }));
+}
+
+#[test]
+fn destructuring_binds_each_element() {
+    let m = resolve_ok(
+        "fn divmod(a: Int, b: Int) -> (Int, Int) { (a / b, a % b) }
+         fn main() { let (q, r) = divmod(7, 2); let t = ((1.5, true), \"s\"); let ((f, _), mut s) = t;
+                     s = \"z\"; let n = t.0.1; }",
+    );
+    assert_eq!(
+        lets(&m),
+        [
+            ("q".into(), Type::Int, false),
+            ("r".into(), Type::Int, false),
+            ("t".into(), tuple(&[tuple(&[Type::Float, Type::Bool]), Type::Str]), false),
+            ("f".into(), Type::Float, false),
+            ("s".into(), Type::Str, true),
+            ("n".into(), Type::Bool, false),
+        ]
+    );
+    let hir::Item::Fn(divmod) = &m.items[0] else { panic!("expected fn") };
+    assert_eq!(divmod.return_ty, tuple(&[Type::Int, Type::Int]));
+    assert!(borrow_check(&m).is_empty(), "{:?}", borrow_check(&m));
+}
+
+#[test]
+fn tuple_errors() {
+    resolve_err("fn main() { let (a, b) = (1, 2, 3); }", "tuple pattern has 2 element(s), but the value has type (Int, Int, Int)");
+    resolve_err("fn main() { let (a, b) = 5; }", "cannot destructure a value of type Int as a tuple");
+    resolve_err("fn main() { let (a, (b, a)) = (1, (2, 3)); }", "identifier `a` is bound more than once");
+    resolve_err("fn main() { let t = (1, 2); print(t.2); }", "no field `2` on type (Int, Int)");
+    resolve_err("fn main() { let (a, b) = (1, 2); b = 3; }", "cannot reassign immutable binding `b`");
+    resolve_err("fn f() -> (Int, Bool) { (1, 2) }", "expected (Int, Bool), got (Int, Int)");
+    resolve_err("fn main() { let (tx, rx) = channel::<(Int, Int)>(); }", "a channel cannot carry a tuple");
+    resolve_err("fn main() { let a = [(1, vec::<Int>()); 2]; }", "an array cannot hold a Vec");
+    // imena postaju vidljiva tek posle cele naredbe
+    resolve_err("fn main() { let (x, y) = (1, x); }", "unknown name `x`");
+    resolve_ok("fn main() { let a = 1; let (a, b) = (true, a + 1); if a { print(b); } }");
+}
+
+#[test]
+fn moving_out_of_a_tuple_is_tracked_per_field() {
+    let take = "fn take(s: Str) { }";
+    borrow_err(
+        &format!("{take} fn main() {{ let t = (\"a\", \"b\"); take(t.0); take(t.0); }}"),
+        "value `t` is moved twice",
+    );
+    borrow_err(
+        &format!("{take} fn main() {{ let t = (\"a\", \"b\"); take(t.0); let u = t; }}"),
+        "use of moved value `t`",
+    );
+    borrow_err(
+        "fn main() { let v = (vec::<Int>(), 1); let (a, _) = v; let (b, _) = v; }",
+        "value `v` is moved twice",
+    );
+    // ostala polja i Copy polja ostaju upotrebljiva, a dodela obnavlja torku
+    let m = resolve_ok(&format!(
+        "{take} fn main() {{ let mut t = (\"a\", \"b\", 1); take(t.0); take(t.1); let n = t.2;
+                             t = (\"c\", \"d\", 2); take(t.0); let u = (n, t.2); let w = u; let z = u; }}"
+    ));
+    assert!(borrow_check(&m).is_empty(), "{:?}", borrow_check(&m));
+}
+
+#[test]
+fn tuples_lower_to_aggregates() {
+    let m = resolve_ok(
+        "let ORIGIN = (0, 1.5);
+         fn swap(p: (Int, Str)) -> (Str, Int) { (p.1, p.0) }
+         fn main() { let (s, n) = swap((ORIGIN.0, \"x\")); print(s); print(n); }",
+    );
+    assert_eq!(m.global_values.values().next(), Some(&ConstValue::Tuple(vec![ConstValue::Int(0), ConstValue::Float(1.5)])));
+
+    let mir_mod = mir::lower_module(&m);
+    let swap = &mir_mod.fns[0].body;
+    let rvs: Vec<_> = swap
+        .blocks
+        .iter()
+        .flat_map(|b| &b.stmts)
+        .filter_map(|s| match s {
+            mir::Statement::Assign { rv, .. } => Some(rv),
+            _ => None,
+        })
+        .collect();
+    assert!(matches!(rvs[0], mir::Rvalue::Field { index: 1, .. }), "{rvs:?}");
+    assert!(rvs.iter().any(|rv| matches!(rv, mir::Rvalue::Tuple(ops) if ops.len() == 2)), "{rvs:?}");
+    assert_eq!(swap.ret_ty, mir::MirType::Tuple(vec![mir::MirType::Str, mir::MirType::Int]));
+
+    let mut llcx = LlvmCtx::new("test");
+    codegen_module(&mut llcx, &mir_mod);
+    llcx.module.verify().unwrap_or_else(|e| panic!("{}", e.to_string()));
+    let ir = llcx.module.print_to_string().to_string();
+    assert!(ir.contains("{ i32, double } { i32 0, double 1.5"), "{ir}");
+    assert!(ir.contains("extractvalue"), "{ir}");
+    assert!(ir.contains("insertvalue"), "{ir}");
+}
@@ -1,9 +1,11 @@
-use aethc_core::{parser::Parser, resolver::resolve};
+use aethc_core::{cfg, parser::Parser, resolver::resolve};
 
 #[test]
 fn missing_return_error() {
     let src = "fn bar() -> Int { }";
-    let (_hir, errs) = resolve(&Parser::new(src).parse_module());
+    let (hir_mod, errs) = resolve(&Parser::new(src).parse_module());
+    assert!(errs.is_empty());
+    let errs = cfg::check_module(&hir_mod).errors;
     assert_eq!(errs.len(), 1);
     assert!(errs[0].msg.contains("expected Int"));
 }
@@ -0,0 +1,213 @@
+use aethc_core::{
+    ast,
+    borrowck::borrow_check,
+    codegen::{LlvmCtx, codegen_module},
+    hir, mir,
+    parser::Parser,
+    type_::Type,
+};
+
+mod common;
+
+use common::{borrow_err, resolve_err, resolve_ok};
+
+fn vec_of(elem: Type) -> Type {
+    Type::Vec(Box::new(elem))
+}
+
+#[test]
+fn vec_constructor_is_contextual() {
+    let m = Parser::new("fn main() { let v = vec::<Int>(); let vec = 1; }").parse_module();
+    let ast::Item::Function(f) = &m.items[0] else { panic!("expected fn") };
+    let ast::Stmt::Let { expr: ast::Expr::VecNew(ast::TypeExpr::Named { name, .. }), .. } = &f.body.stmts[0]
+    else {
+        panic!("expected vec::<T>(), got {:?}", f.body.stmts[0]);
+    };
+    assert_eq!(name, "Int");
+    // bez `::<` je `vec` obično ime
+    assert!(matches!(&f.body.stmts[1], ast::Stmt::Let { name, .. } if name == "vec"));
+}
+
+#[test]
+fn vec_types() {
+    let m = resolve_ok(
+        "fn f(v: Vec<Float>) -> Vec<Float> { v }
+         fn main() { let mut v = vec::<Int>(); push(v, 1); let n = len(v); let x = pop(v); let y = v[n - 2]; }",
+    );
+    let hir::Item::Fn(f) = &m.items[0] else { panic!("expected fn") };
+    assert_eq!(f.params[0].ty, vec_of(Type::Float));
+    assert_eq!(f.return_ty, vec_of(Type::Float));
+    let hir::Item::Fn(main) = &m.items[1] else { panic!("expected main") };
+    let tys: Vec<_> = main
+        .body
+        .stmts
+        .iter()
+        .filter_map(|s| match s {
+            hir::Stmt::Let(l) => Some(l.ty.clone()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(tys, [vec_of(Type::Int), Type::Int, Type::Int, Type::Int]);
+}
+
+#[test]
+fn vec_errors() {
+    resolve_err("fn main() { let v = vec::<Int>(); push(v, 1); }", "`push` needs a mutable Vec");
+    resolve_err("fn main() { let v = vec::<Int>(); print(pop(v)); }", "`pop` needs a mutable Vec");
+    resolve_err("fn main() { let mut v = vec::<Int>(); push(v, \"s\"); }", "expected Int");
+    resolve_err("fn main() { print(len(3)); }", "`len` expects a Vec, got Int");
+    resolve_err("fn main() { let mut v = vec::<Int>(); push(v); }", "`push` takes 2 argument(s), got 1");
+    resolve_err("fn main() { let v = vec::<[Int; 2]>(); }", "a Vec cannot hold an array");
+    resolve_err("fn f(v: Vec<Vec<Int> >) { }", "a Vec cannot hold another Vec");
+    resolve_err("fn main() { let a = [vec::<Int>(); 2]; }", "an array cannot hold a Vec");
+    resolve_err("fn main() { let v = vec::<Int>(); print(v[true]); }", "Vec index must be Int, got Bool");
+    resolve_err("fn f(v: Vec) { }", "`Vec` takes 1 type argument(s)");
+}
+
+#[test]
+fn for_iterates_arrays_and_vecs() {
+    let m = resolve_ok(
+        "fn main() { let mut v = vec::<Float>(); push(v, 1.5);
+                     for x in v { let y = x * 2.0; } for s in [\"a\", \"b\"] { print(s); } print(len(v)); }",
+    );
+    // petlja ne troši imenovani Vec
+    assert!(borrow_check(&m).is_empty());
+    resolve_err("fn main() { for x in 3 { } }", "an array or a Vec, got Int");
+}
+
+#[test]
+fn moves_and_borrows_of_vecs_are_checked() {
+    let take = "fn take(v: Vec<Int>) { }";
+    borrow_err(
+        &format!("{take} fn main() {{ let v = vec::<Int>(); take(v); print(len(v)); }}"),
+        "use of moved value `v`",
+    );
+    borrow_err(
+        "fn main() { let mut v = vec::<Int>(); let w = v; push(v, 1); }",
+        "use of moved value `v`",
+    );
+    borrow_err(
+        "fn main() { let mut v = vec::<Int>(); push(v, pop(v)); }",
+        "cannot borrow `v` as mutable more than once",
+    );
+    borrow_err(
+        "fn main() { let (tx, rx) = channel::<Vec<Int>>(); let v = vec::<Int>(); send(tx, v); send(tx, v); }",
+        "value `v` is moved twice",
+    );
+    // izvršava se samo jedna grana `if`-a, a `print` ne premešta
+    let m = resolve_ok(&format!(
+        "{take} fn main() {{ let v = vec::<Int>(); if true {{ take(v); }} else {{ take(v); }}
+                             let s = \"a\"; print(s); print(s);
+                             let mut w = vec::<Int>(); push(w, 1); push(w, len(w)); }}"
+    ));
+    assert!(borrow_check(&m).is_empty(), "{:?}", borrow_check(&m));
+}
+
+#[test]
+fn tail_values_of_blocks_and_branches_are_moved() {
+    borrow_err("fn main() { let v = vec::<Int>(); let w = { v }; print(len(v)); }", "use of moved value `v`");
+    borrow_err(
+        "fn main() { let c = true; let mut v = vec::<Int>(); let w = if c { v } else { vec::<Int>() }; push(v, 3); }",
+        "use of moved value `v`",
+    );
+    borrow_err(
+        "fn main() { let v = vec::<Int>(); let w = match Some(1) { Some(_) => v, None => vec::<Int>() }; len(v); }",
+        "use of moved value `v`",
+    );
+}
+
+#[test]
+fn loops_and_live_references_are_checked() {
+    let take = "fn take(v: Vec<Int>) { }";
+    // telo petlje se izvršava više puta
+    borrow_err(
+        &format!("{take} fn main() {{ let v = vec::<Int>(); for i in 0..2 {{ take(v); }} }}"),
+        "value `v` is moved twice",
+    );
+    borrow_err(
+        &format!("{take} fn main() {{ let v = vec::<Int>(); while true {{ if true {{ take(v); continue; }} }} }}"),
+        "value `v` is moved twice",
+    );
+    borrow_err(
+        "fn main() { let mut v = vec::<Int>(); let r = &v; push(v, 2); print(len(*r)); }",
+        "cannot borrow `v` as mutable while a reference to it is still used",
+    );
+    borrow_err(
+        "fn main() { let mut v = vec::<Int>(); let a = &mut v; let b = &mut v; push(*a, 1); push(*b, 2); }",
+        "cannot borrow `v` as mutable while a reference to it is still used",
+    );
+    borrow_err(
+        &format!("{take} fn main() {{ let v = vec::<Int>(); let r = &v; take(v); print(len(*r)); }}"),
+        "cannot move out of `v` while a reference to it is still used",
+    );
+    // posle `break` ili `return` nema sledećeg prolaza, a referenca koja se
+    // više ne koristi ne smeta
+    let m = resolve_ok(&format!(
+        "{take} fn main() {{ let v = vec::<Int>(); while true {{ take(v); break; }}
+                             let w = vec::<Int>(); for i in 0..3 {{ if i == 1 {{ take(w); return; }} }} }}
+         fn f() {{ let mut v = vec::<Int>(); for i in 0..3 {{ let r = &v; print(len(*r)); push(v, i); }}
+                  let r = &mut v; push(*r, 1); print(len(v)); }}"
+    ));
+    assert!(borrow_check(&m).is_empty(), "{:?}", borrow_check(&m));
+}
+
+#[test]
+fn for_borrows_the_vec_it_walks() {
+    // telo ne sme da menja Vec kroz koji petlja prolazi
+    let msg = "cannot borrow `v` as mutable while a reference to it is still used";
+    borrow_err("fn main() { let mut v = vec::<Int>(); for x in v { pop(v); } }", msg);
+    borrow_err("fn main() { let mut v = vec::<Int>(); for x in v { push(v, x); } }", msg);
+    let m = resolve_ok("fn main() { let mut v = vec::<Int>(); for x in v { print(x + len(v)); } push(v, 1); }");
+    assert!(borrow_check(&m).is_empty(), "{:?}", borrow_check(&m));
+
+    // provera granica ukazuje na `for`
+    let m = resolve_ok("fn main() {\n    let v = vec::<Int>();\n    for x in v { print(x); }\n}");
+    let mir_mod = mir::lower_module(&m);
+    let span = mir_mod.fns[0].body.blocks.iter().flat_map(|b| &b.stmts).find_map(|s| match s {
+        mir::Statement::BoundsCheck { span, .. } => Some(*span),
+        _ => None,
+    });
+    assert_eq!(span.map(|s| (s.line, s.column)), Some((3, 5)));
+}
+
+#[test]
+fn vec_lowers_to_runtime_calls() {
+    let m = resolve_ok(
+        "fn main() { let mut v = vec::<Int>(); push(v, 3); v[0] = v[0] + 1; print(pop(v)); }",
+    );
+    let mir_mod = mir::lower_module(&m);
+    let stmts: Vec<_> = mir_mod.fns[0].body.blocks.iter().flat_map(|b| &b.stmts).collect();
+    // obe provere granica koriste dužinu iz runtime-a
+    let checks = stmts
+        .iter()
+        .filter(|s| matches!(s, mir::Statement::BoundsCheck { len: mir::Operand::Temp(_), .. }))
+        .count();
+    assert_eq!(checks, 2, "{stmts:?}");
+
+    let mut llcx = LlvmCtx::new("test");
+    codegen_module(&mut llcx, &mir_mod);
+    llcx.module.verify().unwrap_or_else(|e| panic!("{}", e.to_string()));
+    let ir = llcx.module.print_to_string().to_string();
+    for f in ["aethc_vec_new", "aethc_vec_push", "aethc_vec_len", "aethc_vec_get", "aethc_vec_set", "aethc_vec_pop"] {
+        assert!(ir.contains(&format!("@{f}(")), "missing {f}: {ir}");
+    }
+    assert!(ir.contains("call void @aethc_panic_bounds("), "{ir}");
+}
+
+#[test]
+fn builtin_names_can_be_shadowed() {
+    // promenljiva i korisnička funkcija imaju prednost nad ugrađenom
+    resolve_ok("fn main() { let len = 3; let pop = len + 1; print(pop); }");
+    resolve_err("fn main() { let len = 3; let v = vec::<Int>(); print(len(v)); }", "cannot call a value of type Int");
+    let m = resolve_ok(
+        "fn len(x: Int) -> Int { x * 2 }
+         fn main() { let mut v = vec::<Int>(); push(v, 1); print(len(v[0])); }",
+    );
+    let mir_mod = mir::lower_module(&m);
+    let mut llcx = LlvmCtx::new("test");
+    codegen_module(&mut llcx, &mir_mod);
+    llcx.module.verify().unwrap_or_else(|e| panic!("{}", e.to_string()));
+    let ir = llcx.module.print_to_string().to_string();
+    assert!(ir.contains("call i32 @len(i32"), "{ir}");
+    assert!(ir.contains("@aethc_vec_len("), "{ir}");
+}